@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use openmls::framing::MlsCiphertext;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((_, consumed)) = MlsCiphertext::parse(data) {
+        assert_eq!(consumed, data.len());
+    }
+});
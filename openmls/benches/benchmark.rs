@@ -55,10 +55,125 @@ fn kp_bundle_evercrypt(c: &mut Criterion) {
     criterion_kp_bundle(c, backend);
 }
 
+/// Number of members of the group in the stage commit benchmark.
+const GROUP_SIZE: usize = 2000;
+
+fn new_key_package(
+    identity: Vec<u8>,
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) -> KeyPackage {
+    let credential_bundle = CredentialBundle::new(
+        identity,
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let key_package_bundle =
+        KeyPackageBundle::new(&[ciphersuite], &credential_bundle, backend, Vec::new())
+            .expect("An unexpected error occurred.");
+    let key_package = key_package_bundle.key_package().clone();
+    backend
+        .key_store()
+        .store(
+            &credential_bundle
+                .credential()
+                .signature_key()
+                .tls_serialize_detached()
+                .expect("An unexpected error occurred."),
+            &credential_bundle,
+        )
+        .expect("An unexpected error occurred.");
+    backend
+        .key_store()
+        .store(
+            key_package
+                .hash_ref(backend.crypto())
+                .expect("An unexpected error occurred.")
+                .as_slice(),
+            &key_package_bundle,
+        )
+        .expect("An unexpected error occurred.");
+    key_package
+}
+
+fn criterion_stage_commit(c: &mut Criterion, backend: &impl OpenMlsCryptoProvider) {
+    for &ciphersuite in backend.crypto().supported_ciphersuites().iter() {
+        // Plaintext commits can be processed repeatedly without advancing
+        // any secret tree ratchets.
+        let mls_group_config = MlsGroupConfig::builder()
+            .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+            .build();
+        let key_packages: Vec<KeyPackage> = (0..GROUP_SIZE)
+            .map(|i| new_key_package(format!("Member {}", i).into_bytes(), ciphersuite, backend))
+            .collect();
+
+        // Alice creates the group and adds everybody else.
+        let mut alice_group = MlsGroup::new(
+            backend,
+            &mls_group_config,
+            key_packages[0]
+                .hash_ref(backend.crypto())
+                .expect("An unexpected error occurred.")
+                .as_slice(),
+        )
+        .expect("An unexpected error occurred.");
+        let (_, welcome) = alice_group
+            .add_members(backend, &key_packages[1..])
+            .expect("An unexpected error occurred.");
+        alice_group
+            .merge_pending_commit()
+            .expect("An unexpected error occurred.");
+        let mut bob_group = MlsGroup::new_from_welcome(
+            backend,
+            &mls_group_config,
+            welcome,
+            Some(alice_group.export_ratchet_tree()),
+        )
+        .expect("An unexpected error occurred.");
+
+        // Alice commits to a path update, which Bob stages over and over.
+        let (commit, _) = alice_group
+            .self_update(backend, None)
+            .expect("An unexpected error occurred.");
+        c.bench_function(
+            &format!(
+                "Stage commit in a group of {} members with ciphersuite: {:?}",
+                GROUP_SIZE, ciphersuite
+            ),
+            |b| {
+                b.iter(|| {
+                    bob_group
+                        .process_message(backend, commit.clone().into())
+                        .expect("An unexpected error occurred.");
+                });
+            },
+        );
+    }
+}
+
+fn stage_commit_rust_crypto(c: &mut Criterion) {
+    let backend = &OpenMlsRustCrypto::default();
+    println!("Backend: RustCrypto");
+    criterion_stage_commit(c, backend);
+}
+
+#[cfg(feature = "evercrypt")]
+fn stage_commit_evercrypt(c: &mut Criterion) {
+    use openmls_evercrypt::OpenMlsEvercrypt;
+    let backend = &OpenMlsEvercrypt::default();
+    println!("Backend: Evercrypt");
+    criterion_stage_commit(c, backend);
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     kp_bundle_rust_crypto(c);
     #[cfg(feature = "evercrypt")]
     kp_bundle_evercrypt(c);
+    stage_commit_rust_crypto(c);
+    #[cfg(feature = "evercrypt")]
+    stage_commit_evercrypt(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
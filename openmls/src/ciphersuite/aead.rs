@@ -1,9 +1,12 @@
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
 use super::*;
 
 /// The default NONCE size in bytes.
 pub(crate) const NONCE_BYTES: usize = 12;
 
-/// AEAD keys holding the plain key value and the AEAD algorithm type.
+/// AEAD keys holding the plain key value and the AEAD algorithm type. The key
+/// value is zeroized on drop.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(any(feature = "test-utils", test), derive(Clone, PartialEq, Eq))]
 pub struct AeadKey {
@@ -11,18 +14,27 @@ pub struct AeadKey {
     value: Vec<u8>,
 }
 
-/// AEAD Nonce
+/// AEAD Nonce. The nonce is zeroized on drop.
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub(crate) struct AeadNonce([u8; NONCE_BYTES]);
 
 impl AeadKey {
+    /// Explicitly copy the key. `Clone` is only implemented for tests, so
+    /// that the key doesn't get duplicated by accident.
+    pub(crate) fn duplicate(&self) -> Self {
+        AeadKey {
+            aead_mode: self.aead_mode,
+            value: self.value.clone(),
+        }
+    }
+
     /// Create an `AeadKey` from a `Secret`. TODO: This function should
     /// disappear when tackling issue #103.
-    pub(crate) fn from_secret(secret: Secret) -> Self {
+    pub(crate) fn from_secret(mut secret: Secret) -> Self {
         log::trace!("AeadKey::from_secret with {}", secret.ciphersuite);
         AeadKey {
             aead_mode: secret.ciphersuite.aead_algorithm(),
-            value: secret.value,
+            value: std::mem::take(&mut secret.value),
         }
     }
 
@@ -116,6 +128,22 @@ impl AeadNonce {
     }
 }
 
+impl Drop for AeadKey {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for AeadKey {}
+
+impl Drop for AeadNonce {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for AeadNonce {}
+
 #[cfg(test)]
 pub(crate) fn aead_key_gen(
     alg: openmls_traits::types::AeadType,
@@ -25,6 +25,11 @@
 //! ciphersuite specified in the KeyPackage determines the hash function used.  For a
 //! ProposalRef, the `value` input is the MLSPlaintext carrying the proposal, and
 //! the hash function is determined by the group's ciphersuite.
+//!
+//! In addition to the references defined in the spec, OpenMLS identifies
+//! Welcome messages by a [`WelcomeId`] with the label `"MLS 1.0 Welcome
+//! Reference"`. The `value` input is the encrypted group info of the Welcome and
+//! the hash function is determined by the Welcome's ciphersuite.
 
 use openmls_traits::{crypto::OpenMlsCrypto, types::CryptoError};
 use serde::{Deserialize, Serialize};
@@ -36,6 +41,7 @@ use super::Ciphersuite;
 
 const KEY_PACKAGE_REF_LABEL: &[u8; 28] = b"MLS 1.0 KeyPackage Reference";
 const PROPOSAL_REF_LABEL: &[u8; 26] = b"MLS 1.0 Proposal Reference";
+const WELCOME_ID_LABEL: &[u8; 25] = b"MLS 1.0 Welcome Reference";
 
 /// A reference to an MLS object computed as a hash of the value.
 #[derive(
@@ -63,6 +69,10 @@ pub type KeyPackageRef = HashReference;
 /// This value uniquely identifies a proposal.
 pub type ProposalRef = HashReference;
 
+/// A reference to a Welcome message.
+/// This value uniquely identifies a Welcome message.
+pub type WelcomeId = HashReference;
+
 #[derive(TlsSerialize, TlsSize)]
 struct HashReferenceInput<'a> {
     label: TlsSliceU8<'a, u8>,
@@ -87,6 +97,15 @@ pub fn make_key_package_ref(
     HashReference::new(value, ciphersuite, backend, KEY_PACKAGE_REF_LABEL)
 }
 
+/// Compute a new [`WelcomeId`] value for a `value`.
+pub fn make_welcome_id(
+    value: &[u8],
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCrypto,
+) -> Result<WelcomeId, CryptoError> {
+    HashReference::new(value, ciphersuite, backend, WELCOME_ID_LABEL)
+}
+
 impl HashReference {
     /// Compute a new [`HashReference`] value for a `value`.
     pub fn new(
@@ -30,7 +30,7 @@ impl Mac {
                     backend,
                     &Secret::from_slice(ikm, salt.mls_version, salt.ciphersuite),
                 )?
-                .value
+                .as_slice()
                 .into(),
         })
     }
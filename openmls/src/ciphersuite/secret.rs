@@ -1,9 +1,11 @@
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
 use super::kdf_label::KdfLabel;
 use super::*;
 
 /// A struct to contain secrets. This is to provide better visibility into where
 /// and how secrets are used and to avoid passing secrets in their raw
-/// representation.
+/// representation. The secret value is zeroized on drop.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq)]
 pub(crate) struct Secret {
     pub(in crate::ciphersuite) ciphersuite: Ciphersuite,
@@ -49,6 +51,20 @@ impl PartialEq for Secret {
     }
 }
 
+impl Zeroize for Secret {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Secret {}
+
 impl Secret {
     /// Randomly sample a fresh `Secret`.
     /// This default random initialiser uses the default Secret length of `hash_length`.
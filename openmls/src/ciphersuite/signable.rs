@@ -36,7 +36,7 @@ use crate::{
     error::LibraryError,
 };
 
-use super::OpenMlsSignaturePublicKey;
+use super::{OpenMlsSignaturePublicKey, SignContent};
 
 /// This trait must be implemented by all structs that contain a self-signature.
 pub trait SignedStruct<T> {
@@ -161,7 +161,11 @@ pub trait Verifiable: Sized {
             .unsigned_payload()
             .map_err(LibraryError::missing_bound_check)?;
         signature_public_key
-            .verify(backend, self.signature(), &payload)
+            .verify_with_label(
+                backend,
+                self.signature(),
+                &SignContent::new(self.label(), payload.into()),
+            )
             .map_err(|_| CredentialError::InvalidSignature)?;
         Ok(T::from_verifiable(self, T::SealingType::default()))
     }
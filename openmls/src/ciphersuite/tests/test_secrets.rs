@@ -1,7 +1,11 @@
 use openmls_rust_crypto::OpenMlsRustCrypto;
 
+use zeroize::ZeroizeOnDrop;
+
 use crate::{
-    ciphersuite::{Ciphersuite, Secret},
+    ciphersuite::{AeadKey, AeadNonce, Ciphersuite, Secret},
+    group::core_group::past_secrets::MessageSecretsStore,
+    schedule::{message_secrets::MessageSecrets, GroupEpochSecrets},
     test_utils::*,
     versions::ProtocolVersion,
 };
@@ -31,3 +35,15 @@ fn secret_incompatible(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoPro
     // This must panic because the two secrets have incompatible MLS versions.
     let _default_extracted = default_secret.hkdf_extract(backend, &draft_secret);
 }
+
+#[test]
+fn secrets_zeroize_on_drop() {
+    fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+
+    assert_zeroize_on_drop::<Secret>();
+    assert_zeroize_on_drop::<AeadKey>();
+    assert_zeroize_on_drop::<AeadNonce>();
+    assert_zeroize_on_drop::<GroupEpochSecrets>();
+    assert_zeroize_on_drop::<MessageSecrets>();
+    assert_zeroize_on_drop::<MessageSecretsStore>();
+}
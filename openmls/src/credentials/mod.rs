@@ -119,6 +119,11 @@ pub struct Credential {
 }
 
 impl Credential {
+    /// Returns the [`CredentialType`] of this credential.
+    pub fn credential_type(&self) -> CredentialType {
+        self.credential_type
+    }
+
     /// Verifies a signature of a given payload against the public key contained
     /// in a credential.
     ///
@@ -5,7 +5,7 @@ use tls_codec::{Deserialize, Serialize, Size, TlsByteVecU32, TlsSliceU32};
 use crate::extensions::{
     ApplicationIdExtension, Extension, ExtensionType, ExternalPubExtension,
     ExternalSendersExtension, LifetimeExtension, RatchetTreeExtension,
-    RequiredCapabilitiesExtension,
+    RequiredCapabilitiesExtension, UnknownExtension,
 };
 
 impl Size for Extension {
@@ -20,6 +20,7 @@ impl Size for Extension {
                 Extension::ExternalPub(e) => e.tls_serialized_len(),
                 Extension::ExternalSenders(e) => e.tls_serialized_len(),
                 Extension::Lifetime(e) => e.tls_serialized_len(),
+                Extension::Unknown(e) => e.data().len(),
             }
     }
 }
@@ -40,6 +41,10 @@ impl Serialize for Extension {
             Extension::ExternalPub(e) => e.tls_serialize(&mut extension_data),
             Extension::ExternalSenders(e) => e.tls_serialize(&mut extension_data),
             Extension::Lifetime(e) => e.tls_serialize(&mut extension_data),
+            Extension::Unknown(e) => {
+                extension_data.extend_from_slice(e.data());
+                Ok(e.data().len())
+            }
         }?;
         debug_assert_eq!(extension_data_written, extension_data_len);
         debug_assert_eq!(extension_data_written, extension_data.len());
@@ -78,6 +83,11 @@ impl Deserialize for Extension {
             ExtensionType::Lifetime => {
                 Extension::Lifetime(LifetimeExtension::tls_deserialize(&mut extension_data)?)
             }
+            ExtensionType::Unknown(extension_type) => Extension::Unknown(
+                UnknownExtension::new(extension_type, extension_data.to_vec()).map_err(|_| {
+                    tls_codec::Error::DecodingError("Invalid unknown extension type".into())
+                })?,
+            ),
         })
     }
 }
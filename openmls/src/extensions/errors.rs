@@ -26,6 +26,9 @@ pub enum ExtensionError {
     /// Unsupported extension type in required capabilities.
     #[error("Unsupported extension type in required capabilities.")]
     UnsupportedExtensionType,
+    /// The required capabilities require themselves or contain duplicates.
+    #[error("The required capabilities require themselves or contain duplicates.")]
+    InvalidRequiredCapabilities,
     /// See [`LibraryError`] for more details.
     #[error(transparent)]
     LibraryError(#[from] LibraryError),
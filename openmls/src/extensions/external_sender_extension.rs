@@ -21,14 +21,32 @@ pub struct ExternalSender {
     credential: Credential,
 }
 
+impl ExternalSender {
+    /// Creates a new [`ExternalSender`] from a signature key and a credential.
+    pub fn new(signature_key: SignaturePublicKey, credential: Credential) -> Self {
+        Self {
+            signature_key,
+            credential,
+        }
+    }
+
+    /// Returns the signature key of the external sender.
+    pub fn signature_key(&self) -> &SignaturePublicKey {
+        &self.signature_key
+    }
+
+    /// Returns the credential of the external sender.
+    pub fn credential(&self) -> &Credential {
+        &self.credential
+    }
+}
+
 /// ExternalSender (extension data)
 ///
 /// ```c
 /// // draft-ietf-mls-protocol-16
 /// ExternalSender external_senders<V>;
 /// ```
-// TODO(884): Remove `#[allow(unused)]` when #884 is closed.
-#[allow(unused)]
 pub type ExternalSendersExtension = Vec<ExternalSender>;
 
 #[cfg(test)]
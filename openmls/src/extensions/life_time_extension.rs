@@ -14,6 +14,37 @@ const DEFAULT_KEY_PACKAGE_LIFETIME: u64 = 60 * 60 * 24 * 28 * 3; // in Seconds
 /// value is in seconds and amounts to 1h.
 const DEFAULT_KEY_PACKAGE_LIFETIME_MARGIN: u64 = 60 * 60; // in Seconds
 
+/// A source of the current time, used to check whether a
+/// [`LifetimeExtension`] is valid. The time is given in seconds since the Unix
+/// epoch.
+pub trait Clock {
+    /// Returns the current time in seconds since the Unix epoch.
+    fn now(&self) -> u64;
+}
+
+/// A [`Clock`] that reads the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("SystemTime before UNIX EPOCH!")
+            .as_secs()
+    }
+}
+
+/// A [`Clock`] that always returns the same time.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FixedClock(pub(crate) u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
 /// # Life time extension
 ///
 /// The lifetime extension represents the times between which clients will
@@ -51,10 +82,12 @@ impl LifetimeExtension {
 
     /// Returns true if this lifetime is valid.
     pub(crate) fn is_valid(&self) -> bool {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("SystemTime before UNIX EPOCH!")
-            .as_secs();
+        self.is_valid_at(SystemClock.now())
+    }
+
+    /// Returns true if this lifetime is valid at the time `now` (in seconds
+    /// since the Unix epoch).
+    pub(crate) fn is_valid_at(&self, now: u64) -> bool {
         self.not_before < now && now < self.not_after
     }
 }
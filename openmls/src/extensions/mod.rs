@@ -19,9 +19,12 @@
 //! - [`RequiredCapabilitiesExtension`] (GroupContext extension)
 //! - [`ExternalPubExtension`] (GroupInfo extension)
 //! - [`LifetimeExtension`] (KeyPackage extension)
+//!
+//! Extensions of other types are kept as [`UnknownExtension`]s with their raw
+//! extension data.
 
 use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, fmt::Debug};
+use std::fmt::Debug;
 use tls_codec::*;
 
 // Private
@@ -32,6 +35,7 @@ mod external_sender_extension;
 mod life_time_extension;
 mod ratchet_tree_extension;
 mod required_capabilities;
+mod unknown_extension;
 use errors::*;
 
 // Public
@@ -40,10 +44,12 @@ pub mod errors;
 // Public re-exports
 pub use application_id_extension::ApplicationIdExtension;
 pub use external_pub_extension::ExternalPubExtension;
-pub use external_sender_extension::ExternalSendersExtension;
-pub use life_time_extension::LifetimeExtension;
-pub use ratchet_tree_extension::RatchetTreeExtension;
+pub use external_sender_extension::{ExternalSender, ExternalSendersExtension};
+pub(crate) use life_time_extension::FixedClock;
+pub use life_time_extension::{Clock, LifetimeExtension, SystemClock};
+pub use ratchet_tree_extension::{RatchetTreeExtension, RatchetTreeNodeReader};
 pub use required_capabilities::RequiredCapabilitiesExtension;
+pub use unknown_extension::UnknownExtension;
 
 use crate::treesync::node::Node;
 
@@ -65,73 +71,134 @@ mod test_extensions;
 /// | 0xff00  - 0xffff | Reserved for Private Use | N/A        | N/A         | RFC XXXX  |
 ///
 /// Note: OpenMLS does not provide a `Reserved` variant in [ExtensionType].
-#[derive(
-    Debug,
-    Copy,
-    Clone,
-    PartialEq,
-    Eq,
-    Hash,
-    Serialize,
-    Deserialize,
-    Ord,
-    PartialOrd,
-    TlsSerialize,
-    TlsDeserialize,
-    TlsSize,
-)]
-#[repr(u16)]
+/// Extension types that are not known to OpenMLS, e.g. application-defined
+/// ones, are represented by [`ExtensionType::Unknown`]. Extension types are
+/// compared by their numeric value.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum ExtensionType {
     /// The application id extension allows applications to add an explicit,
     /// application-defined identifier to a KeyPackage.
-    ApplicationId = 1,
+    ApplicationId,
 
     /// The ratchet tree extensions provides the whole public state of the ratchet
     /// tree.
-    RatchetTree = 2,
+    RatchetTree,
 
     /// The required capabilities extension defines the configuration of a group
     /// that imposes certain requirements on clients in the group.
-    RequiredCapabilities = 3,
+    RequiredCapabilities,
 
     /// To join a group via an External Commit, a new member needs a GroupInfo
     /// with an ExternalPub extension present in its extensions field.
-    ExternalPub = 4,
+    ExternalPub,
 
     /// Group context extension that contains the credentials and signature keys
     /// of senders that are permitted to send external proposals to the group.
-    ExternalSenders = 5,
+    ExternalSenders,
 
     /// The lifetime extension represents the times between which clients will
     /// consider a KeyPackage valid.
     /// TODO(#819): This extension will be deleted.
-    Lifetime = 0xff01,
-}
+    Lifetime,
 
-impl TryFrom<u16> for ExtensionType {
-    type Error = tls_codec::Error;
+    /// An extension type that is not known to OpenMLS. Use
+    /// [`ExtensionType::from()`] to create an [`ExtensionType`] from a `u16`,
+    /// so that known extension types are mapped to their variant.
+    Unknown(u16),
+}
 
+impl ExtensionType {
     /// Get the [`ExtensionType`] from a u16.
-    /// Returns an error if the extension type is not known.
-    /// Note that this returns a [`tls_codec::Error`](`tls_codec::Error`).
-    fn try_from(a: u16) -> Result<Self, Self::Error> {
+    const fn from_u16(a: u16) -> Self {
         match a {
-            1 => Ok(ExtensionType::ApplicationId),
-            2 => Ok(ExtensionType::RatchetTree),
-            3 => Ok(ExtensionType::RequiredCapabilities),
-            4 => Ok(ExtensionType::ExternalPub),
-            5 => Ok(ExtensionType::ExternalSenders),
-            0xff01 => Ok(ExtensionType::Lifetime),
-            _ => Err(tls_codec::Error::DecodingError(format!(
-                "{} is an unkown extension type",
-                a
-            ))),
+            1 => ExtensionType::ApplicationId,
+            2 => ExtensionType::RatchetTree,
+            3 => ExtensionType::RequiredCapabilities,
+            4 => ExtensionType::ExternalPub,
+            5 => ExtensionType::ExternalSenders,
+            0xff01 => ExtensionType::Lifetime,
+            unknown => ExtensionType::Unknown(unknown),
+        }
+    }
+
+    /// Get the u16 value of the [`ExtensionType`].
+    const fn to_u16(self) -> u16 {
+        match self {
+            ExtensionType::ApplicationId => 1,
+            ExtensionType::RatchetTree => 2,
+            ExtensionType::RequiredCapabilities => 3,
+            ExtensionType::ExternalPub => 4,
+            ExtensionType::ExternalSenders => 5,
+            ExtensionType::Lifetime => 0xff01,
+            ExtensionType::Unknown(unknown) => unknown,
         }
     }
 }
 
+impl From<u16> for ExtensionType {
+    /// Get the [`ExtensionType`] from a u16. Extension types that are not
+    /// known to OpenMLS are mapped to [`ExtensionType::Unknown`].
+    fn from(a: u16) -> Self {
+        Self::from_u16(a)
+    }
+}
+
+impl From<ExtensionType> for u16 {
+    fn from(extension_type: ExtensionType) -> Self {
+        extension_type.to_u16()
+    }
+}
+
+impl PartialEq for ExtensionType {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_u16() == other.to_u16()
+    }
+}
+
+impl Eq for ExtensionType {}
+
+impl std::hash::Hash for ExtensionType {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.to_u16().hash(state)
+    }
+}
+
+impl PartialOrd for ExtensionType {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ExtensionType {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.to_u16().cmp(&other.to_u16())
+    }
+}
+
+impl Size for ExtensionType {
+    fn tls_serialized_len(&self) -> usize {
+        2
+    }
+}
+
+impl tls_codec::Serialize for ExtensionType {
+    fn tls_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<usize, tls_codec::Error> {
+        tls_codec::Serialize::tls_serialize(&self.to_u16(), writer)
+    }
+}
+
+impl tls_codec::Deserialize for ExtensionType {
+    fn tls_deserialize<R: std::io::Read>(bytes: &mut R) -> Result<Self, tls_codec::Error> {
+        <u16 as tls_codec::Deserialize>::tls_deserialize(bytes).map(Self::from_u16)
+    }
+}
+
 impl ExtensionType {
     /// Check whether an [`ExtensionType`] is supported or not.
+    ///
+    /// [`ExtensionType::Unknown`] extension types are not supported. Their
+    /// extensions are carried along unchanged, but not interpreted by
+    /// OpenMLS.
     pub fn is_supported(&self) -> bool {
         match self {
             ExtensionType::ApplicationId
@@ -140,6 +207,7 @@ impl ExtensionType {
             | ExtensionType::ExternalPub
             | ExtensionType::ExternalSenders
             | ExtensionType::Lifetime => true,
+            ExtensionType::Unknown(_) => false,
         }
     }
 }
@@ -178,6 +246,9 @@ pub enum Extension {
     /// A [`LifetimeExtension`]
     /// TODO(#819): This extension will be deleted.
     Lifetime(LifetimeExtension),
+
+    /// An [`UnknownExtension`]
+    Unknown(UnknownExtension),
 }
 
 impl Extension {
@@ -257,6 +328,18 @@ impl Extension {
         }
     }
 
+    /// Get a reference to this extension as [`UnknownExtension`].
+    /// Returns an [`ExtensionError::InvalidExtensionType`] if called on an
+    /// [`Extension`] that's not an [`UnknownExtension`].
+    pub fn as_unknown_extension(&self) -> Result<&UnknownExtension, ExtensionError> {
+        match self {
+            Self::Unknown(e) => Ok(e),
+            _ => Err(ExtensionError::InvalidExtensionType(
+                "This is not an UnknownExtension".into(),
+            )),
+        }
+    }
+
     /// Returns the [`ExtensionType`]
     #[inline]
     pub const fn extension_type(&self) -> ExtensionType {
@@ -267,6 +350,7 @@ impl Extension {
             Extension::ExternalPub(_) => ExtensionType::ExternalPub,
             Extension::ExternalSenders(_) => ExtensionType::ExternalSenders,
             Extension::Lifetime(_) => ExtensionType::Lifetime,
+            Extension::Unknown(e) => ExtensionType::from_u16(e.extension_type()),
         }
     }
 }
@@ -1,4 +1,6 @@
-use tls_codec::{TlsDeserialize, TlsSerialize, TlsSize};
+use std::io::Read;
+
+use tls_codec::{Deserialize as TlsDeserializeTrait, Size, TlsDeserialize, TlsSerialize, TlsSize};
 
 use super::{Deserialize, Serialize};
 use crate::treesync::node::Node;
@@ -33,4 +35,88 @@ impl RatchetTreeExtension {
     pub(crate) fn as_slice(&self) -> &[Option<Node>] {
         self.tree.as_slice()
     }
+
+    /// Start decoding a TLS-encoded [`RatchetTreeExtension`] from `reader` one
+    /// node at a time. Only the length of the encoded tree is read right away.
+    /// The returned [`RatchetTreeNodeReader`] then decodes the nodes on
+    /// demand, so that they can be processed without first holding all of
+    /// them in memory.
+    ///
+    /// Collecting the nodes yields the same extension as the derived
+    /// [`tls_codec::Deserialize`] implementation.
+    pub fn deserialize_streaming<R: Read>(
+        mut reader: R,
+    ) -> Result<RatchetTreeNodeReader<R>, tls_codec::Error> {
+        let remaining = read_vector_length(&mut reader)?;
+        Ok(RatchetTreeNodeReader {
+            reader,
+            remaining,
+            failed: false,
+        })
+    }
+}
+
+/// Decodes the nodes of a TLS-encoded [`RatchetTreeExtension`] one at a time.
+/// See [`RatchetTreeExtension::deserialize_streaming()`].
+///
+/// The iterator stops after the first error.
+pub struct RatchetTreeNodeReader<R: Read> {
+    reader: R,
+    // The number of encoded bytes of the tree that haven't been read yet.
+    remaining: usize,
+    failed: bool,
+}
+
+impl<R: Read> RatchetTreeNodeReader<R> {
+    /// Decode the remaining nodes into a [`RatchetTreeExtension`].
+    pub fn into_extension(self) -> Result<RatchetTreeExtension, tls_codec::Error> {
+        self.collect::<Result<Vec<_>, _>>()
+            .map(RatchetTreeExtension::new)
+    }
+}
+
+impl<R: Read> Iterator for RatchetTreeNodeReader<R> {
+    type Item = Result<Option<Node>, tls_codec::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.remaining == 0 {
+            return None;
+        }
+        let node_option = Option::<Node>::tls_deserialize(&mut self.reader).and_then(|node| {
+            // The node must not extend beyond the end of the encoded tree.
+            self.remaining = self
+                .remaining
+                .checked_sub(node.tls_serialized_len())
+                .ok_or_else(|| {
+                    tls_codec::Error::DecodingError("Node exceeds the length of the tree".into())
+                })?;
+            Ok(node)
+        });
+        self.failed = node_option.is_err();
+        Some(node_option)
+    }
+}
+
+/// Read the variable-length encoded length of a TLS vector.
+fn read_vector_length<R: Read>(reader: &mut R) -> Result<usize, tls_codec::Error> {
+    let mut byte = [0u8; 1];
+    reader
+        .read_exact(&mut byte)
+        .map_err(|_| tls_codec::Error::EndOfStream)?;
+    // The two most significant bits encode the length of the length.
+    let length_length = 1usize << (byte[0] >> 6);
+    // MLS only allows lengths of up to 30 bits.
+    if length_length > 4 {
+        return Err(tls_codec::Error::DecodingError(
+            "Invalid vector length encoding".into(),
+        ));
+    }
+    let mut length = (byte[0] & 0x3f) as usize;
+    for _ in 1..length_length {
+        reader
+            .read_exact(&mut byte)
+            .map_err(|_| tls_codec::Error::EndOfStream)?;
+        length = (length << 8) | byte[0] as usize;
+    }
+    Ok(length)
 }
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use tls_codec::{TlsDeserialize, TlsSerialize, TlsSize};
 
 use crate::messages::proposals::ProposalType;
@@ -37,6 +39,9 @@ pub struct RequiredCapabilitiesExtension {
 
 impl RequiredCapabilitiesExtension {
     /// Creates a new [`RequiredCapabilitiesExtension`] from extension and proposal types.
+    ///
+    /// Note that the extension is only checked for validity when it is used,
+    /// e.g. when creating a group with it.
     pub fn new(extensions: &[ExtensionType], proposals: &[ProposalType]) -> Self {
         Self {
             extensions: extensions.into(),
@@ -54,12 +59,35 @@ impl RequiredCapabilitiesExtension {
         self.proposals.as_slice()
     }
 
-    /// Check if all extension and proposal types are supported.
+    /// Check if the extension is well-formed and all proposal types are
+    /// supported.
+    ///
+    /// Extension types that are unknown to OpenMLS may be required. OpenMLS
+    /// carries such extensions along unchanged and it's up to the members to
+    /// advertise support for them in their capabilities.
+    ///
+    /// Returns [`ExtensionError::InvalidRequiredCapabilities`] if the
+    /// extension requires the required capabilities extension type itself,
+    /// or if it lists an extension or proposal type more than once.
     pub(crate) fn check_support(&self) -> Result<(), ExtensionError> {
-        for extension in self.extensions() {
-            if !extension.is_supported() {
-                return Err(ExtensionError::UnsupportedExtensionType);
-            }
+        if self
+            .extensions()
+            .contains(&ExtensionType::RequiredCapabilities)
+        {
+            return Err(ExtensionError::InvalidRequiredCapabilities);
+        }
+        let mut extensions = HashSet::new();
+        let mut proposals = HashSet::new();
+        if !self
+            .extensions()
+            .iter()
+            .all(|extension| extensions.insert(extension))
+            || !self
+                .proposals()
+                .iter()
+                .all(|proposal| proposals.insert(proposal))
+        {
+            return Err(ExtensionError::InvalidRequiredCapabilities);
         }
         for proposal in self.proposals() {
             if !proposal.is_supported() {
@@ -10,7 +10,7 @@ use super::*;
 
 use crate::{
     credentials::*, framing::*, group::errors::*, group::*, key_packages::*,
-    messages::proposals::ProposalType,
+    messages::proposals::ProposalType, treesync::errors::PublicTreeError,
 };
 
 #[test]
@@ -92,6 +92,7 @@ fn ratchet_tree_extension(ciphersuite: Ciphersuite, backend: &impl OpenMlsCrypto
 
     let config = CoreGroupConfig {
         add_ratchet_tree_extension: true,
+        ..CoreGroupConfig::default()
     };
 
     // === Alice creates a group with the ratchet tree extension ===
@@ -169,6 +170,7 @@ fn ratchet_tree_extension(ciphersuite: Ciphersuite, backend: &impl OpenMlsCrypto
 
     let config = CoreGroupConfig {
         add_ratchet_tree_extension: false,
+        ..CoreGroupConfig::default()
     };
 
     let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
@@ -222,6 +224,159 @@ fn ratchet_tree_extension(ciphersuite: Ciphersuite, backend: &impl OpenMlsCrypto
     );
 }
 
+// This tests that a joiner rejects a ratchet tree that doesn't match the tree
+// hash in the signed group context.
+#[apply(ciphersuites_and_backends)]
+fn ratchet_tree_tree_hash_mismatch(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    // Define credential bundles and KeyPackages
+    let mut key_package_bundles = Vec::new();
+    let mut credential_bundles = Vec::new();
+    for identity in ["Alice", "Bob", "Charlie"] {
+        let credential_bundle = CredentialBundle::new(
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+            backend,
+        )
+        .expect("An unexpected error occurred.");
+        key_package_bundles.push(
+            KeyPackageBundle::new(&[ciphersuite], &credential_bundle, backend, Vec::new())
+                .expect("An unexpected error occurred."),
+        );
+        credential_bundles.push(credential_bundle);
+    }
+    let charlie_key_package_bundle = key_package_bundles.pop().expect("Missing KeyPackage.");
+    let bob_key_package_bundle = key_package_bundles.pop().expect("Missing KeyPackage.");
+    let alice_key_package_bundle = key_package_bundles.pop().expect("Missing KeyPackage.");
+    let alice_credential_bundle = &credential_bundles[0];
+
+    // === Alice creates a group without the ratchet tree extension ===
+    let config = CoreGroupConfig {
+        add_ratchet_tree_extension: false,
+        ..CoreGroupConfig::default()
+    };
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .with_config(config)
+        .build(alice_credential_bundle, backend)
+        .expect("Error creating group.");
+
+    // === Alice adds Bob and then Charlie ===
+    let mut add_member = |key_package_bundle: &KeyPackageBundle| {
+        let add_proposal = alice_group
+            .create_add_proposal(
+                framing_parameters,
+                alice_credential_bundle,
+                key_package_bundle.key_package().clone(),
+                backend,
+            )
+            .expect("Could not create proposal.");
+        let proposal_store = ProposalStore::from_queued_proposal(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, add_proposal)
+                .expect("Could not create QueuedProposal."),
+        );
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(alice_credential_bundle)
+            .proposal_store(&proposal_store)
+            .force_self_update(false)
+            .build();
+        let create_commit_result = alice_group
+            .create_commit(params, backend)
+            .expect("Error creating commit");
+        alice_group
+            .merge_commit(create_commit_result.staged_commit)
+            .expect("error merging commit");
+        (
+            create_commit_result
+                .welcome_option
+                .expect("An unexpected error occurred."),
+            alice_group.treesync().export_nodes(),
+        )
+    };
+    let (bob_welcome, bob_nodes) = add_member(&bob_key_package_bundle);
+    let (_charlie_welcome, charlie_nodes) = add_member(&charlie_key_package_bundle);
+
+    // The tree of the later epoch contains Bob and is signed by Alice, but it
+    // doesn't match the tree hash in the GroupInfo of Bob's Welcome.
+    let error = CoreGroup::new_from_welcome(
+        bob_welcome.clone(),
+        Some(charlie_nodes),
+        bob_key_package_bundle.clone(),
+        backend,
+    )
+    .expect_err("Joined with a ratchet tree that doesn't match the tree hash.");
+    assert_eq!(
+        error,
+        WelcomeError::PublicTreeError(PublicTreeError::TreeHashMismatch)
+    );
+
+    // The matching tree is accepted.
+    CoreGroup::new_from_welcome(
+        bob_welcome,
+        Some(bob_nodes),
+        bob_key_package_bundle,
+        backend,
+    )
+    .expect("Error joining group.");
+}
+
+// This tests that streaming deserialization of a large ratchet tree extension
+// yields the same nodes as the regular deserialization.
+#[apply(ciphersuites_and_backends)]
+fn ratchet_tree_extension_deserialize_streaming(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let alice_credential_bundle = CredentialBundle::new(
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package_bundle = KeyPackageBundle::new(
+        &[ciphersuite],
+        &alice_credential_bundle,
+        backend,
+        Vec::new(),
+    )
+    .expect("An unexpected error occurred.");
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating group.");
+    let leaf = alice_group.treesync().export_nodes()[0].clone();
+    assert!(leaf.is_some());
+
+    // Build a tree with 4096 nodes: leaves at even indices, blanks in between.
+    let nodes: Vec<Option<Node>> = (0..4096)
+        .map(|index| if index % 2 == 0 { leaf.clone() } else { None })
+        .collect();
+    let ratchet_tree_extension = RatchetTreeExtension::new(nodes);
+    let encoded = ratchet_tree_extension
+        .tls_serialize_detached()
+        .expect("Error serializing ratchet tree extension.");
+
+    let deserialized = RatchetTreeExtension::tls_deserialize(&mut encoded.as_slice())
+        .expect("Error deserializing ratchet tree extension.");
+    let streamed = RatchetTreeExtension::deserialize_streaming(encoded.as_slice())
+        .expect("Error reading the length of the ratchet tree extension.")
+        .into_extension()
+        .expect("Error deserializing ratchet tree extension.");
+    assert_eq!(deserialized, ratchet_tree_extension);
+    assert_eq!(streamed, deserialized);
+    assert_eq!(streamed.as_slice().len(), 4096);
+
+    // Truncated input is rejected by both.
+    let truncated = &encoded[..encoded.len() - 1];
+    assert!(RatchetTreeExtension::tls_deserialize(&mut &truncated[..]).is_err());
+    assert!(RatchetTreeExtension::deserialize_streaming(truncated)
+        .expect("Error reading the length of the ratchet tree extension.")
+        .into_extension()
+        .is_err());
+}
+
 #[test]
 fn required_capabilities() {
     // A required capabilities extension with the default values for openmls (none).
@@ -261,3 +416,113 @@ fn required_capabilities() {
     assert_eq!(ext, ext_decoded);
     assert_eq!(extension_bytes, encoded);
 }
+
+#[test]
+fn invalid_required_capabilities() {
+    // A well-formed extension is accepted.
+    let required_capabilities = RequiredCapabilitiesExtension::new(
+        &[ExtensionType::ApplicationId, ExtensionType::RatchetTree],
+        &[ProposalType::Reinit],
+    );
+    assert_eq!(required_capabilities.check_support(), Ok(()));
+
+    // Requiring the required capabilities extension itself is rejected.
+    let required_capabilities = RequiredCapabilitiesExtension::new(
+        &[
+            ExtensionType::ApplicationId,
+            ExtensionType::RequiredCapabilities,
+        ],
+        &[],
+    );
+    assert_eq!(
+        required_capabilities.check_support(),
+        Err(ExtensionError::InvalidRequiredCapabilities)
+    );
+
+    // So are duplicate extension types ...
+    let required_capabilities = RequiredCapabilitiesExtension::new(
+        &[ExtensionType::ApplicationId, ExtensionType::ApplicationId],
+        &[],
+    );
+    assert_eq!(
+        required_capabilities.check_support(),
+        Err(ExtensionError::InvalidRequiredCapabilities)
+    );
+
+    // ... and duplicate proposal types.
+    let required_capabilities =
+        RequiredCapabilitiesExtension::new(&[], &[ProposalType::Reinit, ProposalType::Reinit]);
+    assert_eq!(
+        required_capabilities.check_support(),
+        Err(ExtensionError::InvalidRequiredCapabilities)
+    );
+
+    // A group can't be created with a malformed extension.
+    let backend = OpenMlsRustCrypto::default();
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let credential_bundle = CredentialBundle::new(
+        b"Alice".to_vec(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        &backend,
+    )
+    .expect("An unexpected error occurred.");
+    let key_package_bundle =
+        KeyPackageBundle::new(&[ciphersuite], &credential_bundle, &backend, Vec::new())
+            .expect("An unexpected error occurred.");
+    let error = CoreGroup::builder(GroupId::random(&backend), key_package_bundle)
+        .with_required_capabilities(RequiredCapabilitiesExtension::new(
+            &[ExtensionType::RequiredCapabilities],
+            &[],
+        ))
+        .build(&credential_bundle, &backend)
+        .expect_err("Created a group with malformed required capabilities.");
+    assert_eq!(error, CoreGroupBuildError::InvalidRequiredCapabilities);
+}
+
+#[test]
+fn unknown_extension_round_trip() {
+    // Extension types that OpenMLS doesn't know are mapped to `Unknown`.
+    assert_eq!(ExtensionType::from(0xF00D), ExtensionType::Unknown(0xF00D));
+    assert_eq!(ExtensionType::from(2), ExtensionType::RatchetTree);
+    assert_eq!(u16::from(ExtensionType::Unknown(0xF00D)), 0xF00D);
+    // Extension types are compared by their value.
+    assert_eq!(ExtensionType::Unknown(1), ExtensionType::ApplicationId);
+    assert!(ExtensionType::Unknown(0xF00D) < ExtensionType::Lifetime);
+    assert!(!ExtensionType::Unknown(0xF00D).is_supported());
+
+    // Unknown extensions can only be created for unknown extension types.
+    assert!(matches!(
+        UnknownExtension::new(2, vec![1, 2, 3]),
+        Err(ExtensionError::InvalidExtensionType(_))
+    ));
+    let extension = Extension::Unknown(
+        UnknownExtension::new(0xF00D, vec![1, 2, 3]).expect("Could not create extension."),
+    );
+    assert_eq!(extension.extension_type(), ExtensionType::Unknown(0xF00D));
+
+    let extension_bytes = vec![0xF0u8, 0x0D, 0, 0, 0, 3, 1, 2, 3];
+    let encoded = extension
+        .tls_serialize_detached()
+        .expect("error encoding unknown extension");
+    assert_eq!(extension_bytes, encoded);
+
+    let decoded = Extension::tls_deserialize(&mut encoded.as_slice())
+        .expect("error decoding unknown extension");
+    assert_eq!(extension, decoded);
+
+    // Unknown extension types in capabilities survive a round trip as well.
+    let required_capabilities =
+        RequiredCapabilitiesExtension::new(&[ExtensionType::Unknown(0xF00D)], &[]);
+    let encoded = required_capabilities
+        .tls_serialize_detached()
+        .expect("error encoding required capabilities extension");
+    assert_eq!(
+        RequiredCapabilitiesExtension::tls_deserialize(&mut encoded.as_slice())
+            .expect("error decoding required capabilities extension"),
+        required_capabilities
+    );
+    // Unknown extensions may be required, it's up to the members to support
+    // them.
+    assert_eq!(required_capabilities.check_support(), Ok(()));
+}
@@ -0,0 +1,42 @@
+use super::{errors::ExtensionError, Deserialize, ExtensionType, Serialize};
+
+/// # Unknown extensions
+///
+/// An extension of a type that is not known to OpenMLS, e.g. an
+/// application-defined extension. The extension data is kept as is, so that
+/// the extension survives (de)serialization unchanged.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct UnknownExtension {
+    extension_type: u16,
+    data: Vec<u8>,
+}
+
+impl UnknownExtension {
+    /// Create a new unknown extension of the given type with the given raw
+    /// extension data.
+    ///
+    /// Returns an [`ExtensionError::InvalidExtensionType`] if the extension
+    /// type is known to OpenMLS. Such extensions have to be created through
+    /// their own [`Extension`](super::Extension) variant.
+    pub fn new(extension_type: u16, data: Vec<u8>) -> Result<Self, ExtensionError> {
+        match ExtensionType::from_u16(extension_type) {
+            ExtensionType::Unknown(_) => Ok(Self {
+                extension_type,
+                data,
+            }),
+            _ => Err(ExtensionError::InvalidExtensionType(
+                "This extension type is known to OpenMLS".into(),
+            )),
+        }
+    }
+
+    /// Get the numeric extension type.
+    pub const fn extension_type(&self) -> u16 {
+        self.extension_type
+    }
+
+    /// Get the raw extension data as byte slice.
+    pub fn data(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+}
@@ -14,6 +14,73 @@ use crate::{
 
 use super::*;
 
+/// The strategy used to pad the content of an [`MlsCiphertext`] in order to
+/// hide the length of the encrypted message. The padded length includes the
+/// AEAD tag.
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+pub enum PaddingStrategy {
+    /// Don't pad.
+    None,
+    /// Pad to the given length. Longer content is not padded.
+    Fixed(usize),
+    /// Pad to the next multiple of the given block size. A block size of zero
+    /// means no padding.
+    PadToMultiple(usize),
+    /// Pad to the smallest of the given lengths that fits the content. Content
+    /// that is longer than the largest bucket is padded to the next multiple
+    /// of the largest bucket. An empty list of buckets means no padding.
+    PadToBucket(Vec<usize>),
+}
+
+impl PaddingStrategy {
+    /// Returns the number of padding bytes to append to content of length
+    /// `content_length`.
+    pub(crate) fn padding_length(&self, content_length: usize) -> usize {
+        let padded_length = match self {
+            PaddingStrategy::None => content_length,
+            PaddingStrategy::Fixed(length) => content_length.max(*length),
+            PaddingStrategy::PadToMultiple(block_size) => {
+                Self::next_multiple(content_length, *block_size)
+            }
+            PaddingStrategy::PadToBucket(buckets) => buckets
+                .iter()
+                .copied()
+                .filter(|bucket| *bucket >= content_length)
+                .min()
+                .unwrap_or_else(|| {
+                    Self::next_multiple(
+                        content_length,
+                        buckets.iter().copied().max().unwrap_or_default(),
+                    )
+                }),
+        };
+        padded_length - content_length
+    }
+
+    /// Rounds `length` up to the next multiple of `block_size`. A block size
+    /// of zero leaves `length` unchanged.
+    fn next_multiple(length: usize, block_size: usize) -> usize {
+        if block_size == 0 {
+            return length;
+        }
+        match length % block_size {
+            0 => length,
+            remainder => length + (block_size - remainder),
+        }
+    }
+}
+
+impl From<usize> for PaddingStrategy {
+    /// Converts a padding size as used by the group APIs, where zero means no
+    /// padding and any other value is a block size.
+    fn from(padding_size: usize) -> Self {
+        match padding_size {
+            0 => PaddingStrategy::None,
+            block_size => PaddingStrategy::PadToMultiple(block_size),
+        }
+    }
+}
+
 /// `MlsCiphertext` is the framing struct for an encrypted `MlsPlaintext`.
 /// This message format is meant to be sent to and received from the Delivery
 /// Service.
@@ -30,7 +97,7 @@ use super::*;
 /// } MLSCiphertext;
 /// ```
 #[derive(Debug, PartialEq, Clone, TlsSerialize, TlsSize, TlsDeserialize)]
-pub(crate) struct MlsCiphertext {
+pub struct MlsCiphertext {
     group_id: GroupId,
     epoch: GroupEpoch,
     content_type: ContentType,
@@ -74,7 +141,7 @@ impl MlsCiphertext {
         ciphersuite: Ciphersuite,
         backend: &impl OpenMlsCryptoProvider,
         message_secrets: &mut MessageSecrets,
-        padding_size: usize,
+        padding_strategy: &PaddingStrategy,
     ) -> Result<MlsCiphertext, MessageEncryptionError> {
         log::debug!("MlsCiphertext::try_from_plaintext");
         log::trace!("  ciphersuite: {}", ciphersuite);
@@ -88,7 +155,7 @@ impl MlsCiphertext {
             ciphersuite,
             backend,
             message_secrets,
-            padding_size,
+            padding_strategy,
         )
     }
 
@@ -106,7 +173,7 @@ impl MlsCiphertext {
             ciphersuite,
             backend,
             message_secrets,
-            padding_size,
+            &padding_size.into(),
         )
     }
 
@@ -125,7 +192,7 @@ impl MlsCiphertext {
             ciphersuite,
             backend,
             message_secrets,
-            padding_size,
+            &padding_size.into(),
         )
     }
 
@@ -137,7 +204,7 @@ impl MlsCiphertext {
         ciphersuite: Ciphersuite,
         backend: &impl OpenMlsCryptoProvider,
         message_secrets: &mut MessageSecrets,
-        padding_size: usize,
+        padding_strategy: &PaddingStrategy,
     ) -> Result<MlsCiphertext, MessageEncryptionError> {
         let sender_index = if let Some(index) = mls_plaintext.sender().as_member() {
             index
@@ -165,6 +232,9 @@ impl MlsCiphertext {
             .map_err(LibraryError::missing_bound_check)?;
         // Extract generation and key material for encryption
         let secret_type = SecretType::from(&mls_plaintext.content().content_type());
+        // Make sure we never reuse a generation, e.g. after loading an outdated
+        // group state.
+        message_secrets.reserve_encryption_generation(backend, sender_index, secret_type)?;
         let (generation, (ratchet_key, ratchet_nonce)) = message_secrets
             .secret_tree_mut()
             // Even in tests we want to use the real sender index, so we have a key to encrypt.
@@ -180,7 +250,7 @@ impl MlsCiphertext {
                 backend,
                 &Self::encode_padded_ciphertext_content_detached(
                     mls_plaintext,
-                    padding_size,
+                    padding_strategy,
                     ciphersuite.mac_length(),
                 )
                 .map_err(LibraryError::missing_bound_check)?,
@@ -341,9 +411,14 @@ impl MlsCiphertext {
                 sender_data.generation,
                 sender_ratchet_configuration,
             )
-            .map_err(|_| {
+            .map_err(|e| {
                 log::error!("  Ciphertext generation out of bounds");
-                MessageDecryptionError::GenerationOutOfBound
+                match e {
+                    SecretTreeError::GenerationOutOfRange => {
+                        MessageDecryptionError::GenerationOutOfRange
+                    }
+                    _ => MessageDecryptionError::GenerationOutOfBound,
+                }
             })?;
         // Prepare the nonce by xoring with the reuse guard.
         let prepared_nonce = ratchet_nonce.xor_with_reuse_guard(&sender_data.reuse_guard);
@@ -381,21 +456,14 @@ impl MlsCiphertext {
     /// Encodes the `MLSCiphertextContent` struct with padding.
     fn encode_padded_ciphertext_content_detached(
         mls_plaintext: &MlsAuthContent,
-        padding_size: usize,
+        padding_strategy: &PaddingStrategy,
         mac_len: usize,
     ) -> Result<Vec<u8>, tls_codec::Error> {
         let plaintext_length = mls_plaintext.content().serialized_len_without_type()
             + mls_plaintext.auth.tls_serialized_len();
 
-        let padding_length = if padding_size > 0 {
-            // Calculate padding block size.
-            // Only the AEAD tag is added.
-            let padding_offset = plaintext_length + mac_len;
-            // Return padding block size
-            (padding_size - (padding_offset % padding_size)) % padding_size
-        } else {
-            0
-        };
+        // Only the AEAD tag is added.
+        let padding_length = padding_strategy.padding_length(plaintext_length + mac_len);
 
         // Persist all initial fields manually (avoids cloning them)
         let buffer = &mut Vec::with_capacity(plaintext_length + padding_length);
@@ -439,6 +507,65 @@ impl MlsCiphertext {
     pub(crate) fn set_ciphertext(&mut self, ciphertext: Vec<u8>) {
         self.ciphertext = ciphertext.into();
     }
+
+    /// Parse an [`MlsCiphertext`] from `bytes` and return it together with
+    /// the number of bytes consumed.
+    ///
+    /// In contrast to [`Deserialize::tls_deserialize`], this reports the
+    /// offset of the field at which parsing failed and rejects trailing data.
+    /// It never panics, which makes it usable as an entry point for fuzzing.
+    #[cfg(any(feature = "fuzzing", test))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fuzzing")))]
+    pub fn parse(bytes: &[u8]) -> Result<(MlsCiphertext, usize), ParseError> {
+        let invalid_length = |offset: usize| ParseError::InvalidLength { offset };
+        let invalid_discriminant = |offset: usize| ParseError::InvalidDiscriminant {
+            offset,
+            value: bytes.get(offset).copied().unwrap_or_default(),
+        };
+        let mut parser = Parser { bytes, offset: 0 };
+        let ciphertext = MlsCiphertext {
+            group_id: parser.field(invalid_length)?,
+            epoch: parser.field(invalid_length)?,
+            content_type: parser.field(invalid_discriminant)?,
+            authenticated_data: parser.field(invalid_length)?,
+            encrypted_sender_data: parser.field(invalid_length)?,
+            ciphertext: parser.field(invalid_length)?,
+        };
+        if parser.offset != bytes.len() {
+            return Err(ParseError::TrailingData {
+                consumed: parser.offset,
+            });
+        }
+        Ok((ciphertext, parser.offset))
+    }
+}
+
+/// Cursor over the input of [`MlsCiphertext::parse`].
+#[cfg(any(feature = "fuzzing", test))]
+struct Parser<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+#[cfg(any(feature = "fuzzing", test))]
+impl<'a> Parser<'a> {
+    /// Deserialize the next field and advance the offset past it. If the
+    /// input ends before the field is complete, this fails with
+    /// [`ParseError::Truncated`]. Any other decoding error is turned into a
+    /// [`ParseError`] by `invalid`, which gets the offset of the field.
+    fn field<T: Deserialize>(
+        &mut self,
+        invalid: impl FnOnce(usize) -> ParseError,
+    ) -> Result<T, ParseError> {
+        let offset = self.offset;
+        let mut remaining = &self.bytes[offset..];
+        let field = T::tls_deserialize(&mut remaining).map_err(|e| match e {
+            tls_codec::Error::EndOfStream => ParseError::Truncated { offset },
+            _ => invalid(offset),
+        })?;
+        self.offset = self.bytes.len() - remaining.len();
+        Ok(field)
+    }
 }
 
 // === Helper structs ===
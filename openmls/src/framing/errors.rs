@@ -19,6 +19,9 @@ pub enum MessageDecryptionError {
     /// Couldn't find a ratcheting secret for the given sender and generation.
     #[error("Couldn't find a ratcheting secret for the given sender and generation.")]
     GenerationOutOfBound,
+    /// The generation is further ahead than the sender ratchet is willing to skip.
+    #[error("The generation is further ahead than the sender ratchet is willing to skip.")]
+    GenerationOutOfRange,
     /// An error occurred during AEAD decryption.
     #[error("An error occurred during AEAD decryption.")]
     AeadError,
@@ -51,6 +54,55 @@ pub(crate) enum MessageEncryptionError {
     /// See [`SenderError`] for more details.
     #[error(transparent)]
     SenderError(#[from] SenderError),
+    /// The generation of the sender ratchet is not larger than the one of the
+    /// previously encrypted message.
+    #[error("The generation of the sender ratchet is not larger than the one of the previously encrypted message.")]
+    RatchetRegression,
+    /// The generation of the sender ratchet could not be written to the key
+    /// store.
+    #[error("The generation of the sender ratchet could not be written to the key store.")]
+    KeyStoreError,
+    /// The authenticated data is longer than the group allows.
+    #[error("The authenticated data is longer than the group allows.")]
+    AadTooLarge,
+    /// The group was terminated by a ReInit proposal.
+    #[error("The group was terminated by a ReInit proposal.")]
+    GroupTerminated,
+}
+
+/// Parse error
+#[cfg(any(feature = "fuzzing", test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "fuzzing")))]
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    /// The input ended before the field starting at `offset` was complete.
+    #[error("The input ended before the field starting at offset {offset} was complete.")]
+    Truncated {
+        /// Offset of the incomplete field.
+        offset: usize,
+    },
+    /// The variable-length vector starting at `offset` has an invalid length encoding.
+    #[error(
+        "The variable-length vector starting at offset {offset} has an invalid length encoding."
+    )]
+    InvalidLength {
+        /// Offset of the malformed vector.
+        offset: usize,
+    },
+    /// The enum at `offset` has an unknown discriminant.
+    #[error("The enum at offset {offset} has an unknown discriminant {value}.")]
+    InvalidDiscriminant {
+        /// Offset of the discriminant.
+        offset: usize,
+        /// The unknown discriminant.
+        value: u8,
+    },
+    /// The input contains data after the first `consumed` bytes.
+    #[error("The input contains data after the first {consumed} bytes.")]
+    TrailingData {
+        /// Number of bytes that were parsed successfully.
+        consumed: usize,
+    },
 }
 
 /// Sender error
@@ -170,6 +170,16 @@ impl MlsMessageIn {
         }
     }
 
+    /// Returns the sender of the message if it is an [`MlsPlaintext`] and
+    /// `None` otherwise, since the sender of an [`MlsCiphertext`] is
+    /// encrypted.
+    pub(crate) fn sender(&self) -> Option<&Sender> {
+        match &self.mls_message.body {
+            MlsMessageBody::Plaintext(p) => Some(p.sender()),
+            MlsMessageBody::Ciphertext(_) => None,
+        }
+    }
+
     /// Tries to deserialize from a byte slice. Returns [`MlsMessageError::UnableToDecode`] on failure.
     pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, MlsMessageError> {
         Ok(Self {
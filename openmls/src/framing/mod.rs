@@ -29,6 +29,9 @@ pub(crate) use sender::*;
 // Public
 pub mod errors;
 
+#[cfg(feature = "fuzzing")]
+pub use ciphertext::MlsCiphertext;
+pub use ciphertext::PaddingStrategy;
 pub use message::*;
 pub use sender::*;
 pub use validation::*;
@@ -160,8 +160,8 @@ impl MlsPlaintext {
         &self.content.body
     }
 
-    pub fn confirmation_tag(&self) -> Option<&ConfirmationTag> {
-        self.auth.confirmation_tag.as_ref()
+    pub fn membership_tag(&self) -> Option<&MembershipTag> {
+        self.membership_tag.as_ref()
     }
 
     pub(crate) fn invalidate_signature(&mut self) {
@@ -294,6 +294,11 @@ impl MlsPlaintext {
         self.content.epoch
     }
 
+    /// Get the confirmation tag.
+    pub(crate) fn confirmation_tag(&self) -> Option<&ConfirmationTag> {
+        self.auth.confirmation_tag.as_ref()
+    }
+
     /// Get the [`GroupId`].
     pub(crate) fn group_id(&self) -> &GroupId {
         &self.content.group_id
@@ -404,7 +409,7 @@ impl<'a> MlsContentTbm<'a> {
 )]
 pub(crate) struct MembershipTag(pub(crate) Mac);
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct MlsContentTbs {
     pub(super) wire_format: WireFormat,
     pub(super) content: MlsContent,
@@ -469,7 +474,7 @@ pub(crate) struct MlsContentAuthData {
 /// If we want to serialize a spec-compliant MLSAuthenticatedContent, we have to
 /// manually ignore the extra fields in the TBS (i.e. context and later
 /// ProtocolVersion).
-#[derive(PartialEq, Debug, Clone, TlsSerialize, TlsSize)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize, TlsSerialize, TlsSize)]
 pub(crate) struct MlsAuthContent {
     pub(super) tbs: MlsContentTbs,
     pub(super) auth: MlsContentAuthData,
@@ -548,9 +553,9 @@ impl MlsAuthContent {
     }
 
     /// This constructor builds an `MlsPlaintext` containing an External Proposal.
-    /// The sender is [Sender::NewMemberProposal].
-    // TODO #151/#106: We don't support preconfigured senders yet
+    /// The sender is either [Sender::NewMemberProposal] or [Sender::External].
     pub(crate) fn new_external_proposal(
+        sender: Sender,
         proposal: Proposal,
         credential_bundle: &CredentialBundle,
         group_id: GroupId,
@@ -563,7 +568,7 @@ impl MlsAuthContent {
             WireFormat::MlsPlaintext,
             group_id,
             epoch,
-            Sender::NewMemberProposal,
+            sender,
             vec![].into(),
             body,
         );
@@ -638,6 +643,21 @@ impl MlsAuthContent {
         &self.tbs.content.sender
     }
 
+    /// Verifies the signature of this [`MlsAuthContent`] against the given
+    /// `credential`, e.g. to re-check a proposal before it is committed. Note
+    /// that for member senders, the serialized group context must be set.
+    pub(crate) fn verify_signature(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        credential: &Credential,
+    ) -> Result<(), ValidationError> {
+        VerifiableMlsAuthContent {
+            auth_content: self.clone(),
+        }
+        .verify_no_out(backend, credential)
+        .map_err(|_| ValidationError::InvalidSignature)
+    }
+
     #[cfg(test)]
     pub fn test_signature(&self) -> &Signature {
         &self.auth.signature
@@ -647,6 +667,11 @@ impl MlsAuthContent {
     pub(super) fn unset_confirmation_tag(&mut self) {
         self.auth.confirmation_tag = None;
     }
+
+    #[cfg(test)]
+    pub(crate) fn set_wire_format(&mut self, wire_format: WireFormat) {
+        self.tbs.wire_format = wire_format;
+    }
 }
 
 #[cfg(any(feature = "test-utils", test))]
@@ -856,6 +881,14 @@ impl<'a> ConfirmedTranscriptHashInput<'a> {
             signature: mls_content.signature(),
         })
     }
+
+    /// Returns the same input, but with the given wire format.
+    pub(crate) fn with_wire_format(self, wire_format: WireFormat) -> Self {
+        Self {
+            wire_format,
+            ..self
+        }
+    }
 }
 
 #[derive(TlsSerialize, TlsSize)]
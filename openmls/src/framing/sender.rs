@@ -38,8 +38,11 @@ pub enum Sender {
     /// The sender is a member of the group
     #[tls_codec(discriminant = 1)]
     Member(u32),
-    /// The sender is not a member of the group and has an external value instead
-    External(TlsByteVecU8),
+    /// The sender is not a member of the group but one of the external senders
+    /// listed in the group's
+    /// [`ExternalSendersExtension`](crate::extensions::ExternalSendersExtension).
+    /// The value is the index of the sender in that extension.
+    External(u32),
     /// The sender is a new member of the group that joins itself through
     /// an [External Add proposal](crate::messages::external_proposals::JoinProposal)
     NewMemberProposal,
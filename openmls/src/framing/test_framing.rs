@@ -158,6 +158,86 @@ fn codec_ciphertext(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvid
     assert!(!orig.is_handshake_message());
 }
 
+/// Feeds truncated, mutated and random inputs to `MlsCiphertext::parse` to
+/// check that it reports errors instead of panicking.
+#[apply(backends)]
+fn parse_ciphertext(backend: &impl OpenMlsCryptoProvider) {
+    let ciphertext = MlsCiphertext::new(
+        GroupId::from_slice(&[5, 5, 5]),
+        GroupEpoch::from(7),
+        ContentType::Application,
+        vec![1, 2].into(),
+        vec![3, 4, 5, 6].into(),
+        vec![7; 100].into(),
+    );
+    let bytes = ciphertext
+        .tls_serialize_detached()
+        .expect("An unexpected error occurred.");
+
+    // A valid ciphertext is parsed in full.
+    assert_eq!(
+        MlsCiphertext::parse(&bytes),
+        Ok((ciphertext.clone(), bytes.len()))
+    );
+
+    // Every strict prefix is reported as truncated.
+    for length in 0..bytes.len() {
+        assert!(matches!(
+            MlsCiphertext::parse(&bytes[..length]),
+            Err(ParseError::Truncated { .. })
+        ));
+    }
+
+    // Trailing data is rejected.
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        MlsCiphertext::parse(&trailing),
+        Err(ParseError::TrailingData {
+            consumed: bytes.len()
+        })
+    );
+
+    // The content type follows the group id (4 bytes) and the epoch (8 bytes).
+    let mut invalid_content_type = bytes.clone();
+    invalid_content_type[12] = 0xff;
+    assert_eq!(
+        MlsCiphertext::parse(&invalid_content_type),
+        Err(ParseError::InvalidDiscriminant {
+            offset: 12,
+            value: 0xff
+        })
+    );
+
+    // The top two bits `0b11` are not a valid length prefix.
+    let mut invalid_length = bytes.clone();
+    invalid_length[0] = 0xc0;
+    assert_eq!(
+        MlsCiphertext::parse(&invalid_length),
+        Err(ParseError::InvalidLength { offset: 0 })
+    );
+
+    // Fuzz with random byte substitutions in the valid ciphertext and with
+    // random inputs. Anything that parses must consume the whole input.
+    for _ in 0..1000 {
+        let randomness = backend
+            .rand()
+            .random_vec(bytes.len() + 2)
+            .expect("An unexpected error occurred.");
+
+        let mut mutated = bytes.clone();
+        let position = usize::from(randomness[0]) % mutated.len();
+        mutated[position] = randomness[1];
+
+        let random_length = usize::from(randomness[2]) % randomness.len();
+        for input in [&mutated[..], &randomness[..random_length]] {
+            if let Ok((_, consumed)) = MlsCiphertext::parse(input) {
+                assert_eq!(consumed, input.len());
+            }
+        }
+    }
+}
+
 /// This tests the correctness of wire format checks
 #[apply(ciphersuites_and_backends)]
 fn wire_format_checks(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
@@ -272,7 +352,7 @@ fn wire_format_checks(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProv
             ciphersuite,
             backend,
             &mut message_secrets,
-            0,
+            &PaddingStrategy::None,
         )
         .expect_err("Could encrypt despite wrong wire format."),
         MessageEncryptionError::WrongWireFormat
@@ -929,3 +1009,34 @@ fn invalid_plaintext_signature(ciphersuite: Ciphersuite, backend: &impl OpenMlsC
     //     .stage_commit(&decoded_commit, &proposal_store, &[], backend)
     //     .expect("Alice: Error staging commit.");
 }
+
+#[test]
+fn padding_strategy_lengths() {
+    // No padding
+    assert_eq!(PaddingStrategy::None.padding_length(100), 0);
+    assert_eq!(PaddingStrategy::from(0).padding_length(100), 0);
+
+    // Fixed length, longer content isn't padded
+    assert_eq!(PaddingStrategy::Fixed(128).padding_length(100), 28);
+    assert_eq!(PaddingStrategy::Fixed(128).padding_length(200), 0);
+
+    // Multiples of a block size
+    assert_eq!(PaddingStrategy::PadToMultiple(64).padding_length(100), 28);
+    assert_eq!(PaddingStrategy::PadToMultiple(64).padding_length(128), 0);
+    assert_eq!(
+        PaddingStrategy::from(64),
+        PaddingStrategy::PadToMultiple(64)
+    );
+
+    // Buckets, content longer than the largest bucket is padded to a multiple
+    // of the largest bucket.
+    let buckets = PaddingStrategy::PadToBucket(vec![512, 128, 256]);
+    assert_eq!(buckets.padding_length(100), 28);
+    assert_eq!(buckets.padding_length(129), 127);
+    assert_eq!(buckets.padding_length(600), 424);
+
+    // Degenerate strategies don't pad
+    assert_eq!(PaddingStrategy::PadToMultiple(0).padding_length(100), 0);
+    assert_eq!(PaddingStrategy::PadToBucket(vec![]).padding_length(100), 0);
+    assert_eq!(PaddingStrategy::PadToBucket(vec![0]).padding_length(100), 0);
+}
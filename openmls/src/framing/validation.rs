@@ -35,7 +35,10 @@
 //! ```
 // TODO #106/#151: Update the above diagram
 
-use crate::{group::errors::ValidationError, tree::index::SecretTreeLeafIndex, treesync::TreeSync};
+use crate::{
+    extensions::ExternalSender, group::errors::ValidationError, tree::index::SecretTreeLeafIndex,
+    treesync::TreeSync,
+};
 use core_group::{proposals::QueuedProposal, staged_commit::StagedCommit};
 use openmls_traits::OpenMlsCryptoProvider;
 
@@ -58,23 +61,12 @@ pub(crate) struct DecryptedMessage {
 
 impl DecryptedMessage {
     /// Constructs a [DecryptedMessage] from a [VerifiableMlsAuthContent].
+    /// The membership tag of the plaintext must have been verified before (see
+    /// [`CoreGroup::verify_membership_tag()`]).
     pub(crate) fn from_inbound_plaintext(
         plaintext: MlsPlaintext,
         message_secrets: &MessageSecrets,
-        backend: &impl OpenMlsCryptoProvider,
     ) -> Result<Self, ValidationError> {
-        if plaintext.sender().is_member() {
-            // Verify the membership tag. This needs to be done explicitly for MlsPlaintext messages,
-            // it is implicit for MlsCiphertext messages (because the encryption can only be known by members).
-            // ValSem007 Membership tag presence
-            // ValSem008
-            plaintext.verify_membership(
-                backend,
-                message_secrets.membership_key(),
-                message_secrets.serialized_context(),
-            )?;
-        }
-
         let context = if matches!(
             plaintext.sender(),
             Sender::NewMemberCommit | Sender::Member(_)
@@ -99,14 +91,13 @@ impl DecryptedMessage {
         let ciphersuite = group.ciphersuite();
         // TODO: #819 The old leaves should not be needed any more.
         //       Revisit when the transition is further along.
-        let (message_secrets, _old_leaves) = group
-            .message_secrets_and_leaves_mut(ciphertext.epoch())
-            .map_err(|_| MessageDecryptionError::AeadError)?;
+        let (message_secrets, _old_leaves) =
+            group.message_secrets_and_leaves_mut(ciphertext.epoch())?;
         let sender_data = ciphertext.sender_data(message_secrets, backend, ciphersuite)?;
         let sender_index = SecretTreeLeafIndex(sender_data.leaf_index);
         let message_secrets = group
             .message_secrets_mut(ciphertext.epoch())
-            .map_err(|_| MessageDecryptionError::AeadError)?;
+            .map_err(MessageDecryptionError::from)?;
         let plaintext = ciphertext.to_plaintext(
             ciphersuite,
             backend,
@@ -122,7 +113,9 @@ impl DecryptedMessage {
     // - Confirmation tag must be present for Commit messages
     // - Membership tag must be present for member messages, if the original incoming message was not an MlsCiphertext
     // - Ensures application messages were originally MlsCiphertext messages
-    fn from_plaintext(plaintext: VerifiableMlsAuthContent) -> Result<Self, ValidationError> {
+    pub(crate) fn from_plaintext(
+        plaintext: VerifiableMlsAuthContent,
+    ) -> Result<Self, ValidationError> {
         // ValSem009
         if plaintext.content_type() == ContentType::Commit && plaintext.confirmation_tag().is_none()
         {
@@ -141,6 +134,9 @@ impl DecryptedMessage {
     }
 
     /// Gets the correct credential from the message depending on the sender type.
+    /// For messages from past epochs, `old_leaves` has to contain the members
+    /// of the message's epoch. The credentials of external senders are taken
+    /// from `external_senders`.
     /// Checks the following semantic validation:
     ///  - ValSem112
     ///  - ValSem245
@@ -150,42 +146,33 @@ impl DecryptedMessage {
         &self,
         treesync: &TreeSync,
         old_leaves: &[Member],
+        external_senders: &[ExternalSender],
     ) -> Result<Credential, ValidationError> {
         let sender = self.sender();
         match sender {
             Sender::Member(leaf_index) => {
+                // Messages from past epochs are attributed using the
+                // membership of that epoch, since the sender's leaf might
+                // have been removed or replaced since.
+                if !old_leaves.is_empty() {
+                    return old_leaves
+                        .iter()
+                        .find(|old_member| *leaf_index == old_member.index)
+                        .map(|old_member| old_member.credential().clone())
+                        .ok_or(ValidationError::UnknownSender);
+                }
                 match treesync
                     .leaf(*leaf_index)
                     .map_err(|_| ValidationError::UnknownMember)?
                 {
                     Some(sender_leaf) => Ok(sender_leaf.credential().clone()),
-                    None => {
-                        // This might not actually be an error but the sender's
-                        // key package changed. Let's check old leaves we still
-                        // have around.
-                        // TODO: As part of #819 looking up old leaves changes.
-                        //       Just checking the index is probably not enough.
-                        //       Revisit when the transition is further along
-                        //       and we have better test cases.
-                        if let Some(Member { index, .. }) = old_leaves
-                            .iter()
-                            .find(|&old_member| *leaf_index == old_member.index)
-                        {
-                            match treesync
-                                .leaf(*index)
-                                .map_err(|_| ValidationError::UnknownMember)?
-                            {
-                                Some(node) => Ok(node.credential().clone()),
-                                None => Err(ValidationError::UnknownMember),
-                            }
-                        } else {
-                            Err(ValidationError::UnknownMember)
-                        }
-                    }
+                    None => Err(ValidationError::UnknownMember),
                 }
             }
-            // External senders are not supported yet #106/#151.
-            Sender::External(_) => unimplemented!(),
+            Sender::External(sender_index) => external_senders
+                .get(*sender_index as usize)
+                .map(|external_sender| external_sender.credential().clone())
+                .ok_or(ValidationError::UnauthorizedExternalSender),
             Sender::NewMemberCommit => {
                 // only external commits can have a sender type `NewMemberCommit`
                 match self.plaintext().content() {
@@ -257,8 +244,6 @@ pub(crate) enum UnverifiedContextMessage {
     /// Unverified message from either a `NewMemberProposal` or a `NewMemberCommit`
     NewMember(UnverifiedNewMemberMessage),
     /// Unverified message from an external sender
-    /// TODO: #106
-    #[allow(dead_code)]
     External(UnverifiedExternalMessage),
 }
 
@@ -278,8 +263,14 @@ impl UnverifiedContextMessage {
                         .ok_or_else(|| LibraryError::custom("Expected credential"))?,
                 }))
             }
-            // TODO #151/#106: We don't support external senders yet
-            Sender::External(_) => unimplemented!(),
+            Sender::External(_) => Ok(UnverifiedContextMessage::External(
+                UnverifiedExternalMessage {
+                    plaintext,
+                    // If the message type is `External` it always contains credentials
+                    credential: credential_option
+                        .ok_or_else(|| LibraryError::custom("Expected credential"))?,
+                },
+            )),
             Sender::NewMemberProposal | Sender::NewMemberCommit => {
                 Ok(UnverifiedContextMessage::NewMember(
                     UnverifiedNewMemberMessage {
@@ -359,10 +350,42 @@ impl UnverifiedNewMemberMessage {
     }
 }
 
-// TODO #151/#106: We don't support external senders yet
 /// Part of [UnverifiedContextMessage].
 pub(crate) struct UnverifiedExternalMessage {
-    _plaintext: VerifiableMlsAuthContent,
+    plaintext: VerifiableMlsAuthContent,
+    credential: Credential,
+}
+
+impl UnverifiedExternalMessage {
+    /// Verifies the signature of an [UnverifiedExternalMessage] against the
+    /// signature key of the external sender and returns a
+    /// [VerifiedExternalMessage] if the verification is successful.
+    /// This function implements the following checks:
+    /// - ValSem010
+    pub(crate) fn into_verified(
+        self,
+        backend: &impl OpenMlsCryptoProvider,
+        signature_key: &SignaturePublicKey,
+    ) -> Result<VerifiedExternalMessage, ValidationError> {
+        let signature_public_key = signature_key
+            .clone()
+            .into_signature_public_key_enriched(self.credential.signature_scheme());
+        // ValSem010
+        self.plaintext
+            .verify_with_key(backend, &signature_public_key)
+            .map(|plaintext| VerifiedExternalMessage { plaintext })
+            .map_err(|_| ValidationError::InvalidSignature)
+    }
+
+    /// Returns the sender.
+    pub(crate) fn sender(&self) -> &Sender {
+        self.plaintext.sender()
+    }
+
+    /// Returns the credential.
+    pub(crate) fn credential(&self) -> &Credential {
+        &self.credential
+    }
 }
 
 /// Member message, where all semantic checks on the framing have been successfully performed.
@@ -379,7 +402,6 @@ impl VerifiedMemberMessage {
 }
 
 /// External message, where all semantic checks on the framing have been successfully performed.
-/// Note: External messages are not fully supported yet #106
 pub(crate) struct VerifiedExternalMessage {
     plaintext: MlsAuthContent,
 }
@@ -21,6 +21,10 @@ pub(crate) struct ApplyProposalsValues {
     pub(crate) invitation_list: Vec<(LeafIndex, AddProposal)>,
     pub(crate) presharedkeys: Vec<PreSharedKeyId>,
     pub(crate) external_init_secret_option: Option<InitSecret>,
+    /// The group context extensions of the next epoch if a
+    /// `GroupContextExtensions` proposal was committed, sorted by
+    /// [`ExtensionType`].
+    pub(crate) extensions: Option<Vec<Extension>>,
 }
 
 impl ApplyProposalsValues {
@@ -163,6 +167,23 @@ impl CoreGroup {
             })
             .collect();
 
+        // Process the group context extensions proposal. The extensions are
+        // sorted so that the serialized group context does not depend on the
+        // order chosen by the committer.
+        let extensions = proposal_queue
+            .filtered_by_type(ProposalType::GroupContextExtensions)
+            .find_map(|queued_proposal| {
+                if let Proposal::GroupContextExtensions(extensions_proposal) =
+                    queued_proposal.proposal()
+                {
+                    let mut extensions = extensions_proposal.extensions().to_vec();
+                    extensions.sort_by_key(|extension| extension.extension_type());
+                    Some(extensions)
+                } else {
+                    None
+                }
+            });
+
         let proposals_require_path = proposal_queue
             .queued_proposals()
             .any(|p| p.proposal().is_path_required());
@@ -182,6 +203,7 @@ impl CoreGroup {
             invitation_list,
             presharedkeys,
             external_init_secret_option,
+            extensions,
         })
     }
 }
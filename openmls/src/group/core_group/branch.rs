@@ -0,0 +1,184 @@
+//! Branching a new group off an existing one, see [`CoreGroup::branch()`].
+
+use openmls_traits::{key_store::OpenMlsKeyStore, OpenMlsCryptoProvider};
+use tls_codec::Serialize;
+
+use crate::{
+    credentials::CredentialBundle,
+    error::LibraryError,
+    framing::{FramingParameters, WireFormat},
+    group::{
+        errors::{BranchError, WelcomeError},
+        GroupId,
+    },
+    key_packages::{KeyPackage, KeyPackageBundle},
+    messages::{
+        proposals::{AddProposal, PreSharedKeyProposal, Proposal},
+        Welcome,
+    },
+    schedule::psk::{PreSharedKeyId, Psk, PskBundle, ResumptionPskUsage},
+    treesync::node::Node,
+};
+
+use super::{
+    create_commit_params::CreateCommitParams, proposals::ProposalStore, CoreGroup, CoreGroupConfig,
+};
+
+impl CoreGroup {
+    /// Create a new group with the ID `new_group_id` that branches off this
+    /// group, and add the given `members` to it. The creator is the first
+    /// member of the new group, with a fresh key package for
+    /// `credential_bundle`.
+    ///
+    /// The resumption PSK of the current epoch of this group (a
+    /// [`ResumptionPsk`](crate::schedule::psk::ResumptionPsk) with usage [`ResumptionPskUsage::Branch`]) is
+    /// injected into the first epoch of the new group and into the epoch the
+    /// `members` join in. Members of this group join the branch with
+    /// [`CoreGroup::new_from_branch_welcome()`], which only succeeds if they
+    /// share the current epoch of this group. This group is not modified.
+    pub(crate) fn branch(
+        &self,
+        new_group_id: GroupId,
+        members: &[KeyPackage],
+        credential_bundle: &CredentialBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(CoreGroup, Welcome), BranchError> {
+        if members.is_empty() {
+            return Err(BranchError::NoMembers);
+        }
+
+        let psk_id = self.resumption_psk_id(ResumptionPskUsage::Branch, backend)?;
+
+        // The key schedule reads the PSK from the key store. It is only
+        // needed while the branch is created.
+        let psk_key = self.store_resumption_psk(&psk_id, backend)?;
+        let result = self.create_branch(new_group_id, members, psk_id, credential_bundle, backend);
+        backend
+            .key_store()
+            .delete(&psk_key)
+            .map_err(|_| BranchError::KeyStoreError)?;
+        result
+    }
+
+    fn create_branch(
+        &self,
+        new_group_id: GroupId,
+        members: &[KeyPackage],
+        psk_id: PreSharedKeyId,
+        credential_bundle: &CredentialBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(CoreGroup, Welcome), BranchError> {
+        let key_package_bundle =
+            KeyPackageBundle::new(&[self.ciphersuite()], credential_bundle, backend, vec![])?;
+        let config = CoreGroupConfig {
+            add_ratchet_tree_extension: self.use_ratchet_tree_extension,
+            ..Default::default()
+        };
+        let mut group = CoreGroup::builder(new_group_id, key_package_bundle)
+            .with_config(config)
+            .with_psk(vec![psk_id.clone()])
+            .build(credential_bundle, backend)?;
+
+        let mut inline_proposals: Vec<Proposal> = members
+            .iter()
+            .map(|key_package| {
+                Proposal::Add(AddProposal {
+                    key_package: key_package.clone(),
+                })
+            })
+            .collect();
+        inline_proposals.push(Proposal::PreSharedKey(PreSharedKeyProposal::new(psk_id)));
+        let proposal_store = ProposalStore::default();
+        let params = CreateCommitParams::builder()
+            .framing_parameters(FramingParameters::new(&[], WireFormat::MlsPlaintext))
+            .credential_bundle(credential_bundle)
+            .proposal_store(&proposal_store)
+            .inline_proposals(inline_proposals)
+            .build();
+        let create_commit_result = group.create_commit(params, backend)?;
+        let welcome = create_commit_result
+            .welcome_option
+            .ok_or_else(|| LibraryError::custom("Adding members didn't create a Welcome"))?;
+        group.merge_commit(create_commit_result.staged_commit);
+
+        Ok((group, welcome))
+    }
+
+    /// Join a group that branches off the `parent` group from a [`Welcome`]
+    /// created with [`CoreGroup::branch()`]. The resumption PSK of the
+    /// current epoch of `parent` is used for the branch PSK listed in the
+    /// [`Welcome`], so joining fails if the creator of the branch was in
+    /// another epoch of `parent`.
+    ///
+    /// Returns an error ([`WelcomeError::NotABranch`]) if the [`Welcome`]
+    /// doesn't list a branch PSK of `parent`.
+    pub(crate) fn new_from_branch_welcome(
+        welcome: Welcome,
+        parent: &CoreGroup,
+        nodes_option: Option<Vec<Option<Node>>>,
+        key_package_bundle: KeyPackageBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<Self, WelcomeError> {
+        let group_secrets = Self::decrypt_group_secrets(&welcome, &key_package_bundle, backend)?;
+        let branch_psk_ids: Vec<&PreSharedKeyId> = group_secrets
+            .psks
+            .iter()
+            .filter(|psk_id| match psk_id.psk() {
+                Psk::Resumption(resumption_psk) => {
+                    resumption_psk.usage() == ResumptionPskUsage::Branch
+                        && resumption_psk.psk_group_id() == parent.group_id()
+                }
+                Psk::External(_) => false,
+            })
+            .collect();
+        if branch_psk_ids.is_empty() {
+            return Err(WelcomeError::NotABranch);
+        }
+
+        let mut psk_keys = Vec::new();
+        for psk_id in branch_psk_ids {
+            let psk_key = parent
+                .store_resumption_psk(psk_id, backend)
+                .map_err(|e| match e {
+                    BranchError::LibraryError(e) => e.into(),
+                    _ => WelcomeError::PskNotFound,
+                })?;
+            psk_keys.push(psk_key);
+        }
+        let result = Self::new_from_welcome(welcome, nodes_option, key_package_bundle, backend);
+        for psk_key in psk_keys {
+            backend
+                .key_store()
+                .delete(&psk_key)
+                .map_err(|_| WelcomeError::KeyStoreDeletionError)?;
+        }
+        result
+    }
+
+    /// Store the resumption PSK of the current epoch in the key store under
+    /// the given `psk_id`, which has to reference the current epoch of this
+    /// group. Returns the key under which the PSK was stored.
+    fn store_resumption_psk(
+        &self,
+        psk_id: &PreSharedKeyId,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<Vec<u8>, BranchError> {
+        match psk_id.psk() {
+            Psk::Resumption(resumption_psk)
+                if resumption_psk.psk_group_id() == self.group_id()
+                    && resumption_psk.psk_epoch() == self.context().epoch() => {}
+            // The secrets of other epochs are not available.
+            _ => return Err(BranchError::KeyStoreError),
+        }
+        let psk_key = psk_id
+            .tls_serialize_detached()
+            .map_err(LibraryError::missing_bound_check)?;
+        let psk_bundle = PskBundle::new(self.resumption_psk_secret().secret().clone())
+            .map_err(LibraryError::unexpected_crypto_error)?;
+        backend
+            .key_store()
+            .store(&psk_key, &psk_bundle)
+            .map_err(|_| BranchError::KeyStoreError)?;
+        Ok(psk_key)
+    }
+}
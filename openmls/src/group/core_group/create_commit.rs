@@ -13,7 +13,7 @@ use crate::{
 
 use super::{
     create_commit_params::{CommitType, CreateCommitParams},
-    proposals::ProposalQueue,
+    proposals::{ProposalQueue, ProposalStore},
     staged_commit::{MemberStagedCommitState, StagedCommit, StagedCommitState},
 };
 
@@ -27,11 +27,51 @@ struct PathProcessingResult {
 }
 
 impl CoreGroup {
+    /// Creates an update-only commit, i.e. a commit that covers no proposals
+    /// but carries a fresh path. Such a commit leaves the membership of the
+    /// group unchanged, but rekeys the own leaf and injects a new
+    /// `CommitSecret` into the key schedule. It can be sent periodically to
+    /// keep up forward secrecy and post-compromise security in a group that
+    /// otherwise doesn't change.
+    ///
+    /// Proposals that are pending in the group are not covered by this
+    /// commit. The commit is framed according to `framing_parameters`; it's up
+    /// to the caller to encrypt it if required. As with
+    /// [`CoreGroup::create_commit()`], the [`StagedCommit`] in the result has
+    /// to be merged with [`CoreGroup::merge_commit()`].
+    pub(crate) fn rekey(
+        &self,
+        framing_parameters: FramingParameters,
+        credential_bundle: &CredentialBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<CreateCommitResult, CreateCommitError> {
+        let proposal_store = ProposalStore::new();
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(credential_bundle)
+            .proposal_store(&proposal_store)
+            .force_self_update(true)
+            .build();
+        self.create_commit(params, backend)
+    }
+
+    /// Creates a commit covering the proposals in `params`. The commit is not
+    /// applied to the group; the [`StagedCommit`] in the result has to be
+    /// merged with [`CoreGroup::merge_commit()`].
+    ///
+    /// Note that the `CoreGroup` doesn't keep track of commits that were
+    /// created but not yet merged. Preventing a second commit in the same
+    /// epoch is the job of the [`MlsGroup`](crate::group::MlsGroup), which
+    /// holds the own commit in its `PendingCommit` state until it is merged or
+    /// cleared and rejects further proposals and commits in the meantime.
     pub(crate) fn create_commit(
         &self,
         params: CreateCommitParams,
         backend: &impl OpenMlsCryptoProvider,
     ) -> Result<CreateCommitResult, CreateCommitError> {
+        if self.terminated {
+            return Err(CreateCommitError::GroupTerminated);
+        }
         let ciphersuite = self.ciphersuite();
 
         // If this is an external commit, we don't have an `own_leaf_index` set
@@ -94,6 +134,21 @@ impl CoreGroup {
             diff.set_own_index(own_leaf_index);
         }
 
+        // Make sure all proposers are still members of the group.
+        self.validate_proposers(&proposal_queue)?;
+
+        // A ReInit proposal has to be committed on its own.
+        self.validate_reinit_proposals(&proposal_queue)?;
+
+        // Make sure we don't create an external commit that the members of
+        // the group would reject.
+        if params.commit_type() == CommitType::External {
+            // ValSem240
+            // ValSem241
+            // ValSem242
+            self.validate_external_commit_proposals(&proposal_queue, None)?;
+        }
+
         // Validate the proposals by doing the following checks:
 
         // ValSem100
@@ -103,10 +158,12 @@ impl CoreGroup {
         // ValSem104
         // ValSem105
         // ValSem106
-        self.validate_add_proposals(&proposal_queue)?;
+        self.validate_add_proposals(&proposal_queue, params.clock())?;
         // ValSem107
         // ValSem108
-        self.validate_remove_proposals(&proposal_queue)?;
+        // Remove proposals require a path, so a member commit that covers one
+        // always has a path.
+        self.validate_remove_proposals(&proposal_queue, sender.as_member(), true)?;
         // Validate update proposals for member commits
         if let Sender::Member(sender_index) = &sender {
             // ValSem109
@@ -116,6 +173,11 @@ impl CoreGroup {
             self.validate_update_proposals(&proposal_queue, *sender_index)?;
         }
 
+        // Collect the signature key and capability changes of Update proposals
+        // before the tree is modified. The path leaf is added once it's known.
+        let mut credential_changes = self.credential_changes(&proposal_queue, None);
+        let mut capability_changes = self.capability_changes(&proposal_queue, None);
+
         // Apply proposals to tree
         let apply_proposals_values = self
             .apply_proposals(&mut diff, backend, &proposal_queue, &[])
@@ -213,6 +275,43 @@ impl CoreGroup {
             .as_ref()
             .map(|update| update.leaf_node().clone());
 
+        // Now that the path leaf is known, check the inline Remove proposal of
+        // a "resync" external commit.
+        if params.commit_type() == CommitType::External {
+            // ValSem243
+            self.validate_external_commit_proposals(
+                &proposal_queue,
+                commit_update_leaf_node.as_ref(),
+            )?;
+        }
+
+        // When joining through an external commit, we are the new member.
+        let new_member_leaf_node = match params.commit_type() {
+            CommitType::External => commit_update_leaf_node
+                .as_ref()
+                .map(|leaf_node| (own_leaf_index, leaf_node)),
+            CommitType::Member => None,
+        };
+        let membership_delta = self.membership_delta(
+            &proposal_queue,
+            &apply_proposals_values.invitation_list,
+            new_member_leaf_node,
+        );
+
+        // The committer might have changed their own credential in the path.
+        if params.commit_type() == CommitType::Member {
+            if let Some(leaf_node) = commit_update_leaf_node.as_ref() {
+                credential_changes.extend(self.credential_changes(
+                    &ProposalQueue::default(),
+                    Some((own_leaf_index, leaf_node)),
+                ));
+                capability_changes.extend(self.capability_changes(
+                    &ProposalQueue::default(),
+                    Some((own_leaf_index, leaf_node)),
+                ));
+            }
+        }
+
         // Create commit message
         let commit = Commit {
             proposals: proposal_reference_list,
@@ -223,9 +322,16 @@ impl CoreGroup {
         let mut provisional_epoch = self.group_context.epoch();
         provisional_epoch.increment();
 
+        // External commits are always sent as plaintext. For member commits,
+        // the group's default handshake wire format takes precedence.
+        let framing_parameters = match params.commit_type() {
+            CommitType::External => *params.framing_parameters(),
+            CommitType::Member => self.handshake_framing_parameters(*params.framing_parameters()),
+        };
+
         // Build MlsAuthContent
         let mut commit = MlsAuthContent::commit(
-            *params.framing_parameters(),
+            framing_parameters,
             sender,
             commit,
             params.credential_bundle(),
@@ -254,9 +360,18 @@ impl CoreGroup {
             provisional_epoch,
             tree_hash.clone(),
             confirmed_transcript_hash.clone(),
-            self.group_context.extensions(),
+            apply_proposals_values
+                .extensions
+                .as_deref()
+                .unwrap_or_else(|| self.group_context.extensions()),
         );
 
+        // Only a ReInit creates a group with a new group ID, so a commit must
+        // never change it.
+        if provisional_group_context.group_id() != self.group_context.group_id() {
+            return Err(LibraryError::custom("The group ID changed in a commit").into());
+        }
+
         let joiner_secret = JoinerSecret::new(
             backend,
             path_processing_result.commit_secret,
@@ -305,10 +420,22 @@ impl CoreGroup {
         // Set the confirmation tag
         commit.set_confirmation_tag(confirmation_tag.clone());
 
-        // Check if new members were added and, if so, create welcome messages
+        // Check if new members were added and, if so, create a welcome message.
+        // All new members share the same welcome key and nonce, so there can
+        // only be a single encrypted `GroupInfo`. It carries the ratchet tree
+        // if the group always embeds it or if any of the new members needs
+        // it. Without a `needs_ratchet_tree` closure, the group's
+        // `use_ratchet_tree_extension` setting decides.
         let welcome_option = if !plaintext_secrets.is_empty() {
+            let with_ratchet_tree = self.always_embed_ratchet_tree
+                || match params.needs_ratchet_tree() {
+                    Some(needs_ratchet_tree) => plaintext_secrets
+                        .iter()
+                        .any(|pts| needs_ratchet_tree(pts.new_leaf_index())),
+                    None => self.use_ratchet_tree_extension,
+                };
             // Create the ratchet tree extension if necessary
-            let other_extensions: Vec<Extension> = if self.use_ratchet_tree_extension {
+            let other_extensions: Vec<Extension> = if with_ratchet_tree {
                 vec![Extension::RatchetTree(RatchetTreeExtension::new(
                     diff.export_nodes()?,
                 ))]
@@ -323,7 +450,7 @@ impl CoreGroup {
                     provisional_group_context.epoch(),
                     tree_hash,
                     confirmed_transcript_hash.clone(),
-                    self.group_context_extensions(),
+                    provisional_group_context.extensions(),
                 );
 
                 GroupInfoTBS::new(
@@ -392,6 +519,9 @@ impl CoreGroup {
             proposal_queue,
             StagedCommitState::GroupMember(Box::new(staged_commit_state)),
             commit_update_leaf_node,
+            credential_changes,
+            capability_changes,
+            membership_delta,
         );
 
         Ok(CreateCommitResult {
@@ -401,6 +531,51 @@ impl CoreGroup {
         })
     }
 
+    /// Create a commit that replaces the stale leaf of the member at
+    /// `member` with the given fresh `key_package`, e.g. after the member lost
+    /// their local state. The commit removes the old leaf and adds the new key
+    /// package in one step, so the returned [`CreateCommitResult`] contains a
+    /// [`Welcome`] for the recovered member.
+    ///
+    /// Returns an error if `member` is not in the tree, if it is the own leaf,
+    /// or if the identity of the key package doesn't match the one of the
+    /// member's leaf.
+    pub(crate) fn export_welcome_for_existing_member(
+        &self,
+        framing_parameters: FramingParameters,
+        credential_bundle: &CredentialBundle,
+        proposal_store: &ProposalStore,
+        member: u32,
+        key_package: KeyPackage,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<CreateCommitResult, CreateCommitError> {
+        if member == self.own_leaf_index() {
+            return Err(CreateCommitError::CannotRemoveSelf);
+        }
+        let stale_leaf = self
+            .treesync()
+            .leaf(member)
+            .map_err(|_| CreateCommitError::UnknownMember)?
+            .ok_or(CreateCommitError::UnknownMember)?;
+        // Same as for the resync flavour of external commits, the new leaf
+        // has to belong to the same client.
+        if stale_leaf.credential().identity() != key_package.credential().identity() {
+            return Err(CreateCommitError::RecoveryIdentityMismatch);
+        }
+
+        let inline_proposals = vec![
+            Proposal::Remove(RemoveProposal { removed: member }),
+            Proposal::Add(AddProposal { key_package }),
+        ];
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(credential_bundle)
+            .proposal_store(proposal_store)
+            .inline_proposals(inline_proposals)
+            .build();
+        self.create_commit(params, backend)
+    }
+
     /// Returns the leftmost free leaf index.
     ///
     /// For External Commits of the "resync" type, this returns the index
@@ -1,6 +1,10 @@
 //! Builder for [CreateCommitParams] that is used in [CoreGroup::create_commit()]
 
 use super::{proposals::ProposalStore, *};
+use crate::{
+    binary_tree::LeafIndex,
+    extensions::{Clock, SystemClock},
+};
 
 /// Can be used to denote the type of a commit.
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -16,6 +20,8 @@ pub(crate) struct CreateCommitParams<'a> {
     inline_proposals: Vec<Proposal>,           // Optional
     force_self_update: bool,                   // Optional
     commit_type: CommitType,                   // Optional (default is `Member`)
+    needs_ratchet_tree: Option<&'a dyn Fn(LeafIndex) -> bool>, // Optional
+    clock: Option<&'a dyn Clock>,              // Optional (default is `SystemClock`)
 }
 
 pub(crate) struct TempBuilderCCPM0 {}
@@ -67,6 +73,8 @@ impl<'a> TempBuilderCCPM2<'a> {
                 inline_proposals: vec![],
                 force_self_update: true,
                 commit_type: CommitType::Member,
+                needs_ratchet_tree: None,
+                clock: None,
             },
         }
     }
@@ -77,7 +85,6 @@ impl<'a> CreateCommitParamsBuilder<'a> {
         self.ccp.inline_proposals = inline_proposals;
         self
     }
-    #[cfg(test)]
     pub(crate) fn force_self_update(mut self, force_self_update: bool) -> Self {
         self.ccp.force_self_update = force_self_update;
         self
@@ -86,6 +93,24 @@ impl<'a> CreateCommitParamsBuilder<'a> {
         self.ccp.commit_type = commit_type;
         self
     }
+    /// Decide for each new member, identified by its leaf index, whether it
+    /// needs the ratchet tree. All new members share one `GroupInfo` in the
+    /// `Welcome`, which carries the ratchet tree if any of them needs it. This
+    /// overrides the group's `use_ratchet_tree_extension` setting, but is
+    /// ignored if the group is configured to always embed the ratchet tree.
+    pub(crate) fn needs_ratchet_tree(
+        mut self,
+        needs_ratchet_tree: &'a dyn Fn(LeafIndex) -> bool,
+    ) -> Self {
+        self.ccp.needs_ratchet_tree = Some(needs_ratchet_tree);
+        self
+    }
+    /// The [`Clock`] against which the lifetimes of added key packages are
+    /// checked. The system time is used if no clock is given.
+    pub(crate) fn clock(mut self, clock: &'a dyn Clock) -> Self {
+        self.ccp.clock = Some(clock);
+        self
+    }
     pub(crate) fn build(self) -> CreateCommitParams<'a> {
         self.ccp
     }
@@ -113,4 +138,10 @@ impl<'a> CreateCommitParams<'a> {
     pub(crate) fn commit_type(&self) -> CommitType {
         self.commit_type
     }
+    pub(crate) fn needs_ratchet_tree(&self) -> Option<&dyn Fn(LeafIndex) -> bool> {
+        self.needs_ratchet_tree
+    }
+    pub(crate) fn clock(&self) -> &dyn Clock {
+        self.clock.unwrap_or(&SystemClock)
+    }
 }
@@ -0,0 +1,118 @@
+//! # Epoch archive
+//!
+//! An [`EpochArchive`] holds the message secrets of a single epoch of a group
+//! together with the members of that epoch. It can decrypt the application
+//! messages sent in that epoch without the rest of the group state, which
+//! limits the exposure of secrets to that one epoch.
+
+use crate::{
+    ciphersuite::signable::Verifiable,
+    group::errors::ValidationError,
+    tree::{index::SecretTreeLeafIndex, sender_ratchet::SenderRatchetConfiguration},
+};
+
+use super::*;
+
+/// The message secrets and members of a single epoch of a group. See
+/// [`MlsGroup::archive_epoch()`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EpochArchive {
+    ciphersuite: Ciphersuite,
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    message_secrets: MessageSecrets,
+    members: Vec<Member>,
+}
+
+impl EpochArchive {
+    /// Returns the group ID of the archived epoch.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the archived epoch.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Decrypt an application message sent in the archived epoch and verify
+    /// its signature against the sender's credential in that epoch.
+    ///
+    /// Returns an error if the message is not an encrypted message of the
+    /// archived group and epoch, or if it isn't an application message.
+    pub fn decrypt(
+        &mut self,
+        message: MlsMessageIn,
+        sender_ratchet_configuration: &SenderRatchetConfiguration,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<ApplicationMessage, ValidationError> {
+        let ciphertext = match message.mls_message.body {
+            MlsMessageBody::Ciphertext(ciphertext) => ciphertext,
+            MlsMessageBody::Plaintext(_) => return Err(ValidationError::WrongWireFormat),
+        };
+        if ciphertext.group_id() != &self.group_id {
+            return Err(ValidationError::WrongGroupId);
+        }
+        if ciphertext.epoch() != self.epoch {
+            return Err(ValidationError::WrongEpoch);
+        }
+
+        let sender_data =
+            ciphertext.sender_data(&self.message_secrets, backend, self.ciphersuite)?;
+        let sender_index = sender_data.leaf_index;
+        let plaintext = ciphertext.to_plaintext(
+            self.ciphersuite,
+            backend,
+            &mut self.message_secrets,
+            SecretTreeLeafIndex(sender_index),
+            sender_ratchet_configuration,
+            sender_data,
+        )?;
+        if plaintext.content_type() != ContentType::Application {
+            return Err(ValidationError::NotAnApplicationMessage);
+        }
+
+        let credential = self
+            .members
+            .iter()
+            .find(|member| member.index == sender_index)
+            .ok_or(ValidationError::UnknownSender)?
+            .credential()?;
+        let plaintext: MlsAuthContent = plaintext
+            .verify(backend, &credential)
+            .map_err(|_| ValidationError::InvalidSignature)?;
+        match plaintext.content() {
+            MlsContentBody::Application(application_message) => Ok(ApplicationMessage::new(
+                application_message.as_slice().to_owned(),
+            )),
+            _ => Err(LibraryError::custom("Expected an application message").into()),
+        }
+    }
+}
+
+impl CoreGroup {
+    /// Copy the message secrets and members of the given `epoch` into an
+    /// [`EpochArchive`]. This works for the current epoch and for the past
+    /// epochs that are still retained in the message secrets store.
+    ///
+    /// Returns `None` if the secrets of the epoch are not available.
+    pub(crate) fn archive_epoch(&self, epoch: GroupEpoch) -> Option<EpochArchive> {
+        let (message_secrets, members) = if epoch == self.context().epoch() {
+            (
+                self.message_secrets_store.message_secrets(),
+                self.treesync().full_leave_members().collect(),
+            )
+        } else {
+            let message_secrets = self.message_secrets_store.secrets_for_epoch(epoch)?;
+            let members = self.message_secrets_store.leaves_for_epoch(epoch).to_vec();
+            (message_secrets, members)
+        };
+        Some(EpochArchive {
+            ciphersuite: self.ciphersuite(),
+            group_id: self.group_id().clone(),
+            epoch,
+            message_secrets: message_secrets.duplicate(),
+            members,
+        })
+    }
+}
@@ -7,12 +7,14 @@
 
 // Private
 mod apply_proposals;
+mod branch;
 mod new_from_welcome;
 mod validation;
 
 // Crate
 pub(crate) mod create_commit;
 pub(crate) mod create_commit_params;
+pub(crate) mod epoch_archive;
 pub(crate) mod new_from_external_init;
 pub(crate) mod past_secrets;
 pub(crate) mod process;
@@ -33,10 +35,10 @@ mod test_past_secrets;
 #[cfg(test)]
 mod test_proposals;
 
-#[cfg(test)]
-use super::errors::CreateGroupContextExtProposalError;
+use super::errors::{CreateGroupContextExtProposalError, ExportGroupInfoError};
 
 use crate::{
+    binary_tree::LeafIndex,
     ciphersuite::{signable::Signable, HpkePublicKey},
     credentials::*,
     error::LibraryError,
@@ -46,26 +48,34 @@ use crate::{
     key_packages::*,
     messages::VerifiableGroupInfo,
     messages::{proposals::*, *},
-    schedule::{message_secrets::*, psk::*, *},
+    schedule::{errors::PskError, message_secrets::*, psk::*, *},
     tree::{secret_tree::SecretTreeError, sender_ratchet::SenderRatchetConfiguration},
     treesync::{node::leaf_node::Capabilities, *},
     versions::ProtocolVersion,
 };
 
+#[cfg(any(feature = "test-utils", test))]
+use self::past_secrets::StoreDebugReport;
 use self::{past_secrets::MessageSecretsStore, staged_commit::StagedCommit};
 use log::{debug, trace};
-use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use openmls_traits::{
+    crypto::OpenMlsCrypto,
+    types::{Ciphersuite, SignatureScheme},
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 #[cfg(test)]
 use std::convert::TryFrom;
-#[cfg(test)]
-use std::io::{Error, Read, Write};
+use std::io::{Chain, Cursor, Error, Read, Write};
+use std::time::Duration;
 use tls_codec::Serialize as TlsSerializeTrait;
+#[cfg(any(feature = "crypto-subtle", test))]
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use super::{
     errors::{
-        CoreGroupBuildError, CreateAddProposalError, ExporterError, ProposalValidationError,
-        ValidationError,
+        CoreGroupBuildError, CreateAddProposalError, DeserializeError, ExporterError,
+        IntegrityError, ProposalValidationError, ValidationError,
     },
     group_context::*,
 };
@@ -88,23 +98,126 @@ pub struct Member {
     pub encryption_key: Vec<u8>,
     /// The member's public signature key.
     pub signature_key: Vec<u8>,
+    capabilities: Capabilities,
+    lifetime: Option<LifetimeExtension>,
+    credential: Credential,
+}
+
+/// The private key material of a client's own leaf in a group, together with
+/// the position of the leaf in the tree.
+///
+/// **These secrets are highly sensitive.** Anyone holding them can decrypt
+/// the path secrets encrypted to the leaf and thereby follow the group until
+/// the leaf is updated. They should only ever be written to protected
+/// storage, e.g. a backup that, together with a cached resumption PSK, allows
+/// the client to recover its membership. The secrets are zeroized on drop.
+#[cfg(any(feature = "crypto-subtle", test))]
+#[cfg_attr(docsrs, doc(cfg(feature = "crypto-subtle")))]
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct OwnLeafSecrets {
+    leaf_index: u32,
+    encryption_private_key: Vec<u8>,
+}
+
+#[cfg(any(feature = "crypto-subtle", test))]
+impl OwnLeafSecrets {
+    /// Returns the index of the own leaf in the ratchet tree.
+    pub fn leaf_index(&self) -> u32 {
+        self.leaf_index
+    }
+
+    /// Returns the raw private HPKE encryption key of the own leaf.
+    pub fn encryption_private_key(&self) -> &[u8] {
+        &self.encryption_private_key
+    }
+}
+
+/// Options for exporting a [`GroupInfo`] with
+/// [`MlsGroup::export_group_info_with_options()`](crate::group::MlsGroup::export_group_info_with_options()).
+///
+/// By default, the `GroupInfo` doesn't contain the ratchet tree, but contains
+/// the `external_pub` extension, which is needed to join the group with an
+/// external commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupInfoExportOptions {
+    with_ratchet_tree: bool,
+    with_external_pub: bool,
+    extensions: Vec<Extension>,
+}
+
+impl Default for GroupInfoExportOptions {
+    fn default() -> Self {
+        Self {
+            with_ratchet_tree: false,
+            with_external_pub: true,
+            extensions: vec![],
+        }
+    }
+}
+
+impl GroupInfoExportOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the ratchet tree extension is included in the
+    /// `GroupInfo`.
+    pub fn with_ratchet_tree(mut self, with_ratchet_tree: bool) -> Self {
+        self.with_ratchet_tree = with_ratchet_tree;
+        self
+    }
+
+    /// Sets whether the `external_pub` extension is included in the
+    /// `GroupInfo`. Without it, the `GroupInfo` can't be used to join the group
+    /// with an external commit.
+    pub fn with_external_pub(mut self, with_external_pub: bool) -> Self {
+        self.with_external_pub = with_external_pub;
+        self
+    }
+
+    /// Sets additional extensions that are included in the `GroupInfo`.
+    pub fn with_extensions(mut self, extensions: Vec<Extension>) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
 impl Member {
-    /// Create new member.
-    pub fn new(
-        index: u32,
-        encryption_key: Vec<u8>,
-        signature_key: Vec<u8>,
-        identity: Vec<u8>,
-    ) -> Self {
+    /// Create the member at the given leaf index from its leaf node. The
+    /// identity and the signature key are taken from the leaf's credential.
+    pub fn new(index: u32, leaf_node: &LeafNode) -> Self {
+        let credential = leaf_node.credential().clone();
         Self {
             index,
-            encryption_key,
-            signature_key,
-            identity,
+            identity: credential.identity().to_vec(),
+            encryption_key: leaf_node.encryption_key().as_slice().to_vec(),
+            signature_key: credential.signature_key().as_slice().to_vec(),
+            capabilities: leaf_node.capabilities().clone(),
+            lifetime: leaf_node.life_time().cloned(),
+            credential,
         }
     }
+
+    /// Get the capabilities advertised in the member's leaf node.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Get the lifetime of the member's leaf node, if present.
+    pub fn lifetime(&self) -> Option<&LifetimeExtension> {
+        self.lifetime.as_ref()
+    }
+
+    /// Get the credential in the member's leaf node.
+    pub fn credential(&self) -> &Credential {
+        &self.credential
+    }
+
+    /// Get the signature scheme of the member's signature key.
+    pub fn signature_scheme(&self) -> SignatureScheme {
+        self.credential.signature_scheme()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -121,6 +234,55 @@ pub(crate) struct CoreGroup {
     use_ratchet_tree_extension: bool,
     // The MLS protocol version used in this group.
     mls_version: ProtocolVersion,
+    // The wire format used for outgoing handshake messages unless the framing
+    // parameters of a call explicitly ask for plaintext.
+    #[serde(default = "default_handshake_wire_format")]
+    default_handshake_wire_format: WireFormat,
+    // The proposal types that may be included in commits of this group as a
+    // matter of local policy. `None` allows all types.
+    #[serde(default)]
+    allowed_proposal_types: Option<HashSet<ProposalType>>,
+    // Set to true if the signatures of proposals committed by reference are
+    // verified again when staging a commit.
+    #[serde(default)]
+    verify_proposal_signatures_in_commit: bool,
+    // Set to true if a confirmation tag mismatch is diagnosed further, which
+    // requires keeping copies of the key schedule inputs of every commit.
+    #[serde(default)]
+    diagnose_confirmation_tag_mismatch: bool,
+    // The minimum time between two commits of other members that are staged
+    // by this group. `None` disables the rate limit.
+    #[serde(default)]
+    min_epoch_interval: Option<Duration>,
+    // The time at which the current epoch started. `None` if it is unknown,
+    // e.g. for state saved by a version that didn't record it.
+    #[serde(default)]
+    epoch_started_at: Option<u64>,
+    // The epoch in which the encryption key of each leaf last changed. Only
+    // changes within the retained-epoch window of the message secrets store
+    // are tracked.
+    #[serde(default)]
+    leaf_key_epochs: HashMap<LeafIndex, GroupEpoch>,
+    // Set to true if every `Welcome` and every exported `GroupInfo` carries
+    // the ratchet tree, regardless of per-call settings.
+    #[serde(default)]
+    always_embed_ratchet_tree: bool,
+    // The maximum length of the authenticated data of application messages.
+    // `None` allows authenticated data of any length.
+    #[serde(default)]
+    max_aad_length: Option<usize>,
+    // The padding strategy for outgoing `MlsCiphertext`s. `None` pads
+    // according to the padding size given when creating a message.
+    #[serde(default)]
+    padding_strategy: Option<PaddingStrategy>,
+    // Set to true once a commit with a ReInit proposal was merged. A
+    // terminated group can't create commits or application messages.
+    #[serde(default)]
+    terminated: bool,
+    // The sender ratchet configuration used to decrypt messages unless a
+    // configuration is given per call.
+    #[serde(default)]
+    sender_ratchet_configuration: SenderRatchetConfiguration,
     /// A [`MessageSecretsStore`] that stores message secrets.
     /// By default this store has the length of 1, i.e. only the [`MessageSecrets`]
     /// of the current epoch is kept.
@@ -138,9 +300,12 @@ pub(crate) struct CoreGroupBuilder {
     config: Option<CoreGroupConfig>,
     psk_ids: Vec<PreSharedKeyId>,
     version: Option<ProtocolVersion>,
+    advertised_versions: Option<Vec<ProtocolVersion>>,
+    advertised_ciphersuites: Option<Vec<Ciphersuite>>,
     required_capabilities: Option<RequiredCapabilitiesExtension>,
     max_past_epochs: usize,
     lifetime: Option<LifetimeExtension>,
+    sender_ratchet_configuration: SenderRatchetConfiguration,
 }
 
 impl CoreGroupBuilder {
@@ -152,10 +317,13 @@ impl CoreGroupBuilder {
             config: None,
             psk_ids: vec![],
             version: None,
+            advertised_versions: None,
+            advertised_ciphersuites: None,
             required_capabilities: None,
             max_past_epochs: 0,
             own_leaf_extensions: vec![],
             lifetime: None,
+            sender_ratchet_configuration: SenderRatchetConfiguration::default(),
         }
     }
     /// Set the [`CoreGroupConfig`] of the [`CoreGroup`].
@@ -163,12 +331,26 @@ impl CoreGroupBuilder {
         self.config = Some(config);
         self
     }
-    /// Set the [`Vec<PreSharedKeyId>`] of the [`CoreGroup`].
-    #[cfg(test)]
+    /// Set the [`Vec<PreSharedKeyId>`] of the [`CoreGroup`]. The secrets of
+    /// the PSKs have to be in the key store when the group is built.
     pub(crate) fn with_psk(mut self, psk_ids: Vec<PreSharedKeyId>) -> Self {
         self.psk_ids = psk_ids;
         self
     }
+    /// Set the protocol versions advertised in the capabilities of the own
+    /// leaf. The set has to contain the version of the group, which is the
+    /// only version advertised by default.
+    pub(crate) fn with_versions(mut self, versions: Vec<ProtocolVersion>) -> Self {
+        self.advertised_versions = Some(versions);
+        self
+    }
+    /// Set the ciphersuites advertised in the capabilities of the own leaf.
+    /// The set has to contain the ciphersuite of the group, which is the only
+    /// ciphersuite advertised by default.
+    pub(crate) fn with_ciphersuites(mut self, ciphersuites: Vec<Ciphersuite>) -> Self {
+        self.advertised_ciphersuites = Some(ciphersuites);
+        self
+    }
     /// Set the [`RequiredCapabilitiesExtension`] of the [`CoreGroup`].
     pub(crate) fn with_required_capabilities(
         mut self,
@@ -182,14 +364,29 @@ impl CoreGroupBuilder {
         self.max_past_epochs = max_past_epochs;
         self
     }
+    /// Set the [`SenderRatchetConfiguration`] the group uses to decrypt
+    /// messages when no configuration is given per call.
+    ///
+    /// Every decryption ratchet retains the keys of up to
+    /// `out_of_order_tolerance` skipped generations, i.e. memory for a large
+    /// window grows with the number of senders and retained epochs, and the
+    /// retained keys weaken forward secrecy within an epoch.
+    pub(crate) fn with_sender_ratchet_configuration(
+        mut self,
+        sender_ratchet_configuration: SenderRatchetConfiguration,
+    ) -> Self {
+        self.sender_ratchet_configuration = sender_ratchet_configuration;
+        self
+    }
     /// Set the [`LifetimeExtension`] for the own leaf in the group.
     pub fn with_lifetime(mut self, lifetime: LifetimeExtension) -> Self {
         self.lifetime = Some(lifetime);
         self
     }
-    /// Set extensions for the own leaf in the group.
-    #[cfg(test)]
-    pub fn with_extensions(mut self, extensions: Vec<Extension>) -> Self {
+    /// Set extensions for the own leaf in the group. The types of the
+    /// extensions have to be advertised in the leaf's capabilities, which
+    /// contain the extension types of the [`RequiredCapabilitiesExtension`].
+    pub(crate) fn with_leaf_node_extensions(mut self, extensions: Vec<Extension>) -> Self {
         self.own_leaf_extensions = extensions;
         self
     }
@@ -212,21 +409,40 @@ impl CoreGroupBuilder {
             .as_ref()
             .map(|re| re.extensions());
         let version = self.version.unwrap_or_default();
+        let advertised_versions = self.advertised_versions.unwrap_or_else(|| vec![version]);
+        if !advertised_versions.contains(&version) {
+            return Err(CoreGroupBuildError::VersionNotAdvertised);
+        }
+        let advertised_ciphersuites = self
+            .advertised_ciphersuites
+            .unwrap_or_else(|| vec![ciphersuite]);
+        if !advertised_ciphersuites.contains(&ciphersuite) {
+            return Err(CoreGroupBuildError::CiphersuiteNotAdvertised);
+        }
 
         debug!("Created group {:x?}", self.group_id);
         trace!(" >>> with {:?}, {:?}", ciphersuite, config);
+        let leaf_capabilities = Capabilities::new(
+            Some(&advertised_versions),
+            Some(&advertised_ciphersuites),
+            capabilities,
+            None,
+            None,
+        );
+        // The leaf has to advertise support for every extension it carries.
+        if self.own_leaf_extensions.iter().any(|extension| {
+            !leaf_capabilities
+                .extensions()
+                .contains(&extension.extension_type())
+        }) {
+            return Err(CoreGroupBuildError::LeafExtensionNotAdvertised);
+        }
         let (tree, commit_secret) = TreeSync::new(
             backend,
             self.key_package_bundle,
             credential_bundle,
             self.lifetime.unwrap_or_default(),
-            Capabilities::new(
-                Some(&[version]),     // TODO: Allow more versions
-                Some(&[ciphersuite]), // TODO: allow more ciphersuites
-                capabilities,
-                None,
-                None,
-            ),
+            leaf_capabilities,
             self.own_leaf_extensions,
         )?;
 
@@ -236,6 +452,9 @@ impl CoreGroupBuilder {
             ExtensionError::UnsupportedExtensionType => {
                 CoreGroupBuildError::UnsupportedExtensionType
             }
+            ExtensionError::InvalidRequiredCapabilities => {
+                CoreGroupBuildError::InvalidRequiredCapabilities
+            }
             _ => LibraryError::custom("Unexpected ExtensionError").into(),
         })?;
         let required_capabilities = &[Extension::RequiredCapabilities(required_capabilities)];
@@ -262,7 +481,11 @@ impl CoreGroupBuilder {
             .map_err(LibraryError::missing_bound_check)?;
 
         // Prepare the PskSecret
-        let psk_secret = PskSecret::new(ciphersuite, backend, &self.psk_ids)?;
+        let psk_secret =
+            PskSecret::new(ciphersuite, backend, &self.psk_ids).map_err(|e| match e {
+                PskError::KeyNotFound => CoreGroupBuildError::UnknownPsk,
+                e => e.into(),
+            })?;
 
         let mut key_schedule = KeySchedule::init(ciphersuite, backend, joiner_secret, psk_secret)?;
         key_schedule
@@ -280,7 +503,7 @@ impl CoreGroupBuilder {
 
         let interim_transcript_hash = vec![];
 
-        Ok(CoreGroup {
+        let group = CoreGroup {
             ciphersuite,
             group_context,
             group_epoch_secrets,
@@ -288,8 +511,27 @@ impl CoreGroupBuilder {
             interim_transcript_hash,
             use_ratchet_tree_extension: config.add_ratchet_tree_extension,
             mls_version: version,
+            default_handshake_wire_format: config.default_handshake_wire_format,
+            allowed_proposal_types: config.allowed_proposal_types,
+            verify_proposal_signatures_in_commit: config.verify_proposal_signatures_in_commit,
+            diagnose_confirmation_tag_mismatch: config.diagnose_confirmation_tag_mismatch,
+            min_epoch_interval: config.min_epoch_interval,
+            epoch_started_at: None,
+            leaf_key_epochs: [(0, GroupEpoch::from(0))].into_iter().collect(),
+            always_embed_ratchet_tree: config.always_embed_ratchet_tree,
+            max_aad_length: config.max_aad_length,
+            padding_strategy: config.padding_strategy,
+            terminated: false,
+            sender_ratchet_configuration: self.sender_ratchet_configuration,
             message_secrets_store,
-        })
+        };
+
+        // Make sure the transcript hash chain uses the group's ciphersuite.
+        if !group.transcript_hash_matches_ciphersuite() {
+            return Err(CoreGroupBuildError::CiphersuiteHashMismatch);
+        }
+
+        Ok(group)
     }
 }
 
@@ -326,7 +568,7 @@ impl CoreGroup {
         };
         let proposal = Proposal::Add(add_proposal);
         MlsAuthContent::member_proposal(
-            framing_parameters,
+            self.handshake_framing_parameters(framing_parameters),
             self.own_leaf_index(),
             proposal,
             credential_bundle,
@@ -352,7 +594,7 @@ impl CoreGroup {
         let update_proposal = UpdateProposal { leaf_node };
         let proposal = Proposal::Update(update_proposal);
         MlsAuthContent::member_proposal(
-            framing_parameters,
+            self.handshake_framing_parameters(framing_parameters),
             self.own_leaf_index(),
             proposal,
             credential_bundle,
@@ -378,7 +620,7 @@ impl CoreGroup {
         let remove_proposal = RemoveProposal { removed };
         let proposal = Proposal::Remove(remove_proposal);
         MlsAuthContent::member_proposal(
-            framing_parameters,
+            self.handshake_framing_parameters(framing_parameters),
             self.own_leaf_index(),
             proposal,
             credential_bundle,
@@ -392,8 +634,9 @@ impl CoreGroup {
     // struct {
     //     PreSharedKeyID psk;
     // } PreSharedKey;
-    // TODO: #751
-    #[cfg(test)]
+    /// Create a `PreSharedKey` proposal. The PSK referenced by `psk` has to be
+    /// present in the key store of every member that processes the commit
+    /// covering this proposal.
     pub(crate) fn create_presharedkey_proposal(
         &self,
         framing_parameters: FramingParameters,
@@ -404,7 +647,7 @@ impl CoreGroup {
         let presharedkey_proposal = PreSharedKeyProposal::new(psk);
         let proposal = Proposal::PreSharedKey(presharedkey_proposal);
         MlsAuthContent::member_proposal(
-            framing_parameters,
+            self.handshake_framing_parameters(framing_parameters),
             self.own_leaf_index(),
             proposal,
             credential_bundle,
@@ -413,8 +656,47 @@ impl CoreGroup {
         )
     }
 
-    /// Create a `GroupContextExtensions` proposal.
-    #[cfg(test)]
+    // 11.1.5. ReInit
+    // struct {
+    //     opaque group_id<V>;
+    //     ProtocolVersion version;
+    //     CipherSuite cipher_suite;
+    //     Extension extensions<V>;
+    // } ReInit;
+    /// Create a `ReInit` proposal that re-initializes the group with the given
+    /// parameters. The commit covering it must not cover other proposals.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn create_reinit_proposal(
+        &self,
+        framing_parameters: FramingParameters,
+        credential_bundle: &CredentialBundle,
+        group_id: GroupId,
+        version: ProtocolVersion,
+        ciphersuite: Ciphersuite,
+        extensions: Vec<Extension>,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<MlsAuthContent, LibraryError> {
+        let reinit_proposal = ReInitProposal::new(group_id, version, ciphersuite, extensions);
+        let proposal = Proposal::ReInit(reinit_proposal);
+        MlsAuthContent::member_proposal(
+            self.handshake_framing_parameters(framing_parameters),
+            self.own_leaf_index(),
+            proposal,
+            credential_bundle,
+            self.context(),
+            backend,
+        )
+    }
+
+    /// Returns `true` if a commit with a ReInit proposal was merged into the
+    /// group.
+    pub(crate) fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Create a `GroupContextExtensions` proposal. If the extensions contain
+    /// a `RequiredCapabilities` extension, all members of the group have to
+    /// support the required capabilities.
     pub(crate) fn create_group_context_ext_proposal(
         &self,
         framing_parameters: FramingParameters,
@@ -443,7 +725,7 @@ impl CoreGroup {
         let proposal = GroupContextExtensionProposal::new(extensions);
         let proposal = Proposal::GroupContextExtensions(proposal);
         MlsAuthContent::member_proposal(
-            framing_parameters,
+            self.handshake_framing_parameters(framing_parameters),
             self.own_leaf_index(),
             proposal,
             credential_bundle,
@@ -462,6 +744,12 @@ impl CoreGroup {
         padding_size: usize,
         backend: &impl OpenMlsCryptoProvider,
     ) -> Result<MlsCiphertext, MessageEncryptionError> {
+        if self.terminated {
+            return Err(MessageEncryptionError::GroupTerminated);
+        }
+        if matches!(self.max_aad_length, Some(max_aad_length) if aad.len() > max_aad_length) {
+            return Err(MessageEncryptionError::AadTooLarge);
+        }
         let mls_plaintext = MlsAuthContent::new_application(
             self.own_leaf_index(),
             aad,
@@ -473,7 +761,8 @@ impl CoreGroup {
         self.encrypt(mls_plaintext, padding_size, backend)
     }
 
-    // Encrypt an MlsPlaintext into an MlsCiphertext
+    // Encrypt an MlsPlaintext into an MlsCiphertext. The group's padding
+    // strategy takes precedence over `padding_size`.
     pub(crate) fn encrypt(
         &mut self,
         mls_plaintext: MlsAuthContent,
@@ -481,25 +770,36 @@ impl CoreGroup {
         backend: &impl OpenMlsCryptoProvider,
     ) -> Result<MlsCiphertext, MessageEncryptionError> {
         log::trace!("{:?}", mls_plaintext.confirmation_tag());
+        let padding_strategy = self
+            .padding_strategy
+            .clone()
+            .unwrap_or_else(|| padding_size.into());
         MlsCiphertext::try_from_plaintext(
             &mls_plaintext,
             self.ciphersuite,
             backend,
             self.message_secrets_store.message_secrets_mut(),
-            padding_size,
+            &padding_strategy,
         )
     }
 
-    /// Decrypt an MlsCiphertext into an MlsPlaintext
+    /// Decrypt an MlsCiphertext into an MlsPlaintext. If no
+    /// `sender_ratchet_configuration` is given, the configuration of the
+    /// group is used (see
+    /// [`CoreGroupBuilder::with_sender_ratchet_configuration()`]).
     #[cfg(any(feature = "test-utils", test))]
-    pub(crate) fn decrypt(
+    pub(crate) fn decrypt<'a>(
         &mut self,
         mls_ciphertext: &MlsCiphertext,
         backend: &impl OpenMlsCryptoProvider,
-        sender_ratchet_configuration: &SenderRatchetConfiguration,
+        sender_ratchet_configuration: impl Into<Option<&'a SenderRatchetConfiguration>>,
     ) -> Result<VerifiableMlsAuthContent, MessageDecryptionError> {
         use crate::tree::index::SecretTreeLeafIndex;
 
+        let sender_ratchet_configuration = sender_ratchet_configuration
+            .into()
+            .cloned()
+            .unwrap_or_else(|| self.sender_ratchet_configuration.clone());
         let ciphersuite = self.ciphersuite();
         let message_secrets = self
             .message_secrets_mut(mls_ciphertext.epoch())
@@ -523,7 +823,7 @@ impl CoreGroup {
             backend,
             message_secrets,
             sender_index,
-            sender_ratchet_configuration,
+            &sender_ratchet_configuration,
             sender_data,
         )
     }
@@ -547,32 +847,106 @@ impl CoreGroup {
             .map_err(LibraryError::unexpected_crypto_error)?)
     }
 
+    /// Exports a key of the length of an AEAD key of the group's ciphersuite
+    /// for the subgroup of members at the given leaf indices. The key is
+    /// bound to the `label`, the current epoch and the sorted set of leaf
+    /// indices. The `label` is prefixed with `"subgroup "`, so that the key
+    /// can't collide with a secret from [`Self::export_secret()`]. The
+    /// members of the subgroup can use the key to encrypt messages to each
+    /// other outside of MLS, e.g. a private fan-out to the admins of a group.
+    ///
+    /// **This is outside of the membership guarantees of MLS.** The key is
+    /// derived from the exporter secret, which every member of the group
+    /// knows. Members outside of the subgroup can derive the same key, so
+    /// the confidentiality towards them relies on them not doing so.
+    ///
+    /// Returns an [`ExporterError::EmptySubgroup`] if `members` is empty and
+    /// an [`ExporterError::UnknownSubgroupMember`] if one of the leaf
+    /// indices is not a member of the group, e.g. because the leaf is blank.
+    pub(crate) fn export_subgroup_key(
+        &self,
+        members: &[u32],
+        label: &str,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<Vec<u8>, ExporterError> {
+        let mut members = members.to_vec();
+        members.sort_unstable();
+        members.dedup();
+        if members.is_empty() {
+            return Err(ExporterError::EmptySubgroup);
+        }
+        if members
+            .iter()
+            .any(|leaf_index| self.treesync().leaf_is_in_tree(*leaf_index).is_err())
+        {
+            return Err(ExporterError::UnknownSubgroupMember);
+        }
+        let mut context = self.context().epoch().as_u64().to_be_bytes().to_vec();
+        for leaf_index in members {
+            context.extend_from_slice(&leaf_index.to_be_bytes());
+        }
+        self.export_secret(
+            backend,
+            &format!("subgroup {}", label),
+            &context,
+            self.ciphersuite().aead_key_length(),
+        )
+    }
+
+    /// Exports a [`GroupInfo`] that contains the `external_pub` extension and,
+    /// if `with_ratchet_tree` is set, the ratchet tree.
     pub(crate) fn export_group_info(
         &self,
         backend: &impl OpenMlsCryptoProvider,
         credential_bundle: &CredentialBundle,
         with_ratchet_tree: bool,
     ) -> Result<GroupInfo, LibraryError> {
-        let extensions = {
-            let ratchet_tree_extension = || {
-                Extension::RatchetTree(RatchetTreeExtension::new(self.treesync().export_nodes()))
-            };
-
-            let external_pub_extension = || {
-                let external_pub = self
-                    .group_epoch_secrets()
-                    .external_secret()
-                    .derive_external_keypair(backend.crypto(), self.ciphersuite)
-                    .public;
-                Extension::ExternalPub(ExternalPubExtension::new(HpkePublicKey::from(external_pub)))
-            };
-
-            if with_ratchet_tree {
-                vec![ratchet_tree_extension(), external_pub_extension()]
-            } else {
-                vec![external_pub_extension()]
+        let options = GroupInfoExportOptions::new().with_ratchet_tree(with_ratchet_tree);
+        self.export_group_info_with_options(backend, credential_bundle, &options)
+            .map_err(|e| match e {
+                ExportGroupInfoError::LibraryError(e) => e,
+                _ => LibraryError::custom("Unexpected error while exporting the group info"),
+            })
+    }
+
+    /// Exports a [`GroupInfo`] with the extensions selected in `options`. The
+    /// ratchet tree is always included if the group is configured to always
+    /// embed it.
+    ///
+    /// Returns an [`ExportGroupInfoError::DuplicateExtension`] if the
+    /// additional extensions contain an extension type more than once or one
+    /// that is already added because of the other options.
+    pub(crate) fn export_group_info_with_options(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        credential_bundle: &CredentialBundle,
+        options: &GroupInfoExportOptions,
+    ) -> Result<GroupInfo, ExportGroupInfoError> {
+        let mut extensions = vec![];
+        if options.with_ratchet_tree || self.always_embed_ratchet_tree {
+            extensions.push(Extension::RatchetTree(RatchetTreeExtension::new(
+                self.treesync().export_nodes(),
+            )));
+        }
+        if options.with_external_pub {
+            let external_pub = self
+                .group_epoch_secrets()
+                .external_secret()
+                .derive_external_keypair(backend.crypto(), self.ciphersuite)
+                .public;
+            extensions.push(Extension::ExternalPub(ExternalPubExtension::new(
+                HpkePublicKey::from(external_pub),
+            )));
+        }
+        for extension in &options.extensions {
+            if extensions
+                .iter()
+                .any(|e| e.extension_type() == extension.extension_type())
+            {
+                return Err(ExportGroupInfoError::DuplicateExtension);
             }
-        };
+            extensions.push(extension.clone());
+        }
 
         // Create to-be-signed group info.
         let group_info_tbs = GroupInfoTBS::new(
@@ -586,30 +960,108 @@ impl CoreGroup {
         );
 
         // Sign to-be-signed group info.
-        group_info_tbs.sign(backend, credential_bundle)
+        group_info_tbs
+            .sign(backend, credential_bundle)
+            .map_err(ExportGroupInfoError::from)
+    }
+
+    /// Returns a [`PublicGroupState`] snapshot of the public parts of the
+    /// group context of the current epoch.
+    pub(crate) fn public_group_state(&self) -> PublicGroupState {
+        PublicGroupState::from_group_context(self.context())
+    }
+
+    /// Compares the public state of the current epoch with the
+    /// [`PublicGroupState`] of another member. See
+    /// [`PublicGroupState::fork_status()`].
+    pub(crate) fn fork_status(&self, other: &PublicGroupState) -> ForkStatus {
+        self.public_group_state().fork_status(other)
     }
 
-    /// Returns the epoch authenticator
+    /// Returns the epoch authenticator. All members in the same epoch of the
+    /// group derive the same epoch authenticator.
     pub(crate) fn epoch_authenticator(&self) -> &EpochAuthenticator {
         self.group_epoch_secrets().epoch_authenticator()
     }
 
-    /// Returns the resumption PSK secret
+    /// Returns the resumption PSK secret of the current epoch.
+    ///
+    /// The secret is shared by all members of the current epoch and lets
+    /// anyone who holds it inject this epoch into the key schedule of another
+    /// group, e.g. to branch or re-initialize the group. It must not leave the
+    /// client.
     pub(crate) fn resumption_psk_secret(&self) -> &ResumptionPskSecret {
         self.group_epoch_secrets().resumption_psk()
     }
 
-    /// Loads the state from persisted state
+    /// Returns a fresh [`PreSharedKeyId`] that refers to the resumption PSK
+    /// of the current epoch with the given `usage`.
+    ///
+    /// The returned ID only identifies the PSK. The secret itself is
+    /// [`Self::resumption_psk_secret()`] and must be stored in the key store
+    /// under the serialized ID before the ID can be used in a commit or a
+    /// Welcome.
+    pub(crate) fn resumption_psk_id(
+        &self,
+        usage: ResumptionPskUsage,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<PreSharedKeyId, LibraryError> {
+        PreSharedKeyId::new(
+            self.ciphersuite(),
+            backend.rand(),
+            Psk::Resumption(ResumptionPsk::new(
+                usage,
+                self.group_id().clone(),
+                self.context().epoch(),
+            )),
+        )
+        .map_err(LibraryError::unexpected_crypto_error)
+    }
+
+    /// Loads the state from state persisted with [`Self::save()`] and checks
+    /// its integrity with [`Self::verify_integrity()`].
+    ///
+    /// Returns an error if the state was persisted in a format version other
+    /// than [`CORE_GROUP_STATE_VERSION`] or [`LEGACY_STATE_VERSION`]
+    /// ([`DeserializeError::UnsupportedVersion`]), if it is malformed
+    /// ([`DeserializeError::MalformedState`]), if it can't be read
+    /// ([`DeserializeError::ReadError`]) or if it is inconsistent
+    /// ([`DeserializeError::IntegrityError`]).
     #[cfg(test)]
-    pub(crate) fn load<R: Read>(reader: R) -> Result<CoreGroup, Error> {
-        serde_json::from_reader(reader).map_err(|e| e.into())
+    pub(crate) fn load<R: Read>(
+        reader: R,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<CoreGroup, DeserializeError> {
+        let (_version, reader) = read_state_version(reader)?;
+        let core_group: CoreGroup = serde_json::from_reader(reader)?;
+        core_group.verify_integrity(backend)?;
+        Ok(core_group)
     }
 
-    /// Persists the state
+    /// Persists the full state, including the ratchet tree and the message
+    /// secrets of past epochs. The state is prefixed with the format version
+    /// [`CORE_GROUP_STATE_VERSION`], so that [`Self::load()`] can detect state
+    /// written in an incompatible format.
     #[cfg(test)]
     pub(crate) fn save<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
-        let serialized_core_group = serde_json::to_string_pretty(self)?;
-        writer.write_all(&serialized_core_group.into_bytes())
+        let serialized_core_group = serde_json::to_vec(self)?;
+        write_state_version(writer)?;
+        writer.write_all(&serialized_core_group)
+    }
+
+    /// Returns `true` if the transcript hashes of the group are consistent
+    /// with the hash algorithm of the group's ciphersuite and `false`
+    /// otherwise. Empty hashes (as in the initial epoch) are considered
+    /// consistent.
+    pub(crate) fn transcript_hash_matches_ciphersuite(&self) -> bool {
+        let hash_length = self.ciphersuite.hash_length();
+        self.group_context.ciphersuite() == self.ciphersuite
+            && [
+                self.group_context.confirmed_transcript_hash(),
+                self.interim_transcript_hash.as_slice(),
+            ]
+            .iter()
+            .all(|hash| hash.is_empty() || hash.len() == hash_length)
     }
 
     /// Returns a reference to the ratchet tree
@@ -617,11 +1069,173 @@ impl CoreGroup {
         &self.tree
     }
 
+    /// Returns the cached tree hash of the ratchet tree.
+    pub(crate) fn tree_hash(&self) -> &[u8] {
+        self.tree.tree_hash()
+    }
+
+    /// Checks that the group state is consistent, e.g. after loading it from
+    /// persisted state that might be corrupted or tampered with.
+    /// [`Self::load()`] calls this before returning the loaded group.
+    ///
+    /// Returns an error if
+    ///  - the recomputed tree hash doesn't match the group context
+    ///    ([`IntegrityError::TreeHashMismatch`]),
+    ///  - the interim transcript hash can't be derived from the confirmed
+    ///    transcript hash and the epoch's confirmation key, or the transcript
+    ///    hashes don't match the epoch and the ciphersuite
+    ///    ([`IntegrityError::TranscriptHashMismatch`]),
+    ///  - the own leaf is blank or outside of the tree
+    ///    ([`IntegrityError::MissingOwnLeaf`]) or
+    ///  - the own leaf's private key doesn't match its public key
+    ///    ([`IntegrityError::OwnLeafKeyMismatch`]).
+    pub(crate) fn verify_integrity(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(), IntegrityError> {
+        let tree_hash = self.recompute_tree_hash(backend)?;
+        if tree_hash != self.group_context.tree_hash() || tree_hash != self.tree_hash() {
+            return Err(IntegrityError::TreeHashMismatch);
+        }
+
+        if !self.transcript_hash_matches_ciphersuite() {
+            return Err(IntegrityError::TranscriptHashMismatch);
+        }
+        // Only the creator of a group has no interim transcript hash, and only
+        // in the initial epoch.
+        if self.interim_transcript_hash.is_empty() {
+            if self.group_context.epoch() != GroupEpoch::from(0) {
+                return Err(IntegrityError::TranscriptHashMismatch);
+            }
+        } else {
+            let confirmation_tag = self
+                .message_secrets()
+                .confirmation_key()
+                .tag(backend, self.group_context.confirmed_transcript_hash())
+                .map_err(LibraryError::unexpected_crypto_error)?;
+            let interim_transcript_hash = update_interim_transcript_hash(
+                self.ciphersuite,
+                backend,
+                &InterimTranscriptHashInput::from(&confirmation_tag),
+                self.group_context.confirmed_transcript_hash(),
+            )?;
+            if interim_transcript_hash != self.interim_transcript_hash {
+                return Err(IntegrityError::TranscriptHashMismatch);
+            }
+        }
+
+        if self.treesync().own_leaf_node().is_err() {
+            return Err(IntegrityError::MissingOwnLeaf);
+        }
+        if !self
+            .treesync()
+            .own_leaf_keys_match(backend, self.ciphersuite)
+        {
+            return Err(IntegrityError::OwnLeafKeyMismatch);
+        }
+        Ok(())
+    }
+
+    /// Verify the confirmation tag of the commit in `mls_content` without
+    /// staging the commit.
+    ///
+    /// The confirmation tag of a commit is computed with the confirmation key
+    /// and the confirmed transcript hash of the epoch the commit creates.
+    /// Deriving the confirmation key requires the commit secret, so it has to
+    /// be provided by the caller. The provisional confirmed transcript hash is
+    /// computed from the commit and the interim transcript hash of the current
+    /// epoch, i.e. the commit has to be one of the current (*pre-commit*)
+    /// epoch that hasn't been merged yet.
+    ///
+    /// This is not exposed publicly: the confirmation key of the new epoch
+    /// can only be derived from the epoch secrets by a member that can decrypt
+    /// the commit's path, so a relay (e.g. a Delivery Service) can't check the
+    /// confirmation tag at all. Members check it when staging a commit.
+    ///
+    /// Returns `Ok(false)` if the message is not a commit of the current epoch
+    /// of this group or if the confirmation tag doesn't match.
+    pub(crate) fn verify_confirmation_tag(
+        &self,
+        mls_content: &MlsAuthContent,
+        confirmation_key: &ConfirmationKey,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<bool, LibraryError> {
+        let confirmation_tag = match mls_content.confirmation_tag() {
+            Some(confirmation_tag) => confirmation_tag,
+            None => return Ok(false),
+        };
+        if !matches!(mls_content.content(), MlsContentBody::Commit(_))
+            || mls_content.group_id() != self.group_id()
+            || mls_content.epoch() != self.context().epoch()
+        {
+            return Ok(false);
+        }
+        let confirmed_transcript_hash = update_confirmed_transcript_hash(
+            self.ciphersuite(),
+            backend,
+            // It is ok to return a library error here, because we know the
+            // content is a Commit.
+            &ConfirmedTranscriptHashInput::try_from(mls_content)
+                .map_err(|_| LibraryError::custom("Could not convert commit content"))?,
+            &self.interim_transcript_hash,
+        )?;
+        confirmation_tag_matches(
+            confirmation_tag,
+            confirmation_key,
+            &confirmed_transcript_hash,
+            backend,
+        )
+    }
+
+    /// Recomputes the tree hash of the ratchet tree from scratch. The result
+    /// can be compared against [`Self::tree_hash()`] to detect a corrupted
+    /// group state, e.g. after loading a persisted group.
+    pub(crate) fn recompute_tree_hash(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<Vec<u8>, LibraryError> {
+        self.tree.recompute_tree_hash(backend, self.ciphersuite())
+    }
+
     /// Get the ciphersuite implementation used in this group.
     pub(crate) fn ciphersuite(&self) -> Ciphersuite {
         self.ciphersuite
     }
 
+    /// Get the signature scheme of the group's ciphersuite. The signature keys
+    /// of all members are expected to be of this scheme.
+    pub(crate) fn signature_scheme(&self) -> SignatureScheme {
+        self.ciphersuite.signature_algorithm()
+    }
+
+    /// Get the length in bytes of confirmation tags in this group, i.e. the
+    /// output length of the ciphersuite's MAC.
+    pub(crate) fn confirmation_tag_length(&self) -> usize {
+        self.ciphersuite.hash_length()
+    }
+
+    /// Get the length in bytes of membership tags in this group, i.e. the
+    /// output length of the ciphersuite's MAC.
+    pub(crate) fn membership_tag_length(&self) -> usize {
+        self.ciphersuite.hash_length()
+    }
+
+    /// Get the age of the current epoch at the time `now`, e.g. to decide
+    /// whether to rotate keys. Returns `None` if the start of the epoch is
+    /// unknown.
+    pub(crate) fn epoch_age(&self, now: u64) -> Option<u64> {
+        self.epoch_started_at
+            .map(|epoch_started_at| now.saturating_sub(epoch_started_at))
+    }
+
+    /// Get the epoch in which the encryption key of the leaf at `leaf_index`
+    /// last changed. Returns `None` if it didn't change within the epochs for
+    /// which past secrets are retained, if the change happened before we
+    /// joined the group, or if the leaf is blank.
+    pub(crate) fn member_last_update_epoch(&self, leaf_index: LeafIndex) -> Option<GroupEpoch> {
+        self.leaf_key_epochs.get(&leaf_index).copied()
+    }
+
     /// Get the MLS version used in this group.
     pub(crate) fn version(&self) -> ProtocolVersion {
         self.mls_version
@@ -647,6 +1261,93 @@ impl CoreGroup {
         self.group_context.required_capabilities()
     }
 
+    /// Returns a [`CoverageReport`] of the group's required capabilities for
+    /// each member, together with the member's leaf index. If the group
+    /// doesn't require any capabilities, all reports are empty.
+    pub(crate) fn capabilities_coverage(&self) -> Vec<(u32, CoverageReport)> {
+        let required_capabilities = self.required_capabilities().cloned().unwrap_or_default();
+        self.treesync()
+            .full_leave_members()
+            .map(|member| {
+                (
+                    member.index,
+                    member.capabilities().coverage(&required_capabilities),
+                )
+            })
+            .collect()
+    }
+
+    /// Get the highest protocol version that is supported by all members of
+    /// the group, i.e. the minimum over the highest version advertised in the
+    /// capabilities of each leaf. Members can be required to support a newer
+    /// version once the floor has reached it.
+    pub(crate) fn membership_version_floor(&self) -> ProtocolVersion {
+        self.member_capabilities()
+            .filter_map(|capabilities| capabilities.versions().iter().max().copied())
+            .min()
+            .unwrap_or(self.mls_version)
+    }
+
+    /// Get the ciphersuites that are supported by all members of the group,
+    /// i.e. the intersection of the ciphersuites advertised in the capabilities
+    /// of each leaf.
+    pub(crate) fn membership_ciphersuite_intersection(&self) -> Vec<Ciphersuite> {
+        let mut member_capabilities = self.member_capabilities();
+        let mut ciphersuites = member_capabilities
+            .next()
+            .map(|capabilities| capabilities.ciphersuites().to_vec())
+            .unwrap_or_default();
+        for capabilities in member_capabilities {
+            ciphersuites.retain(|ciphersuite| capabilities.ciphersuites().contains(ciphersuite));
+        }
+        ciphersuites
+    }
+
+    /// Returns a [`ProposalSummary`] for each proposal in the given store, in
+    /// the order of the store. Member senders are resolved to the identity in
+    /// their leaf's credential.
+    pub(crate) fn describe_pending_proposals(
+        &self,
+        proposal_store: &ProposalStore,
+    ) -> Vec<ProposalSummary> {
+        proposal_store
+            .proposals()
+            .map(|queued_proposal| {
+                let sender_identity = match (queued_proposal.sender(), queued_proposal.proposal()) {
+                    (Sender::Member(leaf_index), _) => self
+                        .treesync()
+                        .leaf(*leaf_index)
+                        .ok()
+                        .flatten()
+                        .map(|leaf| leaf.credential().identity().to_vec()),
+                    (Sender::NewMemberProposal, Proposal::Add(add_proposal)) => {
+                        Some(add_proposal.key_package().credential().identity().to_vec())
+                    }
+                    _ => None,
+                };
+                ProposalSummary {
+                    sender: queued_proposal.sender().clone(),
+                    sender_identity,
+                    // Pending proposals are always sent by reference in the
+                    // current epoch.
+                    proposed_in_epoch: queued_proposal
+                        .proposed_in_epoch()
+                        .unwrap_or_else(|| self.context().epoch()),
+                    proposal: queued_proposal.proposal().clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the [`Capabilities`] of all non-blank leaves in the tree.
+    fn member_capabilities(&self) -> impl Iterator<Item = &Capabilities> + '_ {
+        self.treesync()
+            .full_leaves()
+            .into_iter()
+            .filter_map(|leaf_index| self.treesync().leaf(leaf_index).ok().flatten())
+            .map(|leaf| leaf.leaf_node().capabilities())
+    }
+
     /// Returns `true` if the group uses the ratchet tree extension anf `false
     /// otherwise
     #[cfg(test)]
@@ -657,11 +1358,42 @@ impl CoreGroup {
 
 // Private and crate functions
 impl CoreGroup {
+    /// Returns the [`FramingParameters`] that are used for an outgoing
+    /// handshake message. If the group is configured to always encrypt
+    /// handshake messages, the wire format is set to
+    /// [`WireFormat::MlsCiphertext`]. Otherwise, the wire format of the given
+    /// framing parameters is used.
+    pub(crate) fn handshake_framing_parameters<'a>(
+        &self,
+        framing_parameters: FramingParameters<'a>,
+    ) -> FramingParameters<'a> {
+        match self.default_handshake_wire_format {
+            WireFormat::MlsCiphertext => {
+                FramingParameters::new(framing_parameters.aad(), WireFormat::MlsCiphertext)
+            }
+            WireFormat::MlsPlaintext => framing_parameters,
+        }
+    }
+
     /// Get the leaf index of this client.
     pub(crate) fn own_leaf_index(&self) -> u32 {
         self.treesync().own_leaf_index()
     }
 
+    /// Export the private key material of the client's own leaf. See
+    /// [`OwnLeafSecrets`] for how to handle the result.
+    #[cfg(any(feature = "crypto-subtle", test))]
+    pub(crate) fn export_own_leaf_secrets(&self) -> Result<OwnLeafSecrets, LibraryError> {
+        let private_key = self
+            .treesync()
+            .own_leaf_private_key()
+            .map_err(|_| LibraryError::custom("Expected own leaf with private key"))?;
+        Ok(OwnLeafSecrets {
+            leaf_index: self.own_leaf_index(),
+            encryption_private_key: private_key.as_slice().to_vec(),
+        })
+    }
+
     /// Get the identity of the client's [`Credential`] owning this group.
     pub(crate) fn own_identity(&self) -> Option<&[u8]> {
         self.treesync()
@@ -670,6 +1402,16 @@ impl CoreGroup {
             .map(|node| node.credential().identity())
     }
 
+    /// Returns the [`Credential`] of the member at the given leaf index, or
+    /// `None` if the leaf is blank or not in the tree.
+    pub(crate) fn credential(&self, leaf_index: u32) -> Option<&Credential> {
+        self.treesync()
+            .leaf(leaf_index)
+            .ok()
+            .flatten()
+            .map(|leaf| leaf.credential())
+    }
+
     /// Get a reference to the group epoch secrets from the group
     pub(crate) fn group_epoch_secrets(&self) -> &GroupEpochSecrets {
         &self.group_epoch_secrets
@@ -680,6 +1422,31 @@ impl CoreGroup {
         self.message_secrets_store.message_secrets()
     }
 
+    /// Applies the given [`CoreGroupConfig`], e.g. to a group that was joined
+    /// from a `Welcome` or through an external commit. The
+    /// `add_ratchet_tree_extension` flag is ignored, because a joined group
+    /// keeps embedding the ratchet tree if it was embedded when joining.
+    pub(crate) fn set_config(&mut self, config: CoreGroupConfig) {
+        self.default_handshake_wire_format = config.default_handshake_wire_format;
+        self.allowed_proposal_types = config.allowed_proposal_types;
+        self.verify_proposal_signatures_in_commit = config.verify_proposal_signatures_in_commit;
+        self.diagnose_confirmation_tag_mismatch = config.diagnose_confirmation_tag_mismatch;
+        self.min_epoch_interval = config.min_epoch_interval;
+        self.always_embed_ratchet_tree = config.always_embed_ratchet_tree;
+        self.max_aad_length = config.max_aad_length;
+        self.padding_strategy = config.padding_strategy;
+    }
+
+    /// Sets the [`SenderRatchetConfiguration`] the group uses to decrypt
+    /// messages when no configuration is given per call. See
+    /// [`CoreGroupBuilder::with_sender_ratchet_configuration()`].
+    pub(crate) fn set_sender_ratchet_configuration(
+        &mut self,
+        sender_ratchet_configuration: SenderRatchetConfiguration,
+    ) {
+        self.sender_ratchet_configuration = sender_ratchet_configuration;
+    }
+
     /// Sets the size of the [`MessageSecretsStore`], i.e. the number of past
     /// epochs to keep.
     /// This allows application messages from previous epochs to be decrypted.
@@ -687,6 +1454,12 @@ impl CoreGroup {
         self.message_secrets_store.resize(max_past_epochs);
     }
 
+    /// Sets the time at which the current epoch started, e.g. when the group
+    /// was created or joined.
+    pub(crate) fn set_epoch_started_at(&mut self, epoch_started_at: u64) {
+        self.epoch_started_at = Some(epoch_started_at);
+    }
+
     /// Get the message secrets. Either from the secrets store or from the group.
     pub(crate) fn message_secrets_mut(
         &mut self,
@@ -737,11 +1510,41 @@ impl CoreGroup {
         }
     }
 
+    /// Get the members of the group in the given epoch. For past epochs, the
+    /// members are taken from the message secrets store.
+    ///
+    /// Returns `None` if the epoch is in the future or if it is too distant
+    /// in the past, i.e. it was already evicted from the message secrets
+    /// store.
+    pub(crate) fn members_at_epoch(&self, epoch: GroupEpoch) -> Option<Vec<Member>> {
+        if epoch == self.context().epoch() {
+            Some(self.treesync().full_leave_members().collect())
+        } else if epoch < self.context().epoch() {
+            self.message_secrets_store
+                .retained_leaves_for_epoch(epoch)
+                .map(|leaves| leaves.to_vec())
+        } else {
+            None
+        }
+    }
+
     #[cfg(any(feature = "test-utils", test))]
     pub(crate) fn message_secrets_test_mut(&mut self) -> &mut MessageSecrets {
         self.message_secrets_store.message_secrets_mut()
     }
 
+    /// Returns a [`StoreDebugReport`] of the message secrets for the given
+    /// epoch. For the current epoch, the known senders are the members in
+    /// the tree.
+    #[cfg(any(feature = "test-utils", test))]
+    pub(crate) fn message_secrets_debug_report(&self, epoch: GroupEpoch) -> StoreDebugReport {
+        let mut report = self.message_secrets_store.debug_report(epoch);
+        if epoch == self.context().epoch() {
+            report.known_senders = self.treesync().full_leaves();
+        }
+        report
+    }
+
     #[cfg(any(feature = "test-utils", test))]
     pub(crate) fn context_mut(&mut self) -> &mut GroupContext {
         &mut self.group_context
@@ -775,6 +1578,20 @@ pub(crate) fn update_confirmed_transcript_hash(
         .map_err(LibraryError::unexpected_crypto_error)
 }
 
+/// Check whether `confirmation_tag` is the tag of the given confirmed
+/// transcript hash under `confirmation_key`.
+pub(crate) fn confirmation_tag_matches(
+    confirmation_tag: &ConfirmationTag,
+    confirmation_key: &ConfirmationKey,
+    confirmed_transcript_hash: &[u8],
+    backend: &impl OpenMlsCryptoProvider,
+) -> Result<bool, LibraryError> {
+    let own_confirmation_tag = confirmation_key
+        .tag(backend, confirmed_transcript_hash)
+        .map_err(LibraryError::unexpected_crypto_error)?;
+    Ok(&own_confirmation_tag == confirmation_tag)
+}
+
 pub(crate) fn update_interim_transcript_hash(
     ciphersuite: Ciphersuite,
     backend: &impl OpenMlsCryptoProvider,
@@ -793,10 +1610,131 @@ pub(crate) fn update_interim_transcript_hash(
         .map_err(LibraryError::unexpected_crypto_error)
 }
 
+/// The format version of the state persisted with
+/// [`MlsGroup::save()`](crate::group::MlsGroup::save()), which includes the
+/// state of the [`CoreGroup`]. It has to be incremented with every change of
+/// the serialized state that older versions can't load.
+pub(crate) const CORE_GROUP_STATE_VERSION: u16 = 1;
+
+/// Writes the [`CORE_GROUP_STATE_VERSION`] header of persisted group state.
+pub(crate) fn write_state_version<W: Write>(writer: &mut W) -> Result<(), Error> {
+    writer.write_all(&CORE_GROUP_STATE_VERSION.to_be_bytes())
+}
+
+/// The format version of group state that was persisted before the state had
+/// a version header. Such state starts directly with the serialized JSON
+/// object and is loaded with the defaults of the fields added since.
+pub(crate) const LEGACY_STATE_VERSION: u16 = 0;
+
+/// Reads the format version header of persisted group state and checks that
+/// it is either [`CORE_GROUP_STATE_VERSION`] or [`LEGACY_STATE_VERSION`].
+///
+/// Returns the version together with a reader for the serialized state that
+/// follows the header. State without a header (starting with `{`) is
+/// [`LEGACY_STATE_VERSION`] and the returned reader starts at its first byte.
+pub(crate) fn read_state_version<R: Read>(
+    mut reader: R,
+) -> Result<(u16, Chain<Cursor<Vec<u8>>, R>), DeserializeError> {
+    let mut first_byte = [0u8; 1];
+    reader
+        .read_exact(&mut first_byte)
+        .map_err(|_| DeserializeError::ReadError)?;
+    if first_byte[0] == b'{' {
+        return Ok((
+            LEGACY_STATE_VERSION,
+            Cursor::new(first_byte.to_vec()).chain(reader),
+        ));
+    }
+    let mut second_byte = [0u8; 1];
+    reader
+        .read_exact(&mut second_byte)
+        .map_err(|_| DeserializeError::ReadError)?;
+    let version = u16::from_be_bytes([first_byte[0], second_byte[0]]);
+    if version != CORE_GROUP_STATE_VERSION {
+        return Err(DeserializeError::UnsupportedVersion { version });
+    }
+    Ok((version, Cursor::new(Vec::new()).chain(reader)))
+}
+
+/// The default wire format for outgoing handshake messages of a group that was
+/// persisted before the wire format was configurable.
+fn default_handshake_wire_format() -> WireFormat {
+    WireFormat::MlsPlaintext
+}
+
 /// Configuration for core group.
-#[derive(Clone, Copy, Default, Debug)]
+#[derive(Clone, Debug)]
 pub(crate) struct CoreGroupConfig {
     /// Flag whether to send the ratchet tree along with the `GroupInfo` or not.
     /// Defaults to false.
     pub(crate) add_ratchet_tree_extension: bool,
+    /// The wire format used for outgoing handshake messages. If this is set to
+    /// [`WireFormat::MlsCiphertext`], all proposals and commits created by the
+    /// group are encrypted, regardless of the framing parameters.
+    /// Defaults to [`WireFormat::MlsPlaintext`], i.e. the framing parameters
+    /// determine the wire format. An `MlsGroup` derives it from its outgoing
+    /// [`WireFormatPolicy`](crate::group::WireFormatPolicy).
+    pub(crate) default_handshake_wire_format: WireFormat,
+    /// The proposal types that may be included in commits of this group. Any
+    /// commit containing a proposal of another type is rejected when staging.
+    /// This is local policy on top of the protocol's validation rules.
+    /// Defaults to `None`, i.e. all proposal types are allowed.
+    pub(crate) allowed_proposal_types: Option<HashSet<ProposalType>>,
+    /// Flag whether to verify the signature of each proposal committed by
+    /// reference against the sender's credential when staging a commit, in
+    /// addition to the verification when the proposal was received.
+    /// Defaults to false.
+    pub(crate) verify_proposal_signatures_in_commit: bool,
+    /// Flag whether to find out why the confirmation tag of an incoming
+    /// commit doesn't match, i.e. whether the committer computed a different
+    /// confirmed transcript hash
+    /// ([`StageCommitError::TranscriptHashMismatch`]). This re-runs the key
+    /// schedule on a mismatch and requires copies of the joiner and PSK
+    /// secrets of every staged commit, so it is meant for debugging interop
+    /// failures. Defaults to false.
+    pub(crate) diagnose_confirmation_tag_mismatch: bool,
+    /// The minimum time that has to pass between the start of an epoch and
+    /// an incoming commit staged with [`CoreGroup::stage_commit_at()`]. Commits
+    /// arriving sooner are rejected to protect against members churning
+    /// through epochs. Caller-supplied timestamps are interpreted as seconds.
+    /// This is local policy on top of the protocol's validation rules.
+    /// Defaults to `None`, i.e. commits are not rate limited.
+    pub(crate) min_epoch_interval: Option<Duration>,
+    /// Flag whether to embed the ratchet tree in the `GroupInfo` of every
+    /// `Welcome` and every exported `GroupInfo`, so that new members can join
+    /// without fetching the tree out of band. This overrides
+    /// `add_ratchet_tree_extension` as well as the per-call ratchet tree
+    /// settings. Defaults to false.
+    pub(crate) always_embed_ratchet_tree: bool,
+    /// The maximum length in bytes of the authenticated data (AAD) of
+    /// application messages. Longer AAD is rejected when creating an
+    /// application message and when validating a received one. The AAD is
+    /// sent in the clear alongside the ciphertext and is not otherwise
+    /// bounded, so without a limit a member can make others receive and
+    /// process arbitrarily large messages.
+    /// Defaults to `None`, i.e. the AAD is unbounded.
+    pub(crate) max_aad_length: Option<usize>,
+    /// The padding strategy for outgoing `MlsCiphertext`s. If set, it
+    /// replaces the padding size passed when creating a message. Padding
+    /// content to a fixed set of lengths hides the length of messages
+    /// better than padding to a block size.
+    /// Defaults to `None`, i.e. the padding size passed when creating a
+    /// message is used as block size.
+    pub(crate) padding_strategy: Option<PaddingStrategy>,
+}
+
+impl Default for CoreGroupConfig {
+    fn default() -> Self {
+        Self {
+            add_ratchet_tree_extension: false,
+            default_handshake_wire_format: WireFormat::MlsPlaintext,
+            allowed_proposal_types: None,
+            verify_proposal_signatures_in_commit: false,
+            diagnose_confirmation_tag_mismatch: false,
+            min_epoch_interval: None,
+            always_embed_ratchet_tree: false,
+            max_aad_length: None,
+            padding_strategy: None,
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
     ciphersuite::signable::Verifiable,
-    group::errors::ExternalCommitError,
+    group::errors::{CreateCommitError, ExternalCommitError, WelcomeError},
     messages::proposals::{ExternalInitProposal, Proposal},
     treesync::{errors::TreeSyncFromNodesError, node::Node},
 };
@@ -18,7 +18,13 @@ impl CoreGroup {
     /// requires a [GroupInfo], as well as the corresponding public tree
     /// `nodes`. After the group state is initialized, this function creates an
     /// `ExternalInit` proposal and commits it along with the given proposals by
-    /// reference and by value.
+    /// reference and by value. Inline proposals in `params` can be used to
+    /// include a resumption PSK for a past epoch of the group, e.g. when
+    /// rejoining after having been offline.
+    ///
+    /// If the joiner knows that the group has reached a certain epoch, it can
+    /// pass it as `min_epoch` to reject a replayed [GroupInfo] of an older
+    /// epoch.
     ///
     /// Returns the new `CoreGroup` object, as well as the `MlsPlaintext`
     /// containing the commit.
@@ -27,6 +33,7 @@ impl CoreGroup {
         params: CreateCommitParams,
         tree_option: Option<&[Option<Node>]>,
         verifiable_group_info: VerifiableGroupInfo,
+        min_epoch: Option<GroupEpoch>,
     ) -> Result<ExternalCommitResult, ExternalCommitError> {
         let ciphersuite = verifiable_group_info.ciphersuite();
 
@@ -75,6 +82,21 @@ impl CoreGroup {
                 .map_err(|_| ExternalCommitError::InvalidGroupInfoSignature)?
         };
 
+        // Make sure the GroupInfo is newer than the epochs of the group we
+        // resume PSKs from and not older than the epoch we expect.
+        let inline_psks = params.inline_proposals().iter().filter_map(|proposal| {
+            if let Proposal::PreSharedKey(psk_proposal) = proposal {
+                Some(psk_proposal.psk())
+            } else {
+                None
+            }
+        });
+        Self::verify_group_info_freshness(group_info.group_context(), inline_psks, min_epoch)
+            .map_err(|e| match e {
+                WelcomeError::StaleGroupInfo => ExternalCommitError::StaleGroupInfo,
+                _ => LibraryError::custom("Unexpected freshness error").into(),
+            })?;
+
         if treesync.tree_hash() != group_info.group_context().tree_hash() {
             return Err(ExternalCommitError::TreeHashMismatch);
         }
@@ -83,27 +105,8 @@ impl CoreGroup {
             return Err(ExternalCommitError::UnsupportedMlsVersion);
         }
 
-        // Obtain external_pub from GroupInfo extensions.
-        // TODO(#720): Check for duplicates.
-        let external_pub = {
-            let ext = group_info
-                .extensions()
-                .iter()
-                .find(|ext| matches!(ext, Extension::ExternalPub(_)))
-                .ok_or(ExternalCommitError::MissingExternalPub)?
-                .as_external_pub_extension()
-                .map_err(|_| {
-                    LibraryError::custom(
-                        "We found an `ExternalPub` so `as_external_pub_extension` must not fail.",
-                    )
-                })?;
-
-            ext.external_pub()
-        };
-
-        let (init_secret, kem_output) =
-            InitSecret::from_group_info(backend, &group_info, external_pub.as_slice())
-                .map_err(|_| ExternalCommitError::UnsupportedCiphersuite)?;
+        let (external_init_proposal, init_secret) =
+            Self::create_external_init_proposal(&group_info, backend)?;
 
         // The `EpochSecrets` we create here are essentially zero, with the
         // exception of the `InitSecret`, which is all we need here for the
@@ -147,12 +150,23 @@ impl CoreGroup {
             interim_transcript_hash,
             use_ratchet_tree_extension: enable_ratchet_tree_extension,
             mls_version: group_info.group_context().protocol_version(),
+            // External commits are always sent as plaintext.
+            default_handshake_wire_format: WireFormat::MlsPlaintext,
+            allowed_proposal_types: None,
+            verify_proposal_signatures_in_commit: false,
+            diagnose_confirmation_tag_mismatch: false,
+            min_epoch_interval: None,
+            epoch_started_at: None,
+            leaf_key_epochs: HashMap::new(),
+            always_embed_ratchet_tree: false,
+            max_aad_length: None,
+            padding_strategy: None,
+            terminated: false,
+            sender_ratchet_configuration: SenderRatchetConfiguration::default(),
             group_epoch_secrets,
             message_secrets_store,
         };
 
-        let external_init_proposal = Proposal::ExternalInit(ExternalInitProposal::from(kem_output));
-
         let mut inline_proposals = vec![external_init_proposal];
 
         // If there is a group member in the group with the same identity as us,
@@ -168,6 +182,8 @@ impl CoreGroup {
             };
         }
 
+        inline_proposals.extend(params.inline_proposals().iter().cloned());
+
         let params = CreateCommitParams::builder()
             .framing_parameters(*params.framing_parameters())
             .credential_bundle(params.credential_bundle())
@@ -179,14 +195,61 @@ impl CoreGroup {
         // Immediately create the commit to add ourselves to the group.
         let create_commit_result = group.create_commit(params, backend);
         debug_assert!(
-            create_commit_result.is_ok(),
+            matches!(
+                create_commit_result,
+                Ok(_) | Err(CreateCommitError::ExternalCommitValidation(_))
+            ),
             "Error creating commit {:?}",
             create_commit_result
         );
 
         Ok((
             group,
-            create_commit_result.map_err(|_| ExternalCommitError::CommitError)?,
+            create_commit_result.map_err(|e| match e {
+                CreateCommitError::ExternalCommitValidation(e) => {
+                    ExternalCommitError::ExternalCommitValidation(e)
+                }
+                _ => ExternalCommitError::CommitError,
+            })?,
+        ))
+    }
+
+    /// Create the `ExternalInit` proposal for joining the group described by
+    /// the given (verified) [GroupInfo] via an external commit. The KEM output
+    /// in the proposal is derived against the `external_pub` extension of the
+    /// [GroupInfo].
+    ///
+    /// Returns the proposal, as well as the [InitSecret] that the members of
+    /// the group will derive from it when staging the external commit.
+    pub(crate) fn create_external_init_proposal(
+        group_info: &GroupInfo,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(Proposal, InitSecret), ExternalCommitError> {
+        // Obtain external_pub from GroupInfo extensions.
+        // TODO(#720): Check for duplicates.
+        let external_pub = {
+            let ext = group_info
+                .extensions()
+                .iter()
+                .find(|ext| matches!(ext, Extension::ExternalPub(_)))
+                .ok_or(ExternalCommitError::MissingExternalPub)?
+                .as_external_pub_extension()
+                .map_err(|_| {
+                    LibraryError::custom(
+                        "We found an `ExternalPub` so `as_external_pub_extension` must not fail.",
+                    )
+                })?;
+
+            ext.external_pub()
+        };
+
+        let (init_secret, kem_output) =
+            InitSecret::from_group_info(backend, group_info, external_pub.as_slice())
+                .map_err(|_| ExternalCommitError::UnsupportedCiphersuite)?;
+
+        Ok((
+            Proposal::ExternalInit(ExternalInitProposal::from(kem_output)),
+            init_secret,
         ))
     }
 }
@@ -1,90 +1,72 @@
 use log::debug;
 use openmls_traits::crypto::OpenMlsCrypto;
+use std::io::Read;
 use tls_codec::Deserialize;
 
 use crate::{
     ciphersuite::{hash_ref::HashReference, signable::Verifiable},
-    extensions::ExtensionType,
+    extensions::{ExtensionType, RatchetTreeExtension},
     group::{core_group::*, errors::WelcomeError},
     schedule::errors::PskError,
-    treesync::{errors::TreeSyncFromNodesError, node::Node},
+    treesync::{
+        errors::{PublicTreeError, TreeSyncFromNodesError},
+        node::Node,
+    },
 };
 
 impl CoreGroup {
-    // Join a group from a welcome message
+    /// Join a group from a welcome message.
+    ///
+    /// Processing the same [`Welcome`] with the same [`KeyPackageBundle`] more
+    /// than once, e.g. after a network retry, yields equivalent [`CoreGroup`]s.
+    /// The group ID and epoch of the resulting group are taken from the
+    /// Welcome, so callers can use them to detect duplicate groups. Duplicate
+    /// Welcomes can also be filtered before processing using [`Welcome::id()`].
     pub fn new_from_welcome(
         welcome: Welcome,
         nodes_option: Option<Vec<Option<Node>>>,
         key_package_bundle: KeyPackageBundle,
         backend: &impl OpenMlsCryptoProvider,
     ) -> Result<Self, WelcomeError> {
-        log::debug!("CoreGroup::new_from_welcome_internal");
-        let mls_version = *welcome.version();
-        if mls_version != ProtocolVersion::Mls10 {
-            return Err(WelcomeError::UnsupportedMlsVersion);
-        }
-
-        let ciphersuite = welcome.ciphersuite();
-
-        // Find key_package in welcome secrets
-        let egs = if let Some(egs) = Self::find_key_package_from_welcome_secrets(
-            key_package_bundle
-                .key_package()
-                .hash_ref(backend.crypto())?,
-            welcome.secrets(),
-        ) {
-            egs
-        } else {
-            return Err(WelcomeError::JoinerSecretNotFound);
-        };
-        if ciphersuite != key_package_bundle.key_package().ciphersuite() {
-            let e = WelcomeError::CiphersuiteMismatch;
-            debug!("new_from_welcome {:?}", e);
-            return Err(e);
-        }
-
-        let group_secrets_bytes = backend
-            .crypto()
-            .hpke_open(
-                ciphersuite.hpke_config(),
-                egs.encrypted_group_secrets(),
-                key_package_bundle.private_key().as_slice(),
-                &[],
-                &[],
-            )
-            .map_err(|_| WelcomeError::UnableToDecrypt)?;
-        let group_secrets = GroupSecrets::tls_deserialize(&mut group_secrets_bytes.as_slice())
-            .map_err(|_| WelcomeError::MalformedWelcomeMessage)?
-            .config(ciphersuite, mls_version);
-        let joiner_secret = group_secrets.joiner_secret;
-
-        // Prepare the PskSecret
-        let psk_secret =
-            PskSecret::new(ciphersuite, backend, &group_secrets.psks).map_err(|e| match e {
-                PskError::LibraryError(e) => e.into(),
-                PskError::TooManyKeys => WelcomeError::PskTooManyKeys,
-                PskError::KeyNotFound => WelcomeError::PskNotFound,
-            })?;
-
-        // Create key schedule
-        let mut key_schedule = KeySchedule::init(ciphersuite, backend, joiner_secret, psk_secret)?;
-
-        // Derive welcome key & nonce from the key schedule
-        let (welcome_key, welcome_nonce) = key_schedule
-            .welcome(backend)
-            .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?
-            .derive_welcome_key_nonce(backend)
-            .map_err(LibraryError::unexpected_crypto_error)?;
+        Self::new_from_welcome_internal(
+            welcome,
+            nodes_option.map(|nodes| nodes.into_iter().map(Ok)),
+            key_package_bundle,
+            backend,
+        )
+    }
 
-        let group_info_bytes = welcome_key
-            .aead_open(backend, welcome.encrypted_group_info(), &[], &welcome_nonce)
-            .map_err(|_| WelcomeError::GroupInfoDecryptionFailure)?;
-        let group_info = GroupInfo::tls_deserialize(&mut group_info_bytes.as_slice())
-            .map_err(|_| WelcomeError::MalformedWelcomeMessage)?;
+    /// Join a group from a welcome message. If the Welcome doesn't contain the
+    /// ratchet tree extension, the TLS-encoded ratchet tree (see
+    /// [`RatchetTreeExtension`]) is read from `tree_reader` and its nodes are
+    /// decoded while the tree is built. The tree is validated the same way as
+    /// in [`CoreGroup::new_from_welcome()`].
+    pub(crate) fn new_from_welcome_with_tree_reader<R: Read>(
+        welcome: Welcome,
+        tree_reader: R,
+        key_package_bundle: KeyPackageBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<Self, WelcomeError> {
+        let nodes = RatchetTreeExtension::deserialize_streaming(tree_reader)
+            .map_err(|_| WelcomeError::PublicTreeError(PublicTreeError::MalformedTree))?
+            .map(|node_option| {
+                node_option
+                    .map_err(|_| TreeSyncFromNodesError::from(PublicTreeError::MalformedTree))
+            });
+        Self::new_from_welcome_internal(welcome, Some(nodes), key_package_bundle, backend)
+    }
 
-        if ciphersuite != group_info.group_context().ciphersuite() {
-            return Err(WelcomeError::GroupInfoCiphersuiteMismatch);
-        }
+    fn new_from_welcome_internal(
+        welcome: Welcome,
+        nodes_option: Option<impl Iterator<Item = Result<Option<Node>, TreeSyncFromNodesError>>>,
+        key_package_bundle: KeyPackageBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<Self, WelcomeError> {
+        log::debug!("CoreGroup::new_from_welcome_internal");
+        let (group_secrets, mut key_schedule, group_info) =
+            Self::decrypt_welcome(&welcome, &key_package_bundle, backend)?;
+        let ciphersuite = welcome.ciphersuite();
+        let mls_version = *welcome.version();
 
         // Make sure that we can support the required capabilities in the group info.
         let group_context_extensions = group_info.group_context().extensions();
@@ -107,6 +89,12 @@ impl CoreGroup {
                 .map_err(|_| WelcomeError::UnsupportedCapability)?
         }
 
+        // Make sure the GroupInfo is newer than the epochs of the group we
+        // resume PSKs from. The welcome is encrypted to our single-use key
+        // package, so there is no prior knowledge of the epoch to check
+        // against.
+        Self::verify_group_info_freshness(group_info.group_context(), &group_secrets.psks, None)?;
+
         let path_secret_option = group_secrets.path_secret;
 
         // Build the ratchet tree
@@ -115,31 +103,39 @@ impl CoreGroup {
         // If we got a ratchet tree extension in the welcome, we enable it for
         // this group. Note that this is not strictly necessary. But there's
         // currently no other mechanism to enable the extension.
-        let (nodes, enable_ratchet_tree_extension) =
-            match try_nodes_from_extensions(group_info.extensions()).map_err(|e| match e {
+        let extension_nodes_option =
+            try_nodes_from_extensions(group_info.extensions()).map_err(|e| match e {
                 ExtensionError::DuplicateRatchetTreeExtension => {
                     WelcomeError::DuplicateRatchetTreeExtension
                 }
                 _ => LibraryError::custom("Unexpected extension error").into(),
-            })? {
-                Some(nodes) => (nodes, true),
-                None => match nodes_option {
-                    Some(n) => (n, false),
-                    None => return Err(WelcomeError::MissingRatchetTree),
-                },
-            };
+            })?;
+        let enable_ratchet_tree_extension = extension_nodes_option.is_some();
 
         // Commit secret is ignored when joining a group, since we already have
         // the joiner_secret.
-        let (tree, _commit_secret_option) = TreeSync::from_nodes_with_secrets(
-            backend,
-            ciphersuite,
-            &nodes,
-            group_info.signer(),
-            path_secret_option,
-            key_package_bundle,
-        )
-        .map_err(|e| match e {
+        let tree_result = match extension_nodes_option {
+            Some(nodes) => TreeSync::from_node_stream_with_secrets(
+                backend,
+                ciphersuite,
+                nodes.into_iter().map(Ok),
+                group_info.signer(),
+                path_secret_option,
+                key_package_bundle,
+            ),
+            None => match nodes_option {
+                Some(nodes) => TreeSync::from_node_stream_with_secrets(
+                    backend,
+                    ciphersuite,
+                    nodes,
+                    group_info.signer(),
+                    path_secret_option,
+                    key_package_bundle,
+                ),
+                None => return Err(WelcomeError::MissingRatchetTree),
+            },
+        };
+        let (tree, _commit_secret_option) = tree_result.map_err(|e| match e {
             TreeSyncFromNodesError::LibraryError(e) => e.into(),
             TreeSyncFromNodesError::PublicTreeError(e) => WelcomeError::PublicTreeError(e),
         })?;
@@ -155,6 +151,14 @@ impl CoreGroup {
             .verify_no_out(backend, signer_credential)
             .map_err(|_| WelcomeError::InvalidGroupInfoSignature)?;
 
+        // The tree, which might have been delivered in the ratchet tree
+        // extension, has to match the tree hash in the signed group context.
+        if tree.tree_hash() != group_info.group_context().tree_hash() {
+            return Err(WelcomeError::PublicTreeError(
+                PublicTreeError::TreeHashMismatch,
+            ));
+        }
+
         // Compute state
         let group_context = GroupContext::new(
             ciphersuite,
@@ -214,13 +218,249 @@ impl CoreGroup {
                 interim_transcript_hash,
                 use_ratchet_tree_extension: enable_ratchet_tree_extension,
                 mls_version,
+                default_handshake_wire_format: WireFormat::MlsPlaintext,
+                allowed_proposal_types: None,
+                verify_proposal_signatures_in_commit: false,
+                diagnose_confirmation_tag_mismatch: false,
+                min_epoch_interval: None,
+                epoch_started_at: None,
+                leaf_key_epochs: HashMap::new(),
+                always_embed_ratchet_tree: false,
+                max_aad_length: None,
+                padding_strategy: None,
+                terminated: false,
+                sender_ratchet_configuration: SenderRatchetConfiguration::default(),
                 message_secrets_store,
             })
         }
     }
 
+    /// Join a group from a [`Welcome`] message and cross-check the resulting
+    /// group state against a separately received, signed [`GroupInfo`].
+    ///
+    /// Returns a [`WelcomeError::GroupInfoMismatch`] if the group context or
+    /// the confirmation tag derived from the Welcome do not match the ones in
+    /// the `GroupInfo`.
+    pub(crate) fn new_from_welcome_verified(
+        welcome: Welcome,
+        verifiable_group_info: VerifiableGroupInfo,
+        nodes_option: Option<Vec<Option<Node>>>,
+        key_package_bundle: KeyPackageBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<Self, WelcomeError> {
+        let group = Self::new_from_welcome(welcome, nodes_option, key_package_bundle, backend)?;
+
+        // Verify the GroupInfo signature against the signer in the tree
+        // derived from the Welcome.
+        let signer_credential = group
+            .treesync()
+            .leaf(verifiable_group_info.signer())
+            .map_err(|_| WelcomeError::UnknownSender)?
+            .ok_or(WelcomeError::UnknownSender)?
+            .credential();
+        let group_info: GroupInfo = verifiable_group_info
+            .verify(backend, signer_credential)
+            .map_err(|_| WelcomeError::InvalidGroupInfoSignature)?;
+
+        let confirmation_tag = group
+            .message_secrets()
+            .confirmation_key()
+            .tag(backend, group.context().confirmed_transcript_hash())
+            .map_err(LibraryError::unexpected_crypto_error)?;
+
+        if group_info.group_context() != group.context()
+            || group_info.confirmation_tag() != &confirmation_tag
+        {
+            log::error!("GroupInfo does not match the group state derived from the Welcome");
+            return Err(WelcomeError::GroupInfoMismatch);
+        }
+
+        Ok(group)
+    }
+
+    /// Verify that the confirmation tag of the [`GroupInfo`] in a [`Welcome`]
+    /// is consistent with the group context advertised in that `GroupInfo`.
+    ///
+    /// The confirmation key is derived from the joiner secret the Welcome
+    /// was encrypted to `key_package_bundle` with, so this check does not
+    /// require the ratchet tree. It allows callers to make sure the advertised
+    /// epoch is authentic before joining the group with
+    /// [`CoreGroup::new_from_welcome()`], which performs the same check on the
+    /// reconstructed group context. Note that the signature of the
+    /// `GroupInfo` is not verified, since the signer's credential is part of
+    /// the ratchet tree.
+    ///
+    /// Returns a [`WelcomeError::ConfirmationTagMismatch`] if the tags differ.
+    pub(crate) fn verify_welcome_confirmation_tag(
+        welcome: &Welcome,
+        key_package_bundle: &KeyPackageBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(), WelcomeError> {
+        let (_group_secrets, mut key_schedule, group_info) =
+            Self::decrypt_welcome(welcome, key_package_bundle, backend)?;
+
+        let serialized_group_context = group_info
+            .group_context()
+            .tls_serialize_detached()
+            .map_err(LibraryError::missing_bound_check)?;
+        key_schedule
+            .add_context(backend, &serialized_group_context)
+            .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?;
+        let epoch_secrets = key_schedule
+            .epoch_secrets(backend)
+            .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?;
+
+        let confirmation_tag = epoch_secrets
+            .confirmation_key()
+            .tag(
+                backend,
+                group_info.group_context().confirmed_transcript_hash(),
+            )
+            .map_err(LibraryError::unexpected_crypto_error)?;
+        if &confirmation_tag != group_info.confirmation_tag() {
+            log::error!("Confirmation tag mismatch");
+            log_crypto!(trace, "  Got:      {:x?}", confirmation_tag);
+            log_crypto!(trace, "  Expected: {:x?}", group_info.confirmation_tag());
+            return Err(WelcomeError::ConfirmationTagMismatch);
+        }
+
+        Ok(())
+    }
+
     // Helper functions
 
+    /// Check that a [`GroupInfo`] with the given [`GroupContext`] is not
+    /// behind the joiner's knowledge of the group. If the joiner injects
+    /// resumption PSKs of the same group, it knows that the group has moved
+    /// past the newest of the PSK epochs. A validly-signed group info of that
+    /// or an older epoch is thus a replay. In addition, the caller can pass
+    /// the minimum epoch it expects the group to be in, e.g. the last epoch it
+    /// saw as a member.
+    ///
+    /// Returns a [`WelcomeError::StaleGroupInfo`] if the group info is stale.
+    pub(crate) fn verify_group_info_freshness<'a>(
+        group_context: &GroupContext,
+        psks: impl IntoIterator<Item = &'a PreSharedKeyId>,
+        min_epoch: Option<GroupEpoch>,
+    ) -> Result<(), WelcomeError> {
+        let epoch = group_context.epoch();
+        if matches!(min_epoch, Some(min_epoch) if epoch < min_epoch) {
+            return Err(WelcomeError::StaleGroupInfo);
+        }
+        let resumed_from_current_or_later_epoch =
+            psks.into_iter().any(|psk_id| match psk_id.psk() {
+                Psk::Resumption(resumption_psk) => {
+                    resumption_psk.psk_group_id() == group_context.group_id()
+                        && epoch <= resumption_psk.psk_epoch()
+                }
+                _ => false,
+            });
+        if resumed_from_current_or_later_epoch {
+            return Err(WelcomeError::StaleGroupInfo);
+        }
+        Ok(())
+    }
+
+    /// Decrypt the [`GroupSecrets`] and the [`GroupInfo`] of a [`Welcome`] for
+    /// the given [`KeyPackageBundle`]. Returns the key schedule in the state
+    /// after the welcome key was derived.
+    pub(crate) fn decrypt_welcome(
+        welcome: &Welcome,
+        key_package_bundle: &KeyPackageBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(GroupSecrets, KeySchedule, GroupInfo), WelcomeError> {
+        let ciphersuite = welcome.ciphersuite();
+        let group_secrets = Self::decrypt_group_secrets(welcome, key_package_bundle, backend)?;
+
+        // Prepare the PskSecret
+        let psk_secret =
+            PskSecret::new(ciphersuite, backend, &group_secrets.psks).map_err(|e| match e {
+                PskError::LibraryError(e) => e.into(),
+                PskError::TooManyKeys => WelcomeError::PskTooManyKeys,
+                PskError::KeyNotFound => WelcomeError::PskNotFound,
+            })?;
+
+        // Create key schedule
+        let mut key_schedule = KeySchedule::init(
+            ciphersuite,
+            backend,
+            group_secrets.joiner_secret.clone(),
+            psk_secret,
+        )?;
+
+        // Derive welcome key & nonce from the key schedule
+        let (welcome_key, welcome_nonce) = key_schedule
+            .welcome(backend)
+            .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?
+            .derive_welcome_key_nonce(backend)
+            .map_err(LibraryError::unexpected_crypto_error)?;
+
+        let group_info_bytes = welcome_key
+            .aead_open(backend, welcome.encrypted_group_info(), &[], &welcome_nonce)
+            .map_err(|_| WelcomeError::GroupInfoDecryptionFailure)?;
+        let group_info = GroupInfo::tls_deserialize(&mut group_info_bytes.as_slice())
+            .map_err(|_| WelcomeError::MalformedWelcomeMessage)?;
+
+        if ciphersuite != group_info.group_context().ciphersuite() {
+            return Err(WelcomeError::GroupInfoCiphersuiteMismatch);
+        }
+
+        Ok((group_secrets, key_schedule, group_info))
+    }
+
+    /// Decrypt the [`GroupSecrets`] of a [`Welcome`] for the given
+    /// [`KeyPackageBundle`]. The PSKs listed in the group secrets don't have
+    /// to be available for this.
+    pub(crate) fn decrypt_group_secrets(
+        welcome: &Welcome,
+        key_package_bundle: &KeyPackageBundle,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<GroupSecrets, WelcomeError> {
+        let mls_version = *welcome.version();
+        if mls_version != ProtocolVersion::Mls10 {
+            return Err(WelcomeError::UnsupportedMlsVersion);
+        }
+
+        let ciphersuite = welcome.ciphersuite();
+        let key_package_ciphersuite = key_package_bundle.key_package().ciphersuite();
+        if ciphersuite != key_package_ciphersuite {
+            let e = WelcomeError::CiphersuiteMismatch {
+                group: ciphersuite,
+                key_package: key_package_ciphersuite,
+            };
+            debug!("new_from_welcome {:?}", e);
+            return Err(e);
+        }
+
+        // Find key_package in welcome secrets
+        let egs = if let Some(egs) = Self::find_key_package_from_welcome_secrets(
+            key_package_bundle
+                .key_package()
+                .hash_ref(backend.crypto())?,
+            welcome.secrets(),
+        ) {
+            egs
+        } else {
+            return Err(WelcomeError::JoinerSecretNotFound);
+        };
+
+        let group_secrets_bytes = backend
+            .crypto()
+            .hpke_open(
+                ciphersuite.hpke_config(),
+                egs.encrypted_group_secrets(),
+                key_package_bundle.private_key().as_slice(),
+                &[],
+                &[],
+            )
+            .map_err(|_| WelcomeError::UnableToDecrypt)?;
+        let group_secrets = GroupSecrets::tls_deserialize(&mut group_secrets_bytes.as_slice())
+            .map_err(|_| WelcomeError::MalformedWelcomeMessage)?
+            .config(ciphersuite, mls_version);
+
+        Ok(group_secrets)
+    }
+
     pub(crate) fn find_key_package_from_welcome_secrets(
         hash_ref: HashReference,
         welcome_secrets: &[EncryptedGroupSecrets],
@@ -13,8 +13,34 @@ struct EpochTree {
     leaves: Vec<Member>,
 }
 
+/// A view of the [`MessageSecretsStore`] for a single epoch that helps to
+/// debug decryption failures. It doesn't contain any secrets.
+#[cfg(any(feature = "test-utils", test))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreDebugReport {
+    /// `true` if the message secrets of the epoch are retained in the store.
+    pub epoch_retained: bool,
+    /// The leaf indices of the members of the epoch. The store only knows the
+    /// members of past epochs, so this is empty for the current epoch.
+    pub known_senders: Vec<u32>,
+    /// The sender ratchets of the epoch that have been initialized.
+    pub sender_ratchets: Vec<SenderRatchetReport>,
+}
+
+/// The state of the sender ratchets of a single leaf in a [`StoreDebugReport`].
+#[cfg(any(feature = "test-utils", test))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SenderRatchetReport {
+    /// The leaf index of the sender.
+    pub leaf_index: u32,
+    /// The current generation of the handshake ratchet, if it is initialized.
+    pub handshake_generation: Option<u32>,
+    /// The current generation of the application ratchet, if it is initialized.
+    pub application_generation: Option<u32>,
+}
+
 /// Can store message secrets for up to `max_epochs`. The trees are added with [`self::add()`] and can be queried
-/// with [`Self::get_epoch()`].
+/// with [`Self::get_epoch()`]. The message secrets of evicted epochs are zeroized when they are dropped.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(test, derive(PartialEq))]
 pub(crate) struct MessageSecretsStore {
@@ -26,6 +52,8 @@ pub(crate) struct MessageSecretsStore {
     message_secrets: MessageSecrets,
 }
 
+impl ZeroizeOnDrop for MessageSecretsStore {}
+
 impl MessageSecretsStore {
     /// Create a new store that can hold up to `max_past_epochs` message secrets.
     /// If `max_past_epochs` is 0, only the current epoch is being stored.
@@ -48,6 +76,11 @@ impl MessageSecretsStore {
         }
     }
 
+    /// Returns the number of past epochs for which secrets are retained.
+    pub(crate) fn max_epochs(&self) -> usize {
+        self.max_epochs
+    }
+
     /// Add a secret tree for a given epoch `group_epoch`.
     /// Note that this does not take the epoch into account and pops out the
     /// oldest element.
@@ -126,13 +159,20 @@ impl MessageSecretsStore {
 
     /// Return a slice with the [`Member`]s of the `group_epoch`.
     pub(crate) fn leaves_for_epoch(&self, group_epoch: impl Into<GroupEpoch>) -> &[Member] {
+        self.retained_leaves_for_epoch(group_epoch).unwrap_or(&[])
+    }
+
+    /// Return a slice with the [`Member`]s of the `group_epoch`. If the epoch
+    /// is not retained in the store, `None` is returned.
+    pub(crate) fn retained_leaves_for_epoch(
+        &self,
+        group_epoch: impl Into<GroupEpoch>,
+    ) -> Option<&[Member]> {
         let epoch = group_epoch.into().as_u64();
-        for epoch_tree in self.past_epoch_trees.iter() {
-            if epoch_tree.epoch == epoch {
-                return &epoch_tree.leaves;
-            }
-        }
-        &[]
+        self.past_epoch_trees
+            .iter()
+            .find(|epoch_tree| epoch_tree.epoch == epoch)
+            .map(|epoch_tree| epoch_tree.leaves.as_slice())
     }
 
     /// Check if the provided epoch contains a leaf index.
@@ -154,4 +194,58 @@ impl MessageSecretsStore {
     pub(crate) fn message_secrets(&self) -> &MessageSecrets {
         &self.message_secrets
     }
+
+    /// Returns a [`StoreDebugReport`] for the given `group_epoch`.
+    #[cfg(any(feature = "test-utils", test))]
+    pub(crate) fn debug_report(&self, group_epoch: impl Into<GroupEpoch>) -> StoreDebugReport {
+        use tls_codec::Deserialize as TlsDeserializeTrait;
+
+        let epoch = group_epoch.into();
+        let current_epoch =
+            GroupContext::tls_deserialize(&mut self.message_secrets.serialized_context())
+                .map(|group_context| group_context.epoch())
+                .ok();
+        let (message_secrets, known_senders) = if current_epoch == Some(epoch) {
+            (Some(&self.message_secrets), vec![])
+        } else {
+            match self
+                .past_epoch_trees
+                .iter()
+                .find(|epoch_tree| epoch_tree.epoch == epoch.as_u64())
+            {
+                Some(epoch_tree) => (
+                    Some(&epoch_tree.message_secrets),
+                    epoch_tree
+                        .leaves
+                        .iter()
+                        .map(|member| member.index)
+                        .collect(),
+                ),
+                None => (None, vec![]),
+            }
+        };
+        let sender_ratchets = message_secrets
+            .map(|message_secrets| {
+                message_secrets
+                    .secret_tree()
+                    .sender_ratchet_generations()
+                    .into_iter()
+                    .map(
+                        |(leaf_index, handshake_generation, application_generation)| {
+                            SenderRatchetReport {
+                                leaf_index,
+                                handshake_generation,
+                                application_generation,
+                            }
+                        },
+                    )
+                    .collect()
+            })
+            .unwrap_or_default();
+        StoreDebugReport {
+            epoch_retained: message_secrets.is_some(),
+            known_senders,
+            sender_ratchets,
+        }
+    }
 }
@@ -2,6 +2,7 @@ use core_group::{proposals::QueuedProposal, staged_commit::StagedCommit};
 
 use crate::{
     group::{errors::ValidationError, mls_group::errors::ProcessMessageError},
+    tree::index::SecretTreeLeafIndex,
     treesync::node::leaf_node::OpenMlsLeafNode,
 };
 
@@ -13,6 +14,9 @@ impl CoreGroup {
     /// If the input is a [MlsCiphertext] message, it will be decrypted.
     /// Returns an [UnverifiedMessage] that can be inspected and later processed in
     /// [Self::process_unverified_message()].
+    /// If no `sender_ratchet_configuration` is given, the configuration of the
+    /// group is used (see
+    /// [`CoreGroupBuilder::with_sender_ratchet_configuration()`]).
     /// Checks the following semantic validation:
     ///  - ValSem002
     ///  - ValSem003
@@ -23,12 +27,17 @@ impl CoreGroup {
     ///  - ValSem009
     ///  - ValSem112
     ///  - ValSem245
-    pub(crate) fn parse_message(
+    pub(crate) fn parse_message<'a>(
         &mut self,
         backend: &impl OpenMlsCryptoProvider,
         message: MlsMessageIn,
-        sender_ratchet_configuration: &SenderRatchetConfiguration,
+        sender_ratchet_configuration: impl Into<Option<&'a SenderRatchetConfiguration>>,
     ) -> Result<UnverifiedMessage, ValidationError> {
+        let sender_ratchet_configuration = sender_ratchet_configuration
+            .into()
+            .cloned()
+            .unwrap_or_else(|| self.sender_ratchet_configuration.clone());
+
         // Checks the following semantic validation:
         //  - ValSem002
         //  - ValSem003
@@ -41,16 +50,14 @@ impl CoreGroup {
         //  - ValSem007 MembershipTag presence
         let decrypted_message = match message.mls_message.body {
             MlsMessageBody::Plaintext(plaintext) => {
+                // Verify the membership tag. This needs to be done explicitly for MlsPlaintext messages,
+                // it is implicit for MlsCiphertext messages (because the encryption can only be known by members).
+                //  - ValSem007 Membership tag presence
+                //  - ValSem008
+                self.verify_membership_tag(&plaintext, backend)?;
                 // If the message is older than the current epoch, we need to fetch the correct secret tree first.
-                let message_secrets =
-                    self.message_secrets_for_epoch(epoch).map_err(|e| match e {
-                        SecretTreeError::TooDistantInThePast => ValidationError::NoPastEpochData,
-                        _ => LibraryError::custom(
-                            "Unexpected error while retrieving message secrets for epoch.",
-                        )
-                        .into(),
-                    })?;
-                DecryptedMessage::from_inbound_plaintext(plaintext, message_secrets, backend)?
+                let message_secrets = self.message_secrets_for_epoch_checked(epoch)?;
+                DecryptedMessage::from_inbound_plaintext(plaintext, message_secrets)?
             }
             MlsMessageBody::Ciphertext(ciphertext) => {
                 // If the message is older than the current epoch, we need to fetch the correct secret tree first
@@ -58,11 +65,26 @@ impl CoreGroup {
                     ciphertext,
                     backend,
                     self,
-                    sender_ratchet_configuration,
+                    &sender_ratchet_configuration,
                 )?
             }
         };
 
+        self.unverified_message(decrypted_message)
+    }
+
+    /// Turns a [`DecryptedMessage`] into an [`UnverifiedMessage`] that carries
+    /// the credential of the sender.
+    /// Checks the following semantic validation:
+    ///  - ValSem004
+    ///  - ValSem005
+    ///  - ValSem009
+    ///  - ValSem112
+    ///  - ValSem245
+    fn unverified_message(
+        &self,
+        decrypted_message: DecryptedMessage,
+    ) -> Result<UnverifiedMessage, ValidationError> {
         // Checks the following semantic validation:
         //  - ValSem004
         //  - ValSem005
@@ -75,11 +97,11 @@ impl CoreGroup {
         //  - ValSem245
         //  - Prepares ValSem246 by setting the right credential. The remainder
         //    of ValSem246 is validated as part of ValSem010.
-        // External senders are not supported yet #106/#151.
         let credential = decrypted_message.credential(
             self.treesync(),
             self.message_secrets_store
                 .leaves_for_epoch(decrypted_message.plaintext().epoch()),
+            self.external_senders(),
         )?;
 
         Ok(UnverifiedMessage::from_decrypted_message(
@@ -88,8 +110,234 @@ impl CoreGroup {
         ))
     }
 
+    /// Parses an [`MlsPlaintext`] message of a member or a new member and
+    /// verifies its membership tag and signature without modifying the group.
+    /// Returns `None` if the message is an [`MlsCiphertext`], because
+    /// decrypting it would consume the message key, or if any of the checks
+    /// fail.
+    pub(crate) fn verified_plaintext(
+        &self,
+        message: MlsMessageIn,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Option<MlsAuthContent> {
+        self.validate_framing(&message).ok()?;
+        let epoch = message.epoch();
+        let plaintext = match message.mls_message.body {
+            MlsMessageBody::Plaintext(plaintext) => plaintext,
+            MlsMessageBody::Ciphertext(_) => return None,
+        };
+        self.verify_membership_tag(&plaintext, backend).ok()?;
+        let message_secrets = self.message_secrets_for_epoch_checked(epoch).ok()?;
+        let decrypted_message =
+            DecryptedMessage::from_inbound_plaintext(plaintext, message_secrets).ok()?;
+        let unverified_message = self.unverified_message(decrypted_message).ok()?;
+        match UnverifiedContextMessage::from_unverified_message(unverified_message).ok()? {
+            UnverifiedContextMessage::Group(message) => {
+                Some(message.into_verified(backend).ok()?.take_plaintext())
+            }
+            UnverifiedContextMessage::NewMember(message) => {
+                Some(message.into_verified(backend).ok()?.take_plaintext())
+            }
+            UnverifiedContextMessage::External(_) => None,
+        }
+    }
+
+    /// Decrypt a batch of ciphertexts, e.g. a backlog received when coming
+    /// online. The ciphertexts may be out of order and may belong to any
+    /// epoch for which the group still holds message secrets.
+    ///
+    /// The ciphertexts are grouped by epoch and decrypted in the order of
+    /// their sender and generation, so that each sender ratchet is advanced
+    /// only once through the sequence and no skipped keys have to be
+    /// retained for messages that arrived out of order. Failures are
+    /// reported per ciphertext and don't abort the batch, e.g. a ciphertext
+    /// whose key was already consumed yields a
+    /// [`MessageDecryptionError::GenerationOutOfBound`].
+    ///
+    /// Returns one result per ciphertext, in the order of `ciphertexts`.
+    pub(crate) fn decrypt_batch(
+        &mut self,
+        ciphertexts: &[MlsCiphertext],
+        sender_ratchet_configuration: &SenderRatchetConfiguration,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Vec<Result<VerifiableMlsAuthContent, MessageDecryptionError>> {
+        let ciphertexts: Vec<&MlsCiphertext> = ciphertexts.iter().collect();
+        self.decrypt_ciphertexts(&ciphertexts, sender_ratchet_configuration, backend)
+    }
+
+    /// Parse a batch of application messages as in [`Self::parse_message()`],
+    /// e.g. a backlog received when coming online. The messages are decrypted
+    /// with [`Self::decrypt_batch()`]. Messages that aren't encrypted
+    /// application messages are rejected with a
+    /// [`ValidationError::NotAnApplicationMessage`].
+    ///
+    /// Returns one result per message, in the order of `messages`.
+    pub(crate) fn parse_application_messages<'a>(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        messages: Vec<MlsMessageIn>,
+        sender_ratchet_configuration: impl Into<Option<&'a SenderRatchetConfiguration>>,
+    ) -> Vec<Result<UnverifiedMessage, ValidationError>> {
+        let sender_ratchet_configuration = sender_ratchet_configuration
+            .into()
+            .cloned()
+            .unwrap_or_else(|| self.sender_ratchet_configuration.clone());
+        let mut results: Vec<Option<Result<UnverifiedMessage, ValidationError>>> =
+            messages.iter().map(|_| None).collect();
+
+        let mut positions = Vec::new();
+        let mut ciphertexts = Vec::new();
+        for (position, message) in messages.into_iter().enumerate() {
+            // Checks the following semantic validation:
+            //  - ValSem002
+            //  - ValSem003
+            let checked =
+                self.validate_framing(&message)
+                    .and_then(|_| match message.mls_message.body {
+                        MlsMessageBody::Ciphertext(ciphertext)
+                            if ciphertext.content_type() == ContentType::Application =>
+                        {
+                            Ok(ciphertext)
+                        }
+                        _ => Err(ValidationError::NotAnApplicationMessage),
+                    });
+            match checked {
+                Ok(ciphertext) => {
+                    positions.push(position);
+                    ciphertexts.push(ciphertext);
+                }
+                Err(e) => results[position] = Some(Err(e)),
+            }
+        }
+
+        let decrypted = self.decrypt_batch(&ciphertexts, &sender_ratchet_configuration, backend);
+        for (position, result) in positions.into_iter().zip(decrypted) {
+            results[position] = Some(
+                result
+                    .map_err(ValidationError::from)
+                    .and_then(DecryptedMessage::from_plaintext)
+                    .and_then(|decrypted_message| self.unverified_message(decrypted_message)),
+            );
+        }
+
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| Err(LibraryError::custom("Message was skipped").into()))
+            })
+            .collect()
+    }
+
+    /// Decrypts the given ciphertexts, see [`Self::decrypt_batch()`].
+    fn decrypt_ciphertexts(
+        &mut self,
+        ciphertexts: &[&MlsCiphertext],
+        sender_ratchet_configuration: &SenderRatchetConfiguration,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Vec<Result<VerifiableMlsAuthContent, MessageDecryptionError>> {
+        let ciphersuite = self.ciphersuite();
+        let mut results: Vec<Option<Result<VerifiableMlsAuthContent, MessageDecryptionError>>> =
+            ciphertexts.iter().map(|_| None).collect();
+
+        // Decrypt the sender data of all ciphertexts first to learn the
+        // sender and generation. This doesn't advance any ratchet.
+        let mut pending = Vec::new();
+        for (position, ciphertext) in ciphertexts.iter().enumerate() {
+            let sender_data = self
+                .message_secrets_for_epoch(ciphertext.epoch())
+                .map_err(MessageDecryptionError::from)
+                .and_then(|message_secrets| {
+                    ciphertext.sender_data(message_secrets, backend, ciphersuite)
+                });
+            match sender_data {
+                Ok(sender_data) => pending.push((position, sender_data)),
+                Err(e) => results[position] = Some(Err(e)),
+            }
+        }
+
+        // Handshake and application messages use different ratchets.
+        pending.sort_by_key(|(position, sender_data)| {
+            (
+                ciphertexts[*position].epoch().as_u64(),
+                sender_data.leaf_index,
+                ciphertexts[*position].content_type() == ContentType::Application,
+                sender_data.generation,
+            )
+        });
+
+        let mut pending = pending.into_iter().peekable();
+        while let Some(&(position, _)) = pending.peek() {
+            // Look up the message secrets only once per epoch.
+            let epoch = ciphertexts[position].epoch();
+            let mut message_secrets = self
+                .message_secrets_mut(epoch)
+                .map_err(MessageDecryptionError::from);
+            while let Some((position, sender_data)) =
+                pending.next_if(|(position, _)| ciphertexts[*position].epoch() == epoch)
+            {
+                let result = match &mut message_secrets {
+                    Ok(message_secrets) => ciphertexts[position].to_plaintext(
+                        ciphersuite,
+                        backend,
+                        message_secrets,
+                        SecretTreeLeafIndex(sender_data.leaf_index),
+                        sender_ratchet_configuration,
+                        sender_data,
+                    ),
+                    Err(e) => Err(e.clone()),
+                };
+                results[position] = Some(result);
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| Err(LibraryError::custom("Message was skipped").into()))
+            })
+            .collect()
+    }
+
+    /// Verifies the membership tag of an [`MlsPlaintext`] against the
+    /// membership key of the epoch the message was sent in. Messages from
+    /// non-members don't carry a membership tag and are accepted as is.
+    /// Checks the following semantic validation:
+    ///  - ValSem007 Membership tag presence
+    ///  - ValSem008
+    pub(crate) fn verify_membership_tag(
+        &self,
+        plaintext: &MlsPlaintext,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(), ValidationError> {
+        if !plaintext.sender().is_member() {
+            return Ok(());
+        }
+        let message_secrets = self.message_secrets_for_epoch_checked(plaintext.epoch())?;
+        plaintext.verify_membership(
+            backend,
+            message_secrets.membership_key(),
+            message_secrets.serialized_context(),
+        )
+    }
+
+    /// Returns the [`MessageSecrets`] of the given epoch or a
+    /// [`ValidationError::NoPastEpochData`] if they are not available anymore.
+    fn message_secrets_for_epoch_checked(
+        &self,
+        epoch: GroupEpoch,
+    ) -> Result<&MessageSecrets, ValidationError> {
+        self.message_secrets_for_epoch(epoch).map_err(|e| match e {
+            SecretTreeError::TooDistantInThePast => ValidationError::NoPastEpochData,
+            _ => {
+                LibraryError::custom("Unexpected error while retrieving message secrets for epoch.")
+                    .into()
+            }
+        })
+    }
+
     /// This processing function does most of the semantic verifications.
-    /// It returns a [ProcessedMessage] enum.
+    /// It returns a [ProcessedMessage] enum. Commits are staged with
+    /// [`Self::stage_commit_at()`] at the time `now`.
     /// Checks the following semantic validation:
     ///  - ValSem008
     ///  - ValSem010
@@ -119,12 +367,14 @@ impl CoreGroup {
     ///  - ValSem243
     ///  - ValSem244
     ///  - ValSem246 (as part of ValSem010)
+    ///  - ValSem247
     pub(crate) fn process_unverified_message(
         &self,
         unverified_message: UnverifiedMessage,
         proposal_store: &ProposalStore,
         own_leaf_nodes: &[OpenMlsLeafNode],
         backend: &impl OpenMlsCryptoProvider,
+        now: Option<u64>,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
         let context_plaintext =
             UnverifiedContextMessage::from_unverified_message(unverified_message)?;
@@ -186,8 +436,13 @@ impl CoreGroup {
                         //  - ValSem242
                         //  - ValSem243
                         //  - ValSem244
-                        let staged_commit =
-                            self.stage_commit(&plaintext, proposal_store, own_leaf_nodes, backend)?;
+                        let staged_commit = self.stage_commit_at(
+                            &plaintext,
+                            proposal_store,
+                            own_leaf_nodes,
+                            backend,
+                            now,
+                        )?;
                         ProcessedMessageContent::StagedCommitMessage(Box::new(staged_commit))
                     }
                 };
@@ -201,10 +456,50 @@ impl CoreGroup {
                     Some(credential),
                 ))
             }
-            UnverifiedContextMessage::External(_external_message) => {
-                // We don't support messages from external senders yet
-                // TODO #151/#106
-                todo!()
+            UnverifiedContextMessage::External(unverified_external_message) => {
+                let credential = unverified_external_message.credential().clone();
+                // The sender index was checked against the external senders
+                // extension in `parse_message()`.
+                let signature_key = match unverified_external_message.sender() {
+                    Sender::External(sender_index) => {
+                        self.external_sender(*sender_index)?.signature_key().clone()
+                    }
+                    _ => return Err(LibraryError::custom("Expected an external sender").into()),
+                };
+                // Signature verification
+                let plaintext = unverified_external_message
+                    .into_verified(backend, &signature_key)
+                    .map_err(|_| ProcessMessageError::InvalidSignature)?
+                    .take_plaintext();
+
+                let sender = plaintext.sender().clone();
+                let authenticated_data = plaintext.authenticated_data().to_owned();
+
+                // External senders can only send proposals, which was checked
+                // in `parse_message()`.
+                let content = match plaintext.content() {
+                    MlsContentBody::Proposal(_) => ProcessedMessageContent::ProposalMessage(
+                        Box::new(QueuedProposal::from_mls_plaintext(
+                            self.ciphersuite(),
+                            backend,
+                            plaintext,
+                        )?),
+                    ),
+                    _ => {
+                        return Err(ProcessMessageError::ValidationError(
+                            ValidationError::UnauthorizedExternalSender,
+                        ))
+                    }
+                };
+
+                Ok(ProcessedMessage::new(
+                    group_id,
+                    epoch,
+                    sender,
+                    authenticated_data,
+                    content,
+                    Some(credential),
+                ))
             }
             UnverifiedContextMessage::NewMember(unverified_new_member_message) => {
                 let credential = unverified_new_member_message.credential().clone();
@@ -230,11 +525,12 @@ impl CoreGroup {
                     }
                     MlsContentBody::Commit(_) => {
                         // We throw a library error here, because a missing confirmation tag should be found during deserialization.
-                        let staged_commit = self.stage_commit(
+                        let staged_commit = self.stage_commit_at(
                             verified_new_member_message.plaintext(),
                             proposal_store,
                             own_leaf_nodes,
                             backend,
+                            now,
                         )?;
                         ProcessedMessageContent::StagedCommitMessage(Box::new(staged_commit))
                     }
@@ -260,7 +556,8 @@ impl CoreGroup {
     /// This function is used to parse messages from the DS. It checks for
     /// syntactic errors and does semantic validation as well. If the input is a
     /// [MlsCiphertext] message, it will be decrypted. It returns a
-    /// [ProcessedMessage] enum. Checks the following semantic validation:
+    /// [ProcessedMessage] enum. See [`Self::parse_message()`] for the
+    /// `sender_ratchet_configuration`. Checks the following semantic validation:
     ///  - ValSem002
     ///  - ValSem003
     ///  - ValSem004
@@ -297,25 +594,50 @@ impl CoreGroup {
     ///  - ValSem244
     ///  - ValSem245
     ///  - ValSem246 (as part of ValSem010)
-    pub(crate) fn process_message(
+    ///  - ValSem247
+    pub(crate) fn process_message<'a>(
         &mut self,
         backend: &impl OpenMlsCryptoProvider,
         message: MlsMessageIn,
-        sender_ratchet_configuration: &SenderRatchetConfiguration,
+        sender_ratchet_configuration: impl Into<Option<&'a SenderRatchetConfiguration>>,
+        proposal_store: &ProposalStore,
+        own_kpbs: &[OpenMlsLeafNode],
+    ) -> Result<ProcessedMessage, ProcessMessageError> {
+        self.process_message_at(
+            backend,
+            message,
+            sender_ratchet_configuration,
+            proposal_store,
+            own_kpbs,
+            None,
+        )
+    }
+
+    /// Like [`Self::process_message()`], but commits are rate limited
+    /// according to [`CoreGroupConfig::min_epoch_interval`] at the time `now`
+    /// (see [`Self::stage_commit_at()`]).
+    pub(crate) fn process_message_at<'a>(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        message: MlsMessageIn,
+        sender_ratchet_configuration: impl Into<Option<&'a SenderRatchetConfiguration>>,
         proposal_store: &ProposalStore,
         own_kpbs: &[OpenMlsLeafNode],
+        now: Option<u64>,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
         let unverified_message = self
             .parse_message(backend, message, sender_ratchet_configuration)
             .map_err(ProcessMessageError::from)?;
-        self.process_unverified_message(unverified_message, proposal_store, own_kpbs, backend)
+        self.process_unverified_message(unverified_message, proposal_store, own_kpbs, backend, now)
     }
 
-    /// Merge a [StagedCommit] into the group after inspection
+    /// Merge a [StagedCommit] into the group after inspection. The
+    /// `timestamp`, if given, is recorded as the start of the new epoch.
     pub(crate) fn merge_staged_commit(
         &mut self,
         staged_commit: StagedCommit,
         proposal_store: &mut ProposalStore,
+        timestamp: Option<u64>,
     ) {
         // Save the past epoch
         let past_epoch = self.context().epoch();
@@ -323,7 +645,7 @@ impl CoreGroup {
         let leaves = self.treesync().full_leave_members().collect();
         // Merge the staged commit into the group state and store the secret tree from the
         // previous epoch in the message secrets store.
-        if let Some(message_secrets) = self.merge_commit(staged_commit) {
+        if let Some(message_secrets) = self.merge_commit_at(staged_commit, timestamp) {
             self.message_secrets_store
                 .add(past_epoch, message_secrets, leaves);
         }
@@ -2,10 +2,10 @@ use crate::{
     ciphersuite::hash_ref::ProposalRef,
     error::LibraryError,
     framing::*,
-    group::errors::*,
+    group::{errors::*, GroupEpoch},
     messages::proposals::{
         AddProposal, PreSharedKeyProposal, Proposal, ProposalOrRef, ProposalOrRefType,
-        ProposalType, RemoveProposal, UpdateProposal,
+        ProposalType, ReInitProposal, RemoveProposal, UpdateProposal,
     },
 };
 
@@ -35,9 +35,29 @@ impl ProposalStore {
     pub(crate) fn add(&mut self, queued_proposal: QueuedProposal) {
         self.queued_proposals.push(queued_proposal);
     }
+    /// Adds the given proposals to the store, skipping every proposal whose
+    /// [`ProposalRef`] is already in the store or earlier in `proposals`.
+    pub(crate) fn insert_many_dedup(
+        &mut self,
+        proposals: impl IntoIterator<Item = QueuedProposal>,
+    ) {
+        let mut proposal_refs: HashSet<ProposalRef> = self.refs().collect();
+        for queued_proposal in proposals {
+            if proposal_refs.insert(queued_proposal.proposal_reference()) {
+                self.queued_proposals.push(queued_proposal);
+            }
+        }
+    }
     pub(crate) fn proposals(&self) -> impl Iterator<Item = &QueuedProposal> {
         self.queued_proposals.iter()
     }
+    /// Returns an iterator over the [`ProposalRef`]s of the stored proposals
+    /// in the order in which they were added.
+    pub fn refs(&self) -> impl Iterator<Item = ProposalRef> + '_ {
+        self.queued_proposals
+            .iter()
+            .map(|queued_proposal| queued_proposal.proposal_reference())
+    }
     pub(crate) fn is_empty(&self) -> bool {
         self.queued_proposals.is_empty()
     }
@@ -54,6 +74,10 @@ pub struct QueuedProposal {
     proposal_reference: ProposalRef,
     sender: Sender,
     proposal_or_ref_type: ProposalOrRefType,
+    /// The signed content the proposal was sent in. This is only set for
+    /// proposals sent by reference.
+    #[serde(default)]
+    authenticated_content: Option<MlsAuthContent>,
 }
 
 impl QueuedProposal {
@@ -73,6 +97,7 @@ impl QueuedProposal {
             proposal_reference,
             sender: mls_plaintext.sender().clone(),
             proposal_or_ref_type: ProposalOrRefType::Reference,
+            authenticated_content: Some(mls_plaintext),
         })
     }
 
@@ -89,6 +114,7 @@ impl QueuedProposal {
             proposal_reference,
             sender: sender.clone(),
             proposal_or_ref_type: ProposalOrRefType::Proposal,
+            authenticated_content: None,
         })
     }
     /// Returns the `Proposal` as a reference
@@ -107,6 +133,34 @@ impl QueuedProposal {
     pub fn sender(&self) -> &Sender {
         &self.sender
     }
+    /// Returns the epoch the proposal was sent in, if it was sent by
+    /// reference. Proposals that were committed inline are not sent in a
+    /// message of their own and thus return `None`.
+    pub fn proposed_in_epoch(&self) -> Option<GroupEpoch> {
+        self.authenticated_content
+            .as_ref()
+            .map(|authenticated_content| authenticated_content.epoch())
+    }
+    /// Returns the signed content the proposal was sent in, if it was sent by
+    /// reference.
+    pub(crate) fn authenticated_content(&self) -> Option<&MlsAuthContent> {
+        self.authenticated_content.as_ref()
+    }
+}
+
+/// A read-only description of a pending proposal, e.g. to display it to the
+/// user. See [`MlsGroup::describe_pending_proposals()`](crate::group::MlsGroup::describe_pending_proposals()).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProposalSummary {
+    /// The sender of the proposal.
+    pub sender: Sender,
+    /// The identity of the sender's credential, if it is known. This is the
+    /// case for members and for new members proposing to add themselves.
+    pub sender_identity: Option<Vec<u8>>,
+    /// The epoch the proposal was sent in.
+    pub proposed_in_epoch: GroupEpoch,
+    /// The proposal.
+    pub proposal: Proposal,
 }
 
 /// Proposal queue that helps filtering and sorting Proposals received during one
@@ -152,6 +206,11 @@ impl ProposalQueue {
         // Build the actual queue
         let mut proposal_queue = ProposalQueue::default();
 
+        // The queue drops duplicate proposals, so the number of committed
+        // proposals has to be taken from the Commit.
+        let committed_proposals_count = committed_proposals.len();
+        let mut contains_reinit = false;
+
         // Iterate over the committed proposals and insert the proposals in the queue
         for proposal_or_ref in committed_proposals.into_iter() {
             let queued_proposal = match proposal_or_ref {
@@ -191,9 +250,17 @@ impl ProposalQueue {
                     }
                 }
             };
+            if let Proposal::ReInit(_) = queued_proposal.proposal {
+                contains_reinit = true;
+            }
             proposal_queue.add(queued_proposal);
         }
 
+        // A ReInit proposal has to be committed on its own.
+        if contains_reinit && committed_proposals_count > 1 {
+            return Err(FromCommittedProposalsError::ReInitNotAlone);
+        }
+
         Ok(proposal_queue)
     }
 
@@ -255,6 +322,15 @@ impl ProposalQueue {
         })
     }
 
+    /// Returns the first ReInit proposal in the queue, if any.
+    pub(crate) fn reinit_proposal(&self) -> Option<&ReInitProposal> {
+        self.queued_proposals()
+            .find_map(|queued_proposal| match queued_proposal.proposal() {
+                Proposal::ReInit(reinit_proposal) => Some(reinit_proposal),
+                _ => None,
+            })
+    }
+
     /// Returns an iterator over all Remove proposals in the queue
     /// in the order of the the Commit message
     pub(crate) fn remove_proposals(&self) -> impl Iterator<Item = QueuedRemoveProposal> {
@@ -343,6 +419,8 @@ impl ProposalQueue {
         let mut proposal_pool: HashMap<ProposalRef, QueuedProposal> = HashMap::new();
         let mut contains_own_updates = false;
         let mut contains_external_init = false;
+        let mut contains_reinit = false;
+        let mut contains_group_context_extensions = false;
 
         // Aggregate both proposal types to a common iterator
         // We checked earlier that only proposals can end up here
@@ -405,8 +483,12 @@ impl ProposalQueue {
                     proposal_pool.insert(queued_proposal.proposal_reference(), queued_proposal);
                 }
                 Proposal::ReInit(_) => {
-                    // TODO #751: Only keep one ReInit
-                    proposal_pool.insert(queued_proposal.proposal_reference(), queued_proposal);
+                    // Only use the first ReInit proposal we find.
+                    if !contains_reinit {
+                        valid_proposals.insert(queued_proposal.proposal_reference());
+                        proposal_pool.insert(queued_proposal.proposal_reference(), queued_proposal);
+                        contains_reinit = true;
+                    }
                 }
                 Proposal::ExternalInit(_) => {
                     // Only use the first external init proposal we find.
@@ -418,8 +500,13 @@ impl ProposalQueue {
                 }
                 Proposal::AppAck(_) => unimplemented!("See #291"),
                 Proposal::GroupContextExtensions(_) => {
-                    // TODO: Validate proposal?
-                    proposal_pool.insert(queued_proposal.proposal_reference(), queued_proposal);
+                    // Only use the first GroupContextExtensions proposal we
+                    // find.
+                    if !contains_group_context_extensions {
+                        valid_proposals.insert(queued_proposal.proposal_reference());
+                        proposal_pool.insert(queued_proposal.proposal_reference(), queued_proposal);
+                        contains_group_context_extensions = true;
+                    }
                 }
             }
         }
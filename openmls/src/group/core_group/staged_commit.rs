@@ -1,4 +1,5 @@
-use crate::ciphersuite::signable::Verifiable;
+use crate::ciphersuite::{signable::Verifiable, SignaturePublicKey};
+use crate::extensions::{Clock, FixedClock, SystemClock};
 use crate::treesync::errors::TreeSyncAddLeaf;
 use crate::treesync::node::leaf_node::{
     LeafNodeTbs, OpenMlsLeafNode, TreeInfoTbs, VerifiableLeafNodeTbs,
@@ -13,7 +14,7 @@ use super::proposals::{
 use super::super::errors::*;
 use super::*;
 use core::fmt::Debug;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::mem;
 
 impl CoreGroup {
@@ -52,6 +53,7 @@ impl CoreGroup {
     ///  - ValSem242
     ///  - ValSem243
     ///  - ValSem244
+    ///  - ValSem247
     /// Returns an error if the given commit was sent by the owner of this
     /// group.
     pub(crate) fn stage_commit(
@@ -60,6 +62,25 @@ impl CoreGroup {
         proposal_store: &ProposalStore,
         own_leaf_nodes: &[OpenMlsLeafNode],
         backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<StagedCommit, StageCommitError> {
+        self.stage_commit_with_clock(
+            mls_content,
+            proposal_store,
+            own_leaf_nodes,
+            backend,
+            &SystemClock,
+        )
+    }
+
+    /// Like [`Self::stage_commit()`], but the lifetimes of added key packages
+    /// are checked against the given `clock` instead of the system time.
+    pub(crate) fn stage_commit_with_clock(
+        &self,
+        mls_content: &MlsAuthContent,
+        proposal_store: &ProposalStore,
+        own_leaf_nodes: &[OpenMlsLeafNode],
+        backend: &impl OpenMlsCryptoProvider,
+        clock: &impl Clock,
     ) -> Result<StagedCommit, StageCommitError> {
         // Extract the sender of the Commit message
         let ciphersuite = self.ciphersuite();
@@ -114,6 +135,9 @@ impl CoreGroup {
             FromCommittedProposalsError::LibraryError(e) => StageCommitError::LibraryError(e),
             FromCommittedProposalsError::ProposalNotFound => StageCommitError::MissingProposal,
             FromCommittedProposalsError::SelfRemoval => StageCommitError::AttemptedSelfRemoval,
+            FromCommittedProposalsError::ReInitNotAlone => {
+                ProposalValidationError::ReInitNotAlone.into()
+            }
         })?;
 
         let commit_update_leaf_node = commit
@@ -121,6 +145,16 @@ impl CoreGroup {
             .as_ref()
             .map(|update_path| update_path.leaf_node().clone());
 
+        // Enforce the group's local proposal type policy.
+        self.validate_allowed_proposal_types(&proposal_queue)?;
+
+        // Verify the signatures of proposals committed by reference again if
+        // the group is configured to do so.
+        self.validate_proposal_signatures(&proposal_queue, backend)?;
+
+        // Make sure all proposers are still members of the group.
+        self.validate_proposers(&proposal_queue)?;
+
         // Validate the staged proposals by doing the following checks:
         // ValSem100
         // ValSem101
@@ -129,13 +163,24 @@ impl CoreGroup {
         // ValSem104
         // ValSem105
         // ValSem106
-        self.validate_add_proposals(&proposal_queue)?;
+        self.validate_add_proposals(&proposal_queue, clock)?;
         // ValSem107
         // ValSem108
-        self.validate_remove_proposals(&proposal_queue)?;
+        self.validate_remove_proposals(
+            &proposal_queue,
+            sender.as_member(),
+            commit.path().is_some(),
+        )?;
 
         let public_key_set = match sender {
             Sender::Member(leaf_index) => {
+                // PSKs must be unique and resumption PSKs must reference a
+                // past epoch of the group. External commits are checked
+                // separately (ValSem247).
+                self.validate_psk_proposals(&proposal_queue)?;
+                // A ReInit proposal has to be committed on its own. External
+                // commits can't cover ReInit proposals (ValSem242).
+                self.validate_reinit_proposals(&proposal_queue)?;
                 // ValSem109
                 // ValSem110
                 // ValSem111
@@ -156,12 +201,31 @@ impl CoreGroup {
                 // ValSem242: External Commit must only cover inline proposal in allowlist (ExternalInit, Remove, PreSharedKey)
                 // ValSem243: External Commit, inline Remove Proposal: The identity and the endpoint_id of the removed
                 //            leaf are identical to the ones in the path KeyPackage.
-                self.validate_external_commit(&proposal_queue, commit_update_leaf_node.as_ref())?;
+                // ValSem247: External Commit, inline PreSharedKey proposal: Resumption PSKs must reference a known
+                //            past epoch of the group.
+                self.validate_external_commit(
+                    &proposal_queue,
+                    commit_update_leaf_node.as_ref(),
+                    backend,
+                )?;
+                // PSKs must be unique.
+                self.validate_psk_proposals(&proposal_queue)?;
                 // Since there are no update proposals in an External Commit we have no public keys to return
                 HashSet::new()
             }
         };
 
+        // Collect the signature key and capability changes before the tree is
+        // modified.
+        let path_leaf_node = match sender {
+            Sender::Member(leaf_index) => commit_update_leaf_node
+                .as_ref()
+                .map(|leaf_node| (*leaf_index, leaf_node)),
+            _ => None,
+        };
+        let credential_changes = self.credential_changes(&proposal_queue, path_leaf_node);
+        let capability_changes = self.capability_changes(&proposal_queue, path_leaf_node);
+
         // Create provisional tree and apply proposals
         let mut diff = self.treesync().empty_diff();
 
@@ -184,6 +248,20 @@ impl CoreGroup {
             }
         };
 
+        // A new member joining through an external commit is added at the
+        // sender index.
+        let new_member_leaf_node = match sender {
+            Sender::NewMemberCommit => commit_update_leaf_node
+                .as_ref()
+                .map(|leaf_node| (sender_index, leaf_node)),
+            _ => None,
+        };
+        let membership_delta = self.membership_delta(
+            &proposal_queue,
+            &apply_proposals_values.invitation_list,
+            new_member_leaf_node,
+        );
+
         // Check if we were removed from the group
         if apply_proposals_values.self_removed {
             let staged_diff = diff.into_staged_diff(backend, ciphersuite)?;
@@ -191,6 +269,9 @@ impl CoreGroup {
                 proposal_queue,
                 StagedCommitState::SelfRemoved(Box::new(staged_diff)),
                 commit_update_leaf_node,
+                credential_changes,
+                capability_changes,
+                membership_delta,
             ));
         }
 
@@ -235,6 +316,25 @@ impl CoreGroup {
             // Make sure that the new path key package is valid
             self.validate_path_key_package(sender_index, &leaf_node, public_key_set, sender)?;
 
+            // The committer's new leaf has to support the required
+            // capabilities of the group in the next epoch.
+            let next_extensions = apply_proposals_values
+                .extensions
+                .as_deref()
+                .unwrap_or_else(|| self.group_context.extensions());
+            if let Some(required_capabilities) = next_extensions
+                .iter()
+                .find(|e| e.extension_type() == ExtensionType::RequiredCapabilities)
+                .and_then(|e| e.as_required_capabilities_extension().ok())
+            {
+                if !leaf_node
+                    .capabilities()
+                    .supports_required_capabilities(required_capabilities)
+                {
+                    return Err(StageCommitError::CommitterInsufficientCapabilities);
+                }
+            }
+
             // If the committer is a `NewMemberCommit`, we have to add the leaf to
             // the tree before we can apply or even decrypt an update path.
             // While `apply_received_update_path` will happily update a
@@ -317,13 +417,28 @@ impl CoreGroup {
             provisional_epoch,
             diff.compute_tree_hashes(backend, ciphersuite)?,
             confirmed_transcript_hash.clone(),
-            self.group_context.extensions(),
+            apply_proposals_values
+                .extensions
+                .as_deref()
+                .unwrap_or_else(|| self.group_context.extensions()),
         );
 
+        // Only a ReInit creates a group with a new group ID, so a commit must
+        // never change it.
+        if provisional_group_context.group_id() != self.group_context.group_id() {
+            return Err(LibraryError::custom("The group ID changed in a commit").into());
+        }
+
         // Prepare the PskSecret
         let psk_secret =
             PskSecret::new(ciphersuite, backend, &apply_proposals_values.presharedkeys)?;
 
+        // Keep copies of the secrets to diagnose a confirmation tag mismatch,
+        // if enabled.
+        let diagnosis_secrets = self
+            .diagnose_confirmation_tag_mismatch
+            .then(|| (joiner_secret.clone(), psk_secret.clone()));
+
         // Create key schedule
         let mut key_schedule = KeySchedule::init(ciphersuite, backend, joiner_secret, psk_secret)?;
 
@@ -356,18 +471,28 @@ impl CoreGroup {
 
         // Verify confirmation tag
         // ValSem205
-        let own_confirmation_tag = provisional_epoch_secrets
-            .confirmation_key()
-            .tag(backend, &confirmed_transcript_hash)
-            .map_err(LibraryError::unexpected_crypto_error)?;
-        if &own_confirmation_tag != received_confirmation_tag {
+        if !confirmation_tag_matches(
+            received_confirmation_tag,
+            provisional_epoch_secrets.confirmation_key(),
+            &confirmed_transcript_hash,
+            backend,
+        )? {
             log::error!("Confirmation tag mismatch");
             log_crypto!(trace, "  Got:      {:x?}", received_confirmation_tag);
-            log_crypto!(trace, "  Expected: {:x?}", own_confirmation_tag);
             // TODO: We have tests expecting this error.
             //       They need to be rewritten.
             // debug_assert!(false, "Confirmation tag mismatch");
-            return Err(StageCommitError::ConfirmationTagMismatch);
+            return Err(match diagnosis_secrets {
+                Some((joiner_secret, psk_secret)) => self.diagnose_confirmation_tag_mismatch(
+                    mls_content,
+                    &provisional_group_context,
+                    received_confirmation_tag,
+                    joiner_secret,
+                    psk_secret,
+                    backend,
+                )?,
+                None => StageCommitError::ConfirmationTagMismatch,
+            });
         }
 
         let (provisional_group_epoch_secrets, provisional_message_secrets) =
@@ -394,25 +519,334 @@ impl CoreGroup {
             proposal_queue,
             staged_commit_state,
             commit_update_leaf_node,
+            credential_changes,
+            capability_changes,
+            membership_delta,
         ))
     }
 
+    /// Like [`Self::stage_commit()`], but first checks that at least the
+    /// group's minimum epoch interval (see
+    /// [`CoreGroupConfig::min_epoch_interval`]) has passed between the start of
+    /// the current epoch and `now`. Commits are never rate limited if `now`
+    /// or the start of the current epoch is unknown. If `now` is given, it is
+    /// also the time at which the lifetimes of added key packages are checked
+    /// (see [`Self::stage_commit_with_clock()`]).
+    ///
+    /// Returns a [`StageCommitError::CommitRateLimited`] if the commit arrived
+    /// too soon.
+    pub(crate) fn stage_commit_at(
+        &self,
+        mls_content: &MlsAuthContent,
+        proposal_store: &ProposalStore,
+        own_leaf_nodes: &[OpenMlsLeafNode],
+        backend: &impl OpenMlsCryptoProvider,
+        now: Option<u64>,
+    ) -> Result<StagedCommit, StageCommitError> {
+        if let (Some(min_epoch_interval), Some(epoch_age)) = (
+            self.min_epoch_interval,
+            now.and_then(|now| self.epoch_age(now)),
+        ) {
+            // Compare the durations, so that sub-second intervals aren't
+            // truncated to zero.
+            if Duration::from_secs(epoch_age) < min_epoch_interval {
+                return Err(StageCommitError::CommitRateLimited);
+            }
+        }
+        match now {
+            Some(now) => self.stage_commit_with_clock(
+                mls_content,
+                proposal_store,
+                own_leaf_nodes,
+                backend,
+                &FixedClock(now),
+            ),
+            None => self.stage_commit(mls_content, proposal_store, own_leaf_nodes, backend),
+        }
+    }
+
+    /// Returns `true` if the two given commits are both valid commits for the
+    /// current epoch of this group, but lead to different confirmed
+    /// transcript hashes, i.e. the group forked. This happens if a server
+    /// equivocates or if two members commit concurrently. The caller can then
+    /// apply its own fork resolution policy.
+    ///
+    /// A commit is valid if it can be staged with
+    /// [`Self::stage_commit()`]. Own commits can't be staged and thus never
+    /// lead to a detected fork. The group state is not modified.
+    pub(crate) fn detect_fork(
+        &self,
+        commit_a: &MlsAuthContent,
+        commit_b: &MlsAuthContent,
+        proposal_store: &ProposalStore,
+        own_leaf_nodes: &[OpenMlsLeafNode],
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> bool {
+        let confirmed_transcript_hash = |commit: &MlsAuthContent| -> Option<Vec<u8>> {
+            if commit.group_id() != self.group_id() {
+                return None;
+            }
+            self.stage_commit(commit, proposal_store, own_leaf_nodes, backend)
+                .ok()?;
+            update_confirmed_transcript_hash(
+                self.ciphersuite(),
+                backend,
+                &ConfirmedTranscriptHashInput::try_from(commit).ok()?,
+                &self.interim_transcript_hash,
+            )
+            .ok()
+        };
+        match (
+            confirmed_transcript_hash(commit_a),
+            confirmed_transcript_hash(commit_b),
+        ) {
+            (Some(hash_a), Some(hash_b)) => hash_a != hash_b,
+            _ => false,
+        }
+    }
+
+    /// Tries to find out why the confirmation tag of a received commit does
+    /// not match our own. If the tag can be reproduced from the same key
+    /// schedule inputs, but with a confirmed transcript hash that a diverging
+    /// implementation would plausibly compute, a
+    /// [`StageCommitError::TranscriptHashMismatch`] is returned. The
+    /// candidates are
+    ///  - the commit hashed with the other wire format and
+    ///  - the commit hashed onto the current confirmed instead of the interim
+    ///    transcript hash.
+    /// Otherwise, a [`StageCommitError::ConfirmationTagMismatch`] is returned.
+    ///
+    /// This only runs when staging a commit already failed and the diagnosis
+    /// is enabled (see
+    /// [`CoreGroupConfig::diagnose_confirmation_tag_mismatch`]), so the cost
+    /// of re-deriving the key schedule for each candidate is acceptable.
+    fn diagnose_confirmation_tag_mismatch(
+        &self,
+        mls_content: &MlsAuthContent,
+        provisional_group_context: &GroupContext,
+        received_confirmation_tag: &ConfirmationTag,
+        joiner_secret: JoinerSecret,
+        psk_secret: PskSecret,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<StageCommitError, LibraryError> {
+        let ciphersuite = self.ciphersuite();
+        let transcript_hash_input = ConfirmedTranscriptHashInput::try_from(mls_content)
+            .map_err(|_| LibraryError::custom("Could not convert commit content"))?;
+        let other_wire_format = match mls_content.wire_format() {
+            WireFormat::MlsPlaintext => WireFormat::MlsCiphertext,
+            WireFormat::MlsCiphertext => WireFormat::MlsPlaintext,
+        };
+        let candidates = [
+            update_confirmed_transcript_hash(
+                ciphersuite,
+                backend,
+                &transcript_hash_input.with_wire_format(other_wire_format),
+                &self.interim_transcript_hash,
+            )?,
+            update_confirmed_transcript_hash(
+                ciphersuite,
+                backend,
+                &ConfirmedTranscriptHashInput::try_from(mls_content)
+                    .map_err(|_| LibraryError::custom("Could not convert commit content"))?,
+                self.group_context.confirmed_transcript_hash(),
+            )?,
+        ];
+
+        for confirmed_transcript_hash in candidates {
+            let group_context = GroupContext::new(
+                ciphersuite,
+                provisional_group_context.group_id().clone(),
+                provisional_group_context.epoch(),
+                provisional_group_context.tree_hash().to_vec(),
+                confirmed_transcript_hash.clone(),
+                provisional_group_context.extensions(),
+            );
+            let serialized_group_context = group_context
+                .tls_serialize_detached()
+                .map_err(LibraryError::missing_bound_check)?;
+            let mut key_schedule = KeySchedule::init(
+                ciphersuite,
+                backend,
+                joiner_secret.clone(),
+                psk_secret.clone(),
+            )?;
+            key_schedule
+                .add_context(backend, &serialized_group_context)
+                .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?;
+            let confirmation_tag = key_schedule
+                .epoch_secrets(backend)
+                .map_err(|_| LibraryError::custom("Using the key schedule in the wrong state"))?
+                .confirmation_key()
+                .tag(backend, &confirmed_transcript_hash)
+                .map_err(LibraryError::unexpected_crypto_error)?;
+            if &confirmation_tag == received_confirmation_tag {
+                log::error!("The committer computed a different confirmed transcript hash");
+                return Ok(StageCommitError::TranscriptHashMismatch);
+            }
+        }
+
+        Ok(StageCommitError::ConfirmationTagMismatch)
+    }
+
+    /// Returns the leaves that are replaced by the Update proposals in the
+    /// given queue and by the (optional) leaf node in the Commit's update path.
+    /// Each entry contains the leaf index, the leaf node in the current tree
+    /// and the new leaf node. Leaves that are blank in the current tree are
+    /// omitted.
+    fn replaced_leaves<'a>(
+        &'a self,
+        proposal_queue: &'a ProposalQueue,
+        path_leaf_node: Option<(u32, &'a LeafNode)>,
+    ) -> Vec<(u32, &'a LeafNode, &'a LeafNode)> {
+        let mut updated_leaves = Vec::new();
+        for queued_proposal in proposal_queue.queued_proposals() {
+            if let (Proposal::Update(update_proposal), Sender::Member(leaf_index)) =
+                (queued_proposal.proposal(), queued_proposal.sender())
+            {
+                updated_leaves.push((*leaf_index, update_proposal.leaf_node()));
+            }
+        }
+        updated_leaves.extend(path_leaf_node);
+
+        updated_leaves
+            .into_iter()
+            .filter_map(
+                |(leaf_index, new_leaf_node)| match self.treesync().leaf(leaf_index) {
+                    Ok(Some(old_leaf_node)) => {
+                        Some((leaf_index, old_leaf_node.leaf_node(), new_leaf_node))
+                    }
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// Returns the signature key changes caused by the Update proposals in the
+    /// given queue and by the (optional) leaf node in the Commit's update path.
+    /// Each entry contains the leaf index, the signature key in the current
+    /// tree and the new signature key. Leaves where the signature key doesn't
+    /// change are omitted.
+    pub(crate) fn credential_changes(
+        &self,
+        proposal_queue: &ProposalQueue,
+        path_leaf_node: Option<(u32, &LeafNode)>,
+    ) -> Vec<(u32, SignaturePublicKey, SignaturePublicKey)> {
+        let mut credential_changes = Vec::new();
+        for (leaf_index, old_leaf_node, new_leaf_node) in
+            self.replaced_leaves(proposal_queue, path_leaf_node)
+        {
+            let old_key = old_leaf_node.credential().signature_key();
+            let new_key = new_leaf_node.credential().signature_key();
+            if old_key != new_key {
+                credential_changes.push((leaf_index, old_key.clone(), new_key.clone()));
+            }
+        }
+        credential_changes
+    }
+
+    /// Returns the changes of advertised [`Capabilities`] caused by the Update
+    /// proposals in the given queue and by the (optional) leaf node in the
+    /// Commit's update path. Each entry contains the leaf index, the
+    /// capabilities in the current tree and the new capabilities. Leaves where
+    /// the capabilities don't change are omitted.
+    pub(crate) fn capability_changes(
+        &self,
+        proposal_queue: &ProposalQueue,
+        path_leaf_node: Option<(u32, &LeafNode)>,
+    ) -> Vec<(u32, Capabilities, Capabilities)> {
+        let mut capability_changes = Vec::new();
+        for (leaf_index, old_leaf_node, new_leaf_node) in
+            self.replaced_leaves(proposal_queue, path_leaf_node)
+        {
+            let old_capabilities = old_leaf_node.capabilities();
+            let new_capabilities = new_leaf_node.capabilities();
+            if old_capabilities != new_capabilities {
+                capability_changes.push((
+                    leaf_index,
+                    old_capabilities.clone(),
+                    new_capabilities.clone(),
+                ));
+            }
+        }
+        capability_changes
+    }
+
+    /// Returns the [`MembershipDelta`] of a Commit covering the given proposal
+    /// queue. `invitation_list` contains the leaves the Add proposals were
+    /// applied to and `new_member_leaf_node` the leaf of a new member joining
+    /// through an external commit, if any. Removed members are looked up in
+    /// the current tree, so this has to be called before the Commit is merged.
+    pub(crate) fn membership_delta(
+        &self,
+        proposal_queue: &ProposalQueue,
+        invitation_list: &[(LeafIndex, AddProposal)],
+        new_member_leaf_node: Option<(u32, &LeafNode)>,
+    ) -> MembershipDelta {
+        let mut removed: BTreeMap<u32, Member> = proposal_queue
+            .remove_proposals()
+            .filter_map(|queued_remove_proposal| {
+                let leaf_index = queued_remove_proposal.remove_proposal().removed();
+                match self.treesync().leaf(leaf_index) {
+                    Ok(Some(leaf_node)) => {
+                        Some((leaf_index, Member::new(leaf_index, leaf_node.leaf_node())))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+
+        let mut membership_delta = MembershipDelta::default();
+        let added_leaf_nodes = invitation_list
+            .iter()
+            .map(|(leaf_index, add_proposal)| (*leaf_index, add_proposal.key_package().leaf_node()))
+            .chain(new_member_leaf_node);
+        for (leaf_index, leaf_node) in added_leaf_nodes {
+            let new_member = Member::new(leaf_index, leaf_node);
+            // A leaf that is blanked by a Remove proposal can be filled again
+            // by an Add proposal in the same Commit.
+            match removed.remove(&leaf_index) {
+                Some(old_member) => membership_delta
+                    .reused
+                    .push((leaf_index, old_member, new_member)),
+                None => membership_delta.added.push(new_member),
+            }
+        }
+        membership_delta.removed = removed.into_values().collect();
+        membership_delta
+    }
+
     /// Merges a [StagedCommit] into the group state and optionally return a [`SecretTree`]
     /// from the previous epoch. The secret tree is returned if the Commit does not contain a self removal.
     ///
     /// This function should not fail and only returns a [`Result`], because it
     /// might throw a `LibraryError`.
     pub(crate) fn merge_commit(&mut self, staged_commit: StagedCommit) -> Option<MessageSecrets> {
+        self.merge_commit_at(staged_commit, None)
+    }
+
+    /// Like [`Self::merge_commit()`], but records `timestamp` as the time at
+    /// which the new epoch started. If no timestamp is given, the start of the
+    /// new epoch is unknown.
+    pub(crate) fn merge_commit_at(
+        &mut self,
+        staged_commit: StagedCommit,
+        timestamp: Option<u64>,
+    ) -> Option<MessageSecrets> {
+        if staged_commit.reinit_proposal().is_some() {
+            self.terminated = true;
+        }
         match staged_commit.state {
             StagedCommitState::SelfRemoved(staged_diff) => {
                 self.tree.merge_diff(*staged_diff);
                 None
             }
             StagedCommitState::GroupMember(state) => {
+                let previous_members: Vec<Member> = self.treesync().full_leave_members().collect();
                 self.group_context = state.group_context;
                 self.group_epoch_secrets = state.group_epoch_secrets;
 
-                // Replace the previous message secrets with the new ones and return the previous message secrets
+                // Replace the previous message secrets with the new ones and return the previous message secrets.
+                // The previous message secrets are zeroized once the caller drops them.
                 let mut message_secrets = state.message_secrets;
                 mem::swap(
                     &mut message_secrets,
@@ -420,12 +854,37 @@ impl CoreGroup {
                 );
 
                 self.interim_transcript_hash = state.interim_transcript_hash;
+                self.epoch_started_at = timestamp;
 
                 self.tree.merge_diff(state.staged_diff);
+                self.update_leaf_key_epochs(&previous_members);
                 Some(message_secrets)
             }
         }
     }
+
+    /// Record the current epoch for every leaf whose encryption key differs
+    /// from the one in `previous_members`. Entries of blank leaves and entries
+    /// older than the retained-epoch window are dropped.
+    fn update_leaf_key_epochs(&mut self, previous_members: &[Member]) {
+        let epoch = self.group_context.epoch();
+        let members: Vec<Member> = self.treesync().full_leave_members().collect();
+        self.leaf_key_epochs
+            .retain(|leaf_index, _| members.iter().any(|member| member.index == *leaf_index));
+        for member in members {
+            let key_unchanged = previous_members.iter().any(|previous_member| {
+                previous_member.index == member.index
+                    && previous_member.encryption_key == member.encryption_key
+            });
+            if !key_unchanged {
+                self.leaf_key_epochs.insert(member.index, epoch);
+            }
+        }
+        let max_epochs = self.message_secrets_store.max_epochs() as u64;
+        self.leaf_key_epochs.retain(|_, last_update_epoch| {
+            last_update_epoch.as_u64() + max_epochs >= epoch.as_u64()
+        });
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -434,12 +893,53 @@ pub(crate) enum StagedCommitState {
     GroupMember(Box<MemberStagedCommitState>),
 }
 
+/// The changes a Commit makes to the membership of a group.
+///
+/// Every leaf index touched by the Commit's Add and Remove proposals (and by
+/// the new member of an external commit) shows up in exactly one of
+/// [`MembershipDelta::added()`], [`MembershipDelta::removed()`] and
+/// [`MembershipDelta::reused_indices()`]. Members that only update their leaf
+/// are not part of the delta.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MembershipDelta {
+    added: Vec<Member>,
+    removed: Vec<Member>,
+    reused: Vec<(u32, Member, Member)>,
+}
+
+impl MembershipDelta {
+    /// Returns an iterator over the members added to a leaf that was blank or
+    /// new before the Commit.
+    pub fn added(&self) -> impl Iterator<Item = &Member> {
+        self.added.iter()
+    }
+
+    /// Returns an iterator over the removed members whose leaf stays blank
+    /// after the Commit.
+    pub fn removed(&self) -> impl Iterator<Item = &Member> {
+        self.removed.iter()
+    }
+
+    /// Returns an iterator over the leaves whose member is removed and that
+    /// are filled with a new member in the same Commit. Each item is a tuple
+    /// of the leaf index, the removed member and the new member.
+    pub fn reused_indices(&self) -> impl Iterator<Item = (u32, Member, Member)> + '_ {
+        self.reused.iter().cloned()
+    }
+}
+
 /// Contains the changes from a commit to the group state.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StagedCommit {
     staged_proposal_queue: ProposalQueue,
     state: StagedCommitState,
     commit_update_leaf_node: Option<LeafNode>,
+    #[serde(default)]
+    credential_changes: Vec<(u32, SignaturePublicKey, SignaturePublicKey)>,
+    #[serde(default)]
+    capability_changes: Vec<(u32, Capabilities, Capabilities)>,
+    #[serde(default)]
+    membership_delta: MembershipDelta,
 }
 
 impl StagedCommit {
@@ -449,11 +949,17 @@ impl StagedCommit {
         staged_proposal_queue: ProposalQueue,
         state: StagedCommitState,
         commit_update_leaf_node: Option<LeafNode>,
+        credential_changes: Vec<(u32, SignaturePublicKey, SignaturePublicKey)>,
+        capability_changes: Vec<(u32, Capabilities, Capabilities)>,
+        membership_delta: MembershipDelta,
     ) -> Self {
         StagedCommit {
             staged_proposal_queue,
             state,
             commit_update_leaf_node,
+            credential_changes,
+            capability_changes,
+            membership_delta,
         }
     }
 
@@ -477,17 +983,81 @@ impl StagedCommit {
         self.staged_proposal_queue.psk_proposals()
     }
 
+    /// Returns the ReInit proposal that is covered by the Commit message, if
+    /// any. A group is terminated once a Commit with a ReInit proposal is
+    /// merged.
+    pub fn reinit_proposal(&self) -> Option<&ReInitProposal> {
+        self.staged_proposal_queue.reinit_proposal()
+    }
+
     /// Returns an optional leaf node from the Commit's update path.
     /// A leaf node is returned for full and empty Commits, but not for partial Commits.
     pub fn commit_update_key_package(&self) -> Option<&LeafNode> {
         self.commit_update_leaf_node.as_ref()
     }
 
+    /// Returns the epoch the group enters when this Commit is merged, or `None`
+    /// if the member was removed through this Commit.
+    pub(crate) fn new_epoch(&self) -> Option<GroupEpoch> {
+        match &self.state {
+            StagedCommitState::GroupMember(state) => Some(state.group_context.epoch()),
+            StagedCommitState::SelfRemoved(_) => None,
+        }
+    }
+
     /// Returns `true` if the member was removed through a proposal covered by this Commit message
     /// and `false` otherwise.
     pub fn self_removed(&self) -> bool {
         matches!(self.state, StagedCommitState::SelfRemoved(_))
     }
+
+    /// Returns an iterator over the members whose signature key is changed by
+    /// this Commit, either through an Update proposal or through the Commit's
+    /// update path. Each item is a tuple of the member's leaf index, the old
+    /// signature key and the new signature key.
+    pub fn credential_changes(
+        &self,
+    ) -> impl Iterator<Item = (u32, SignaturePublicKey, SignaturePublicKey)> + '_ {
+        self.credential_changes.iter().cloned()
+    }
+
+    /// Returns an iterator over the members whose advertised [`Capabilities`]
+    /// are changed by this Commit, either through an Update proposal or
+    /// through the Commit's update path. Each item is a tuple of the member's
+    /// leaf index, the old capabilities and the new capabilities.
+    pub fn capability_changes(
+        &self,
+    ) -> impl Iterator<Item = (u32, Capabilities, Capabilities)> + '_ {
+        self.capability_changes.iter().cloned()
+    }
+
+    /// Returns the [`MembershipDelta`] of this Commit, i.e. the members that
+    /// are added, removed or replaced at the same leaf index.
+    pub fn membership_delta(&self) -> &MembershipDelta {
+        &self.membership_delta
+    }
+
+    /// Returns an iterator over the members removed by this Commit, ordered by
+    /// leaf index. This includes removed members whose leaf is filled with a
+    /// new member in the same Commit, and the own member if this Commit
+    /// removes it (see [`Self::self_removed()`]).
+    ///
+    /// The members are resolved in the tree before the Commit, so that they
+    /// are still available after the Commit is merged.
+    pub fn removed_members(&self) -> impl Iterator<Item = Member> {
+        let mut removed_members: Vec<Member> = self
+            .membership_delta
+            .removed()
+            .cloned()
+            .chain(
+                self.membership_delta
+                    .reused_indices()
+                    .map(|(_, removed_member, _)| removed_member),
+            )
+            .collect();
+        removed_members.sort_by_key(|member| member.index);
+        removed_members.into_iter()
+    }
 }
 
 /// This struct is used internally by [StagedCommit] to encapsulate all the modified group state.
@@ -2,18 +2,30 @@ use openmls_rust_crypto::OpenMlsRustCrypto;
 use openmls_traits::{
     crypto::OpenMlsCrypto, key_store::OpenMlsKeyStore, types::HpkeCiphertext, OpenMlsCryptoProvider,
 };
-use tls_codec::Serialize;
+use std::time::Duration;
+use tls_codec::{Deserialize, Serialize, Size};
 
 use crate::{
-    ciphersuite::{signable::Signable, AeadNonce},
+    ciphersuite::{signable::Signable, AeadNonce, Mac, Secret},
     credentials::*,
+    extensions::{ApplicationIdExtension, Extension, ExtensionType, RequiredCapabilitiesExtension},
     framing::*,
     group::{errors::*, *},
     key_packages::*,
-    messages::*,
-    schedule::psk::*,
+    messages::{
+        proposals::{
+            AddProposal, GroupContextExtensionProposal, PreSharedKeyProposal, Proposal,
+            ProposalOrRef, ProposalType, RemoveProposal, UpdateProposal,
+        },
+        *,
+    },
+    schedule::{psk::*, KeySchedule},
     test_utils::*,
-    treesync::errors::ApplyUpdatePathError,
+    tree::sender_ratchet::SenderRatchetConfiguration,
+    treesync::{
+        errors::ApplyUpdatePathError,
+        node::leaf_node::{LeafNodeTbs, TreeInfoTbs},
+    },
     versions::ProtocolVersion,
 };
 
@@ -39,7 +51,7 @@ fn test_core_group_persistence(ciphersuite: Ciphersuite, backend: &impl OpenMlsC
 
     // Alice creates a group
     let alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
-        .with_extensions(vec![])
+        .with_leaf_node_extensions(vec![])
         .build(&alice_credential_bundle, backend)
         .expect("Error creating group.");
 
@@ -52,9 +64,17 @@ fn test_core_group_persistence(ciphersuite: Ciphersuite, backend: &impl OpenMlsC
         .reopen()
         .expect("Error re-opening serialized group state file");
     let alice_group_deserialized =
-        CoreGroup::load(file_in).expect("Could not deserialize mls group");
+        CoreGroup::load(file_in, backend).expect("Could not deserialize mls group");
 
     assert_eq!(alice_group, alice_group_deserialized);
+
+    // The cached tree hash of the loaded group matches a fresh computation.
+    assert_eq!(
+        alice_group_deserialized
+            .recompute_tree_hash(backend)
+            .expect("Could not recompute tree hash."),
+        alice_group_deserialized.tree_hash()
+    );
 }
 
 /// This function flips the last byte of the ciphertext.
@@ -815,3 +835,4785 @@ fn test_proposal_application_after_self_was_removed(
     assert_eq!(bob_members.next().unwrap().identity, b"Alice");
     assert_eq!(bob_members.next().unwrap().identity, b"Charlie");
 }
+
+#[apply(ciphersuites_and_backends)]
+fn test_default_handshake_wire_format(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    // The framing parameters ask for plaintext, but the group is configured to
+    // always encrypt handshake messages.
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let config = CoreGroupConfig {
+        default_handshake_wire_format: WireFormat::MlsCiphertext,
+        ..CoreGroupConfig::default()
+    };
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_config(config)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    assert_eq!(bob_add_proposal.wire_format(), WireFormat::MlsCiphertext);
+
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    assert_eq!(
+        create_commit_result.commit.wire_format(),
+        WireFormat::MlsCiphertext
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_verify_proposal_signatures_in_commit(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    // === Alice creates a group that verifies proposal signatures in commits ===
+    let config = CoreGroupConfig {
+        verify_proposal_signatures_in_commit: true,
+        ..CoreGroupConfig::default()
+    };
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_config(config)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // === Alice adds Bob ===
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal.");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob commits a proposal by reference, which is signed with either his or
+    // Charlie's credential.
+    let commit_add_proposal = |signer: &CredentialBundle| {
+        let charlie_add_proposal = bob_group
+            .create_add_proposal(
+                framing_parameters,
+                signer,
+                charlie_kpb.key_package().clone(),
+                backend,
+            )
+            .expect("Could not create proposal.");
+        let proposal_store = ProposalStore::from_queued_proposal(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, charlie_add_proposal)
+                .expect("Could not create QueuedProposal."),
+        );
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&bob_credential_bundle)
+            .proposal_store(&proposal_store)
+            .force_self_update(false)
+            .build();
+        let create_commit_result = bob_group
+            .create_commit(params, backend)
+            .expect("Error creating commit");
+        (create_commit_result.commit, proposal_store)
+    };
+
+    // A proposal with a valid signature is accepted.
+    let (commit, proposal_store) = commit_add_proposal(&bob_credential_bundle);
+    alice_group
+        .stage_commit(&commit, &proposal_store, &[], backend)
+        .expect("Error staging commit with a valid proposal signature.");
+
+    // A proposal that wasn't signed by its sender is rejected.
+    let (commit, proposal_store) = commit_add_proposal(&charlie_credential_bundle);
+    let error = alice_group
+        .stage_commit(&commit, &proposal_store, &[], backend)
+        .expect_err("Staged commit with an invalid proposal signature.");
+    assert_eq!(
+        error,
+        StageCommitError::ValidationError(ValidationError::InvalidSignature)
+    );
+
+    // Without the strict mode, the signature is not checked again.
+    alice_group.verify_proposal_signatures_in_commit = false;
+    alice_group
+        .stage_commit(&commit, &proposal_store, &[], backend)
+        .expect("Error staging commit.");
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_epoch_age(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    let proposal_store = ProposalStore::default();
+
+    // Groups created without a clock don't know when their epoch started.
+    assert_eq!(alice_group.epoch_age(100), None);
+
+    let self_update = |alice_group: &CoreGroup| {
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&alice_credential_bundle)
+            .proposal_store(&proposal_store)
+            .force_self_update(true)
+            .build();
+        alice_group
+            .create_commit(params, backend)
+            .expect("Error creating commit")
+            .staged_commit
+    };
+
+    // The epoch start is taken from the timestamp supplied when merging.
+    let staged_commit = self_update(&alice_group);
+    alice_group.merge_commit_at(staged_commit, Some(100));
+    assert_eq!(alice_group.epoch_age(150), Some(50));
+    assert_eq!(alice_group.epoch_age(50), Some(0));
+
+    // The epoch start survives persistence.
+    let mut file_out = tempfile::NamedTempFile::new().expect("Could not create file");
+    alice_group
+        .save(&mut file_out)
+        .expect("Could not write group state to file");
+    let file_in = file_out
+        .reopen()
+        .expect("Error re-opening serialized group state file");
+    let alice_group_deserialized =
+        CoreGroup::load(file_in, backend).expect("Could not deserialize core group");
+    assert_eq!(alice_group_deserialized.epoch_age(150), Some(50));
+
+    // Merging without a timestamp leaves the start of the new epoch unknown.
+    let staged_commit = self_update(&alice_group);
+    alice_group.merge_commit(staged_commit);
+    assert_eq!(alice_group.epoch_age(150), None);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_member_last_update_epoch(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    // Alice keeps the secrets of one past epoch.
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_max_past_epoch_secrets(1)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    assert_eq!(
+        alice_group.member_last_update_epoch(0),
+        Some(GroupEpoch::from(0))
+    );
+
+    let commit = |alice_group: &mut CoreGroup, key_package: Option<&KeyPackage>| {
+        let mut proposal_store = ProposalStore::default();
+        if let Some(key_package) = key_package {
+            let add_proposal = alice_group
+                .create_add_proposal(
+                    framing_parameters,
+                    &alice_credential_bundle,
+                    key_package.clone(),
+                    backend,
+                )
+                .expect("Could not create proposal.");
+            proposal_store.add(
+                QueuedProposal::from_mls_plaintext(ciphersuite, backend, add_proposal)
+                    .expect("Could not create QueuedProposal."),
+            );
+        }
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&alice_credential_bundle)
+            .proposal_store(&proposal_store)
+            .force_self_update(key_package.is_none())
+            .build();
+        let create_commit_result = alice_group
+            .create_commit(params, backend)
+            .expect("Error creating commit");
+        alice_group
+            .merge_commit(create_commit_result.staged_commit)
+            .expect("error merging own staged commit");
+    };
+
+    // Epoch 1: Alice adds Bob without updating her own key.
+    commit(&mut alice_group, Some(bob_kpb.key_package()));
+    assert_eq!(
+        alice_group.member_last_update_epoch(0),
+        Some(GroupEpoch::from(0))
+    );
+    assert_eq!(
+        alice_group.member_last_update_epoch(1),
+        Some(GroupEpoch::from(1))
+    );
+
+    // Epoch 2: Alice updates her own key.
+    commit(&mut alice_group, None);
+    assert_eq!(
+        alice_group.member_last_update_epoch(0),
+        Some(GroupEpoch::from(2))
+    );
+    assert_eq!(
+        alice_group.member_last_update_epoch(1),
+        Some(GroupEpoch::from(1))
+    );
+
+    // Epoch 3: Bob's last update falls out of the retained-epoch window.
+    commit(&mut alice_group, Some(charlie_kpb.key_package()));
+    assert_eq!(
+        alice_group.member_last_update_epoch(0),
+        Some(GroupEpoch::from(2))
+    );
+    assert_eq!(alice_group.member_last_update_epoch(1), None);
+    assert_eq!(
+        alice_group.member_last_update_epoch(2),
+        Some(GroupEpoch::from(3))
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_credential_changes(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    let proposal_store = ProposalStore::default();
+
+    // A self update with the same credential doesn't change the signature key.
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    assert_eq!(
+        create_commit_result
+            .staged_commit
+            .credential_changes()
+            .count(),
+        0
+    );
+
+    // Alice rotates her signature key in a self update.
+    let new_credential_bundle = CredentialBundle::new(
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&new_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let credential_changes: Vec<_> = create_commit_result
+        .staged_commit
+        .credential_changes()
+        .collect();
+    assert_eq!(
+        credential_changes,
+        vec![(
+            alice_group.own_leaf_index(),
+            alice_credential_bundle.credential().signature_key().clone(),
+            new_credential_bundle.credential().signature_key().clone(),
+        )]
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_capability_changes(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob updates his leaf and only advertises the group's ciphersuite.
+    let old_capabilities = bob_group
+        .treesync()
+        .own_leaf_node()
+        .expect("Missing own leaf.")
+        .leaf_node()
+        .capabilities()
+        .clone();
+    let bob_update_kpb =
+        KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, Vec::new())
+            .expect("An unexpected error occurred.");
+    let mut bob_update_leaf_node = bob_update_kpb.key_package().leaf_node().clone();
+    bob_update_leaf_node
+        .capabilities_mut()
+        .set_ciphersuites(vec![ciphersuite]);
+    let new_capabilities = bob_update_leaf_node.capabilities().clone();
+    assert_ne!(old_capabilities, new_capabilities);
+
+    let bob_update_proposal = bob_group
+        .create_update_proposal(
+            framing_parameters,
+            &bob_credential_bundle,
+            bob_update_leaf_node,
+            backend,
+        )
+        .expect("Could not create proposal.");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_update_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+
+    // Alice commits Bob's update. Her own path leaf keeps its capabilities.
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let capability_changes: Vec<_> = create_commit_result
+        .staged_commit
+        .capability_changes()
+        .collect();
+    assert_eq!(
+        capability_changes,
+        vec![(
+            bob_group.own_leaf_index(),
+            old_capabilities,
+            new_capabilities
+        )]
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_new_from_welcome_verified(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    // === Alice creates a group ===
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating group.");
+
+    // Export a GroupInfo for the current (soon to be outdated) epoch.
+    let stale_group_info = alice_group
+        .export_group_info(backend, &alice_credential_bundle, false)
+        .expect("Error exporting group info.")
+        .into_verifiable_group_info();
+
+    // === Alice adds Bob and Charlie ===
+    let mut proposal_store = ProposalStore::default();
+    for key_package in [bob_kpb.key_package(), charlie_kpb.key_package()] {
+        let add_proposal = alice_group
+            .create_add_proposal(
+                framing_parameters,
+                &alice_credential_bundle,
+                key_package.clone(),
+                backend,
+            )
+            .expect("Could not create proposal.");
+        proposal_store.add(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, add_proposal)
+                .expect("Could not create QueuedProposal."),
+        );
+    }
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging own staged commit");
+    let welcome = create_commit_result
+        .welcome_option
+        .expect("An unexpected error occurred.");
+
+    let group_info = alice_group
+        .export_group_info(backend, &alice_credential_bundle, false)
+        .expect("Error exporting group info.")
+        .into_verifiable_group_info();
+
+    // === Bob tries to join with a GroupInfo that doesn't match the Welcome ===
+    let error = CoreGroup::new_from_welcome_verified(
+        welcome.clone(),
+        stale_group_info,
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect_err("Joining with a mismatching GroupInfo should fail.");
+    assert_eq!(error, WelcomeError::GroupInfoMismatch);
+
+    // === Charlie joins with a matching GroupInfo ===
+    let charlie_group = CoreGroup::new_from_welcome_verified(
+        welcome,
+        group_info,
+        Some(alice_group.treesync().export_nodes()),
+        charlie_kpb,
+        backend,
+    )
+    .expect("Joining with a matching GroupInfo failed.");
+    assert_eq!(charlie_group.context(), alice_group.context());
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_allowed_proposal_types(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    // Alice only accepts commits containing Remove proposals.
+    let config = CoreGroupConfig {
+        allowed_proposal_types: Some([ProposalType::Remove].into_iter().collect()),
+        ..CoreGroupConfig::default()
+    };
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_config(config)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // The policy only applies to incoming commits, so Alice can still add Bob.
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob commits an Add proposal for Charlie, which Alice's policy forbids.
+    let charlie_add_proposal = bob_group
+        .create_add_proposal(
+            framing_parameters,
+            &bob_credential_bundle,
+            charlie_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, charlie_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&bob_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = bob_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+
+    let error = alice_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect_err("Staging a commit with a disallowed proposal type should fail.");
+    assert_eq!(
+        error,
+        StageCommitError::ProposalValidationError(ProposalValidationError::ProposalTypeNotAllowed)
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_verify_membership_tag(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let mut plaintext = MlsPlaintext::from(bob_add_proposal);
+    plaintext
+        .set_membership_tag(
+            backend,
+            alice_group.message_secrets().serialized_context(),
+            alice_group.message_secrets().membership_key(),
+        )
+        .expect("Could not set membership tag.");
+
+    alice_group
+        .verify_membership_tag(&plaintext, backend)
+        .expect("Valid membership tag was rejected.");
+
+    // Tamper with the membership tag.
+    plaintext.set_membership_tag_test(MembershipTag(
+        Mac::new(backend, &Secret::default(), &[1, 2, 3])
+            .expect("Could not compute membership tag."),
+    ));
+    let error = alice_group
+        .verify_membership_tag(&plaintext, backend)
+        .expect_err("Tampered membership tag was accepted.");
+    assert_eq!(error, ValidationError::InvalidMembershipTag);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_transcript_hash_matches_ciphersuite(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    assert!(alice_group.transcript_hash_matches_ciphersuite());
+
+    // A transcript hash of the right length is fine.
+    alice_group.interim_transcript_hash = vec![0u8; ciphersuite.hash_length()];
+    assert!(alice_group.transcript_hash_matches_ciphersuite());
+
+    // A transcript hash computed with a different hash algorithm is not.
+    alice_group.interim_transcript_hash = vec![0u8; ciphersuite.hash_length() + 1];
+    assert!(!alice_group.transcript_hash_matches_ciphersuite());
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_member_leaf_node_metadata(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let bob_key_package = bob_kpb.key_package().clone();
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_key_package.clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    // Bob's member entry carries the capabilities and lifetime of his leaf.
+    let bob = alice_group
+        .treesync()
+        .full_leave_members()
+        .find(|member| member.identity == b"Bob")
+        .expect("Couldn't find Bob in tree.");
+    assert_eq!(
+        bob.capabilities(),
+        bob_key_package.leaf_node().capabilities()
+    );
+    assert_eq!(bob.lifetime(), bob_key_package.leaf_node().life_time());
+    assert!(bob.capabilities().ciphersuites().contains(&ciphersuite));
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_welcome_ciphersuite_mismatch(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    // We need a second ciphersuite that is supported by the backend.
+    let other_ciphersuite = match backend
+        .crypto()
+        .supported_ciphersuites()
+        .into_iter()
+        .find(|&cs| cs != ciphersuite)
+    {
+        Some(other_ciphersuite) => other_ciphersuite,
+        None => return,
+    };
+
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_, bob_other_kpb) = setup_client("Bob", other_ciphersuite, backend);
+
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+
+    // Bob tries to join with a key package bundle of a different ciphersuite.
+    let error = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        None,
+        bob_other_kpb,
+        backend,
+    )
+    .expect_err("Joining with a mismatching ciphersuite should fail.");
+    assert_eq!(
+        error,
+        WelcomeError::CiphersuiteMismatch {
+            group: ciphersuite,
+            key_package: other_ciphersuite,
+        }
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_group_context_extensions_ordering(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice lists the extensions in descending order of their type.
+    let application_id = Extension::ApplicationId(ApplicationIdExtension::new(b"app"));
+    let external_senders = Extension::ExternalSenders(Vec::new());
+    let proposal = Proposal::GroupContextExtensions(GroupContextExtensionProposal::new(&[
+        external_senders.clone(),
+        application_id.clone(),
+    ]));
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![proposal])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+
+    let staged_commit = bob_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+    bob_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    // Both members end up with the same, canonically ordered group context.
+    assert_eq!(alice_group.context(), bob_group.context());
+    assert_eq!(
+        alice_group.group_context_extensions(),
+        &[application_id, external_senders]
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_tag_lengths(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let mut plaintext = MlsPlaintext::from(create_commit_result.commit);
+    plaintext
+        .set_membership_tag(
+            backend,
+            alice_group.message_secrets().serialized_context(),
+            alice_group.message_secrets().membership_key(),
+        )
+        .expect("Could not set membership tag.");
+
+    let confirmation_tag = plaintext
+        .confirmation_tag()
+        .expect("Commit without confirmation tag.");
+    assert_eq!(
+        confirmation_tag.0.mac_value.len(),
+        alice_group.confirmation_tag_length()
+    );
+    let membership_tag = plaintext
+        .membership_tag()
+        .expect("Plaintext without membership tag.");
+    assert_eq!(
+        membership_tag.0.mac_value.len(),
+        alice_group.membership_tag_length()
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_min_epoch_interval(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    // Alice accepts at most one commit per minute.
+    let config = CoreGroupConfig {
+        min_epoch_interval: Some(Duration::from_secs(60)),
+        ..CoreGroupConfig::default()
+    };
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_config(config)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    let proposal_store = ProposalStore::default();
+    let bob_self_update = |bob_group: &mut CoreGroup| {
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&bob_credential_bundle)
+            .proposal_store(&proposal_store)
+            .force_self_update(true)
+            .build();
+        let create_commit_result = bob_group
+            .create_commit(params, backend)
+            .expect("Error creating commit");
+        bob_group
+            .merge_commit(create_commit_result.staged_commit)
+            .expect("error merging own commit");
+        create_commit_result.commit
+    };
+
+    // Alice merged the last commit without a timestamp, so she doesn't know
+    // when the epoch started and doesn't rate limit the first commit.
+    let bob_commit = bob_self_update(&mut bob_group);
+    let staged_commit = alice_group
+        .stage_commit_at(&bob_commit, &proposal_store, &[], backend, Some(0))
+        .expect("Error staging commit.");
+    alice_group.merge_commit_at(staged_commit, Some(100));
+
+    // A commit arriving too soon after the start of the epoch is rejected.
+    let bob_commit = bob_self_update(&mut bob_group);
+    let error = alice_group
+        .stage_commit_at(&bob_commit, &proposal_store, &[], backend, Some(130))
+        .expect_err("Commit arriving too soon was staged.");
+    assert_eq!(error, StageCommitError::CommitRateLimited);
+
+    // Once the interval has passed, the commit is accepted.
+    alice_group
+        .stage_commit_at(&bob_commit, &proposal_store, &[], backend, Some(160))
+        .expect("Error staging commit.");
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_committer_insufficient_capabilities(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    // The group requires support for the application id extension.
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_required_capabilities(RequiredCapabilitiesExtension::new(
+            &[ExtensionType::ApplicationId],
+            &[],
+        ))
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob commits with a path.
+    let proposal_store = ProposalStore::new();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&bob_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let create_commit_result = bob_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+
+    // The untouched commit is fine.
+    alice_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+
+    // Now Bob's path leaf drops support for the required extension. We have
+    // to re-sign the leaf node, since we changed its content.
+    let commit = match create_commit_result.commit.content() {
+        MlsContentBody::Commit(commit) => commit.clone(),
+        _ => panic!("Bob created a commit, which does not contain an actual commit."),
+    };
+    let mut path = commit.path.expect("Missing path.");
+    let mut leaf_node = path.leaf_node().clone();
+    leaf_node
+        .capabilities_mut()
+        .set_extensions(vec![ExtensionType::Lifetime]);
+    let leaf_node = LeafNodeTbs::from(
+        leaf_node,
+        TreeInfoTbs::commit(bob_group.group_id().clone(), bob_group.own_leaf_index()),
+    )
+    .sign(backend, &bob_credential_bundle)
+    .expect("Error signing leaf node");
+    path.set_leaf_node(leaf_node);
+
+    let mut broken_plaintext = MlsAuthContent::commit(
+        framing_parameters,
+        create_commit_result.commit.sender().clone(),
+        Commit {
+            proposals: commit.proposals,
+            path: Some(path),
+        },
+        &bob_credential_bundle,
+        bob_group.context(),
+        backend,
+    )
+    .expect("Could not create plaintext.");
+    broken_plaintext.set_confirmation_tag(
+        create_commit_result
+            .commit
+            .confirmation_tag()
+            .cloned()
+            .expect("An unexpected error occurred."),
+    );
+
+    assert_eq!(
+        alice_group
+            .stage_commit(&broken_plaintext, &proposal_store, &[], backend)
+            .expect_err("Staged a commit with insufficient capabilities."),
+        StageCommitError::CommitterInsufficientCapabilities
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_membership_capabilities(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    let alice_capabilities = alice_group
+        .treesync()
+        .own_leaf_node()
+        .expect("Missing own leaf.")
+        .leaf_node()
+        .capabilities()
+        .clone();
+    assert_eq!(
+        alice_group.membership_version_floor(),
+        ProtocolVersion::Mls10
+    );
+    assert_eq!(
+        alice_group.membership_ciphersuite_intersection(),
+        alice_capabilities.ciphersuites()
+    );
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob updates his leaf. He only supports the group's ciphersuite, but
+    // advertises a version that Alice doesn't support.
+    let bob_update_kpb =
+        KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, Vec::new())
+            .expect("An unexpected error occurred.");
+    let mut bob_update_leaf_node = bob_update_kpb.key_package().leaf_node().clone();
+    bob_update_leaf_node
+        .capabilities_mut()
+        .set_ciphersuites(vec![ciphersuite]);
+    bob_update_leaf_node
+        .capabilities_mut()
+        .set_versions(vec![ProtocolVersion::Mls10, ProtocolVersion::Mls10Draft11]);
+    let bob_update_proposal = bob_group
+        .create_update_proposal(
+            framing_parameters,
+            &bob_credential_bundle,
+            bob_update_leaf_node,
+            backend,
+        )
+        .expect("Could not create proposal.");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_update_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    // Alice still holds back the version, Bob restricts the ciphersuites.
+    assert_eq!(
+        alice_group.membership_version_floor(),
+        ProtocolVersion::Mls10
+    );
+    assert_eq!(
+        alice_group.membership_ciphersuite_intersection(),
+        vec![ciphersuite]
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_welcome_processed_twice(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let mut add_member = |key_package: KeyPackage| {
+        let add_proposal = alice_group
+            .create_add_proposal(
+                framing_parameters,
+                &alice_credential_bundle,
+                key_package,
+                backend,
+            )
+            .expect("Could not create proposal");
+        let proposal_store = ProposalStore::from_queued_proposal(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, add_proposal)
+                .expect("Could not create QueuedProposal."),
+        );
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&alice_credential_bundle)
+            .proposal_store(&proposal_store)
+            .force_self_update(false)
+            .build();
+        let create_commit_result = alice_group
+            .create_commit(params, backend)
+            .expect("Error creating commit");
+        alice_group
+            .merge_commit(create_commit_result.staged_commit)
+            .expect("error merging pending commit");
+        (
+            create_commit_result
+                .welcome_option
+                .expect("An unexpected error occurred."),
+            alice_group.treesync().export_nodes(),
+        )
+    };
+
+    let (bob_welcome, bob_ratchet_tree) = add_member(bob_kpb.key_package().clone());
+    let (charlie_welcome, _) = add_member(charlie_kpb.key_package().clone());
+
+    // A retransmitted Welcome has the same id, a different one doesn't.
+    let bob_welcome_id = bob_welcome.id(backend).expect("Error computing welcome id");
+    assert_eq!(
+        bob_welcome_id,
+        bob_welcome
+            .clone()
+            .id(backend)
+            .expect("Error computing welcome id")
+    );
+    assert_ne!(
+        bob_welcome_id,
+        charlie_welcome
+            .id(backend)
+            .expect("Error computing welcome id")
+    );
+
+    // Processing the same Welcome twice yields equivalent groups.
+    let bob_group = CoreGroup::new_from_welcome(
+        bob_welcome.clone(),
+        Some(bob_ratchet_tree.clone()),
+        bob_kpb.clone(),
+        backend,
+    )
+    .expect("Error joining group.");
+    let bob_group_retry =
+        CoreGroup::new_from_welcome(bob_welcome, Some(bob_ratchet_tree), bob_kpb, backend)
+            .expect("Error joining group.");
+    assert_eq!(bob_group.group_id(), bob_group_retry.group_id());
+    assert_eq!(
+        bob_group.context().epoch(),
+        bob_group_retry.context().epoch()
+    );
+    assert_eq!(bob_group, bob_group_retry);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_build_with_unknown_psk(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+
+    // The secret of this PSK is not in the key store.
+    let preshared_key_id = PreSharedKeyId::new(
+        ciphersuite,
+        backend.rand(),
+        Psk::External(ExternalPsk::new(vec![4u8, 5, 6])),
+    )
+    .expect("An unexpected error occured.");
+
+    let err = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_psk(vec![preshared_key_id])
+        .build(&alice_credential_bundle, backend)
+        .expect_err("Created a group with an unknown PSK.");
+    assert_eq!(err, CoreGroupBuildError::UnknownPsk);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_can_process_commit(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let proposal_store = ProposalStore::new();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let commit = MlsPlaintext::from(create_commit_result.commit);
+
+    alice_group
+        .can_process_commit(&MlsMessageIn::from(commit.clone()))
+        .expect("Commit should be applicable.");
+
+    // Wrong group
+    let mut wrong_group = commit.clone();
+    wrong_group.set_group_id(GroupId::random(backend));
+    assert_eq!(
+        alice_group.can_process_commit(&MlsMessageIn::from(wrong_group)),
+        Err(ValidationError::WrongGroupId)
+    );
+
+    // Wrong epoch
+    let mut wrong_epoch = commit.clone();
+    wrong_epoch.set_epoch(alice_group.context().epoch().as_u64() + 1);
+    assert_eq!(
+        alice_group.can_process_commit(&MlsMessageIn::from(wrong_epoch)),
+        Err(ValidationError::WrongEpoch)
+    );
+
+    // Unknown member
+    let mut unknown_sender = commit.clone();
+    unknown_sender.set_sender(Sender::build_member(5));
+    assert_eq!(
+        alice_group.can_process_commit(&MlsMessageIn::from(unknown_sender)),
+        Err(ValidationError::UnknownMember)
+    );
+
+    // External commits are applicable
+    let mut external_commit = commit;
+    external_commit.set_sender(Sender::NewMemberCommit);
+    alice_group
+        .can_process_commit(&MlsMessageIn::from(external_commit))
+        .expect("External commit should be applicable.");
+
+    // Proposals are not commits
+    let add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    assert_eq!(
+        alice_group.can_process_commit(&MlsMessageIn::from(MlsPlaintext::from(add_proposal))),
+        Err(ValidationError::NotACommit)
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_missing_path_for_self_update(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob commits an inline update of his own leaf without a path. The
+    // committer has to update its own leaf through the path, so this is
+    // rejected by ValSem111.
+    let bob_update_kpb =
+        KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, Vec::new())
+            .expect("An unexpected error occurred.");
+    let commit = Commit {
+        proposals: vec![ProposalOrRef::Proposal(Proposal::Update(UpdateProposal {
+            leaf_node: bob_update_kpb.key_package().leaf_node().clone(),
+        }))],
+        path: None,
+    };
+    let plaintext = MlsAuthContent::commit(
+        framing_parameters,
+        Sender::build_member(bob_group.own_leaf_index()),
+        commit,
+        &bob_credential_bundle,
+        bob_group.context(),
+        backend,
+    )
+    .expect("Could not create plaintext.");
+
+    let proposal_store = ProposalStore::new();
+    assert_eq!(
+        alice_group
+            .stage_commit(&plaintext, &proposal_store, &[], backend)
+            .expect_err("Staged a self-update without a path."),
+        StageCommitError::ProposalValidationError(
+            ProposalValidationError::CommitterIncludedOwnUpdate
+        )
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_export_welcome_for_existing_member(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+    let bob_index = bob_group.own_leaf_index();
+
+    // Bob loses the local state and publishes a fresh key package.
+    drop(bob_group);
+    let bob_new_kpb =
+        KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, Vec::new())
+            .expect("An unexpected error occurred.");
+
+    let proposal_store = ProposalStore::new();
+
+    // The key package has to belong to the member that is replaced.
+    assert_eq!(
+        alice_group
+            .export_welcome_for_existing_member(
+                framing_parameters,
+                &alice_credential_bundle,
+                &proposal_store,
+                bob_index,
+                charlie_kpb.key_package().clone(),
+                backend,
+            )
+            .expect_err("Replaced Bob with Charlie."),
+        CreateCommitError::RecoveryIdentityMismatch
+    );
+    assert_eq!(
+        alice_group
+            .export_welcome_for_existing_member(
+                framing_parameters,
+                &alice_credential_bundle,
+                &proposal_store,
+                5,
+                bob_new_kpb.key_package().clone(),
+                backend,
+            )
+            .expect_err("Replaced an unknown member."),
+        CreateCommitError::UnknownMember
+    );
+    assert_eq!(
+        alice_group
+            .export_welcome_for_existing_member(
+                framing_parameters,
+                &alice_credential_bundle,
+                &proposal_store,
+                alice_group.own_leaf_index(),
+                bob_new_kpb.key_package().clone(),
+                backend,
+            )
+            .expect_err("Replaced the own leaf."),
+        CreateCommitError::CannotRemoveSelf
+    );
+
+    // Alice replaces Bob's stale leaf and Bob joins again.
+    let create_commit_result = alice_group
+        .export_welcome_for_existing_member(
+            framing_parameters,
+            &alice_credential_bundle,
+            &proposal_store,
+            bob_index,
+            bob_new_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_new_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    assert_eq!(bob_group.own_leaf_index(), bob_index);
+    assert_eq!(bob_group.context(), alice_group.context());
+    assert_eq!(alice_group.treesync().full_leaves().len(), 2);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_verify_welcome_confirmation_tag(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let welcome = create_commit_result
+        .welcome_option
+        .expect("An unexpected error occurred.");
+
+    CoreGroup::verify_welcome_confirmation_tag(&welcome, &bob_kpb, backend)
+        .expect("Valid confirmation tag was rejected.");
+
+    // Decrypt the GroupInfo and replace its confirmation tag.
+    let egs = CoreGroup::find_key_package_from_welcome_secrets(
+        bob_kpb
+            .key_package()
+            .hash_ref(backend.crypto())
+            .expect("An unexpected error occurred."),
+        welcome.secrets(),
+    )
+    .expect("JoinerSecret not found");
+    let group_secrets_bytes = backend
+        .crypto()
+        .hpke_open(
+            ciphersuite.hpke_config(),
+            egs.encrypted_group_secrets(),
+            bob_kpb.private_key().as_slice(),
+            &[],
+            &[],
+        )
+        .expect("Could not decrypt group secrets");
+    let group_secrets = GroupSecrets::tls_deserialize(&mut group_secrets_bytes.as_slice())
+        .expect("Could not decode GroupSecrets")
+        .config(ciphersuite, ProtocolVersion::default());
+    let psk_secret = PskSecret::new(ciphersuite, backend, &group_secrets.psks)
+        .expect("An unexpected error occurred.");
+    let key_schedule = KeySchedule::init(
+        ciphersuite,
+        backend,
+        group_secrets.joiner_secret,
+        psk_secret,
+    )
+    .expect("Could not create KeySchedule.");
+    let (welcome_key, welcome_nonce) = key_schedule
+        .welcome(backend)
+        .expect("Expected a WelcomeSecret")
+        .derive_welcome_key_nonce(backend)
+        .expect("Could not derive welcome nonce.");
+    let group_info_bytes = welcome_key
+        .aead_open(backend, welcome.encrypted_group_info(), &[], &welcome_nonce)
+        .expect("Could not decrypt GroupInfo");
+    let mut group_info = GroupInfo::tls_deserialize(&mut group_info_bytes.as_slice())
+        .expect("Could not decode GroupInfo");
+
+    group_info.set_confirmation_tag(ConfirmationTag(
+        Mac::new(backend, &Secret::default(), &[1, 2, 3])
+            .expect("Could not compute confirmation tag."),
+    ));
+    let group_info = group_info
+        .re_sign(&alice_credential_bundle, backend)
+        .expect("Error re-signing GroupInfo");
+    let encrypted_group_info = welcome_key
+        .aead_seal(
+            backend,
+            &group_info
+                .tls_serialize_detached()
+                .expect("Could not encode GroupInfo"),
+            &[],
+            &welcome_nonce,
+        )
+        .expect("An unexpected error occurred.");
+    let mut tampered_welcome = welcome.clone();
+    tampered_welcome.set_encrypted_group_info(encrypted_group_info);
+
+    assert_eq!(
+        CoreGroup::verify_welcome_confirmation_tag(&tampered_welcome, &bob_kpb, backend)
+            .expect_err("Tampered confirmation tag was accepted."),
+        WelcomeError::ConfirmationTagMismatch
+    );
+
+    // Bob can still join with the original Welcome after the check.
+    let bob_group = CoreGroup::new_from_welcome(
+        welcome,
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+    assert_eq!(bob_group.context(), alice_group.context());
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_always_embed_ratchet_tree(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let config = CoreGroupConfig {
+        always_embed_ratchet_tree: true,
+        ..CoreGroupConfig::default()
+    };
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_config(config)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob and asks for a Welcome without the ratchet tree.
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .needs_ratchet_tree(&|_| false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    // Bob joins without an external source for the ratchet tree.
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        None,
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+    assert_eq!(bob_group.context(), alice_group.context());
+
+    // Exported group infos carry the ratchet tree as well.
+    let group_info = alice_group
+        .export_group_info(backend, &alice_credential_bundle, false)
+        .expect("Error exporting group info.");
+    assert!(group_info
+        .extensions()
+        .iter()
+        .any(|extension| extension.extension_type() == ExtensionType::RatchetTree));
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_transcript_hash_mismatch(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice commits. The wire format of the commit is changed afterwards, so
+    // that Bob computes a different confirmed transcript hash than Alice.
+    let proposal_store = ProposalStore::new();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let mut create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    create_commit_result
+        .commit
+        .set_wire_format(WireFormat::MlsCiphertext);
+
+    // Without the diagnosis, the divergence is a plain tag mismatch.
+    assert_eq!(
+        bob_group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect_err("Staged a commit with a diverging transcript hash."),
+        StageCommitError::ConfirmationTagMismatch
+    );
+
+    bob_group.diagnose_confirmation_tag_mismatch = true;
+    assert_eq!(
+        bob_group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect_err("Staged a commit with a diverging transcript hash."),
+        StageCommitError::TranscriptHashMismatch
+    );
+
+    // A tag that can't be reproduced is still reported as a tag mismatch.
+    create_commit_result
+        .commit
+        .set_confirmation_tag(ConfirmationTag(
+            Mac::new(backend, &Secret::default(), &[1, 2, 3])
+                .expect("Could not compute confirmation tag."),
+        ));
+    assert_eq!(
+        bob_group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect_err("Staged a commit with an invalid confirmation tag."),
+        StageCommitError::ConfirmationTagMismatch
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_rekey(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    let alice_encryption_key = alice_group
+        .treesync()
+        .own_leaf_node()
+        .expect("Error getting own leaf.")
+        .public_key()
+        .clone();
+
+    // Alice rekeys the group.
+    let create_commit_result = alice_group
+        .rekey(framing_parameters, &alice_credential_bundle, backend)
+        .expect("Error creating commit");
+    match create_commit_result.commit.content() {
+        MlsContentBody::Commit(commit) => {
+            assert!(commit.proposals.is_empty());
+            assert!(commit.path.is_some());
+        }
+        _ => panic!("Expected a commit."),
+    }
+    assert!(create_commit_result.welcome_option.is_none());
+
+    let staged_commit = bob_group
+        .stage_commit(
+            &create_commit_result.commit,
+            &ProposalStore::new(),
+            &[],
+            backend,
+        )
+        .expect("Error staging commit.");
+    bob_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    assert_eq!(alice_group.context().epoch(), GroupEpoch::from(2));
+    assert_eq!(bob_group.context(), alice_group.context());
+    assert_ne!(
+        alice_group
+            .treesync()
+            .own_leaf_node()
+            .expect("Error getting own leaf.")
+            .public_key(),
+        &alice_encryption_key
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_commits_preserve_group_id(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let group_id = GroupId::random(backend);
+    let mut alice_group = CoreGroup::builder(group_id.clone(), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Add commit
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+    assert_eq!(alice_group.group_id(), &group_id);
+    assert_eq!(bob_group.group_id(), &group_id);
+
+    // Path-only commit and group context extensions commit
+    let proposal_store = ProposalStore::default();
+    let inline_proposals = [
+        vec![],
+        vec![Proposal::GroupContextExtensions(
+            GroupContextExtensionProposal::new(&[Extension::ApplicationId(
+                ApplicationIdExtension::new(b"app"),
+            )]),
+        )],
+    ];
+    for inline_proposals in inline_proposals {
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&alice_credential_bundle)
+            .proposal_store(&proposal_store)
+            .inline_proposals(inline_proposals)
+            .build();
+        let create_commit_result = alice_group
+            .create_commit(params, backend)
+            .expect("Error creating commit");
+        let staged_commit = bob_group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect("Error staging commit");
+        bob_group
+            .merge_commit(staged_commit)
+            .expect("error merging commit");
+        alice_group
+            .merge_commit(create_commit_result.staged_commit)
+            .expect("error merging pending commit");
+
+        assert_eq!(alice_group.group_id(), &group_id);
+        assert_eq!(bob_group.group_id(), &group_id);
+    }
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_max_aad_length(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    // Alice allows at most 4 bytes of AAD.
+    let config = CoreGroupConfig {
+        max_aad_length: Some(4),
+        ..CoreGroupConfig::default()
+    };
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_config(config)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice can't send an application message with too much AAD.
+    assert_eq!(
+        alice_group
+            .create_application_message(
+                &[1, 2, 3, 4, 5],
+                b"Hello, Bob!",
+                &alice_credential_bundle,
+                0,
+                backend,
+            )
+            .expect_err("Created an application message with too much AAD."),
+        MessageEncryptionError::AadTooLarge
+    );
+    let message = alice_group
+        .create_application_message(
+            &[1, 2, 3, 4],
+            b"Hello, Bob!",
+            &alice_credential_bundle,
+            0,
+            backend,
+        )
+        .expect("Error creating application message.");
+
+    // Bob allows at most 2 bytes of AAD and rejects the message.
+    bob_group.set_config(CoreGroupConfig {
+        max_aad_length: Some(2),
+        ..CoreGroupConfig::default()
+    });
+    assert_eq!(
+        bob_group
+            .process_message(
+                backend,
+                message.into(),
+                &SenderRatchetConfiguration::default(),
+                &ProposalStore::default(),
+                &[],
+            )
+            .expect_err("Processed an application message with too much AAD."),
+        ProcessMessageError::ValidationError(ValidationError::AadTooLarge)
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_export_own_leaf_secrets(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let alice_private_key = alice_kpb.private_key().as_slice().to_vec();
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Initially, the secrets are the ones of the key package.
+    let own_leaf_secrets = alice_group
+        .export_own_leaf_secrets()
+        .expect("Error exporting own leaf secrets.");
+    assert_eq!(own_leaf_secrets.leaf_index(), 0);
+    assert_eq!(own_leaf_secrets.encryption_private_key(), alice_private_key);
+
+    // After a rekey, the exported key decrypts to the new leaf key.
+    let create_commit_result = alice_group
+        .rekey(framing_parameters, &alice_credential_bundle, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let own_leaf_secrets = alice_group
+        .export_own_leaf_secrets()
+        .expect("Error exporting own leaf secrets.");
+    assert_ne!(own_leaf_secrets.encryption_private_key(), alice_private_key);
+
+    let encryption_key = alice_group
+        .treesync()
+        .own_leaf_node()
+        .expect("Error getting own leaf.")
+        .public_key()
+        .clone();
+    let ciphertext = backend.crypto().hpke_seal(
+        ciphersuite.hpke_config(),
+        encryption_key.as_slice(),
+        &[],
+        &[],
+        b"backup",
+    );
+    let plaintext = backend
+        .crypto()
+        .hpke_open(
+            ciphersuite.hpke_config(),
+            &ciphertext,
+            own_leaf_secrets.encryption_private_key(),
+            &[],
+            &[],
+        )
+        .expect("Error decrypting with the exported key.");
+    assert_eq!(plaintext, b"backup");
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_future_resumption_psk(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let group_id = GroupId::random(backend);
+    let mut alice_group = CoreGroup::builder(group_id.clone(), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice commits resumption PSKs of the current and of a future epoch.
+    for epoch in [1, 2] {
+        let psk_id = PreSharedKeyId::new(
+            ciphersuite,
+            backend.rand(),
+            Psk::Resumption(ResumptionPsk::new(
+                ResumptionPskUsage::Application,
+                group_id.clone(),
+                GroupEpoch::from(epoch),
+            )),
+        )
+        .expect("An unexpected error occurred.");
+        let secret = Secret::random(ciphersuite, backend, None /* MLS version */)
+            .expect("Not enough randomness.");
+        backend
+            .key_store()
+            .store(
+                &psk_id
+                    .tls_serialize_detached()
+                    .expect("Error serializing PSK id."),
+                &PskBundle::new(secret).expect("Could not create PskBundle."),
+            )
+            .expect("An unexpected error occurred.");
+
+        let proposal_store = ProposalStore::default();
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&alice_credential_bundle)
+            .proposal_store(&proposal_store)
+            .inline_proposals(vec![Proposal::PreSharedKey(PreSharedKeyProposal::new(
+                psk_id,
+            ))])
+            .build();
+        let create_commit_result = alice_group
+            .create_commit(params, backend)
+            .expect("Error creating commit");
+
+        let err = bob_group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect_err("Staging a commit with a future resumption PSK succeeded.");
+        assert_eq!(
+            err,
+            StageCommitError::ProposalValidationError(
+                ProposalValidationError::InvalidResumptionEpoch
+            )
+        );
+    }
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_archive_epoch(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_max_past_epoch_secrets(2)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob sends two messages in epoch 1.
+    let first_message = bob_group
+        .create_application_message(&[], b"first", &bob_credential_bundle, 0, backend)
+        .expect("Error creating application message.");
+    let second_message = bob_group
+        .create_application_message(&[], b"second", &bob_credential_bundle, 0, backend)
+        .expect("Error creating application message.");
+
+    // An archive of the current epoch decrypts messages of that epoch.
+    let epoch = alice_group.context().epoch();
+    let mut archive = alice_group
+        .archive_epoch(epoch)
+        .expect("Error archiving current epoch.");
+    assert_eq!(archive.epoch(), epoch);
+    assert_eq!(archive.group_id(), alice_group.group_id());
+    let application_message = archive
+        .decrypt(
+            first_message.into(),
+            &SenderRatchetConfiguration::default(),
+            backend,
+        )
+        .expect("Error decrypting with the archive.");
+    assert_eq!(application_message.into_bytes(), b"first");
+
+    // Alice moves the group to the next epoch.
+    let create_commit_result = alice_group
+        .rekey(framing_parameters, &alice_credential_bundle, backend)
+        .expect("Error creating commit");
+    let staged_commit = bob_group
+        .stage_commit(
+            &create_commit_result.commit,
+            &ProposalStore::default(),
+            &[],
+            backend,
+        )
+        .expect("Error staging commit");
+    bob_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    // Messages of other epochs can't be decrypted with the archive.
+    let third_message = bob_group
+        .create_application_message(&[], b"third", &bob_credential_bundle, 0, backend)
+        .expect("Error creating application message.");
+    let error = archive
+        .decrypt(
+            third_message.into(),
+            &SenderRatchetConfiguration::default(),
+            backend,
+        )
+        .expect_err("Decrypted a message of another epoch.");
+    assert_eq!(error, ValidationError::WrongEpoch);
+
+    // Past epochs can be archived as well and the archive survives
+    // serialization.
+    let archive = alice_group
+        .archive_epoch(epoch)
+        .expect("Error archiving past epoch.");
+    let serialized = serde_json::to_vec(&archive).expect("Error serializing archive.");
+    let mut archive: EpochArchive =
+        serde_json::from_slice(&serialized).expect("Error deserializing archive.");
+    let application_message = archive
+        .decrypt(
+            second_message.into(),
+            &SenderRatchetConfiguration::default(),
+            backend,
+        )
+        .expect("Error decrypting with the archive.");
+    assert_eq!(application_message.into_bytes(), b"second");
+
+    // Future epochs can't be archived.
+    assert!(alice_group.archive_epoch(GroupEpoch::from(3)).is_none());
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_parse_application_messages(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob sends a backlog of messages.
+    let payloads: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 8]).collect();
+    let ciphertexts: Vec<MlsCiphertext> = payloads
+        .iter()
+        .map(|payload| {
+            bob_group
+                .create_application_message(&[], payload, &bob_credential_bundle, 0, backend)
+                .expect("Error creating application message.")
+        })
+        .collect();
+
+    // Alice receives them out of order, one of them twice and a handshake
+    // message in between.
+    let order = [2, 0, 3, 1, 0];
+    let mut batch: Vec<MlsMessageIn> = order
+        .iter()
+        .map(|&index| MlsMessageIn::from(ciphertexts[index].clone()))
+        .collect();
+    let proposal_store = ProposalStore::new();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&bob_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let create_commit_result = bob_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    batch.push(MlsMessageIn::from(MlsPlaintext::from(
+        create_commit_result.commit,
+    )));
+    let results = alice_group.parse_application_messages(
+        backend,
+        batch,
+        &SenderRatchetConfiguration::default(),
+    );
+    assert_eq!(results.len(), order.len() + 1);
+
+    // The results are in the order of the batch.
+    for (result, &index) in results.iter().zip(order.iter()).take(4) {
+        let (plaintext, credential) = result
+            .clone()
+            .expect("Error decrypting message.")
+            .into_parts();
+        assert_eq!(
+            plaintext.content(),
+            &MlsContentBody::Application(payloads[index].clone().into())
+        );
+        assert_eq!(
+            credential.as_ref(),
+            Some(bob_credential_bundle.credential())
+        );
+    }
+    // The replayed message can't be decrypted a second time.
+    assert_eq!(
+        results[4].clone().expect_err("Decrypted a message twice."),
+        ValidationError::UnableToDecrypt(MessageDecryptionError::GenerationOutOfBound)
+    );
+    // The handshake message is rejected.
+    assert_eq!(
+        results[5]
+            .clone()
+            .expect_err("Parsed a handshake message as application message."),
+        ValidationError::NotAnApplicationMessage
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_decrypt_batch(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_max_past_epoch_secrets(2)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Bob sends 25 messages in each of two epochs.
+    let send_messages = |bob_group: &mut CoreGroup, epoch: u8| -> Vec<(Vec<u8>, MlsCiphertext)> {
+        (0..25u8)
+            .map(|i| {
+                let payload = vec![epoch, i];
+                let ciphertext = bob_group
+                    .create_application_message(&[], &payload, &bob_credential_bundle, 0, backend)
+                    .expect("Error creating application message.");
+                (payload, ciphertext)
+            })
+            .collect()
+    };
+    let first_epoch_messages = send_messages(&mut bob_group, 1);
+
+    let create_commit_result = alice_group
+        .rekey(framing_parameters, &alice_credential_bundle, backend)
+        .expect("Error creating commit");
+    let staged_commit = bob_group
+        .stage_commit(
+            &create_commit_result.commit,
+            &ProposalStore::default(),
+            &[],
+            backend,
+        )
+        .expect("Error staging commit");
+    bob_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    let second_epoch_messages = send_messages(&mut bob_group, 2);
+
+    // Alice receives the messages of both epochs interleaved and out of
+    // order.
+    let mut batch = Vec::new();
+    for i in 0..25 {
+        batch.push(second_epoch_messages[(i * 7) % 25].clone());
+        batch.push(first_epoch_messages[(i * 11) % 25].clone());
+    }
+    let (payloads, ciphertexts): (Vec<Vec<u8>>, Vec<MlsCiphertext>) = batch.into_iter().unzip();
+    let results = alice_group.decrypt_batch(
+        &ciphertexts,
+        &SenderRatchetConfiguration::default(),
+        backend,
+    );
+    assert_eq!(results.len(), 50);
+
+    // The results are in the order of the batch.
+    for (result, payload) in results.iter().zip(payloads.iter()) {
+        let plaintext = result.as_ref().expect("Error decrypting message.");
+        assert_eq!(
+            plaintext.content(),
+            &MlsContentBody::Application(payload.clone().into())
+        );
+    }
+
+    // A message whose key was already consumed fails without aborting the
+    // rest of the batch.
+    let next_ciphertext = bob_group
+        .create_application_message(&[], b"next", &bob_credential_bundle, 0, backend)
+        .expect("Error creating application message.");
+    let results = alice_group.decrypt_batch(
+        &[ciphertexts[0].clone(), next_ciphertext],
+        &SenderRatchetConfiguration::default(),
+        backend,
+    );
+    assert_eq!(
+        results[0].as_ref().expect_err("Decrypted a message twice."),
+        &MessageDecryptionError::GenerationOutOfBound
+    );
+    assert_eq!(
+        results[1]
+            .as_ref()
+            .expect("Error decrypting message.")
+            .content(),
+        &MlsContentBody::Application(b"next".to_vec().into())
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_group_sender_ratchet_configuration(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    // Alice keeps the keys of up to two skipped generations.
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_sender_ratchet_configuration(SenderRatchetConfiguration::new(2, 1000))
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    let messages: Vec<MlsCiphertext> = (0..10u8)
+        .map(|i| {
+            bob_group
+                .create_application_message(&[], &[i], &bob_credential_bundle, 0, backend)
+                .expect("Error creating application message.")
+        })
+        .collect();
+
+    // Without a per-call configuration, the configuration of the group is
+    // used. A message within the window decrypts.
+    alice_group
+        .decrypt(&messages[5], backend, None)
+        .expect("Error decrypting message.");
+    alice_group
+        .decrypt(&messages[4], backend, None)
+        .expect("Error decrypting message within the window.");
+
+    // A message outside of the window doesn't. The ratchet rejects it as
+    // `SecretTreeError::TooDistantInThePast`, which is reported as
+    // `MessageDecryptionError::GenerationOutOfBound`.
+    assert_eq!(
+        alice_group
+            .decrypt(&messages[3], backend, None)
+            .expect_err("Decrypted a message outside of the window."),
+        MessageDecryptionError::GenerationOutOfBound
+    );
+
+    // A per-call configuration overrides the configuration of the group.
+    let wide_configuration = SenderRatchetConfiguration::new(5, 1000);
+    alice_group
+        .decrypt(&messages[9], backend, &wide_configuration)
+        .expect("Error decrypting message.");
+    alice_group
+        .decrypt(&messages[6], backend, &wide_configuration)
+        .expect("Error decrypting message within the wider window.");
+}
+
+// Test that a member removed and a member added at the same leaf index in one
+// Commit are reported as a reused index.
+#[apply(ciphersuites_and_backends)]
+fn test_membership_delta_reused_index(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let membership_delta = create_commit_result.staged_commit.membership_delta();
+    assert_eq!(
+        membership_delta
+            .added()
+            .map(|member| (member.index, member.identity.clone()))
+            .collect::<Vec<_>>(),
+        vec![(1, b"Bob".to_vec())]
+    );
+    assert_eq!(membership_delta.removed().count(), 0);
+    assert_eq!(membership_delta.reused_indices().count(), 0);
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice removes Bob and adds Charlie in the same Commit. Charlie takes
+    // over Bob's leaf.
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![
+            Proposal::Remove(RemoveProposal { removed: 1 }),
+            Proposal::Add(AddProposal {
+                key_package: charlie_kpb.key_package().clone(),
+            }),
+        ])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let staged_commit = bob_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+    assert!(staged_commit.self_removed());
+
+    // Both the committer and the removed member see the reused index.
+    for membership_delta in [
+        create_commit_result.staged_commit.membership_delta(),
+        staged_commit.membership_delta(),
+    ] {
+        assert_eq!(membership_delta.added().count(), 0);
+        assert_eq!(membership_delta.removed().count(), 0);
+        let reused: Vec<(u32, Member, Member)> = membership_delta.reused_indices().collect();
+        assert_eq!(reused.len(), 1);
+        let (leaf_index, old_member, new_member) = &reused[0];
+        assert_eq!(*leaf_index, 1);
+        assert_eq!(old_member.index, 1);
+        assert_eq!(old_member.identity, b"Bob");
+        assert_eq!(new_member.index, 1);
+        assert_eq!(new_member.identity, b"Charlie");
+    }
+}
+
+// Test that the members removed by a Commit can be inspected, also by a
+// member that is removed itself.
+#[apply(ciphersuites_and_backends)]
+fn test_staged_commit_removed_members(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob and Charlie
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![
+            Proposal::Add(AddProposal {
+                key_package: bob_kpb.key_package().clone(),
+            }),
+            Proposal::Add(AddProposal {
+                key_package: charlie_kpb.key_package().clone(),
+            }),
+        ])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    assert_eq!(
+        create_commit_result.staged_commit.removed_members().count(),
+        0
+    );
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice removes Bob and Charlie
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![
+            Proposal::Remove(RemoveProposal { removed: 2 }),
+            Proposal::Remove(RemoveProposal { removed: 1 }),
+        ])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let staged_commit = bob_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+    assert!(staged_commit.self_removed());
+
+    // Both the committer and the removed member see who was removed.
+    let expected_members = vec![
+        (
+            1,
+            b"Bob".to_vec(),
+            bob_credential_bundle
+                .credential()
+                .signature_key()
+                .as_slice()
+                .to_vec(),
+        ),
+        (
+            2,
+            b"Charlie".to_vec(),
+            charlie_credential_bundle
+                .credential()
+                .signature_key()
+                .as_slice()
+                .to_vec(),
+        ),
+    ];
+    for staged_commit in [&create_commit_result.staged_commit, &staged_commit] {
+        let removed_members: Vec<(u32, Vec<u8>, Vec<u8>)> = staged_commit
+            .removed_members()
+            .map(|member| (member.index, member.identity, member.signature_key))
+            .collect();
+        assert_eq!(removed_members, expected_members);
+    }
+
+    // The removed members are still available after the Commit is merged.
+    let removed_members: Vec<Member> = staged_commit.removed_members().collect();
+    assert!(bob_group.merge_commit(staged_commit).is_none());
+    assert_eq!(removed_members.len(), 2);
+}
+
+// Test that a member of a group can join a branch of the group and derives
+// the same secrets as the member that created the branch.
+#[apply(ciphersuites_and_backends)]
+fn test_branch(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: bob_kpb.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice branches off a new group with Bob.
+    let bob_branch_kpb =
+        KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, Vec::new())
+            .expect("An unexpected error occurred.");
+    let branch_group_id = GroupId::random(backend);
+    let (alice_branch, welcome) = alice_group
+        .branch(
+            branch_group_id.clone(),
+            &[bob_branch_kpb.key_package().clone()],
+            &alice_credential_bundle,
+            backend,
+        )
+        .expect("Error branching group.");
+    assert_eq!(alice_branch.group_id(), &branch_group_id);
+    assert_eq!(alice_branch.treesync().full_leave_members().count(), 2);
+
+    // The parent group is unaffected.
+    assert_eq!(alice_group.context().epoch(), bob_group.context().epoch());
+    assert_eq!(
+        alice_group.export_secret(backend, "test", &[], 32),
+        bob_group.export_secret(backend, "test", &[], 32)
+    );
+
+    // Without the resumption PSK of the parent, the Welcome can't be used.
+    let error = CoreGroup::new_from_welcome(
+        welcome.clone(),
+        Some(alice_branch.treesync().export_nodes()),
+        bob_branch_kpb.clone(),
+        backend,
+    )
+    .expect_err("Joined a branch without the resumption PSK.");
+    assert_eq!(error, WelcomeError::PskNotFound);
+
+    // Bob joins the branch with the resumption PSK of the parent group.
+    let bob_branch = CoreGroup::new_from_branch_welcome(
+        welcome,
+        &bob_group,
+        Some(alice_branch.treesync().export_nodes()),
+        bob_branch_kpb,
+        backend,
+    )
+    .expect("Error joining branch.");
+    assert_eq!(bob_branch.group_id(), &branch_group_id);
+    assert_eq!(
+        alice_branch
+            .export_secret(backend, "test", &[], 32)
+            .expect("Error exporting secret."),
+        bob_branch
+            .export_secret(backend, "test", &[], 32)
+            .expect("Error exporting secret.")
+    );
+
+    // A Welcome of another group is not a branch of the parent group.
+    let (_charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: charlie_kpb.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_branch
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let error = CoreGroup::new_from_branch_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        &bob_group,
+        None,
+        charlie_kpb,
+        backend,
+    )
+    .expect_err("Joined a group that is not a branch.");
+    assert_eq!(error, WelcomeError::NotABranch);
+}
+
+// Test that two valid commits for the same epoch are detected as a fork.
+#[apply(ciphersuites_and_backends)]
+fn test_detect_fork(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice creates two different commits for the same epoch.
+    let commit_a = alice_group
+        .rekey(framing_parameters, &alice_credential_bundle, backend)
+        .expect("Error creating commit");
+    let commit_b = alice_group
+        .rekey(framing_parameters, &alice_credential_bundle, backend)
+        .expect("Error creating commit");
+
+    let proposal_store = ProposalStore::default();
+    assert!(bob_group.detect_fork(
+        &commit_a.commit,
+        &commit_b.commit,
+        &proposal_store,
+        &[],
+        backend
+    ));
+    // The same commit twice is not a fork.
+    assert!(!bob_group.detect_fork(
+        &commit_a.commit,
+        &commit_a.commit,
+        &proposal_store,
+        &[],
+        backend
+    ));
+
+    // Detecting a fork doesn't change the group, so Bob can still apply one
+    // of the commits.
+    let staged_commit = bob_group
+        .stage_commit(&commit_a.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+    bob_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    alice_group
+        .merge_commit(commit_a.staged_commit)
+        .expect("error merging commit");
+
+    // Commits for the past epoch are no longer valid.
+    assert!(!bob_group.detect_fork(
+        &commit_a.commit,
+        &commit_b.commit,
+        &proposal_store,
+        &[],
+        backend
+    ));
+}
+
+// Test that the own leaf can only carry extensions that are listed in its
+// capabilities.
+#[apply(ciphersuites_and_backends)]
+fn test_leaf_node_extensions_not_advertised(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let application_id = Extension::ApplicationId(ApplicationIdExtension::new(b"app"));
+
+    // The application id extension is not in the leaf's capabilities.
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let error = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_leaf_node_extensions(vec![application_id.clone()])
+        .build(&alice_credential_bundle, backend)
+        .expect_err("Built a group with an extension that is not advertised.");
+    assert_eq!(error, CoreGroupBuildError::LeafExtensionNotAdvertised);
+
+    // Once the extension type is required, the leaf advertises it.
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_required_capabilities(RequiredCapabilitiesExtension::new(
+            &[ExtensionType::ApplicationId],
+            &[],
+        ))
+        .with_leaf_node_extensions(vec![application_id])
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+}
+
+// Test that the coverage report lists the supported and the missing required
+// capabilities of each member.
+#[apply(ciphersuites_and_backends)]
+fn test_capabilities_coverage(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Without required capabilities, every member is covered.
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    let coverage = alice_group.capabilities_coverage();
+    assert_eq!(coverage.len(), 1);
+    assert_eq!(coverage[0].0, 0);
+    assert!(coverage[0].1.is_satisfied());
+    assert!(coverage[0].1.satisfied_extensions().is_empty());
+
+    // Alice's leaf only advertises the required extension types, but no
+    // proposal types.
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_required_capabilities(RequiredCapabilitiesExtension::new(
+            &[ExtensionType::ApplicationId],
+            &[ProposalType::Reinit],
+        ))
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    let coverage = alice_group.capabilities_coverage();
+    assert_eq!(coverage.len(), 1);
+    let (leaf_index, report) = &coverage[0];
+    assert_eq!(*leaf_index, 0);
+    assert!(!report.is_satisfied());
+    assert_eq!(
+        report.satisfied_extensions(),
+        &[ExtensionType::ApplicationId]
+    );
+    assert!(report.missing_extensions().is_empty());
+    assert!(report.satisfied_proposals().is_empty());
+    assert_eq!(report.missing_proposals(), &[ProposalType::Reinit]);
+}
+
+// Test that subgroup keys agree between members and are bound to the subgroup,
+// the label and the epoch.
+#[apply(ciphersuites_and_backends)]
+fn test_export_subgroup_key(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_charly_credential_bundle, charly_kpb) = setup_client("Charly", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob and Charly
+    let mut proposal_store = ProposalStore::new();
+    for key_package_bundle in [&bob_kpb, &charly_kpb] {
+        let add_proposal = alice_group
+            .create_add_proposal(
+                framing_parameters,
+                &alice_credential_bundle,
+                key_package_bundle.key_package().clone(),
+                backend,
+            )
+            .expect("Could not create proposal");
+        proposal_store.add(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, add_proposal)
+                .expect("Could not create QueuedProposal."),
+        );
+    }
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Both members derive the same key, regardless of the order of the
+    // subgroup.
+    let key = alice_group
+        .export_subgroup_key(&[0, 1], "admins", backend)
+        .expect("Error exporting subgroup key.");
+    assert_eq!(key.len(), ciphersuite.aead_key_length());
+    assert_eq!(
+        bob_group
+            .export_subgroup_key(&[1, 0], "admins", backend)
+            .expect("Error exporting subgroup key."),
+        key
+    );
+
+    // Other subgroups and labels lead to different keys.
+    assert_ne!(
+        alice_group
+            .export_subgroup_key(&[0], "admins", backend)
+            .expect("Error exporting subgroup key."),
+        key
+    );
+    assert_ne!(
+        alice_group
+            .export_subgroup_key(&[0, 1], "moderators", backend)
+            .expect("Error exporting subgroup key."),
+        key
+    );
+
+    // The subgroup must be a non-empty set of members.
+    assert_eq!(
+        alice_group.export_subgroup_key(&[], "admins", backend),
+        Err(ExporterError::EmptySubgroup)
+    );
+    assert_eq!(
+        alice_group.export_subgroup_key(&[0, 5], "admins", backend),
+        Err(ExporterError::UnknownSubgroupMember)
+    );
+
+    // The label is domain-separated from the exporter.
+    let context = [
+        &0u64.to_be_bytes()[..],
+        &0u32.to_be_bytes(),
+        &1u32.to_be_bytes(),
+    ]
+    .concat();
+    assert_ne!(
+        alice_group
+            .export_secret(backend, "admins", &context, ciphersuite.aead_key_length())
+            .expect("Error exporting secret."),
+        key
+    );
+
+    // The key changes with the epoch.
+    let create_commit_result = alice_group
+        .rekey(framing_parameters, &alice_credential_bundle, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    assert_ne!(
+        alice_group
+            .export_subgroup_key(&[0, 1], "admins", backend)
+            .expect("Error exporting subgroup key."),
+        key
+    );
+
+    // Once Alice removes Bob, Bob's leaf is blank and can't be part of a
+    // subgroup anymore.
+    let bob_remove_proposal = alice_group
+        .create_remove_proposal(framing_parameters, &alice_credential_bundle, 1, backend)
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_remove_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    assert_eq!(alice_group.treesync().leaf_count(), 3);
+    assert_eq!(
+        alice_group.export_subgroup_key(&[0, 1], "admins", backend),
+        Err(ExporterError::UnknownSubgroupMember)
+    );
+    alice_group
+        .export_subgroup_key(&[0, 2], "admins", backend)
+        .expect("Error exporting subgroup key.");
+}
+
+// Test that the integrity check accepts consistent group states and detects
+// tampered persisted state.
+#[apply(ciphersuites_and_backends)]
+fn test_verify_integrity(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    alice_group
+        .verify_integrity(backend)
+        .expect("A new group is inconsistent.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+    alice_group
+        .verify_integrity(backend)
+        .expect("Alice's group is inconsistent.");
+    bob_group
+        .verify_integrity(backend)
+        .expect("Bob's group is inconsistent.");
+
+    // A loaded group is consistent.
+    let mut serialized = Vec::new();
+    bob_group
+        .save(&mut serialized)
+        .expect("Could not write group state");
+    let loaded_group =
+        CoreGroup::load(serialized.as_slice(), backend).expect("Could not load group state");
+    loaded_group
+        .verify_integrity(backend)
+        .expect("The loaded group is inconsistent.");
+
+    // Tampering with the persisted state is detected.
+    let tamper = |pointer: &str| -> CoreGroup {
+        let mut value = serde_json::to_value(&bob_group).expect("Could not serialize group.");
+        let byte = value
+            .pointer_mut(pointer)
+            .expect("Unknown field in the group state.");
+        *byte = serde_json::Value::from(byte.as_u64().expect("Not a byte.") ^ 0xff);
+        serde_json::from_value(value).expect("Could not deserialize group.")
+    };
+    assert_eq!(
+        tamper("/tree/tree_hash/0").verify_integrity(backend),
+        Err(IntegrityError::TreeHashMismatch)
+    );
+    assert_eq!(
+        tamper("/interim_transcript_hash/0").verify_integrity(backend),
+        Err(IntegrityError::TranscriptHashMismatch)
+    );
+
+    // Loading tampered state fails.
+    let mut tampered = CORE_GROUP_STATE_VERSION.to_be_bytes().to_vec();
+    tampered.extend_from_slice(
+        &serde_json::to_vec(&tamper("/tree/tree_hash/0")).expect("Could not serialize group."),
+    );
+    assert_eq!(
+        CoreGroup::load(tampered.as_slice(), backend).expect_err("Loaded tampered state."),
+        DeserializeError::IntegrityError(IntegrityError::TreeHashMismatch)
+    );
+}
+
+// Test that an external PSK injected through a PreSharedKey proposal is folded
+// into the key schedule of the committer and of the other members.
+#[apply(ciphersuites_and_backends)]
+fn test_external_psk_proposal(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    let exporter_secret = |group: &CoreGroup| {
+        group
+            .export_secret(backend, "psk test", &[], 32)
+            .expect("Error exporting secret.")
+    };
+    let pre_psk_secret = exporter_secret(&alice_group);
+    assert_eq!(pre_psk_secret, exporter_secret(&bob_group));
+
+    // Both members know the out-of-band secret
+    let preshared_key_id = PreSharedKeyId::new(
+        ciphersuite,
+        backend.rand(),
+        Psk::External(ExternalPsk::new(b"password".to_vec())),
+    )
+    .expect("An unexpected error occurred.");
+    let psk_key = preshared_key_id
+        .tls_serialize_detached()
+        .expect("Error serializing PSK ID.");
+    let psk_bundle = PskBundle::new(
+        Secret::random(ciphersuite, backend, None /* MLS version */)
+            .expect("Not enough randomness."),
+    )
+    .expect("Could not create PskBundle.");
+    backend
+        .key_store()
+        .store(&psk_key, &psk_bundle)
+        .expect("An unexpected error occurred.");
+
+    // Alice proposes and commits the PSK
+    let psk_proposal = alice_group
+        .create_presharedkey_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            preshared_key_id.clone(),
+            backend,
+        )
+        .expect("Could not create PSK proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, psk_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+
+    // Without the PSK, Bob can't process the commit
+    backend
+        .key_store()
+        .delete(&psk_key)
+        .expect("An unexpected error occurred.");
+    assert_eq!(
+        bob_group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect_err("Staging a commit with an unknown PSK should fail."),
+        StageCommitError::PskError(crate::schedule::errors::PskError::KeyNotFound)
+    );
+
+    // With the PSK, both members end up with the same exporter secret
+    backend
+        .key_store()
+        .store(&psk_key, &psk_bundle)
+        .expect("An unexpected error occurred.");
+    let staged_commit = bob_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit.");
+    bob_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    let post_psk_secret = exporter_secret(&alice_group);
+    assert_eq!(post_psk_secret, exporter_secret(&bob_group));
+    assert_ne!(post_psk_secret, pre_psk_secret);
+}
+
+// Test that the group's padding strategy hides the length of application
+// messages.
+#[apply(ciphersuites_and_backends)]
+fn test_padding_strategy(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let config = CoreGroupConfig {
+        padding_strategy: Some(PaddingStrategy::PadToMultiple(256)),
+        ..CoreGroupConfig::default()
+    };
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_config(config)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Messages of different lengths result in ciphertexts of the same length.
+    // The padding strategy takes precedence over the padding size.
+    let short_message = alice_group
+        .create_application_message(&[], b"Hi", &alice_credential_bundle, 0, backend)
+        .expect("Error creating application message.");
+    let long_message = alice_group
+        .create_application_message(
+            &[],
+            b"Hello, Bob! How are you doing today?",
+            &alice_credential_bundle,
+            16,
+            backend,
+        )
+        .expect("Error creating application message.");
+    assert_eq!(
+        short_message.tls_serialized_len(),
+        long_message.tls_serialized_len()
+    );
+    for message in [short_message, long_message] {
+        bob_group
+            .process_message(
+                backend,
+                message.into(),
+                &SenderRatchetConfiguration::default(),
+                &ProposalStore::default(),
+                &[],
+            )
+            .expect("Error processing application message.");
+    }
+
+    // Without a padding strategy, the lengths differ.
+    alice_group.set_config(CoreGroupConfig::default());
+    let short_message = alice_group
+        .create_application_message(&[], b"Hi", &alice_credential_bundle, 0, backend)
+        .expect("Error creating application message.");
+    let long_message = alice_group
+        .create_application_message(
+            &[],
+            b"Hello, Bob! How are you doing today?",
+            &alice_credential_bundle,
+            0,
+            backend,
+        )
+        .expect("Error creating application message.");
+    assert!(short_message.tls_serialized_len() < long_message.tls_serialized_len());
+}
+
+// Test that a ReInit proposal has to be committed on its own and that merging
+// it terminates the group.
+#[apply(ciphersuites_and_backends)]
+fn test_reinit_proposal(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+    let (_charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice proposes to re-initialize the group
+    let new_group_id = GroupId::random(backend);
+    let reinit_proposal = alice_group
+        .create_reinit_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            new_group_id.clone(),
+            ProtocolVersion::default(),
+            ciphersuite,
+            vec![],
+            backend,
+        )
+        .expect("Could not create ReInit proposal");
+    let reinit_queued_proposal =
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, reinit_proposal)
+            .expect("Could not create QueuedProposal.");
+
+    // A ReInit proposal can't be committed together with another proposal
+    let charlie_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            charlie_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let mut proposal_store = ProposalStore::from_queued_proposal(reinit_queued_proposal.clone());
+    proposal_store.add(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, charlie_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    assert_eq!(
+        alice_group
+            .create_commit(params, backend)
+            .expect_err("Created a commit with a ReInit and an Add proposal."),
+        CreateCommitError::ProposalValidationError(ProposalValidationError::ReInitNotAlone)
+    );
+
+    // Committing the ReInit proposal twice is rejected as well
+    let committed_reinit = ProposalOrRef::Proposal(reinit_queued_proposal.proposal().clone());
+    assert_eq!(
+        crate::group::proposals::ProposalQueue::from_committed_proposals(
+            ciphersuite,
+            backend,
+            vec![committed_reinit.clone(), committed_reinit],
+            &ProposalStore::default(),
+            &Sender::build_member(0),
+        )
+        .expect_err("Accepted a commit with two ReInit proposals."),
+        FromCommittedProposalsError::ReInitNotAlone
+    );
+
+    // On its own, the ReInit proposal can be committed
+    let proposal_store = ProposalStore::from_queued_proposal(reinit_queued_proposal);
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let staged_commit = bob_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit.");
+    let staged_reinit_proposal = staged_commit
+        .reinit_proposal()
+        .expect("Missing ReInit proposal.");
+    assert_eq!(staged_reinit_proposal.group_id(), &new_group_id);
+    assert_eq!(staged_reinit_proposal.ciphersuite(), ciphersuite);
+    assert_eq!(staged_reinit_proposal.version(), ProtocolVersion::default());
+    assert!(staged_reinit_proposal.extensions().is_empty());
+
+    // Merging the commit terminates the group
+    assert!(!bob_group.is_terminated());
+    bob_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    assert!(alice_group.is_terminated());
+    assert!(bob_group.is_terminated());
+
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&ProposalStore::default())
+        .build();
+    assert_eq!(
+        alice_group
+            .create_commit(params, backend)
+            .expect_err("Created a commit in a terminated group."),
+        CreateCommitError::GroupTerminated
+    );
+    assert_eq!(
+        alice_group
+            .create_application_message(&[], b"Hello", &alice_credential_bundle, 0, backend)
+            .expect_err("Created an application message in a terminated group."),
+        MessageEncryptionError::GroupTerminated
+    );
+}
+
+// Test that a group persisted at a later epoch can be restored and decrypts a
+// message that was encrypted before it was persisted, and that state written
+// in another format version is rejected.
+#[apply(ciphersuites_and_backends)]
+fn test_save_and_load_versioned_state(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice moves the group to epoch 3 with two empty commits
+    let proposal_store = ProposalStore::default();
+    for _ in 0..2 {
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&alice_credential_bundle)
+            .proposal_store(&proposal_store)
+            .build();
+        let create_commit_result = alice_group
+            .create_commit(params, backend)
+            .expect("Error creating commit");
+        let staged_commit = bob_group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect("Error staging commit.");
+        bob_group
+            .merge_commit(staged_commit)
+            .expect("error merging commit");
+        alice_group
+            .merge_commit(create_commit_result.staged_commit)
+            .expect("error merging pending commit");
+    }
+    assert_eq!(bob_group.context().epoch(), GroupEpoch::from(3));
+
+    // Alice sends a message before Bob persists his state
+    let message = alice_group
+        .create_application_message(&[], b"Hello, Bob!", &alice_credential_bundle, 0, backend)
+        .expect("Error creating application message.");
+
+    let mut serialized = Vec::new();
+    bob_group
+        .save(&mut serialized)
+        .expect("Could not write group state");
+    assert_eq!(serialized[..2], CORE_GROUP_STATE_VERSION.to_be_bytes());
+    let mut loaded_group =
+        CoreGroup::load(serialized.as_slice(), backend).expect("Could not load group state");
+    assert_eq!(loaded_group, bob_group);
+
+    let processed_message = loaded_group
+        .process_message(
+            backend,
+            message.into(),
+            &SenderRatchetConfiguration::default(),
+            &ProposalStore::default(),
+            &[],
+        )
+        .expect("Error processing message.");
+    match processed_message.into_content() {
+        ProcessedMessageContent::ApplicationMessage(application_message) => {
+            assert_eq!(application_message.into_bytes(), b"Hello, Bob!")
+        }
+        _ => panic!("Expected an application message."),
+    }
+
+    // State written in another format version is rejected.
+    let unsupported_version = CORE_GROUP_STATE_VERSION + 1;
+    serialized[..2].copy_from_slice(&unsupported_version.to_be_bytes());
+    assert_eq!(
+        CoreGroup::load(serialized.as_slice(), backend)
+            .expect_err("Loaded an unsupported version."),
+        DeserializeError::UnsupportedVersion {
+            version: unsupported_version
+        }
+    );
+
+    // Truncated and malformed state is rejected.
+    assert_eq!(
+        CoreGroup::load(&serialized[..1], backend).expect_err("Loaded truncated state."),
+        DeserializeError::ReadError
+    );
+    let mut malformed = CORE_GROUP_STATE_VERSION.to_be_bytes().to_vec();
+    malformed.extend_from_slice(b"{}");
+    assert_eq!(
+        CoreGroup::load(malformed.as_slice(), backend).expect_err("Loaded malformed state."),
+        DeserializeError::MalformedState
+    );
+}
+
+// Test that the own leaf can advertise more protocol versions than the version
+// of the group, but has to advertise the version of the group.
+#[apply(ciphersuites_and_backends)]
+fn test_advertised_versions(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+
+    let versions = vec![ProtocolVersion::Mls10, ProtocolVersion::Mls10Draft11];
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_versions(versions.clone())
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    assert_eq!(alice_group.version(), ProtocolVersion::Mls10);
+    assert_eq!(
+        alice_group
+            .treesync()
+            .own_leaf_node()
+            .expect("Missing own leaf.")
+            .leaf_node()
+            .capabilities()
+            .versions(),
+        versions.as_slice()
+    );
+
+    // The version of the group has to be advertised.
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    assert_eq!(
+        CoreGroup::builder(GroupId::random(backend), alice_kpb)
+            .with_versions(vec![ProtocolVersion::Mls10Draft11])
+            .build(&alice_credential_bundle, backend)
+            .expect_err("Built a group that doesn't advertise its version."),
+        CoreGroupBuildError::VersionNotAdvertised
+    );
+}
+
+// Test that the own leaf can advertise more ciphersuites than the ciphersuite
+// of the group, and that new members only have to support the ciphersuite of
+// the group.
+#[apply(ciphersuites_and_backends)]
+fn test_advertised_ciphersuites(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let ciphersuites = backend.crypto().supported_ciphersuites();
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .with_ciphersuites(ciphersuites.clone())
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    assert_eq!(alice_group.ciphersuite(), ciphersuite);
+    let advertised_ciphersuites = alice_group
+        .treesync()
+        .own_leaf_node()
+        .expect("Missing own leaf.")
+        .leaf_node()
+        .capabilities()
+        .ciphersuites()
+        .to_vec();
+    assert!(ciphersuites
+        .iter()
+        .all(|cs| advertised_ciphersuites.contains(cs)));
+
+    // Bob only supports the ciphersuite of the group and can be added.
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(false)
+        .build();
+    alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+
+    // The ciphersuite of the group has to be advertised.
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let other_ciphersuites: Vec<Ciphersuite> = ciphersuites
+        .into_iter()
+        .filter(|cs| *cs != ciphersuite)
+        .collect();
+    assert_eq!(
+        CoreGroup::builder(GroupId::random(backend), alice_kpb)
+            .with_ciphersuites(other_ciphersuites)
+            .build(&alice_credential_bundle, backend)
+            .expect_err("Built a group that doesn't advertise its ciphersuite."),
+        CoreGroupBuildError::CiphersuiteNotAdvertised
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_verify_confirmation_tag(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_key_package_bundle) =
+        setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating group.");
+
+    // === Alice adds Bob ===
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_key_package_bundle.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal.");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging commit");
+
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_key_package_bundle,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // === Alice updates her leaf ===
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let commit = create_commit_result.commit;
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging commit");
+
+    // Bob hasn't staged the commit yet, but can verify the tag with the
+    // confirmation key of the new epoch.
+    let confirmation_key = alice_group.message_secrets().confirmation_key();
+    assert_eq!(
+        bob_group.verify_confirmation_tag(&commit, confirmation_key, backend),
+        Ok(true)
+    );
+
+    // The confirmation key of the current epoch doesn't verify the tag.
+    assert_eq!(
+        bob_group.verify_confirmation_tag(
+            &commit,
+            bob_group.message_secrets().confirmation_key(),
+            backend
+        ),
+        Ok(false)
+    );
+
+    // A tampered confirmation tag doesn't verify.
+    let mut tampered_commit = commit.clone();
+    let mut confirmation_tag = commit
+        .confirmation_tag()
+        .expect("The commit has no confirmation tag.")
+        .clone();
+    confirmation_tag.0.flip_last_byte();
+    tampered_commit.set_confirmation_tag(confirmation_tag);
+    assert_eq!(
+        bob_group.verify_confirmation_tag(&tampered_commit, confirmation_key, backend),
+        Ok(false)
+    );
+
+    // A commit without a confirmation tag doesn't verify either.
+    let mut plaintext = MlsPlaintext::from(commit.clone());
+    plaintext.set_confirmation_tag(None);
+    let untagged_commit =
+        MlsAuthContent::from(VerifiableMlsAuthContent::from_plaintext(plaintext, None));
+    assert_eq!(
+        bob_group.verify_confirmation_tag(&untagged_commit, confirmation_key, backend),
+        Ok(false)
+    );
+
+    // Once the commit is merged, it's no longer a commit of the current epoch.
+    let staged_commit = bob_group
+        .stage_commit(&commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+    bob_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    assert_eq!(
+        bob_group.verify_confirmation_tag(&commit, confirmation_key, backend),
+        Ok(false)
+    );
+}
+
+// Test that a commit covering the same PSK twice is rejected.
+#[apply(ciphersuites_and_backends)]
+fn test_duplicate_psk_proposals(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_kpb.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Both members know the out-of-band secret
+    let preshared_key_id = PreSharedKeyId::new(
+        ciphersuite,
+        backend.rand(),
+        Psk::External(ExternalPsk::new(b"password".to_vec())),
+    )
+    .expect("An unexpected error occurred.");
+    backend
+        .key_store()
+        .store(
+            &preshared_key_id
+                .tls_serialize_detached()
+                .expect("Error serializing PSK ID."),
+            &PskBundle::new(
+                Secret::random(ciphersuite, backend, None /* MLS version */)
+                    .expect("Not enough randomness."),
+            )
+            .expect("Could not create PskBundle."),
+        )
+        .expect("An unexpected error occurred.");
+
+    // Alice and Bob both propose the same PSK and Alice commits to both
+    // proposals.
+    let alice_psk_proposal = alice_group
+        .create_presharedkey_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            preshared_key_id.clone(),
+            backend,
+        )
+        .expect("Could not create PSK proposal");
+    let bob_psk_proposal = bob_group
+        .create_presharedkey_proposal(
+            framing_parameters,
+            &bob_credential_bundle,
+            preshared_key_id,
+            backend,
+        )
+        .expect("Could not create PSK proposal");
+    let mut proposal_store = ProposalStore::new();
+    for psk_proposal in [alice_psk_proposal, bob_psk_proposal] {
+        proposal_store.add(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, psk_proposal)
+                .expect("Could not create QueuedProposal."),
+        );
+    }
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+
+    // Bob rejects the commit.
+    assert_eq!(
+        bob_group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect_err("Staging a commit with duplicate PSKs should fail."),
+        StageCommitError::ProposalValidationError(ProposalValidationError::DuplicatePsk)
+    );
+}
+
+// Test that all members in the same epoch derive the same epoch authenticator
+// and resumption PSK, and that both change with the epoch.
+#[apply(ciphersuites_and_backends)]
+fn test_epoch_authenticator_and_resumption_psk(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: bob_kpb.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    assert_eq!(
+        alice_group.epoch_authenticator().as_slice(),
+        bob_group.epoch_authenticator().as_slice()
+    );
+    assert_eq!(
+        alice_group.resumption_psk_secret().as_slice(),
+        bob_group.resumption_psk_secret().as_slice()
+    );
+
+    // The resumption PSK ID refers to the current epoch of the group.
+    let psk_id = bob_group
+        .resumption_psk_id(ResumptionPskUsage::Branch, backend)
+        .expect("Error creating resumption PSK ID.");
+    match psk_id.psk() {
+        Psk::Resumption(resumption_psk) => {
+            assert_eq!(resumption_psk.usage(), ResumptionPskUsage::Branch);
+            assert_eq!(resumption_psk.psk_group_id(), bob_group.group_id());
+            assert_eq!(resumption_psk.psk_epoch(), bob_group.context().epoch());
+        }
+        Psk::External(_) => panic!("Expected a resumption PSK."),
+    }
+
+    let old_epoch_authenticator = alice_group.epoch_authenticator().as_slice().to_vec();
+
+    // Bob updates and commits
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&bob_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = bob_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let staged_commit = alice_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+    alice_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    bob_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    assert_eq!(
+        alice_group.epoch_authenticator().as_slice(),
+        bob_group.epoch_authenticator().as_slice()
+    );
+    assert_eq!(
+        alice_group.resumption_psk_secret().as_slice(),
+        bob_group.resumption_psk_secret().as_slice()
+    );
+    assert_ne!(
+        alice_group.epoch_authenticator().as_slice(),
+        old_epoch_authenticator.as_slice()
+    );
+}
+
+// Test that the full credential of each member can be retrieved by leaf index.
+#[apply(ciphersuites_and_backends)]
+fn test_credential_by_leaf_index(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    // Only basic credentials are currently supported, see issue #134.
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: bob_kpb.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    for group in [&alice_group, &bob_group] {
+        let alice_credential = group.credential(0).expect("Alice should be in the group.");
+        assert_eq!(alice_credential, alice_credential_bundle.credential());
+        assert_eq!(alice_credential.credential_type(), CredentialType::Basic);
+
+        let bob_credential = group.credential(1).expect("Bob should be in the group.");
+        assert_eq!(bob_credential, bob_credential_bundle.credential());
+        assert_eq!(bob_credential.credential_type(), CredentialType::Basic);
+        assert_ne!(
+            alice_credential.signature_key(),
+            bob_credential.signature_key()
+        );
+
+        // There is no member at index 2.
+        assert!(group.credential(2).is_none());
+    }
+}
+
+// Test that an update-only commit carries a path, leaves the membership
+// unchanged and changes the exporter secret.
+#[apply(ciphersuites_and_backends)]
+fn test_path_only_commit(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: bob_kpb.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    let exporter_secret = |group: &CoreGroup| {
+        group
+            .export_secret(backend, "heartbeat", &[], 32)
+            .expect("Error exporting secret.")
+    };
+    let old_exporter_secret = exporter_secret(&alice_group);
+    let old_epoch = alice_group.context().epoch();
+    let identities = |group: &CoreGroup| {
+        group
+            .treesync()
+            .full_leave_members()
+            .map(|member| (member.index, member.identity))
+            .collect::<Vec<_>>()
+    };
+    let old_identities = identities(&alice_group);
+
+    // Bob sends an update-only commit.
+    let create_commit_result = bob_group
+        .rekey(framing_parameters, &bob_credential_bundle, backend)
+        .expect("Error creating commit");
+    let commit = match create_commit_result.commit.content() {
+        MlsContentBody::Commit(commit) => commit,
+        _ => panic!("Expected a commit."),
+    };
+    assert!(commit.proposals.is_empty());
+    assert!(commit.has_path());
+    assert!(create_commit_result.welcome_option.is_none());
+
+    let staged_commit = alice_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+    alice_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    bob_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    // The epoch advanced with the same members, but with new secrets.
+    assert_eq!(
+        alice_group.context().epoch().as_u64(),
+        old_epoch.as_u64() + 1
+    );
+    assert_eq!(identities(&alice_group), old_identities);
+    assert_eq!(identities(&bob_group), old_identities);
+    assert_ne!(exporter_secret(&alice_group), old_exporter_secret);
+    assert_eq!(exporter_secret(&alice_group), exporter_secret(&bob_group));
+}
+
+// Test that members in the same epoch produce identical public group states
+// and that the public group state changes with the epoch.
+#[apply(ciphersuites_and_backends)]
+fn test_public_group_state(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: bob_kpb.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    let serialized_state = |group: &CoreGroup| {
+        group
+            .public_group_state()
+            .tls_serialize_detached()
+            .expect("Error serializing public group state.")
+    };
+
+    let public_group_state = alice_group.public_group_state();
+    assert_eq!(public_group_state.group_id(), alice_group.group_id());
+    assert_eq!(public_group_state.epoch(), alice_group.context().epoch());
+    assert_eq!(
+        public_group_state.tree_hash(),
+        alice_group.context().tree_hash()
+    );
+    assert_eq!(
+        public_group_state.confirmed_transcript_hash(),
+        alice_group.context().confirmed_transcript_hash()
+    );
+    assert_eq!(
+        public_group_state.extensions(),
+        alice_group.context().extensions()
+    );
+    assert_eq!(serialized_state(&alice_group), serialized_state(&bob_group));
+
+    // The public group state survives a serialization round trip.
+    let deserialized_state =
+        PublicGroupState::tls_deserialize(&mut serialized_state(&alice_group).as_slice())
+            .expect("Error deserializing public group state.");
+    assert_eq!(deserialized_state, public_group_state);
+
+    // Bob moves to the next epoch, while Alice is still in the old one.
+    let create_commit_result = bob_group
+        .rekey(framing_parameters, &bob_credential_bundle, backend)
+        .expect("Error creating commit");
+    bob_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    assert_ne!(serialized_state(&alice_group), serialized_state(&bob_group));
+
+    // Once Alice catches up, the states are identical again.
+    let staged_commit = alice_group
+        .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+        .expect("Error staging commit");
+    alice_group
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    assert_eq!(serialized_state(&alice_group), serialized_state(&bob_group));
+}
+
+// Test that members detect a fork when their views of the group diverge.
+#[apply(ciphersuites_and_backends)]
+fn test_fork_status(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_kpb) = setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_kpb) = setup_client("Bob", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_kpb)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob
+    let proposal_store = ProposalStore::default();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: bob_kpb.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let mut bob_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        bob_kpb,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice and Bob agree on epoch 1.
+    assert_eq!(alice_group.context().epoch().as_u64(), 1);
+    let epoch_1_state = alice_group.public_group_state();
+    assert_eq!(
+        bob_group.fork_status(&alice_group.public_group_state()),
+        ForkStatus::Consistent
+    );
+    assert_eq!(
+        alice_group.fork_status(&bob_group.public_group_state()),
+        ForkStatus::Consistent
+    );
+
+    // Alice and Bob both commit and merge their own commits, which makes
+    // their views of epoch 2 diverge.
+    let alice_commit_result = alice_group
+        .rekey(framing_parameters, &alice_credential_bundle, backend)
+        .expect("Error creating commit");
+    let bob_commit_result = bob_group
+        .rekey(framing_parameters, &bob_credential_bundle, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(alice_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    bob_group
+        .merge_commit(bob_commit_result.staged_commit)
+        .expect("error merging pending commit");
+
+    assert_eq!(alice_group.context().epoch().as_u64(), 2);
+    assert_eq!(bob_group.context().epoch().as_u64(), 2);
+    assert_eq!(
+        alice_group.fork_status(&bob_group.public_group_state()),
+        ForkStatus::Forked
+    );
+    assert_eq!(
+        bob_group.fork_status(&alice_group.public_group_state()),
+        ForkStatus::Forked
+    );
+
+    // Snapshots of other epochs or other groups can't be compared.
+    assert_eq!(
+        alice_group.fork_status(&epoch_1_state),
+        ForkStatus::DifferentEpoch
+    );
+    let (charlie_credential_bundle, charlie_kpb) = setup_client("Charlie", ciphersuite, backend);
+    let charlie_group = CoreGroup::builder(GroupId::random(backend), charlie_kpb)
+        .build(&charlie_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    assert_eq!(
+        alice_group.fork_status(&charlie_group.public_group_state()),
+        ForkStatus::DifferentGroup
+    );
+}
@@ -1,14 +1,27 @@
 use crate::{
+    ciphersuite::Secret,
     credentials::{CredentialBundle, CredentialType},
-    framing::{FramingParameters, WireFormat},
-    group::{errors::ExternalCommitError, GroupId},
+    framing::{FramingParameters, ProcessedMessageContent, WireFormat},
+    group::{
+        errors::{ExternalCommitError, ExternalCommitValidationError, StageCommitError},
+        GroupId,
+    },
     key_packages::KeyPackageBundle,
-    messages::proposals::{ProposalOrRef, ProposalType},
+    messages::proposals::{
+        AddProposal, PreSharedKeyProposal, Proposal, ProposalOrRef, ProposalType,
+    },
+    schedule::{
+        psk::{PreSharedKeyId, Psk, PskBundle, ResumptionPsk, ResumptionPskUsage},
+        InitSecret,
+    },
     test_utils::*,
+    tree::sender_ratchet::SenderRatchetConfiguration,
+    versions::ProtocolVersion,
 };
 
 use openmls_rust_crypto::OpenMlsRustCrypto;
-use openmls_traits::{types::Ciphersuite, OpenMlsCryptoProvider};
+use openmls_traits::{key_store::OpenMlsKeyStore, types::Ciphersuite, OpenMlsCryptoProvider};
+use tls_codec::Serialize;
 
 use super::{
     create_commit_params::CreateCommitParams,
@@ -119,7 +132,7 @@ fn test_external_init(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProv
         .proposal_store(&proposal_store)
         .build();
     let (mut group_charly, create_commit_result) =
-        CoreGroup::join_by_external_commit(backend, params, None, verifiable_group_info)
+        CoreGroup::join_by_external_commit(backend, params, None, verifiable_group_info, None)
             .expect("Error initializing group externally.");
 
     // Have alice and bob process the commit resulting from external init.
@@ -196,6 +209,7 @@ fn test_external_init(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProv
         params,
         Some(&nodes_option),
         verifiable_group_info,
+        None,
     )
     .expect("Error initializing group externally.");
 
@@ -308,6 +322,7 @@ fn test_external_init_single_member_group(
         params,
         Some(&nodes_option),
         verifiable_group_info,
+        None,
     )
     .expect("Error initializing group externally.");
 
@@ -434,7 +449,555 @@ fn test_external_init_broken_signature(
         .build();
     assert_eq!(
         ExternalCommitError::InvalidGroupInfoSignature,
-        CoreGroup::join_by_external_commit(backend, params, None, verifiable_group_info)
+        CoreGroup::join_by_external_commit(backend, params, None, verifiable_group_info, None)
             .expect_err("Signature was corrupted. This should have failed.")
     );
 }
+
+#[apply(ciphersuites_and_backends)]
+fn test_create_external_init_proposal(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let alice_credential_bundle = CredentialBundle::new(
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package_bundle = KeyPackageBundle::new(
+        &[ciphersuite],
+        &alice_credential_bundle,
+        backend,
+        Vec::new(),
+    )
+    .expect("An unexpected error occurred.");
+
+    let group_alice = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("An unexpected error occurred.");
+
+    let group_info = group_alice
+        .export_group_info(backend, &alice_credential_bundle, false)
+        .expect("An unexpected error occurred.");
+
+    let (proposal, init_secret) = CoreGroup::create_external_init_proposal(&group_info, backend)
+        .expect("Error creating external init proposal.");
+    let kem_output = match proposal {
+        Proposal::ExternalInit(external_init_proposal) => {
+            external_init_proposal.kem_output().to_vec()
+        }
+        _ => panic!("Expected an ExternalInit proposal."),
+    };
+
+    // Alice derives the same init secret from the KEM output as when staging
+    // an external commit.
+    let external_priv = group_alice
+        .group_epoch_secrets()
+        .external_secret()
+        .derive_external_keypair(backend.crypto(), ciphersuite)
+        .private
+        .into();
+    let alice_init_secret = InitSecret::from_kem_output(
+        backend,
+        ciphersuite,
+        ProtocolVersion::Mls10,
+        &external_priv,
+        &kem_output,
+    )
+    .expect("Error deriving init secret.");
+    assert_eq!(init_secret, alice_init_secret);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_external_init_resumption_psk(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let alice_credential_bundle = CredentialBundle::new(
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package_bundle = KeyPackageBundle::new(
+        &[ciphersuite],
+        &alice_credential_bundle,
+        backend,
+        Vec::new(),
+    )
+    .expect("An unexpected error occurred.");
+    let charly_credential_bundle = CredentialBundle::new(
+        "Charly".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+
+    let group_id = GroupId::random(backend);
+    let mut group_alice = CoreGroup::builder(group_id.clone(), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("An unexpected error occurred.");
+
+    // Alice moves the group to epoch 1 so that epoch 0 is in the past.
+    let proposal_store = ProposalStore::new();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let create_commit_result = group_alice
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    group_alice
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging commit");
+
+    // Creates a resumption PSK proposal and makes the PSK available in the
+    // (shared) key store.
+    let resumption_psk_proposal = |group_id: GroupId, epoch: u64| {
+        let psk_id = PreSharedKeyId::new(
+            ciphersuite,
+            backend.rand(),
+            Psk::Resumption(ResumptionPsk::new(
+                ResumptionPskUsage::Application,
+                group_id,
+                epoch.into(),
+            )),
+        )
+        .expect("An unexpected error occurred.");
+        let secret = Secret::random(ciphersuite, backend, None /* MLS version */)
+            .expect("Not enough randomness.");
+        backend
+            .key_store()
+            .store(
+                &psk_id
+                    .tls_serialize_detached()
+                    .expect("Error serializing PSK id."),
+                &PskBundle::new(secret).expect("Could not create PskBundle."),
+            )
+            .expect("An unexpected error occurred.");
+        Proposal::PreSharedKey(PreSharedKeyProposal::new(psk_id))
+    };
+
+    // Charly commits externally with the given PSK proposal.
+    let external_commit = |psk_proposal: Proposal| {
+        let verifiable_group_info = group_alice
+            .export_group_info(backend, &alice_credential_bundle, false)
+            .expect("An unexpected error occurred.")
+            .into_verifiable_group_info();
+        let nodes = group_alice.treesync().export_nodes();
+        let proposal_store = ProposalStore::new();
+        let params = CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&charly_credential_bundle)
+            .proposal_store(&proposal_store)
+            .inline_proposals(vec![psk_proposal])
+            .build();
+        CoreGroup::join_by_external_commit(
+            backend,
+            params,
+            Some(&nodes),
+            verifiable_group_info,
+            None,
+        )
+    };
+
+    // The current epoch is rejected by the joiner, as the group info has to
+    // be newer than the resumed epoch.
+    let err = external_commit(resumption_psk_proposal(group_id.clone(), 1))
+        .expect_err("Resuming a PSK from the epoch of the group info succeeded.");
+    assert_eq!(err, ExternalCommitError::StaleGroupInfo);
+
+    // Other groups' epochs are rejected by the members.
+    let (_, create_commit_result) =
+        external_commit(resumption_psk_proposal(GroupId::random(backend), 0))
+            .expect("Error initializing group externally.");
+    let err = group_alice
+        .stage_commit(
+            &create_commit_result.commit,
+            &ProposalStore::default(),
+            &[],
+            backend,
+        )
+        .expect_err("Staging an external commit with an unknown resumption PSK succeeded.");
+    assert_eq!(
+        err,
+        StageCommitError::ExternalCommitValidation(
+            ExternalCommitValidationError::UnknownResumptionEpoch
+        )
+    );
+
+    // A past epoch of the group is accepted.
+    let (mut group_charly, create_commit_result) =
+        external_commit(resumption_psk_proposal(group_id, 0))
+            .expect("Error initializing group externally.");
+    let staged_commit = group_alice
+        .stage_commit(
+            &create_commit_result.commit,
+            &ProposalStore::default(),
+            &[],
+            backend,
+        )
+        .expect("error staging commit");
+    group_alice
+        .merge_commit(staged_commit)
+        .expect("error merging commit");
+    group_charly
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging own external commit");
+
+    assert_eq!(
+        group_charly.export_secret(backend, "", &[], ciphersuite.hash_length()),
+        group_alice.export_secret(backend, "", &[], ciphersuite.hash_length())
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_external_init_stale_group_info(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let alice_credential_bundle = CredentialBundle::new(
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package_bundle = KeyPackageBundle::new(
+        &[ciphersuite],
+        &alice_credential_bundle,
+        backend,
+        Vec::new(),
+    )
+    .expect("An unexpected error occurred.");
+    let charly_credential_bundle = CredentialBundle::new(
+        "Charly".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+
+    let group_id = GroupId::random(backend);
+    let mut group_alice = CoreGroup::builder(group_id.clone(), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("An unexpected error occurred.");
+
+    // Export a group info in epoch 0 and its tree.
+    let stale_group_info = group_alice
+        .export_group_info(backend, &alice_credential_bundle, false)
+        .expect("An unexpected error occurred.")
+        .into_verifiable_group_info();
+    let stale_nodes = group_alice.treesync().export_nodes();
+    assert_eq!(stale_group_info.epoch(), 0.into());
+
+    // Alice moves the group to epoch 1.
+    let proposal_store = ProposalStore::new();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .force_self_update(true)
+        .build();
+    let create_commit_result = group_alice
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    group_alice
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging commit");
+
+    // Charly knows of epoch 1 and resumes a PSK from it, but is presented the
+    // replayed group info of epoch 0.
+    let psk_id = PreSharedKeyId::new(
+        ciphersuite,
+        backend.rand(),
+        Psk::Resumption(ResumptionPsk::new(
+            ResumptionPskUsage::Application,
+            group_id,
+            1.into(),
+        )),
+    )
+    .expect("An unexpected error occurred.");
+    let proposal_store = ProposalStore::new();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&charly_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::PreSharedKey(PreSharedKeyProposal::new(
+            psk_id,
+        ))])
+        .build();
+    let err = CoreGroup::join_by_external_commit(
+        backend,
+        params,
+        Some(&stale_nodes),
+        stale_group_info.clone(),
+        None,
+    )
+    .expect_err("Joining with a stale group info succeeded.");
+    assert_eq!(err, ExternalCommitError::StaleGroupInfo);
+
+    // Without a PSK, Charly can pass the known epoch directly.
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&charly_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let err = CoreGroup::join_by_external_commit(
+        backend,
+        params,
+        Some(&stale_nodes),
+        stale_group_info,
+        Some(1.into()),
+    )
+    .expect_err("Joining with a stale group info succeeded.");
+    assert_eq!(err, ExternalCommitError::StaleGroupInfo);
+
+    // The group info of the current epoch is accepted.
+    let verifiable_group_info = group_alice
+        .export_group_info(backend, &alice_credential_bundle, false)
+        .expect("An unexpected error occurred.")
+        .into_verifiable_group_info();
+    let nodes = group_alice.treesync().export_nodes();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&charly_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    CoreGroup::join_by_external_commit(
+        backend,
+        params,
+        Some(&nodes),
+        verifiable_group_info,
+        Some(1.into()),
+    )
+    .expect("Error initializing group externally.");
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_external_init_three_member_group(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let credential_bundle = |identity: &str| {
+        CredentialBundle::new(
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+            backend,
+        )
+        .expect("An unexpected error occurred.")
+    };
+    let key_package_bundle = |credential_bundle: &CredentialBundle| {
+        KeyPackageBundle::new(&[ciphersuite], credential_bundle, backend, Vec::new())
+            .expect("An unexpected error occurred.")
+    };
+    let alice_credential_bundle = credential_bundle("Alice");
+    let bob_credential_bundle = credential_bundle("Bob");
+    let charly_credential_bundle = credential_bundle("Charly");
+    let dave_credential_bundle = credential_bundle("Dave");
+    let bob_key_package_bundle = key_package_bundle(&bob_credential_bundle);
+    let charly_key_package_bundle = key_package_bundle(&charly_credential_bundle);
+
+    // === Alice creates a group with Bob and Charly ===
+    let mut group_alice = CoreGroup::builder(
+        GroupId::random(backend),
+        key_package_bundle(&alice_credential_bundle),
+    )
+    .build(&alice_credential_bundle, backend)
+    .expect("An unexpected error occurred.");
+
+    let mut proposal_store = ProposalStore::new();
+    for key_package_bundle in [&bob_key_package_bundle, &charly_key_package_bundle] {
+        let add_proposal = group_alice
+            .create_add_proposal(
+                framing_parameters,
+                &alice_credential_bundle,
+                key_package_bundle.key_package().clone(),
+                backend,
+            )
+            .expect("Could not create proposal.");
+        proposal_store.add(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, add_proposal)
+                .expect("Could not create QueuedProposal."),
+        );
+    }
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let create_commit_result = group_alice
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    group_alice
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging commit");
+
+    let welcome = create_commit_result
+        .welcome_option
+        .expect("no welcome after committing to add proposals");
+    let ratchet_tree = group_alice.treesync().export_nodes();
+    let mut group_bob = CoreGroup::new_from_welcome(
+        welcome.clone(),
+        Some(ratchet_tree.clone()),
+        bob_key_package_bundle,
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let mut group_charly = CoreGroup::new_from_welcome(
+        welcome,
+        Some(ratchet_tree),
+        charly_key_package_bundle,
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+
+    // === Dave joins through an external commit ===
+    // The GroupInfo doesn't contain the ratchet tree, so Dave gets it
+    // separately.
+    let verifiable_group_info = group_alice
+        .export_group_info(backend, &alice_credential_bundle, false)
+        .expect("An unexpected error occurred.")
+        .into_verifiable_group_info();
+    let nodes = group_alice.treesync().export_nodes();
+    let proposal_store = ProposalStore::new();
+    let params = || {
+        CreateCommitParams::builder()
+            .framing_parameters(framing_parameters)
+            .credential_bundle(&dave_credential_bundle)
+            .proposal_store(&proposal_store)
+            .build()
+    };
+
+    // Joining without a tree fails.
+    let err = CoreGroup::join_by_external_commit(
+        backend,
+        params(),
+        None,
+        verifiable_group_info.clone(),
+        None,
+    )
+    .expect_err("Joined a group without the ratchet tree.");
+    assert_eq!(err, ExternalCommitError::MissingRatchetTree);
+
+    let (mut group_dave, create_commit_result) = CoreGroup::join_by_external_commit(
+        backend,
+        params(),
+        Some(&nodes),
+        verifiable_group_info,
+        None,
+    )
+    .expect("Error initializing group externally.");
+
+    for group in [&mut group_alice, &mut group_bob, &mut group_charly] {
+        let staged_commit = group
+            .stage_commit(&create_commit_result.commit, &proposal_store, &[], backend)
+            .expect("error staging commit");
+        group
+            .merge_commit(staged_commit)
+            .expect("error merging commit");
+    }
+    group_dave
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging own external commit");
+
+    assert_eq!(group_dave.treesync().leaf_count(), 4);
+    assert_eq!(
+        group_dave.export_secret(backend, "", &[], ciphersuite.hash_length()),
+        group_alice.export_secret(backend, "", &[], ciphersuite.hash_length())
+    );
+
+    // Dave can decrypt an application message from Charly.
+    let message = group_charly
+        .create_application_message(&[], b"Hello, Dave!", &charly_credential_bundle, 0, backend)
+        .expect("Error creating application message.");
+    let processed_message = group_dave
+        .process_message(
+            backend,
+            message.into(),
+            &SenderRatchetConfiguration::default(),
+            &ProposalStore::default(),
+            &[],
+        )
+        .expect("Error processing message.");
+    match processed_message.into_content() {
+        ProcessedMessageContent::ApplicationMessage(application_message) => {
+            assert_eq!(application_message.into_bytes(), b"Hello, Dave!")
+        }
+        _ => panic!("Expected an application message."),
+    }
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_external_init_invalid_inline_proposals(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+
+    let alice_credential_bundle = CredentialBundle::new(
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package_bundle = KeyPackageBundle::new(
+        &[ciphersuite],
+        &alice_credential_bundle,
+        backend,
+        Vec::new(),
+    )
+    .expect("An unexpected error occurred.");
+    let bob_credential_bundle = CredentialBundle::new(
+        "Bob".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let bob_key_package_bundle =
+        KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, Vec::new())
+            .expect("An unexpected error occurred.");
+
+    let group_alice = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("An unexpected error occurred.");
+    let verifiable_group_info = group_alice
+        .export_group_info(backend, &alice_credential_bundle, true)
+        .expect("An unexpected error occurred.")
+        .into_verifiable_group_info();
+
+    // ValSem242: An external commit must not contain inline Add proposals.
+    let proposal_store = ProposalStore::new();
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&bob_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: bob_key_package_bundle.key_package().clone(),
+        })])
+        .build();
+    let err =
+        CoreGroup::join_by_external_commit(backend, params, None, verifiable_group_info, None)
+            .expect_err("Created an external commit with an inline Add proposal.");
+    assert_eq!(
+        err,
+        ExternalCommitError::ExternalCommitValidation(
+            ExternalCommitValidationError::InvalidInlineProposals
+        )
+    );
+}
@@ -1,12 +1,16 @@
 use crate::test_utils::*;
 use openmls_rust_crypto::OpenMlsRustCrypto;
-use openmls_traits::{types::Ciphersuite, OpenMlsCryptoProvider};
+use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite, OpenMlsCryptoProvider};
+use std::collections::HashSet;
 
 use super::CoreGroup;
 use crate::{
     ciphersuite::hash_ref::ProposalRef,
     credentials::{CredentialBundle, CredentialType},
-    extensions::{ApplicationIdExtension, Extension, ExtensionType, RequiredCapabilitiesExtension},
+    extensions::{
+        ApplicationIdExtension, Clock, Extension, ExtensionType, FixedClock,
+        RequiredCapabilitiesExtension, SystemClock,
+    },
     framing::sender::Sender,
     framing::{FramingParameters, MlsAuthContent, WireFormat},
     group::{
@@ -16,7 +20,7 @@ use crate::{
         GroupContext, GroupId,
     },
     key_packages::KeyPackageBundle,
-    messages::proposals::{AddProposal, Proposal, ProposalOrRef, ProposalType},
+    messages::proposals::{AddProposal, Proposal, ProposalOrRef, ProposalType, RemoveProposal},
 };
 
 fn setup_client(
@@ -271,6 +275,161 @@ fn proposal_queue_order(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoPr
     assert_eq!(proposal_collection[1].proposal(), &proposal_add_alice1);
 }
 
+/// Test that the refs of a [`ProposalStore`] can be used to commit the stored
+/// proposals by reference.
+#[apply(ciphersuites_and_backends)]
+fn proposal_store_refs(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Framing parameters
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+    // Define identities
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_key_package_bundle) =
+        setup_client("Bob", ciphersuite, backend);
+
+    let group_context = GroupContext::new(
+        ciphersuite,
+        GroupId::random(backend),
+        0,
+        vec![],
+        vec![],
+        &[],
+    );
+
+    let proposal_add_alice = Proposal::Add(AddProposal {
+        key_package: alice_key_package_bundle.key_package().clone(),
+    });
+    let proposal_add_bob = Proposal::Add(AddProposal {
+        key_package: bob_key_package_bundle.key_package().clone(),
+    });
+
+    let mut proposal_store = ProposalStore::default();
+    for proposal in [&proposal_add_alice, &proposal_add_bob] {
+        let mls_plaintext = MlsAuthContent::member_proposal(
+            framing_parameters,
+            0,
+            proposal.clone(),
+            &alice_credential_bundle,
+            &group_context,
+            backend,
+        )
+        .expect("Could not create proposal.");
+        proposal_store.add(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, mls_plaintext)
+                .expect("Could not create QueuedProposal."),
+        );
+    }
+
+    // The refs are the ones computed from the proposals, in order.
+    let refs: Vec<ProposalRef> = proposal_store.refs().collect();
+    assert_eq!(
+        refs,
+        vec![
+            ProposalRef::from_proposal(ciphersuite, backend, &proposal_add_alice)
+                .expect("An unexpected error occurred."),
+            ProposalRef::from_proposal(ciphersuite, backend, &proposal_add_bob)
+                .expect("An unexpected error occurred."),
+        ]
+    );
+
+    // The refs resolve to the stored proposals when staging a commit.
+    let proposal_queue = ProposalQueue::from_committed_proposals(
+        ciphersuite,
+        backend,
+        refs.into_iter().map(ProposalOrRef::Reference).collect(),
+        &proposal_store,
+        &Sender::build_member(1),
+    )
+    .expect("An unexpected error occurred.");
+
+    let proposal_collection: Vec<&QueuedProposal> =
+        proposal_queue.filtered_by_type(ProposalType::Add).collect();
+
+    assert_eq!(proposal_collection[0].proposal(), &proposal_add_alice);
+    assert_eq!(proposal_collection[1].proposal(), &proposal_add_bob);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn proposal_store_insert_many_dedup(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    // Framing parameters
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+    // Define identities
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_key_package_bundle) = setup_client("Bob", ciphersuite, backend);
+
+    let group_context = GroupContext::new(
+        ciphersuite,
+        GroupId::random(backend),
+        0,
+        vec![],
+        vec![],
+        &[],
+    );
+
+    let proposal_add_alice = Proposal::Add(AddProposal {
+        key_package: alice_key_package_bundle.key_package().clone(),
+    });
+    let proposal_add_bob = Proposal::Add(AddProposal {
+        key_package: bob_key_package_bundle.key_package().clone(),
+    });
+
+    // Proposals are equal and hash equally if their content is equal.
+    let proposals: HashSet<Proposal> = [
+        proposal_add_alice.clone(),
+        proposal_add_bob.clone(),
+        proposal_add_alice.clone(),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(proposals.len(), 2);
+
+    let queued_proposal =
+        |proposal: &Proposal, sender: u32, credential_bundle: &CredentialBundle| {
+            let mls_plaintext = MlsAuthContent::member_proposal(
+                framing_parameters,
+                sender,
+                proposal.clone(),
+                credential_bundle,
+                &group_context,
+                backend,
+            )
+            .expect("Could not create proposal.");
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, mls_plaintext)
+                .expect("Could not create QueuedProposal.")
+        };
+
+    let mut proposal_store = ProposalStore::default();
+    proposal_store.add(queued_proposal(
+        &proposal_add_alice,
+        0,
+        &alice_credential_bundle,
+    ));
+
+    // The same proposal re-broadcast by another sender, as well as duplicates
+    // within the batch, are skipped.
+    proposal_store.insert_many_dedup([
+        queued_proposal(&proposal_add_alice, 1, &bob_credential_bundle),
+        queued_proposal(&proposal_add_bob, 1, &bob_credential_bundle),
+        queued_proposal(&proposal_add_bob, 0, &alice_credential_bundle),
+    ]);
+
+    let stored: Vec<(&Proposal, &Sender)> = proposal_store
+        .proposals()
+        .map(|queued_proposal| (queued_proposal.proposal(), queued_proposal.sender()))
+        .collect();
+    assert_eq!(
+        stored,
+        vec![
+            (&proposal_add_alice, &Sender::build_member(0)),
+            (&proposal_add_bob, &Sender::build_member(1)),
+        ]
+    );
+}
+
 #[apply(ciphersuites_and_backends)]
 fn test_required_unsupported_proposals(
     ciphersuite: Ciphersuite,
@@ -662,3 +821,464 @@ fn test_group_context_extension_proposal(
             .expect("Error exporting secret.")
     )
 }
+
+#[apply(ciphersuites_and_backends)]
+fn test_add_proposal_signature_scheme_mismatch(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    // We need a second ciphersuite with a different signature scheme.
+    let other_ciphersuite = match backend
+        .crypto()
+        .supported_ciphersuites()
+        .into_iter()
+        .find(|cs| cs.signature_algorithm() != ciphersuite.signature_algorithm())
+    {
+        Some(other_ciphersuite) => other_ciphersuite,
+        None => return,
+    };
+
+    // Basic group setup.
+    let group_aad = b"Alice's test group";
+    let framing_parameters = FramingParameters::new(group_aad, WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+
+    // Bob's credential uses the signature scheme of the other ciphersuite, but
+    // his key package claims the group's ciphersuite.
+    let bob_credential_bundle = CredentialBundle::new(
+        "Bob".into(),
+        CredentialType::Basic,
+        other_ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let mut bob_key_package_bundle = KeyPackageBundle::new(
+        &[ciphersuite, other_ciphersuite],
+        &bob_credential_bundle,
+        backend,
+        Vec::new(),
+    )
+    .expect("An unexpected error occurred.");
+    bob_key_package_bundle.set_ciphersuite(ciphersuite);
+
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+    assert_eq!(
+        alice_group.signature_scheme(),
+        ciphersuite.signature_algorithm()
+    );
+    assert!(alice_group
+        .treesync()
+        .full_leave_members()
+        .all(|member| member.signature_scheme() == ciphersuite.signature_algorithm()));
+
+    let bob_add_proposal = alice_group
+        .create_add_proposal(
+            framing_parameters,
+            &alice_credential_bundle,
+            bob_key_package_bundle.key_package().clone(),
+            backend,
+        )
+        .expect("Could not create proposal");
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+            .expect("Could not create QueuedProposal."),
+    );
+
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let e = alice_group
+        .create_commit(params, backend)
+        .expect_err("Created a commit adding a member with the wrong signature scheme.");
+    assert_eq!(
+        e,
+        CreateCommitError::ProposalValidationError(
+            ProposalValidationError::SignatureSchemeMismatch
+        )
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_proposer_no_longer_member(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Basic group setup.
+    let group_aad = b"Alice's test group";
+    let framing_parameters = FramingParameters::new(group_aad, WireFormat::MlsPlaintext);
+
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+    let (bob_credential_bundle, bob_key_package_bundle) = setup_client("Bob", ciphersuite, backend);
+    let (_charlie_credential_bundle, charlie_key_package_bundle) =
+        setup_client("Charlie", ciphersuite, backend);
+
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob.
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&ProposalStore::new())
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: bob_key_package_bundle.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging commit");
+
+    // Bob proposes to add Charlie.
+    let bob_proposal = |alice_group: &CoreGroup| {
+        let bob_add_proposal = MlsAuthContent::member_proposal(
+            framing_parameters,
+            1,
+            Proposal::Add(AddProposal {
+                key_package: charlie_key_package_bundle.key_package().clone(),
+            }),
+            &bob_credential_bundle,
+            alice_group.context(),
+            backend,
+        )
+        .expect("Could not create proposal.");
+        ProposalStore::from_queued_proposal(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, bob_add_proposal)
+                .expect("Could not create QueuedProposal."),
+        )
+    };
+
+    // Alice can commit Bob's proposal while removing Bob in the same commit.
+    let proposal_store = bob_proposal(&alice_group);
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Remove(RemoveProposal { removed: 1 })])
+        .build();
+    alice_group
+        .create_commit(params, backend)
+        .expect("Error committing the proposal of a member removed by the same commit.");
+
+    // Alice removes Bob.
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&ProposalStore::new())
+        .inline_proposals(vec![Proposal::Remove(RemoveProposal { removed: 1 })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging commit");
+
+    // Bob's proposal can't be committed anymore.
+    let proposal_store = bob_proposal(&alice_group);
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    let e = alice_group
+        .create_commit(params, backend)
+        .expect_err("Committed the proposal of a member that is no longer in the group.");
+    assert_eq!(
+        e,
+        CreateCommitError::ProposalValidationError(ProposalValidationError::ProposerNoLongerMember)
+    );
+}
+
+/// Test that a committer can't remove itself in a commit with a path
+/// (ValSem108).
+#[apply(ciphersuites_and_backends)]
+fn test_remove_proposal_self_remove_with_path(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice removes herself.
+    let mut proposal_queue = ProposalQueue::default();
+    proposal_queue.add(
+        QueuedProposal::from_proposal_and_sender(
+            ciphersuite,
+            backend,
+            Proposal::Remove(RemoveProposal { removed: 0 }),
+            &Sender::build_member(0),
+        )
+        .expect("Could not create QueuedProposal."),
+    );
+
+    // With a path, the self-removal is rejected.
+    assert_eq!(
+        alice_group
+            .validate_remove_proposals(&proposal_queue, Some(0), true)
+            .expect_err("Self-removal with a path was accepted."),
+        ProposalValidationError::SelfRemoveWithPath
+    );
+
+    // Removals by another committer may carry a path.
+    alice_group
+        .validate_remove_proposals(&proposal_queue, Some(1), true)
+        .expect("Removal with a path was rejected.");
+
+    // Remove proposals require a path (ValSem201), so Alice can't commit her
+    // own removal.
+    let proposal_store = ProposalStore::from_queued_proposal(
+        QueuedProposal::from_proposal_and_sender(
+            ciphersuite,
+            backend,
+            Proposal::Remove(RemoveProposal { removed: 0 }),
+            &Sender::build_member(0),
+        )
+        .expect("Could not create QueuedProposal."),
+    );
+    let params = CreateCommitParams::builder()
+        .framing_parameters(FramingParameters::new(&[], WireFormat::MlsPlaintext))
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .build();
+    assert_eq!(
+        alice_group
+            .create_commit(params, backend)
+            .expect_err("Committed the own removal."),
+        CreateCommitError::ProposalValidationError(ProposalValidationError::SelfRemoveWithPath)
+    );
+}
+
+/// Test that the key packages of Add proposals have to be valid at the current
+/// time.
+#[apply(ciphersuites_and_backends)]
+fn test_add_proposal_key_package_lifetime(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_key_package_bundle) =
+        setup_client("Bob", ciphersuite, backend);
+    let alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    // Alice adds Bob.
+    let mut proposal_queue = ProposalQueue::default();
+    proposal_queue.add(
+        QueuedProposal::from_proposal_and_sender(
+            ciphersuite,
+            backend,
+            Proposal::Add(AddProposal {
+                key_package: bob_key_package_bundle.key_package().clone(),
+            }),
+            &Sender::build_member(0),
+        )
+        .expect("Could not create QueuedProposal."),
+    );
+
+    // Bob's key package is valid now.
+    let now = SystemClock.now();
+    alice_group
+        .validate_add_proposals(&proposal_queue, &FixedClock(now))
+        .expect("A valid key package was rejected.");
+
+    // A year from now, it has expired.
+    assert_eq!(
+        alice_group
+            .validate_add_proposals(&proposal_queue, &FixedClock(now + 60 * 60 * 24 * 365))
+            .expect_err("An expired key package was accepted."),
+        ProposalValidationError::KeyPackageExpired
+    );
+}
+
+/// Test that the clock used to check the lifetimes of added key packages can
+/// be injected when creating and when staging a commit.
+#[apply(ciphersuites_and_backends)]
+fn test_commit_key_package_lifetime_clock(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_key_package_bundle) =
+        setup_client("Bob", ciphersuite, backend);
+    let (_charlie_credential_bundle, charlie_key_package_bundle) =
+        setup_client("Charlie", ciphersuite, backend);
+    let mut alice_group = CoreGroup::builder(GroupId::random(backend), alice_key_package_bundle)
+        .build(&alice_credential_bundle, backend)
+        .expect("Error creating CoreGroup.");
+
+    let now = SystemClock.now();
+    let next_year = FixedClock(now + 60 * 60 * 24 * 365);
+    let proposal_store = ProposalStore::default();
+    let add_bob = vec![Proposal::Add(AddProposal {
+        key_package: bob_key_package_bundle.key_package().clone(),
+    })];
+
+    // Alice can't add Bob a year from now.
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(add_bob.clone())
+        .clock(&next_year)
+        .build();
+    let err = alice_group
+        .create_commit(params, backend)
+        .expect_err("An expired key package was accepted.");
+    assert_eq!(
+        err,
+        CreateCommitError::ProposalValidationError(ProposalValidationError::KeyPackageExpired)
+    );
+
+    // Alice adds Charlie.
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(vec![Proposal::Add(AddProposal {
+            key_package: charlie_key_package_bundle.key_package().clone(),
+        })])
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    alice_group
+        .merge_commit(create_commit_result.staged_commit)
+        .expect("error merging pending commit");
+    let charlie_group = CoreGroup::new_from_welcome(
+        create_commit_result
+            .welcome_option
+            .expect("An unexpected error occurred."),
+        Some(alice_group.treesync().export_nodes()),
+        charlie_key_package_bundle,
+        backend,
+    )
+    .expect("Error joining group.");
+
+    // Alice adds Bob now. Charlie accepts the commit now, but not a year
+    // from now.
+    let params = CreateCommitParams::builder()
+        .framing_parameters(framing_parameters)
+        .credential_bundle(&alice_credential_bundle)
+        .proposal_store(&proposal_store)
+        .inline_proposals(add_bob)
+        .clock(&FixedClock(now))
+        .build();
+    let create_commit_result = alice_group
+        .create_commit(params, backend)
+        .expect("Error creating commit");
+    let err = charlie_group
+        .stage_commit_with_clock(
+            &create_commit_result.commit,
+            &proposal_store,
+            &[],
+            backend,
+            &next_year,
+        )
+        .expect_err("An expired key package was accepted.");
+    assert_eq!(
+        err,
+        StageCommitError::ProposalValidationError(ProposalValidationError::KeyPackageExpired)
+    );
+    charlie_group
+        .stage_commit_at(
+            &create_commit_result.commit,
+            &proposal_store,
+            &[],
+            backend,
+            Some(now),
+        )
+        .expect("A valid key package was rejected.");
+}
+
+/// Test that proposals committed by reference are only accepted if they are
+/// in the proposal store.
+#[apply(ciphersuites_and_backends)]
+fn test_committed_proposal_references(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    // Framing parameters
+    let framing_parameters = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+    // Define identities
+    let (alice_credential_bundle, alice_key_package_bundle) =
+        setup_client("Alice", ciphersuite, backend);
+    let (_bob_credential_bundle, bob_key_package_bundle) =
+        setup_client("Bob", ciphersuite, backend);
+
+    let group_context = GroupContext::new(
+        ciphersuite,
+        GroupId::random(backend),
+        0,
+        vec![],
+        vec![],
+        &[],
+    );
+
+    let proposal_add_alice = Proposal::Add(AddProposal {
+        key_package: alice_key_package_bundle.key_package().clone(),
+    });
+    let proposal_reference_add_alice =
+        ProposalRef::from_proposal(ciphersuite, backend, &proposal_add_alice)
+            .expect("An unexpected error occurred.");
+    let proposal_add_bob = Proposal::Add(AddProposal {
+        key_package: bob_key_package_bundle.key_package().clone(),
+    });
+    let proposal_reference_add_bob =
+        ProposalRef::from_proposal(ciphersuite, backend, &proposal_add_bob)
+            .expect("An unexpected error occurred.");
+
+    let queued_proposal = |proposal: &Proposal| {
+        let mls_plaintext = MlsAuthContent::member_proposal(
+            framing_parameters,
+            0,
+            proposal.clone(),
+            &alice_credential_bundle,
+            &group_context,
+            backend,
+        )
+        .expect("Could not create proposal.");
+        QueuedProposal::from_mls_plaintext(ciphersuite, backend, mls_plaintext)
+            .expect("Could not create QueuedProposal.")
+    };
+    let sender = Sender::build_member(1);
+
+    // Matching reference
+    let proposal_store = ProposalStore::from_queued_proposal(queued_proposal(&proposal_add_alice));
+    let proposal_queue = ProposalQueue::from_committed_proposals(
+        ciphersuite,
+        backend,
+        vec![ProposalOrRef::Reference(
+            proposal_reference_add_alice.clone(),
+        )],
+        &proposal_store,
+        &sender,
+    )
+    .expect("An unexpected error occurred.");
+    assert!(proposal_queue.contains(&[proposal_reference_add_alice]));
+
+    // Missing proposal
+    let err = ProposalQueue::from_committed_proposals(
+        ciphersuite,
+        backend,
+        vec![ProposalOrRef::Reference(proposal_reference_add_bob)],
+        &proposal_store,
+        &sender,
+    )
+    .expect_err("Committing a missing proposal by reference was accepted.");
+    assert_eq!(err, FromCommittedProposalsError::ProposalNotFound);
+}
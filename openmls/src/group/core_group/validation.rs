@@ -3,18 +3,22 @@
 
 use std::collections::HashSet;
 
+use openmls_traits::{key_store::OpenMlsKeyStore, OpenMlsCryptoProvider};
+use tls_codec::Serialize;
+
 use crate::{
     error::LibraryError,
-    extensions::ExtensionType,
+    extensions::{Clock, ExtensionType, ExternalSender},
     framing::Sender,
     group::errors::ExternalCommitValidationError,
     group::errors::ValidationError,
     messages::proposals::{Proposal, ProposalOrRefType, ProposalType},
+    schedule::psk::{Psk, PskBundle},
     treesync::node::leaf_node::LeafNode,
 };
 
 use super::{
-    proposals::ProposalQueue, ContentType, CoreGroup, Member, MlsMessageIn,
+    proposals::ProposalQueue, ContentType, CoreGroup, Member, MlsContentBody, MlsMessageIn,
     ProposalValidationError, VerifiableMlsAuthContent, WireFormat,
 };
 
@@ -50,27 +54,72 @@ impl CoreGroup {
         Ok(())
     }
 
+    /// Cheaply checks whether a received commit can be applied to the current
+    /// state of the group before it is decrypted or staged. This doesn't do
+    /// any cryptographic operations, so a commit that passes can still be
+    /// rejected by [`CoreGroup::stage_commit()`].
+    ///
+    /// Checks the following semantic validation:
+    ///  - ValSem002
+    ///  - ValSem003
+    ///  - ValSem004 (only for [`MlsPlaintext`](crate::framing::MlsPlaintext)s,
+    ///    since the sender of an [`MlsCiphertext`](crate::framing::MlsCiphertext)
+    ///    is encrypted)
+    pub(crate) fn can_process_commit(&self, message: &MlsMessageIn) -> Result<(), ValidationError> {
+        if message.content_type() != ContentType::Commit {
+            return Err(ValidationError::NotACommit);
+        }
+
+        // ValSem002 and ValSem003
+        self.validate_framing(message)?;
+
+        // ValSem004
+        match message.sender() {
+            Some(Sender::Member(leaf_index)) => self
+                .treesync()
+                .leaf_is_in_tree(*leaf_index)
+                .map_err(|_| ValidationError::UnknownMember),
+            Some(Sender::NewMemberCommit) | None => Ok(()),
+            Some(Sender::External(_)) | Some(Sender::NewMemberProposal) => {
+                Err(ValidationError::UnknownMember)
+            }
+        }
+    }
+
     /// Checks the following semantic validation:
     ///  - ValSem004
     ///  - ValSem005
     ///  - ValSem009
+    ///
+    /// It also checks the length of the authenticated data of application
+    /// messages against the group's `max_aad_length`.
     pub(crate) fn validate_plaintext(
         &self,
         plaintext: &VerifiableMlsAuthContent,
     ) -> Result<(), ValidationError> {
         // ValSem004
-        let sender = plaintext.sender();
-        if let Sender::Member(leaf_index) = sender {
-            // If the sender is a member, it has to be in the tree.
-            // TODO: #133 Lookup of a leaf index in the old tree isn't very
-            //       useful. Add a proper validation step here.
-            if self.treesync().leaf_is_in_tree(*leaf_index).is_err()
-                && !self
-                    .message_secrets_store
-                    .epoch_has_leaf(plaintext.epoch(), *leaf_index)
-            {
-                return Err(ValidationError::UnknownMember);
+        match plaintext.sender() {
+            Sender::Member(leaf_index) => {
+                // If the sender is a member, it has to be in the tree.
+                // TODO: #133 Lookup of a leaf index in the old tree isn't very
+                //       useful. Add a proper validation step here.
+                if self.treesync().leaf_is_in_tree(*leaf_index).is_err()
+                    && !self
+                        .message_secrets_store
+                        .epoch_has_leaf(plaintext.epoch(), *leaf_index)
+                {
+                    return Err(ValidationError::UnknownMember);
+                }
             }
+            Sender::External(sender_index) => {
+                // An external sender has to be listed in the group's external
+                // senders extension and can only send proposals.
+                self.external_sender(*sender_index)?;
+                if plaintext.content_type() != ContentType::Proposal {
+                    return Err(ValidationError::UnauthorizedExternalSender);
+                }
+            }
+            Sender::NewMemberProposal | Sender::NewMemberCommit => (),
         }
 
         // ValSem005
@@ -81,6 +130,10 @@ impl CoreGroup {
             } else if !plaintext.sender().is_member() {
                 return Err(ValidationError::NonMemberApplicationMessage);
             }
+            let aad_length = plaintext.authenticated_data().len();
+            if matches!(self.max_aad_length, Some(max_aad_length) if aad_length > max_aad_length) {
+                return Err(ValidationError::AadTooLarge);
+            }
         }
 
         // ValSem009
@@ -92,8 +145,109 @@ impl CoreGroup {
         Ok(())
     }
 
+    /// Returns the external senders listed in the group's external senders
+    /// extension. The list is empty if the group doesn't have the extension.
+    pub(crate) fn external_senders(&self) -> &[ExternalSender] {
+        self.group_context_extensions()
+            .iter()
+            .find_map(|extension| extension.as_external_senders_extension().ok())
+            .map(|external_senders| external_senders.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns the external sender with the given index in the group's
+    /// external senders extension or a
+    /// [`ValidationError::UnauthorizedExternalSender`] if there is none.
+    pub(crate) fn external_sender(
+        &self,
+        sender_index: u32,
+    ) -> Result<&ExternalSender, ValidationError> {
+        self.external_senders()
+            .get(sender_index as usize)
+            .ok_or(ValidationError::UnauthorizedExternalSender)
+    }
+
     // === Proposals ===
 
+    /// Validate that all proposals in the queue are of a type allowed by the
+    /// group's local policy (see `CoreGroupConfig::allowed_proposal_types`).
+    pub(crate) fn validate_allowed_proposal_types(
+        &self,
+        proposal_queue: &ProposalQueue,
+    ) -> Result<(), ProposalValidationError> {
+        if let Some(allowed_proposal_types) = &self.allowed_proposal_types {
+            if proposal_queue.queued_proposals().any(|queued_proposal| {
+                !allowed_proposal_types.contains(&queued_proposal.proposal().proposal_type())
+            }) {
+                return Err(ProposalValidationError::ProposalTypeNotAllowed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate that the senders of all member proposals in the queue that were
+    /// committed by reference are still members of the group. A proposer that
+    /// is removed by the commit itself is still a member at this point and
+    /// thus allowed.
+    pub(crate) fn validate_proposers(
+        &self,
+        proposal_queue: &ProposalQueue,
+    ) -> Result<(), ProposalValidationError> {
+        for queued_proposal in proposal_queue.queued_proposals().filter(|queued_proposal| {
+            queued_proposal.proposal_or_ref_type() == ProposalOrRefType::Reference
+        }) {
+            if let Sender::Member(leaf_index) = queued_proposal.sender() {
+                if !matches!(self.treesync().leaf(*leaf_index), Ok(Some(_))) {
+                    return Err(ProposalValidationError::ProposerNoLongerMember);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify the signatures of all proposals in the queue that were committed
+    /// by reference against the credentials of their senders, if required by
+    /// the group's configuration (see
+    /// `CoreGroupConfig::verify_proposal_signatures_in_commit`).
+    pub(crate) fn validate_proposal_signatures(
+        &self,
+        proposal_queue: &ProposalQueue,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(), ValidationError> {
+        if !self.verify_proposal_signatures_in_commit {
+            return Ok(());
+        }
+        for queued_proposal in proposal_queue.queued_proposals().filter(|queued_proposal| {
+            queued_proposal.proposal_or_ref_type() == ProposalOrRefType::Reference
+        }) {
+            let authenticated_content = queued_proposal
+                .authenticated_content()
+                .ok_or(ValidationError::InvalidSignature)?;
+            // The signed content must actually contain the committed proposal.
+            if authenticated_content.content()
+                != &MlsContentBody::Proposal(queued_proposal.proposal().clone())
+            {
+                return Err(ValidationError::InvalidSignature);
+            }
+            let credential = match queued_proposal.sender() {
+                Sender::Member(leaf_index) => self
+                    .treesync()
+                    .leaf(*leaf_index)
+                    .map_err(|_| ValidationError::UnknownMember)?
+                    .ok_or(ValidationError::UnknownMember)?
+                    .credential(),
+                Sender::NewMemberProposal => match queued_proposal.proposal() {
+                    Proposal::Add(add_proposal) => add_proposal.key_package().credential(),
+                    _ => return Err(ValidationError::NotAnExternalAddProposal),
+                },
+                Sender::External(sender_index) => self.external_sender(*sender_index)?.credential(),
+                Sender::NewMemberCommit => return Err(ValidationError::InvalidSignature),
+            };
+            authenticated_content.verify_signature(backend, credential)?;
+        }
+        Ok(())
+    }
+
     /// Validate Add proposals. This function implements the following checks:
     ///  - ValSem100
     ///  - ValSem101
@@ -105,8 +259,10 @@ impl CoreGroup {
     pub(crate) fn validate_add_proposals(
         &self,
         proposal_queue: &ProposalQueue,
+        clock: &(impl Clock + ?Sized),
     ) -> Result<(), ProposalValidationError> {
         let add_proposals = proposal_queue.add_proposals();
+        let now = clock.now();
 
         let mut identity_set = HashSet::new();
         let mut signature_key_set = HashSet::new();
@@ -144,6 +300,30 @@ impl CoreGroup {
                 return Err(ProposalValidationError::DuplicatePublicKeyAddProposal);
             }
 
+            // Check that the signature key of the new member is of the
+            // signature scheme expected by the group.
+            if add_proposal
+                .add_proposal()
+                .key_package()
+                .credential()
+                .signature_scheme()
+                != self.signature_scheme()
+            {
+                return Err(ProposalValidationError::SignatureSchemeMismatch);
+            }
+
+            // The lifetime of the key package has to contain the current time.
+            if let Some(lifetime) = add_proposal
+                .add_proposal()
+                .key_package()
+                .leaf_node()
+                .life_time()
+            {
+                if !lifetime.is_valid_at(now) {
+                    return Err(ProposalValidationError::KeyPackageExpired);
+                }
+            }
+
             // ValSem106: Check the required capabilities of the add proposals
             // This includes the following checks:
             // - Do ciphersuite and version match that of the group?
@@ -203,6 +383,7 @@ impl CoreGroup {
             identity,
             encryption_key: _,
             signature_key,
+            ..
         } in self.treesync().full_leave_members()
         {
             // ValSem103
@@ -231,9 +412,15 @@ impl CoreGroup {
     /// Validate Remove proposals. This function implements the following checks:
     ///  - ValSem107
     ///  - ValSem108
+    ///
+    /// `committer` is the leaf index of the member that issued the commit, if
+    /// it is known, and `commit_has_path` indicates whether the commit has a
+    /// path. A committer can't remove itself while updating its own path.
     pub(crate) fn validate_remove_proposals(
         &self,
         proposal_queue: &ProposalQueue,
+        committer: Option<u32>,
+        commit_has_path: bool,
     ) -> Result<(), ProposalValidationError> {
         let remove_proposals = proposal_queue.remove_proposals();
 
@@ -246,15 +433,65 @@ impl CoreGroup {
                 return Err(ProposalValidationError::DuplicateMemberRemoval);
             }
 
-            // TODO: ValSem108
+            // ValSem108
             if self.treesync().leaf_is_in_tree(removed).is_err() {
                 return Err(ProposalValidationError::UnknownMemberRemoval);
             }
+            if commit_has_path && committer == Some(removed) {
+                return Err(ProposalValidationError::SelfRemoveWithPath);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate PreSharedKey proposals. Each PSK can only be included once,
+    /// since it would otherwise be folded into the PSK secret twice. A
+    /// resumption PSK of this group must reference an epoch strictly before
+    /// the current one, since later epochs don't exist yet.
+    pub(crate) fn validate_psk_proposals(
+        &self,
+        proposal_queue: &ProposalQueue,
+    ) -> Result<(), ProposalValidationError> {
+        let mut psk_ids_set = HashSet::new();
+
+        for queued_psk_proposal in proposal_queue.psk_proposals() {
+            let psk_id = queued_psk_proposal
+                .psk_proposal()
+                .psk()
+                .tls_serialize_detached()
+                .map_err(LibraryError::missing_bound_check)?;
+            if !psk_ids_set.insert(psk_id) {
+                return Err(ProposalValidationError::DuplicatePsk);
+            }
+
+            if let Psk::Resumption(resumption_psk) = queued_psk_proposal.psk_proposal().psk().psk()
+            {
+                if resumption_psk.psk_group_id() == self.group_id()
+                    && resumption_psk.psk_epoch() >= self.context().epoch()
+                {
+                    return Err(ProposalValidationError::InvalidResumptionEpoch);
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Validate ReInit proposals. A commit that covers a ReInit proposal must
+    /// not cover any other proposal.
+    pub(crate) fn validate_reinit_proposals(
+        &self,
+        proposal_queue: &ProposalQueue,
+    ) -> Result<(), ProposalValidationError> {
+        if proposal_queue.reinit_proposal().is_some()
+            && proposal_queue.queued_proposals().count() > 1
+        {
+            return Err(ProposalValidationError::ReInitNotAlone);
+        }
+        Ok(())
+    }
+
     /// Validate Update proposals. This function implements the following checks:
     ///  - ValSem109
     ///  - ValSem110
@@ -367,13 +604,53 @@ impl CoreGroup {
         Ok(())
     }
 
-    /// Validate constraints on an external commit. This function implements the following checks:
+    /// Validate constraints on an external commit. This function implements the
+    /// checks of [`Self::validate_external_commit_proposals()`] (ValSem240 -
+    /// ValSem243), as well as the following check:
+    ///  - ValSem247: External Commit, inline PreSharedKey proposal: A resumption
+    ///               PSK must reference a known past epoch of this group.
+    pub(crate) fn validate_external_commit(
+        &self,
+        proposal_queue: &ProposalQueue,
+        path_leaf_node: Option<&LeafNode>,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(), ExternalCommitValidationError> {
+        self.validate_external_commit_proposals(proposal_queue, path_leaf_node)?;
+
+        // ValSem247: External Commit, inline PreSharedKey proposal: A
+        //            resumption PSK must reference a past epoch of this group
+        //            for which we can resolve the PSK.
+        for proposal in proposal_queue.filtered_by_type(ProposalType::Presharedkey) {
+            if let Proposal::PreSharedKey(psk_proposal) = proposal.proposal() {
+                if let Psk::Resumption(resumption_psk) = psk_proposal.psk().psk() {
+                    if resumption_psk.psk_group_id() != self.group_id()
+                        || resumption_psk.psk_epoch() >= self.context().epoch()
+                    {
+                        return Err(ExternalCommitValidationError::UnknownResumptionEpoch);
+                    }
+                    let psk_id = psk_proposal
+                        .psk()
+                        .tls_serialize_detached()
+                        .map_err(LibraryError::missing_bound_check)?;
+                    if backend.key_store().read::<PskBundle>(&psk_id).is_none() {
+                        return Err(ExternalCommitValidationError::UnknownResumptionEpoch);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate the proposals of an external commit. This function is used
+    /// both when creating and when staging an external commit and implements
+    /// the following checks:
     ///  - ValSem240: External Commit, inline Proposals: There MUST be at least one ExternalInit proposal.
     ///  - ValSem241: External Commit, inline Proposals: There MUST be at most one ExternalInit proposal.
     ///  - ValSem242: External Commit must only cover inline proposal in allowlist (ExternalInit, Remove, PreSharedKey)
     ///  - ValSem243: External Commit, inline Remove Proposal: The identity and the endpoint_id of the removed
-    ///               leaf are identical to the ones in the path KeyPackage.
-    pub(crate) fn validate_external_commit(
+    ///               leaf are identical to the ones in the path KeyPackage. Leaf nodes don't carry an endpoint_id,
+    ///               so only the identities are compared.
+    pub(crate) fn validate_external_commit_proposals(
         &self,
         proposal_queue: &ProposalQueue,
         path_leaf_node: Option<&LeafNode>,
@@ -412,7 +689,9 @@ impl CoreGroup {
                         // ValSem243: External Commit, inline Remove Proposal:
                         //            The identity and the endpoint_id of the
                         //            removed leaf are identical to the ones
-                        //            in the path leaf node.
+                        //            in the path leaf node. Leaf nodes don't
+                        //            carry an endpoint_id, so we only
+                        //            compare the identities.
                         let removed_leaf = self
                             .treesync()
                             .leaf(removed_leaf)
@@ -426,6 +705,7 @@ impl CoreGroup {
                 }
             }
         }
+
         Ok(())
     }
 }
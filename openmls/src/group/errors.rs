@@ -6,10 +6,11 @@ use crate::{
     error::LibraryError,
     extensions::errors::ExtensionError,
     framing::errors::{MessageDecryptionError, SenderError},
-    key_packages::errors::KeyPackageExtensionSupportError,
+    key_packages::errors::{KeyPackageBundleNewError, KeyPackageExtensionSupportError},
     schedule::errors::PskError,
     treesync::errors::*,
 };
+use openmls_traits::types::Ciphersuite;
 use thiserror::Error;
 
 // === Public errors ===
@@ -23,8 +24,15 @@ pub enum WelcomeError {
     #[error(transparent)]
     LibraryError(#[from] LibraryError),
     /// Ciphersuites in Welcome and key package bundle don't match.
-    #[error("Ciphersuites in Welcome and key package bundle don't match.")]
-    CiphersuiteMismatch,
+    #[error(
+        "Ciphersuites in Welcome ({group:?}) and key package bundle ({key_package:?}) don't match."
+    )]
+    CiphersuiteMismatch {
+        /// The ciphersuite of the group.
+        group: Ciphersuite,
+        /// The ciphersuite of the key package bundle.
+        key_package: Ciphersuite,
+    },
     /// Ciphersuites in Welcome/GroupInfo and key package bundle don't match.
     #[error("Ciphersuites in Welcome/GroupInfo and key package bundle don't match.")]
     GroupInfoCiphersuiteMismatch,
@@ -79,6 +87,15 @@ pub enum WelcomeError {
     /// This error indicates the public tree is invalid. See [`PublicTreeError`] for more details.
     #[error(transparent)]
     PublicTreeError(#[from] PublicTreeError),
+    /// The group state derived from the Welcome does not match the GroupInfo.
+    #[error("The group state derived from the Welcome does not match the GroupInfo.")]
+    GroupInfoMismatch,
+    /// The GroupInfo is from an older epoch than the joiner already knows of.
+    #[error("The GroupInfo is from an older epoch than the joiner already knows of.")]
+    StaleGroupInfo,
+    /// The Welcome doesn't inject a branch PSK of the given group.
+    #[error("The Welcome doesn't inject a branch PSK of the given group.")]
+    NotABranch,
 }
 
 /// External Commit error
@@ -117,6 +134,32 @@ pub enum ExternalCommitError {
     /// This error indicates the public tree is invalid. See [`PublicTreeError`] for more details.
     #[error(transparent)]
     PublicTreeError(#[from] PublicTreeError),
+    /// The GroupInfo is from an older epoch than the joiner already knows of.
+    #[error("The GroupInfo is from an older epoch than the joiner already knows of.")]
+    StaleGroupInfo,
+    /// See [`ExternalCommitValidationError`] for more details.
+    #[error(transparent)]
+    ExternalCommitValidation(ExternalCommitValidationError),
+}
+
+/// Public group error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum PublicGroupError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The computed tree hash does not match the one in the GroupInfo.
+    #[error("The computed tree hash does not match the one in the GroupInfo.")]
+    TreeHashMismatch,
+    /// We don't support the version of the group we are trying to observe.
+    #[error("We don't support the version of the group we are trying to observe.")]
+    UnsupportedMlsVersion,
+    /// The signature over the given group info is invalid.
+    #[error("The signature over the given group info is invalid.")]
+    InvalidGroupInfoSignature,
+    /// This error indicates the public tree is invalid. See [`PublicTreeError`] for more details.
+    #[error(transparent)]
+    PublicTreeError(#[from] PublicTreeError),
 }
 
 /// Stage Commit error
@@ -146,6 +189,12 @@ pub enum StageCommitError {
     /// The confirmation tag is invalid.
     #[error("The confirmation tag is invalid.")]
     ConfirmationTagMismatch,
+    /// The confirmation tag is valid for a different confirmed transcript hash
+    /// than the one computed locally. This is only detected if the diagnosis
+    /// of confirmation tag mismatches is enabled (see
+    /// [`MlsGroupConfig::diagnose_confirmation_tag_mismatch()`](crate::group::MlsGroupConfig::diagnose_confirmation_tag_mismatch())).
+    #[error("The confirmation tag is valid for a different confirmed transcript hash than the one computed locally.")]
+    TranscriptHashMismatch,
     /// The committer can't remove themselves.
     #[error("The committer can't remove themselves.")]
     AttemptedSelfRemoval,
@@ -167,6 +216,12 @@ pub enum StageCommitError {
     /// Too many new members: the tree is full.
     #[error("Too many new members: the tree is full.")]
     TooManyNewMembers,
+    /// The commit arrived sooner than the group's minimum epoch interval allows.
+    #[error("The commit arrived sooner than the group's minimum epoch interval allows.")]
+    CommitRateLimited,
+    /// The committer's path leaf doesn't support the required capabilities of the group.
+    #[error("The committer's path leaf doesn't support the required capabilities of the group.")]
+    CommitterInsufficientCapabilities,
     /// See [`ProposalValidationError`] for more details.
     #[error(transparent)]
     ProposalValidationError(#[from] ProposalValidationError),
@@ -179,6 +234,9 @@ pub enum StageCommitError {
     /// See [`ApplyUpdatePathError`] for more details.
     #[error(transparent)]
     UpdatePathError(#[from] ApplyUpdatePathError),
+    /// See [`ValidationError`] for more details.
+    #[error(transparent)]
+    ValidationError(#[from] ValidationError),
 }
 
 /// Create commit error
@@ -199,12 +257,24 @@ pub enum CreateCommitError {
     /// A proposal has the wrong sender type.
     #[error("A proposal has the wrong sender type.")]
     WrongProposalSenderType,
+    /// The member is not part of the group.
+    #[error("The member is not part of the group.")]
+    UnknownMember,
+    /// The identity of the new key package doesn't match the one of the member's leaf.
+    #[error("The identity of the new key package doesn't match the one of the member's leaf.")]
+    RecoveryIdentityMismatch,
     /// See [`PskError`] for more details.
     #[error(transparent)]
     PskError(#[from] PskError),
     /// See [`ProposalValidationError`] for more details.
     #[error(transparent)]
     ProposalValidationError(#[from] ProposalValidationError),
+    /// The group was terminated by a ReInit proposal.
+    #[error("The group was terminated by a ReInit proposal.")]
+    GroupTerminated,
+    /// See [`ExternalCommitValidationError`] for more details.
+    #[error(transparent)]
+    ExternalCommitValidation(#[from] ExternalCommitValidationError),
 }
 
 /// Validation error
@@ -234,6 +304,9 @@ pub enum ValidationError {
     /// Sender is not part of the group.
     #[error("Sender is not part of the group.")]
     UnknownMember,
+    /// Sender was not part of the group in the epoch of the message.
+    #[error("Sender was not part of the group in the epoch of the message.")]
+    UnknownSender,
     /// Membership tag is missing.
     #[error("Membership tag is missing.")]
     MissingMembershipTag,
@@ -252,12 +325,23 @@ pub enum ValidationError {
     /// An application message was sent from an external sender.
     #[error("An application message was sent from an external sender.")]
     NonMemberApplicationMessage,
+    /// The external sender is not listed in the group's external senders
+    /// extension or is not allowed to send this type of message.
+    #[error("The external sender is not authorized to send this message to the group.")]
+    UnauthorizedExternalSender,
     /// Could not decrypt the message
     #[error(transparent)]
     UnableToDecrypt(#[from] MessageDecryptionError),
     /// The message is from an epoch too far in the past.
     #[error("The message is from an epoch too far in the past.")]
     NoPastEpochData,
+    /// The authenticated data of the application message is longer than the
+    /// group allows.
+    #[error("The authenticated data of the application message is longer than the group allows.")]
+    AadTooLarge,
+    /// The message is not an application message.
+    #[error("The message is not an application message.")]
+    NotAnApplicationMessage,
 }
 
 /// Proposal validation error
@@ -269,6 +353,9 @@ pub enum ProposalValidationError {
     /// The sender could not be matched to a member of the group.
     #[error("The sender could not be matched to a member of the group.")]
     UnknownMember,
+    /// The group's local policy does not allow proposals of this type.
+    #[error("The group's local policy does not allow proposals of this type.")]
+    ProposalTypeNotAllowed,
     /// Found two add proposals with the same identity.
     #[error("Found two add proposals with the same identity.")]
     DuplicateIdentityAddProposal,
@@ -311,6 +398,32 @@ pub enum ProposalValidationError {
     /// The capabilities of the add proposal are insufficient for this group.
     #[error("The capabilities of the add proposal are insufficient for this group.")]
     InsufficientCapabilities,
+    /// The signature scheme of the add proposal's credential doesn't match the
+    /// one of the group's ciphersuite.
+    #[error("The signature scheme of the add proposal's credential doesn't match the one of the group's ciphersuite.")]
+    SignatureSchemeMismatch,
+    /// The lifetime of the key package of an add proposal doesn't contain the
+    /// current time.
+    #[error(
+        "The lifetime of the key package of an add proposal doesn't contain the current time."
+    )]
+    KeyPackageExpired,
+    /// A proposal committed by reference was sent by a member that is no
+    /// longer part of the group.
+    #[error("A proposal committed by reference was sent by a member that is no longer part of the group.")]
+    ProposerNoLongerMember,
+    /// A resumption PSK references the current or a future epoch of the group.
+    #[error("A resumption PSK references the current or a future epoch of the group.")]
+    InvalidResumptionEpoch,
+    /// A ReInit proposal was committed together with other proposals.
+    #[error("A ReInit proposal was committed together with other proposals.")]
+    ReInitNotAlone,
+    /// The committer removes itself in a commit with a path.
+    #[error("The committer removes itself in a commit with a path.")]
+    SelfRemoveWithPath,
+    /// Duplicate PreSharedKey proposals for the same PSK.
+    #[error("Duplicate PreSharedKey proposals for the same PSK.")]
+    DuplicatePsk,
 }
 
 /// External Commit validaton error
@@ -345,6 +458,9 @@ pub enum ExternalCommitValidationError {
     /// External commit contains referenced proposal
     #[error("Found a referenced proposal in an External Commit.")]
     ReferencedProposal,
+    /// A resumption PSK does not reference a known past epoch of the group.
+    #[error("A resumption PSK does not reference a known past epoch of the group.")]
+    UnknownResumptionEpoch,
 }
 
 // === Crate errors ===
@@ -368,6 +484,12 @@ pub(crate) enum ExporterError {
     LibraryError(#[from] LibraryError),
     #[error("The requested key length is not supported (too large).")]
     KeyLengthTooLong,
+    /// The subgroup doesn't contain any members.
+    #[error("The subgroup doesn't contain any members.")]
+    EmptySubgroup,
+    /// A member of the subgroup is not a member of the group.
+    #[error("A member of the subgroup is not a member of the group.")]
+    UnknownSubgroupMember,
 }
 
 /// Proposal queue error
@@ -397,6 +519,9 @@ pub(crate) enum FromCommittedProposalsError {
     /// The sender of a Commit tried to remove themselves.
     #[error("The sender of a Commit tried to remove themselves.")]
     SelfRemoval,
+    /// A ReInit proposal was committed together with other proposals.
+    #[error("A ReInit proposal was committed together with other proposals.")]
+    ReInitNotAlone,
 }
 
 /// Creation proposal queue error
@@ -421,6 +546,56 @@ pub(crate) enum ApplyProposalsError {
     MissingLeafNode,
 }
 
+/// Integrity error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum IntegrityError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The tree hash of the ratchet tree doesn't match the group context.
+    #[error("The tree hash of the ratchet tree doesn't match the group context.")]
+    TreeHashMismatch,
+    /// The transcript hashes are inconsistent with each other or the epoch.
+    #[error("The transcript hashes are inconsistent with each other or the epoch.")]
+    TranscriptHashMismatch,
+    /// The own leaf is missing from the ratchet tree.
+    #[error("The own leaf is missing from the ratchet tree.")]
+    MissingOwnLeaf,
+    /// The private key of the own leaf doesn't match its public key.
+    #[error("The private key of the own leaf doesn't match its public key.")]
+    OwnLeafKeyMismatch,
+}
+
+/// Group state deserialization error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum DeserializeError {
+    /// The persisted group state could not be read.
+    #[error("The persisted group state could not be read.")]
+    ReadError,
+    /// The persisted group state was written in an unsupported format version.
+    #[error("The persisted group state was written in the unsupported format version {version}.")]
+    UnsupportedVersion {
+        /// The format version of the persisted group state.
+        version: u16,
+    },
+    /// The persisted group state is malformed.
+    #[error("The persisted group state is malformed.")]
+    MalformedState,
+    /// See [`IntegrityError`] for more details.
+    #[error(transparent)]
+    IntegrityError(#[from] IntegrityError),
+}
+
+impl From<serde_json::Error> for DeserializeError {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            DeserializeError::ReadError
+        } else {
+            DeserializeError::MalformedState
+        }
+    }
+}
+
 // Core group build error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub(crate) enum CoreGroupBuildError {
@@ -433,9 +608,50 @@ pub(crate) enum CoreGroupBuildError {
     /// Unsupported extension type in required capabilities.
     #[error("Unsupported extension type in required capabilities.")]
     UnsupportedExtensionType,
+    /// The required capabilities require themselves or contain duplicates.
+    #[error("The required capabilities require themselves or contain duplicates.")]
+    InvalidRequiredCapabilities,
+    /// A PSK could not be found in the key store.
+    #[error("A PSK could not be found in the key store.")]
+    UnknownPsk,
     /// See [`PskError`] for more details.
     #[error(transparent)]
     PskError(#[from] PskError),
+    /// The transcript hashes weren't computed with the hash algorithm of the group's ciphersuite.
+    #[error("The transcript hashes weren't computed with the hash algorithm of the group's ciphersuite.")]
+    CiphersuiteHashMismatch,
+    /// An extension of the own leaf is not listed in the leaf's capabilities.
+    #[error("An extension of the own leaf is not listed in the leaf's capabilities.")]
+    LeafExtensionNotAdvertised,
+    /// The version of the group is not listed in the leaf's capabilities.
+    #[error("The version of the group is not listed in the leaf's capabilities.")]
+    VersionNotAdvertised,
+    /// The ciphersuite of the group is not listed in the leaf's capabilities.
+    #[error("The ciphersuite of the group is not listed in the leaf's capabilities.")]
+    CiphersuiteNotAdvertised,
+}
+
+// Branch error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub(crate) enum BranchError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// A branch needs at least one member besides its creator.
+    #[error("A branch needs at least one member besides its creator.")]
+    NoMembers,
+    /// The resumption PSK could not be stored in or deleted from the key store.
+    #[error("The resumption PSK could not be stored in or deleted from the key store.")]
+    KeyStoreError,
+    /// See [`KeyPackageBundleNewError`] for more details.
+    #[error(transparent)]
+    KeyPackageBundleNewError(#[from] KeyPackageBundleNewError),
+    /// See [`CoreGroupBuildError`] for more details.
+    #[error(transparent)]
+    CoreGroupBuildError(#[from] CoreGroupBuildError),
+    /// See [`CreateCommitError`] for more details.
+    #[error(transparent)]
+    CreateCommitError(#[from] CreateCommitError),
 }
 
 // CoreGroup parse message error
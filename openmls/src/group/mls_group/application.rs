@@ -1,5 +1,7 @@
 use tls_codec::Serialize;
 
+use crate::framing::errors::MessageEncryptionError;
+
 use super::{errors::CreateMessageError, *};
 
 impl MlsGroup {
@@ -26,6 +28,11 @@ impl MlsGroup {
                 MlsGroupStateError::PendingProposal,
             ));
         }
+        if self.group.is_terminated() {
+            return Err(CreateMessageError::GroupStateError(
+                MlsGroupStateError::GroupTerminated,
+            ));
+        }
 
         let credential = self
             .credential()
@@ -50,8 +57,13 @@ impl MlsGroup {
                 self.configuration().padding_size(),
                 backend,
             )
-            // We know the application message is wellformed and we have the key material of the current epoch
-            .map_err(|_| LibraryError::custom("Malformed plaintext"))?;
+            .map_err(|e| match e {
+                MessageEncryptionError::AadTooLarge => CreateMessageError::AadTooLarge,
+                MessageEncryptionError::RatchetRegression => CreateMessageError::RatchetRegression,
+                MessageEncryptionError::KeyStoreError => CreateMessageError::KeyStoreError,
+                // We know the application message is wellformed and we have the key material of the current epoch
+                _ => LibraryError::custom("Malformed plaintext").into(),
+            })?;
 
         // Since the state of the group might be changed, arm the state flag
         self.flag_state_change();
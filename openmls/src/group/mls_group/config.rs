@@ -28,8 +28,9 @@
 //! ```
 
 use super::*;
-use crate::tree::sender_ratchet::SenderRatchetConfiguration;
+use crate::{tree::sender_ratchet::SenderRatchetConfiguration, versions::ProtocolVersion};
 use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, time::Duration};
 
 /// Specifies the configuration parameters for a [`MlsGroup`]. Refer to
 /// the [User Manual](https://openmls.tech/book/user_manual/group_config.html) for more information about the different configuration values.
@@ -53,6 +54,39 @@ pub struct MlsGroupConfig {
     pub(crate) sender_ratchet_configuration: SenderRatchetConfiguration,
     /// Lifetime of the own leaf node
     pub(crate) lifetime: LifetimeExtension,
+    /// Flag to always embed the ratchet tree in `Welcome`s and exported
+    /// `GroupInfo`s
+    #[serde(default)]
+    pub(crate) always_embed_ratchet_tree: bool,
+    /// Maximum length in bytes of the AAD of application messages
+    #[serde(default)]
+    pub(crate) max_aad_length: Option<usize>,
+    /// Padding strategy that replaces the padding size
+    #[serde(default)]
+    pub(crate) padding_strategy: Option<PaddingStrategy>,
+    /// Minimum time between the start of an epoch and an incoming commit
+    #[serde(default)]
+    pub(crate) min_epoch_interval: Option<Duration>,
+    /// Protocol versions advertised in the capabilities of the own leaf
+    #[serde(default)]
+    pub(crate) versions: Option<Vec<ProtocolVersion>>,
+    /// Ciphersuites advertised in the capabilities of the own leaf
+    #[serde(default)]
+    pub(crate) ciphersuites: Option<Vec<Ciphersuite>>,
+    /// Extensions of the own leaf
+    #[serde(default)]
+    pub(crate) leaf_extensions: Vec<Extension>,
+    /// Proposal types that may be included in incoming commits
+    #[serde(default)]
+    pub(crate) allowed_proposal_types: Option<HashSet<ProposalType>>,
+    /// Flag to verify the signatures of proposals committed by reference
+    /// again when staging a commit
+    #[serde(default)]
+    pub(crate) verify_proposal_signatures_in_commit: bool,
+    /// Flag to find out why the confirmation tag of an incoming commit
+    /// doesn't match
+    #[serde(default)]
+    pub(crate) diagnose_confirmation_tag_mismatch: bool,
 }
 
 impl MlsGroupConfig {
@@ -96,6 +130,84 @@ impl MlsGroupConfig {
         &self.lifetime
     }
 
+    /// Returns the [`MlsGroupConfig`] boolean flag that indicates whether the
+    /// ratchet tree is always embedded in `Welcome`s and exported `GroupInfo`s.
+    pub fn always_embed_ratchet_tree(&self) -> bool {
+        self.always_embed_ratchet_tree
+    }
+
+    /// Returns the [`MlsGroupConfig`] maximum AAD length.
+    pub fn max_aad_length(&self) -> Option<usize> {
+        self.max_aad_length
+    }
+
+    /// Returns the [`MlsGroupConfig`] padding strategy.
+    pub fn padding_strategy(&self) -> Option<&PaddingStrategy> {
+        self.padding_strategy.as_ref()
+    }
+
+    /// Returns the [`MlsGroupConfig`] minimum epoch interval.
+    pub fn min_epoch_interval(&self) -> Option<Duration> {
+        self.min_epoch_interval
+    }
+
+    /// Returns the [`MlsGroupConfig`] protocol versions advertised in the
+    /// own leaf.
+    pub fn versions(&self) -> Option<&[ProtocolVersion]> {
+        self.versions.as_deref()
+    }
+
+    /// Returns the [`MlsGroupConfig`] ciphersuites advertised in the own
+    /// leaf.
+    pub fn ciphersuites(&self) -> Option<&[Ciphersuite]> {
+        self.ciphersuites.as_deref()
+    }
+
+    /// Returns the [`MlsGroupConfig`] required capabilities.
+    pub fn required_capabilities(&self) -> &RequiredCapabilitiesExtension {
+        &self.required_capabilities
+    }
+
+    /// Returns the [`MlsGroupConfig`] extensions of the own leaf.
+    pub fn leaf_extensions(&self) -> &[Extension] {
+        &self.leaf_extensions
+    }
+
+    /// Returns the [`MlsGroupConfig`] proposal types that may be included in
+    /// incoming commits.
+    pub fn allowed_proposal_types(&self) -> Option<&HashSet<ProposalType>> {
+        self.allowed_proposal_types.as_ref()
+    }
+
+    /// Returns the [`MlsGroupConfig`] boolean flag that indicates whether the
+    /// signatures of proposals committed by reference are verified again when
+    /// staging a commit.
+    pub fn verify_proposal_signatures_in_commit(&self) -> bool {
+        self.verify_proposal_signatures_in_commit
+    }
+
+    /// Returns the [`MlsGroupConfig`] boolean flag that indicates whether a
+    /// confirmation tag mismatch of an incoming commit is diagnosed further.
+    pub fn diagnose_confirmation_tag_mismatch(&self) -> bool {
+        self.diagnose_confirmation_tag_mismatch
+    }
+
+    /// Returns the [`CoreGroupConfig`] of the group's [`CoreGroup`].
+    pub(crate) fn core_group_config(&self) -> CoreGroupConfig {
+        CoreGroupConfig {
+            add_ratchet_tree_extension: self.use_ratchet_tree_extension,
+            default_handshake_wire_format: self.wire_format_policy.outgoing().into(),
+            always_embed_ratchet_tree: self.always_embed_ratchet_tree,
+            max_aad_length: self.max_aad_length,
+            padding_strategy: self.padding_strategy.clone(),
+            min_epoch_interval: self.min_epoch_interval,
+            allowed_proposal_types: self.allowed_proposal_types.clone(),
+            verify_proposal_signatures_in_commit: self.verify_proposal_signatures_in_commit,
+            diagnose_confirmation_tag_mismatch: self.diagnose_confirmation_tag_mismatch,
+            ..CoreGroupConfig::default()
+        }
+    }
+
     #[cfg(any(feature = "test-utils", test))]
     pub fn test_default() -> Self {
         Self::builder()
@@ -175,6 +287,143 @@ impl MlsGroupConfigBuilder {
         self
     }
 
+    /// Sets the `always_embed_ratchet_tree` property of the MlsGroupConfig.
+    /// If set, the ratchet tree is embedded in every `Welcome` and every
+    /// exported `GroupInfo`, so that new members can join without fetching
+    /// the tree out of band. This overrides `use_ratchet_tree_extension`.
+    pub fn always_embed_ratchet_tree(mut self, always_embed_ratchet_tree: bool) -> Self {
+        self.config.always_embed_ratchet_tree = always_embed_ratchet_tree;
+        self
+    }
+
+    /// Sets the `max_aad_length` property of the MlsGroupConfig.
+    ///
+    /// Application messages with longer AAD are rejected when they are
+    /// created and when they are received. The AAD is sent in the clear and
+    /// is otherwise unbounded, so without a limit a member can make others
+    /// receive and process arbitrarily large messages.
+    pub fn max_aad_length(mut self, max_aad_length: usize) -> Self {
+        self.config.max_aad_length = Some(max_aad_length);
+        self
+    }
+
+    /// Sets the `padding_strategy` property of the MlsGroupConfig. If set, it
+    /// replaces the `padding_size` when encrypting messages.
+    /// See [`PaddingStrategy`] for more information.
+    pub fn padding_strategy(mut self, padding_strategy: PaddingStrategy) -> Self {
+        self.config.padding_strategy = Some(padding_strategy);
+        self
+    }
+
+    /// Sets the `min_epoch_interval` property of the MlsGroupConfig.
+    ///
+    /// Commits of other members are rejected if they arrive less than
+    /// `min_epoch_interval` after the start of the current epoch. The start of
+    /// an epoch is recorded when the group is created or joined and when a
+    /// commit is merged.
+    ///
+    /// [`MlsGroup::process_message()`] and [`MlsGroup::merge_staged_commit()`]
+    /// use the current system time. Applications with their own clock have to
+    /// pair [`MlsGroup::process_message_at()`] with
+    /// [`MlsGroup::merge_staged_commit_at()`] and
+    /// [`MlsGroup::merge_pending_commit_at()`], so that both times come from
+    /// the same clock.
+    pub fn min_epoch_interval(mut self, min_epoch_interval: Duration) -> Self {
+        self.config.min_epoch_interval = Some(min_epoch_interval);
+        self
+    }
+
+    /// Sets the `versions` property of the MlsGroupConfig.
+    ///
+    /// The protocol versions are advertised in the capabilities of the own
+    /// leaf when creating a group and have to contain the version of the
+    /// group. By default, only the version of the group is advertised. When
+    /// joining a group, the capabilities of the own key package are used
+    /// instead.
+    pub fn versions(mut self, versions: Vec<ProtocolVersion>) -> Self {
+        self.config.versions = Some(versions);
+        self
+    }
+
+    /// Sets the `ciphersuites` property of the MlsGroupConfig.
+    ///
+    /// The ciphersuites are advertised in the capabilities of the own leaf
+    /// when creating a group and have to contain the ciphersuite of the
+    /// group. By default, only the ciphersuite of the group is advertised.
+    /// New members only have to support the ciphersuite of the group.
+    pub fn ciphersuites(mut self, ciphersuites: Vec<Ciphersuite>) -> Self {
+        self.config.ciphersuites = Some(ciphersuites);
+        self
+    }
+
+    /// Sets the `required_capabilities` property of the MlsGroupConfig.
+    ///
+    /// The required capabilities are added to the group context when creating
+    /// a group, and the own leaf advertises support for their extension
+    /// types.
+    pub fn required_capabilities(
+        mut self,
+        required_capabilities: RequiredCapabilitiesExtension,
+    ) -> Self {
+        self.config.required_capabilities = required_capabilities;
+        self
+    }
+
+    /// Sets the `leaf_extensions` property of the MlsGroupConfig.
+    ///
+    /// The extensions are added to the own leaf when creating a group. Their
+    /// types have to be advertised in the capabilities of the leaf, which
+    /// contain the extension types of the `required_capabilities`.
+    pub fn leaf_extensions(mut self, leaf_extensions: Vec<Extension>) -> Self {
+        self.config.leaf_extensions = leaf_extensions;
+        self
+    }
+
+    /// Sets the `allowed_proposal_types` property of the MlsGroupConfig.
+    ///
+    /// Incoming commits that contain a proposal of another type are rejected
+    /// with [`ProposalTypeNotAllowed`](crate::group::errors::ProposalValidationError::ProposalTypeNotAllowed).
+    /// This is local policy on top of the protocol's validation rules. By
+    /// default, all proposal types are allowed.
+    pub fn allowed_proposal_types(mut self, allowed_proposal_types: HashSet<ProposalType>) -> Self {
+        self.config.allowed_proposal_types = Some(allowed_proposal_types);
+        self
+    }
+
+    /// Sets the `verify_proposal_signatures_in_commit` property of the
+    /// MlsGroupConfig.
+    ///
+    /// If set, the signature of each proposal that an incoming commit includes
+    /// by reference is verified again against the credential of its sender
+    /// when the commit is staged, in addition to the verification when the
+    /// proposal was received. Commits including a proposal with an invalid
+    /// signature are rejected. This is off by default.
+    pub fn verify_proposal_signatures_in_commit(
+        mut self,
+        verify_proposal_signatures_in_commit: bool,
+    ) -> Self {
+        self.config.verify_proposal_signatures_in_commit = verify_proposal_signatures_in_commit;
+        self
+    }
+
+    /// Sets the `diagnose_confirmation_tag_mismatch` property of the
+    /// MlsGroupConfig.
+    ///
+    /// If set, an incoming commit with a confirmation tag that doesn't match
+    /// is checked for a diverging confirmed transcript hash, which is reported
+    /// as a `StageCommitError::TranscriptHashMismatch` instead of a
+    /// `StageCommitError::ConfirmationTagMismatch`. This keeps copies of the
+    /// key schedule inputs of every commit and re-runs the key schedule on a
+    /// mismatch, so it is meant for debugging interop failures. This is off
+    /// by default.
+    pub fn diagnose_confirmation_tag_mismatch(
+        mut self,
+        diagnose_confirmation_tag_mismatch: bool,
+    ) -> Self {
+        self.config.diagnose_confirmation_tag_mismatch = diagnose_confirmation_tag_mismatch;
+        self
+    }
+
     /// Finalizes the builder and retursn an `[MlsGroupConfig`].
     pub fn build(self) -> MlsGroupConfig {
         self.config
@@ -209,7 +458,8 @@ impl IncomingWireFormatPolicy {
 /// Note that application messages must always be encrypted.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OutgoingWireFormatPolicy {
-    /// Handshake messages must always be MlsCiphertext
+    /// Handshake messages must always be MlsCiphertext. This is how a group
+    /// enforces encryption of all the proposals and commits it sends.
     AlwaysCiphertext,
     /// Handshake messages must always be MlsPlaintext
     AlwaysPlaintext,
@@ -1,9 +1,11 @@
 use crate::{
+    extensions::{Clock, SystemClock},
     group::{
         core_group::create_commit_params::CreateCommitParams,
-        errors::{CoreGroupBuildError, ExternalCommitError, WelcomeError},
+        errors::{BranchError, CoreGroupBuildError, ExternalCommitError, WelcomeError},
     },
     messages::VerifiableGroupInfo,
+    treesync::{errors::TreeSyncFromNodesError, TreeSync},
 };
 use tls_codec::Serialize;
 
@@ -66,14 +68,22 @@ impl MlsGroup {
                     })?,
             )
             .ok_or(NewGroupError::NoMatchingCredentialBundle)?;
-        let group_config = CoreGroupConfig {
-            add_ratchet_tree_extension: mls_group_config.use_ratchet_tree_extension,
-        };
-        let group = CoreGroup::builder(group_id, key_package_bundle)
-            .with_config(group_config)
+        let mut group_builder = CoreGroup::builder(group_id, key_package_bundle)
+            .with_config(mls_group_config.core_group_config())
+            .with_sender_ratchet_configuration(
+                mls_group_config.sender_ratchet_configuration().clone(),
+            )
             .with_required_capabilities(mls_group_config.required_capabilities.clone())
             .with_max_past_epoch_secrets(mls_group_config.max_past_epochs)
             .with_lifetime(mls_group_config.lifetime().clone())
+            .with_leaf_node_extensions(mls_group_config.leaf_extensions().to_vec());
+        if let Some(versions) = mls_group_config.versions() {
+            group_builder = group_builder.with_versions(versions.to_vec());
+        }
+        if let Some(ciphersuites) = mls_group_config.ciphersuites() {
+            group_builder = group_builder.with_ciphersuites(ciphersuites.to_vec());
+        }
+        let mut group = group_builder
             .build(&credential_bundle, backend)
             .map_err(|e| match e {
                 CoreGroupBuildError::LibraryError(e) => e.into(),
@@ -83,12 +93,29 @@ impl MlsGroup {
                 CoreGroupBuildError::UnsupportedExtensionType => {
                     NewGroupError::UnsupportedExtensionType
                 }
-                // We don't support PSKs yet
+                CoreGroupBuildError::InvalidRequiredCapabilities => {
+                    NewGroupError::InvalidRequiredCapabilities
+                }
+                CoreGroupBuildError::CiphersuiteHashMismatch => {
+                    LibraryError::custom("Transcript hash doesn't match the ciphersuite").into()
+                }
+                CoreGroupBuildError::LeafExtensionNotAdvertised => {
+                    NewGroupError::LeafExtensionNotAdvertised
+                }
+                CoreGroupBuildError::VersionNotAdvertised => NewGroupError::VersionNotAdvertised,
+                CoreGroupBuildError::CiphersuiteNotAdvertised => {
+                    NewGroupError::CiphersuiteNotAdvertised
+                }
+                // MlsGroup doesn't set PSKs
+                CoreGroupBuildError::UnknownPsk => {
+                    LibraryError::custom("Unexpected unknown PSK").into()
+                }
                 CoreGroupBuildError::PskError(e) => {
                     log::debug!("Unexpected PSK error: {:?}", e);
                     LibraryError::custom("Unexpected PSK error").into()
                 }
             })?;
+        group.set_epoch_started_at(SystemClock.now());
 
         let resumption_psk_store =
             ResumptionPskStore::new(mls_group_config.number_of_resumption_psks);
@@ -116,31 +143,256 @@ impl MlsGroup {
         welcome: Welcome,
         ratchet_tree: Option<Vec<Option<Node>>>,
     ) -> Result<Self, WelcomeError> {
-        let resumption_psk_store =
-            ResumptionPskStore::new(mls_group_config.number_of_resumption_psks);
+        let key_package_bundle = Self::take_key_package_bundle(backend, &welcome)?;
+        // TODO #751
+        let group =
+            CoreGroup::new_from_welcome(welcome, ratchet_tree, key_package_bundle, backend)?;
+
+        Ok(Self::from_core_group(mls_group_config, group))
+    }
+
+    /// Creates a new group from a [`Welcome`] message, reading the
+    /// TLS-encoded ratchet tree extension from `ratchet_tree_reader` if the
+    /// [`Welcome`] doesn't contain one. The nodes are decoded one at a time
+    /// while the tree is built, so that large trees don't need to be
+    /// deserialized into memory first. Returns an error
+    /// ([`WelcomeError::NoMatchingKeyPackageBundle`]) if no
+    /// [`KeyPackageBundle`] can be found.
+    pub fn new_from_welcome_with_tree_reader<R: Read>(
+        backend: &impl OpenMlsCryptoProvider,
+        mls_group_config: &MlsGroupConfig,
+        welcome: Welcome,
+        ratchet_tree_reader: R,
+    ) -> Result<Self, WelcomeError> {
+        let key_package_bundle = Self::take_key_package_bundle(backend, &welcome)?;
+        let group = CoreGroup::new_from_welcome_with_tree_reader(
+            welcome,
+            ratchet_tree_reader,
+            key_package_bundle,
+            backend,
+        )?;
+
+        Ok(Self::from_core_group(mls_group_config, group))
+    }
+
+    /// Verifies the TLS-encoded ratchet tree (see
+    /// [`RatchetTreeExtension`](crate::extensions::RatchetTreeExtension))
+    /// read from `reader` against the `expected_tree_hash` of a group with
+    /// the given `ciphersuite`, e.g. to check a tree that was fetched out of
+    /// band before joining the group. The nodes are decoded and verified one
+    /// at a time, so that large trees don't need to be deserialized into
+    /// memory. The verification still keeps one public key per member (and
+    /// the resolutions of blank subtrees), so memory use grows linearly with
+    /// the size of the group, just with a smaller constant.
+    ///
+    /// Returns a [`PublicTreeError::MalformedTree`] if the tree can't be
+    /// decoded, a [`PublicTreeError::InvalidParentHash`] if a parent hash is
+    /// invalid and a [`PublicTreeError::TreeHashMismatch`] if the tree hash
+    /// doesn't match.
+    pub fn verify_ratchet_tree<R: Read>(
+        backend: &impl OpenMlsCryptoProvider,
+        ciphersuite: Ciphersuite,
+        reader: R,
+        expected_tree_hash: &[u8],
+    ) -> Result<(), VerifyRatchetTreeError> {
+        TreeSync::verify_streaming(reader, ciphersuite, expected_tree_hash, backend).map_err(|e| {
+            match e {
+                TreeSyncFromNodesError::LibraryError(e) => e.into(),
+                TreeSyncFromNodesError::PublicTreeError(e) => e.into(),
+            }
+        })
+    }
+
+    /// Creates a new group from a [`Welcome`] message and checks that the
+    /// resulting group state matches the given [`VerifiableGroupInfo`].
+    /// Returns an error ([`WelcomeError::GroupInfoMismatch`]) if the group
+    /// context or the confirmation tag of the two differ and an error
+    /// ([`WelcomeError::NoMatchingKeyPackageBundle`]) if no
+    /// [`KeyPackageBundle`] can be found.
+    pub fn new_from_welcome_verified(
+        backend: &impl OpenMlsCryptoProvider,
+        mls_group_config: &MlsGroupConfig,
+        welcome: Welcome,
+        verifiable_group_info: VerifiableGroupInfo,
+        ratchet_tree: Option<Vec<Option<Node>>>,
+    ) -> Result<Self, WelcomeError> {
+        let key_package_bundle = Self::take_key_package_bundle(backend, &welcome)?;
+        let group = CoreGroup::new_from_welcome_verified(
+            welcome,
+            verifiable_group_info,
+            ratchet_tree,
+            key_package_bundle,
+            backend,
+        )?;
+
+        Ok(Self::from_core_group(mls_group_config, group))
+    }
+
+    /// Creates a new group with the ID `group_id` that branches off this
+    /// group and adds the members of the given `key_packages` to it, e.g. to
+    /// split a conversation. The creator is the first member of the new
+    /// group, with a fresh key package for the own credential.
+    ///
+    /// The resumption PSK of the current epoch of this group is injected into
+    /// the new group, so that members of this group can verify the
+    /// continuity when joining with [`MlsGroup::new_from_branch_welcome()`].
+    /// This group is not modified.
+    ///
+    /// Returns the new group together with the [`Welcome`] for the added
+    /// members.
+    pub fn branch(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        mls_group_config: &MlsGroupConfig,
+        group_id: GroupId,
+        key_packages: &[KeyPackage],
+    ) -> Result<(Self, Welcome), BranchGroupError> {
+        let credential = self.credential()?;
+        let credential_bundle: CredentialBundle = backend
+            .key_store()
+            .read(
+                &credential
+                    .signature_key()
+                    .tls_serialize_detached()
+                    .map_err(LibraryError::missing_bound_check)?,
+            )
+            .ok_or(BranchGroupError::NoMatchingCredentialBundle)?;
+
+        let (group, welcome) = self
+            .group
+            .branch(group_id, key_packages, &credential_bundle, backend)
+            .map_err(|e| match e {
+                BranchError::LibraryError(e) => e.into(),
+                BranchError::NoMembers => BranchGroupError::NoMembers,
+                BranchError::KeyStoreError => BranchGroupError::KeyStoreError,
+                BranchError::KeyPackageBundleNewError(e) => e.into(),
+                BranchError::CreateCommitError(e) => e.into(),
+                // The branch is built with the ciphersuite of this group and
+                // the PSK that was just stored.
+                BranchError::CoreGroupBuildError(e) => {
+                    log::debug!("Unexpected error building the branch: {:?}", e);
+                    LibraryError::custom("Unexpected error building the branch").into()
+                }
+            })?;
+
+        Ok((Self::from_core_group(mls_group_config, group), welcome))
+    }
+
+    /// Joins a group that branches off the `parent` group from a [`Welcome`]
+    /// created with [`MlsGroup::branch()`]. Joining fails with
+    /// [`WelcomeError::PskNotFound`] if the creator of the branch was in
+    /// another epoch of `parent` and with [`WelcomeError::NotABranch`] if the
+    /// [`Welcome`] doesn't list a branch PSK of `parent`. Returns an error
+    /// ([`WelcomeError::NoMatchingKeyPackageBundle`]) if no
+    /// [`KeyPackageBundle`] can be found.
+    pub fn new_from_branch_welcome(
+        backend: &impl OpenMlsCryptoProvider,
+        mls_group_config: &MlsGroupConfig,
+        welcome: Welcome,
+        parent: &MlsGroup,
+        ratchet_tree: Option<Vec<Option<Node>>>,
+    ) -> Result<Self, WelcomeError> {
+        let key_package_bundle = Self::take_key_package_bundle(backend, &welcome)?;
+        let group = CoreGroup::new_from_branch_welcome(
+            welcome,
+            &parent.group,
+            ratchet_tree,
+            key_package_bundle,
+            backend,
+        )?;
+
+        Ok(Self::from_core_group(mls_group_config, group))
+    }
+
+    /// Verifies that the confirmation tag of the group info in a [`Welcome`]
+    /// is consistent with the advertised group context, without joining the
+    /// group. The [`KeyPackageBundle`] the [`Welcome`] was encrypted to is
+    /// left in the key store, so the group can be joined afterwards using
+    /// [`MlsGroup::new_from_welcome()`].
+    ///
+    /// Returns an error ([`WelcomeError::ConfirmationTagMismatch`]) if the
+    /// confirmation tags differ and an error
+    /// ([`WelcomeError::NoMatchingKeyPackageBundle`]) if no
+    /// [`KeyPackageBundle`] can be found.
+    pub fn verify_welcome(
+        backend: &impl OpenMlsCryptoProvider,
+        welcome: &Welcome,
+    ) -> Result<(), WelcomeError> {
+        let (key_package_bundle, _hash_ref) = Self::find_key_package_bundle(backend, welcome)?;
+        CoreGroup::verify_welcome_confirmation_tag(welcome, &key_package_bundle, backend)
+    }
+
+    /// Finds the [`KeyPackageBundle`] the [`Welcome`] was encrypted to and
+    /// removes it from the key store. See
+    /// [`MlsGroup::find_key_package_bundle()`] for errors.
+    fn take_key_package_bundle(
+        backend: &impl OpenMlsCryptoProvider,
+        welcome: &Welcome,
+    ) -> Result<KeyPackageBundle, WelcomeError> {
+        let (key_package_bundle, hash_ref) = Self::find_key_package_bundle(backend, welcome)?;
+
+        // Delete the KeyPackageBundle from the key store
+        backend
+            .key_store()
+            .delete(&hash_ref)
+            .map_err(|_| WelcomeError::KeyStoreDeletionError)?;
+
+        Ok(key_package_bundle)
+    }
+
+    /// Finds the [`KeyPackageBundle`] the [`Welcome`] was encrypted to and
+    /// returns it together with its key store reference. Returns an error
+    /// ([`WelcomeError::CiphersuiteMismatch`]) if [`KeyPackageBundle`]s were
+    /// found, but none of them matches the ciphersuite of the group.
+    fn find_key_package_bundle(
+        backend: &impl OpenMlsCryptoProvider,
+        welcome: &Welcome,
+    ) -> Result<(KeyPackageBundle, Vec<u8>), WelcomeError> {
+        let group_ciphersuite = welcome.ciphersuite();
+        // The ciphersuite of the first candidate that doesn't match the group.
+        let mut mismatching_ciphersuite = None;
         let (key_package_bundle, hash_ref) = welcome
             .secrets()
             .iter()
-            .find_map(|egs| {
+            .filter_map(|egs| {
                 let hash_ref = egs.new_member().as_slice().to_vec();
                 backend
                     .key_store()
                     .read(&hash_ref)
                     .map(|kpb: KeyPackageBundle| (kpb, hash_ref))
             })
-            .ok_or(WelcomeError::NoMatchingKeyPackageBundle)?;
+            .find(|(kpb, _)| {
+                let key_package_ciphersuite = kpb.key_package().ciphersuite();
+                if key_package_ciphersuite != group_ciphersuite {
+                    mismatching_ciphersuite.get_or_insert(key_package_ciphersuite);
+                    return false;
+                }
+                true
+            })
+            .ok_or(match mismatching_ciphersuite {
+                Some(key_package) => WelcomeError::CiphersuiteMismatch {
+                    group: group_ciphersuite,
+                    key_package,
+                },
+                None => WelcomeError::NoMatchingKeyPackageBundle,
+            })?;
 
-        // Delete the KeyPackageBundle from the key store
-        backend
-            .key_store()
-            .delete(&hash_ref)
-            .map_err(|_| WelcomeError::KeyStoreDeletionError)?;
-        // TODO #751
-        let mut group =
-            CoreGroup::new_from_welcome(welcome, ratchet_tree, key_package_bundle, backend)?;
+        Ok((key_package_bundle, hash_ref))
+    }
+
+    /// Wraps a [`CoreGroup`] that was created from a [`Welcome`] message or
+    /// branched off another group.
+    fn from_core_group(mls_group_config: &MlsGroupConfig, mut group: CoreGroup) -> Self {
+        let resumption_psk_store =
+            ResumptionPskStore::new(mls_group_config.number_of_resumption_psks);
         group.set_max_past_epochs(mls_group_config.max_past_epochs);
+        group.set_config(mls_group_config.core_group_config());
+        group.set_sender_ratchet_configuration(
+            mls_group_config.sender_ratchet_configuration().clone(),
+        );
+        group.set_epoch_started_at(SystemClock.now());
 
-        let mls_group = MlsGroup {
+        MlsGroup {
             mls_group_config: mls_group_config.clone(),
             group,
             proposal_store: ProposalStore::new(),
@@ -149,9 +401,7 @@ impl MlsGroup {
             resumption_psk_store,
             group_state: MlsGroupState::Operational,
             state_changed: InnerState::Changed,
-        };
-
-        Ok(mls_group)
+        }
     }
 
     /// Join an existing group through an External Commit.
@@ -165,10 +415,16 @@ impl MlsGroup {
     /// created using this function based on the latest `ratchet_tree` and
     /// group info. For more information on the external init process,
     /// please see Section 11.2.1 in the MLS specification.
+    ///
+    /// If the client knows that the group has reached a certain epoch, e.g.
+    /// because it was a member in that epoch, it can pass it as `min_epoch`.
+    /// Joining then fails with [`ExternalCommitError::StaleGroupInfo`] if the
+    /// group info belongs to an older epoch.
     pub fn join_by_external_commit(
         backend: &impl OpenMlsCryptoProvider,
         tree_option: Option<&[Option<Node>]>,
         verifiable_group_info: VerifiableGroupInfo,
+        min_epoch: Option<GroupEpoch>,
         mls_group_config: &MlsGroupConfig,
         aad: &[u8],
         credential_bundle: &CredentialBundle,
@@ -190,8 +446,13 @@ impl MlsGroup {
             params,
             tree_option,
             verifiable_group_info,
+            min_epoch,
         )?;
         group.set_max_past_epochs(mls_group_config.max_past_epochs);
+        group.set_config(mls_group_config.core_group_config());
+        group.set_sender_ratchet_configuration(
+            mls_group_config.sender_ratchet_configuration().clone(),
+        );
 
         let mls_group = MlsGroup {
             mls_group_config: mls_group_config.clone(),
@@ -8,6 +8,8 @@
 use crate::{
     error::LibraryError,
     group::errors::{CreateCommitError, StageCommitError, ValidationError},
+    key_packages::errors::KeyPackageBundleNewError,
+    treesync::errors::PublicTreeError,
 };
 use thiserror::Error;
 
@@ -32,6 +34,56 @@ pub enum NewGroupError {
     /// Unsupported extension type in required capabilities.
     #[error("Unsupported extension type in required capabilities.")]
     UnsupportedExtensionType,
+    /// The required capabilities require themselves or contain duplicates.
+    #[error("The required capabilities require themselves or contain duplicates.")]
+    InvalidRequiredCapabilities,
+    /// The version of the group is not listed in the advertised versions.
+    #[error("The version of the group is not listed in the advertised versions.")]
+    VersionNotAdvertised,
+    /// The ciphersuite of the group is not listed in the advertised
+    /// ciphersuites.
+    #[error("The ciphersuite of the group is not listed in the advertised ciphersuites.")]
+    CiphersuiteNotAdvertised,
+    /// An extension of the own leaf is not listed in the leaf's capabilities.
+    #[error("An extension of the own leaf is not listed in the leaf's capabilities.")]
+    LeafExtensionNotAdvertised,
+}
+
+/// Branch group error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum BranchGroupError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The own CredentialBundle could not be found in the key store.
+    #[error("The own CredentialBundle could not be found in the key store.")]
+    NoMatchingCredentialBundle,
+    /// A branch needs at least one member besides its creator.
+    #[error("A branch needs at least one member besides its creator.")]
+    NoMembers,
+    /// The resumption PSK could not be stored in or deleted from the key store.
+    #[error("The resumption PSK could not be stored in or deleted from the key store.")]
+    KeyStoreError,
+    /// See [`KeyPackageBundleNewError`] for more details.
+    #[error(transparent)]
+    KeyPackageBundleNewError(#[from] KeyPackageBundleNewError),
+    /// See [`CreateCommitError`] for more details.
+    #[error(transparent)]
+    CreateCommitError(#[from] CreateCommitError),
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+}
+
+/// Verify ratchet tree error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum VerifyRatchetTreeError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`PublicTreeError`] for more details.
+    #[error(transparent)]
+    PublicTreeError(#[from] PublicTreeError),
 }
 
 /// EmptyInput error
@@ -63,6 +115,10 @@ pub enum MlsGroupStateError {
     /// Can't execute operation because there is no pending commit.
     #[error("Can't execute operation because there is no pending commit")]
     NoPendingCommit,
+    /// Can't execute operation because the group was terminated by a ReInit
+    /// proposal.
+    #[error("Can't execute operation because the group was terminated by a ReInit proposal.")]
+    GroupTerminated,
 }
 
 /// Process message error
@@ -91,6 +147,20 @@ pub enum ProcessMessageError {
     InvalidCommit(#[from] StageCommitError),
 }
 
+/// Group registry error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum GroupRegistryError {
+    /// The registry already contains a group with the same group ID.
+    #[error("The registry already contains a group with the same group ID.")]
+    DuplicateGroupId,
+    /// The registry contains no group with the message's group ID.
+    #[error("The registry contains no group with the message's group ID.")]
+    UnknownGroupId,
+    /// See [`ProcessMessageError`] for more details.
+    #[error(transparent)]
+    ProcessMessageError(#[from] ProcessMessageError),
+}
+
 /// Create message error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub enum CreateMessageError {
@@ -103,6 +173,17 @@ pub enum CreateMessageError {
     /// See [`MlsGroupStateError`] for more details.
     #[error(transparent)]
     GroupStateError(#[from] MlsGroupStateError),
+    /// The AAD is longer than the configured maximum AAD length.
+    #[error("The AAD is longer than the configured maximum AAD length.")]
+    AadTooLarge,
+    /// The own sender ratchet went backwards, e.g. because an outdated group
+    /// state was loaded. Sending would reuse a nonce.
+    #[error("The own sender ratchet went backwards, e.g. because an outdated group state was loaded. Sending would reuse a nonce.")]
+    RatchetRegression,
+    /// The generation of the own sender ratchet could not be written to the
+    /// key store.
+    #[error("The generation of the own sender ratchet could not be written to the key store.")]
+    KeyStoreError,
 }
 
 /// Add members error
@@ -227,6 +308,51 @@ pub enum ProposeSelfUpdateError {
     GroupStateError(#[from] MlsGroupStateError),
 }
 
+/// Propose group context extensions error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ProposeGroupContextExtensionsError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The own CredentialBundle could not be found in the key store.
+    #[error("The own CredentialBundle could not be found in the key store.")]
+    NoMatchingCredentialBundle,
+    /// Not all members of the group support the required capabilities.
+    #[error("Not all members of the group support the required capabilities.")]
+    UnsupportedCapabilities,
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+}
+
+/// Propose PSK error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ProposePskError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The own CredentialBundle could not be found in the key store.
+    #[error("The own CredentialBundle could not be found in the key store.")]
+    NoMatchingCredentialBundle,
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+}
+
+/// Propose ReInit error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ProposeReInitError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The own CredentialBundle could not be found in the key store.
+    #[error("The own CredentialBundle could not be found in the key store.")]
+    NoMatchingCredentialBundle,
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+}
+
 /// Commit to pending proposals error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub enum CommitToPendingProposalsError {
@@ -256,6 +382,9 @@ pub enum ExportGroupInfoError {
     /// See [`MlsGroupStateError`] for more details.
     #[error(transparent)]
     GroupStateError(#[from] MlsGroupStateError),
+    /// An extension type would be contained more than once in the group info.
+    #[error("An extension type would be contained more than once in the group info.")]
+    DuplicateExtension,
 }
 
 /// Export secret error
@@ -270,4 +399,10 @@ pub enum ExportSecretError {
     /// See [`MlsGroupStateError`] for more details.
     #[error(transparent)]
     GroupStateError(#[from] MlsGroupStateError),
+    /// The subgroup doesn't contain any members.
+    #[error("The subgroup doesn't contain any members.")]
+    EmptySubgroup,
+    /// A member of the subgroup is not a member of the group.
+    #[error("A member of the subgroup is not a member of the group.")]
+    UnknownSubgroupMember,
 }
@@ -26,6 +26,9 @@ impl MlsGroup {
                 .map_err(|e| match e {
                     ExporterError::LibraryError(e) => e.into(),
                     ExporterError::KeyLengthTooLong => ExportSecretError::KeyLengthTooLong,
+                    ExporterError::EmptySubgroup | ExporterError::UnknownSubgroupMember => {
+                        LibraryError::custom("Unexpected subgroup error").into()
+                    }
                 })?)
         } else {
             Err(ExportSecretError::GroupStateError(
@@ -34,12 +37,77 @@ impl MlsGroup {
         }
     }
 
+    /// Exports a key for the subgroup of the members with the given leaf
+    /// indices from the current epoch, e.g. for a private fan-out to the
+    /// admins of a group outside of MLS. The key is bound to the epoch and
+    /// the sorted leaf indices.
+    ///
+    /// **This is outside of the membership guarantees of MLS.** Members
+    /// outside of the subgroup can derive the same key, so the
+    /// confidentiality towards them relies on them not doing so.
+    ///
+    /// Returns [`ExportSecretError::EmptySubgroup`] if `members` is empty and
+    /// [`ExportSecretError::UnknownSubgroupMember`] if one of the leaf
+    /// indices is not a member of the group.
+    /// Returns [`ExportSecretError::GroupStateError(MlsGroupStateError::UseAfterEviction)`](MlsGroupStateError::UseAfterEviction)
+    /// if the group is not active.
+    pub fn export_subgroup_key(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        members: &[u32],
+        label: &str,
+    ) -> Result<Vec<u8>, ExportSecretError> {
+        if !self.is_active() {
+            return Err(ExportSecretError::GroupStateError(
+                MlsGroupStateError::UseAfterEviction,
+            ));
+        }
+        self.group
+            .export_subgroup_key(members, label, backend)
+            .map_err(|e| match e {
+                ExporterError::LibraryError(e) => e.into(),
+                // The key length is the one of the ciphersuite's AEAD.
+                ExporterError::KeyLengthTooLong => {
+                    LibraryError::custom("Unexpected key length error").into()
+                }
+                ExporterError::EmptySubgroup => ExportSecretError::EmptySubgroup,
+                ExporterError::UnknownSubgroupMember => ExportSecretError::UnknownSubgroupMember,
+            })
+    }
+
+    /// Returns a [`PublicGroupState`] snapshot of the current epoch. It
+    /// contains no secrets and can be shared with external services, e.g. to
+    /// detect forks of the group state by comparing the snapshots of
+    /// different members.
+    pub fn public_group_state(&self) -> PublicGroupState {
+        self.group.public_group_state()
+    }
+
+    /// Compares the public state of the current epoch with the
+    /// [`PublicGroupState`] of another member of the group, e.g. one received
+    /// out-of-band, and returns whether the two members have forked.
+    pub fn fork_status(&self, other: &PublicGroupState) -> ForkStatus {
+        self.group.fork_status(other)
+    }
+
     /// Returns the epoch authenticator of the current epoch.
+    ///
+    /// All members in the same epoch of the group have the same epoch
+    /// authenticator, so comparing it out-of-band confirms that two members
+    /// share the same view of the group.
     pub fn epoch_authenticator(&self) -> &EpochAuthenticator {
         self.group.epoch_authenticator()
     }
 
     /// Returns the resumption PSK secret of the current epoch.
+    ///
+    /// **Security:** the resumption PSK is a secret shared by all members of
+    /// the current epoch. Anyone who learns it can prove membership in this
+    /// epoch when it is injected as a PSK into another group, e.g. when
+    /// branching or re-initializing the group. It should never be sent over
+    /// the wire or stored outside the key store. Use the
+    /// [`Self::epoch_authenticator()`] to compare group state with other
+    /// members instead.
     pub fn resumption_psk_secret(&self) -> &ResumptionPskSecret {
         self.group.resumption_psk_secret()
     }
@@ -50,11 +118,30 @@ impl MlsGroup {
         self.resumption_psk_store.get(epoch)
     }
 
-    /// Export a group info object for this group.
+    /// Export a group info object for this group. The group info contains the
+    /// `external_pub` extension and, if `with_ratchet_tree` is set, the
+    /// ratchet tree.
     pub fn export_group_info(
         &self,
         backend: &impl OpenMlsCryptoProvider,
         with_ratchet_tree: bool,
+    ) -> Result<GroupInfo, ExportGroupInfoError> {
+        self.export_group_info_with_options(
+            backend,
+            &GroupInfoExportOptions::new().with_ratchet_tree(with_ratchet_tree),
+        )
+    }
+
+    /// Export a group info object for this group with the extensions selected
+    /// in `options`.
+    ///
+    /// Returns [`ExportGroupInfoError::DuplicateExtension`] if the additional
+    /// extensions in `options` contain an extension type more than once or
+    /// one that is already added because of the other options.
+    pub fn export_group_info_with_options(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        options: &GroupInfoExportOptions,
     ) -> Result<GroupInfo, ExportGroupInfoError> {
         match self.credential() {
             Ok(credential) => {
@@ -67,9 +154,8 @@ impl MlsGroup {
                             .map_err(LibraryError::missing_bound_check)?,
                     )
                     .ok_or(ExportGroupInfoError::NoMatchingCredentialBundle)?;
-                Ok(self
-                    .group
-                    .export_group_info(backend, &credential_bundle, with_ratchet_tree)?)
+                self.group
+                    .export_group_info_with_options(backend, &credential_bundle, options)
             }
             Err(e) => Err(e.into()),
         }
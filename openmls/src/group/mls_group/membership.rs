@@ -5,7 +5,10 @@
 use core_group::create_commit_params::CreateCommitParams;
 use tls_codec::Serialize;
 
-use crate::prelude::LeafNode;
+use crate::{
+    prelude::{CoverageReport, LeafNode},
+    versions::ProtocolVersion,
+};
 
 use super::{
     errors::{AddMembersError, LeaveGroupError, RemoveMembersError},
@@ -27,6 +30,37 @@ impl MlsGroup {
         &mut self,
         backend: &impl OpenMlsCryptoProvider,
         key_packages: &[KeyPackage],
+    ) -> Result<(MlsMessageOut, Welcome), AddMembersError> {
+        self.add_members_internal(backend, key_packages, None)
+    }
+
+    /// Adds members to the group and decides for each new member whether it
+    /// needs the ratchet tree.
+    ///
+    /// The `needs_ratchet_tree` closure is called with the leaf index of each
+    /// new member. All new members share the `GroupInfo` in the [Welcome], so
+    /// it carries the ratchet tree if the closure returns `true` for any of
+    /// them. This allows omitting the ratchet tree when only re-adding members
+    /// that can reconstruct it themselves. The group's
+    /// `use_ratchet_tree_extension` setting is ignored for this commit.
+    ///
+    /// If successful, it returns a tuple of [MlsMessageOut] and [Welcome].
+    ///
+    /// Returns an error if there is a pending commit.
+    pub fn add_members_with_ratchet_tree_selection(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        key_packages: &[KeyPackage],
+        needs_ratchet_tree: impl Fn(u32) -> bool,
+    ) -> Result<(MlsMessageOut, Welcome), AddMembersError> {
+        self.add_members_internal(backend, key_packages, Some(&needs_ratchet_tree))
+    }
+
+    fn add_members_internal(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        key_packages: &[KeyPackage],
+        needs_ratchet_tree: Option<&dyn Fn(u32) -> bool>,
     ) -> Result<(MlsMessageOut, Welcome), AddMembersError> {
         self.is_operational()?;
 
@@ -57,13 +91,15 @@ impl MlsGroup {
 
         // Create Commit over all proposals
         // TODO #751
-        let params = CreateCommitParams::builder()
+        let mut params = CreateCommitParams::builder()
             .framing_parameters(self.framing_parameters())
             .credential_bundle(&credential_bundle)
             .proposal_store(&self.proposal_store)
-            .inline_proposals(inline_proposals)
-            .build();
-        let create_commit_result = self.group.create_commit(params, backend)?;
+            .inline_proposals(inline_proposals);
+        if let Some(needs_ratchet_tree) = needs_ratchet_tree {
+            params = params.needs_ratchet_tree(needs_ratchet_tree);
+        }
+        let create_commit_result = self.group.create_commit(params.build(), backend)?;
 
         let welcome = match create_commit_result.welcome_option {
             Some(welcome) => welcome,
@@ -161,6 +197,64 @@ impl MlsGroup {
         Ok((mls_message, create_commit_result.welcome_option))
     }
 
+    /// Replaces the leaf of the member at the given leaf index with a fresh
+    /// `key_package` of the same client, e.g. after the member lost their
+    /// local state. The old leaf is removed and the key package is added in
+    /// the same commit.
+    ///
+    /// If successful, it returns a tuple of [`MlsMessageOut`] and the
+    /// [`Welcome`] for the recovered member.
+    ///
+    /// Returns an error if there is a pending commit, if `member` is the own
+    /// leaf or not in the group, or if the identity of the key package
+    /// doesn't match the one of the member.
+    pub fn recover_member(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        member: u32,
+        key_package: KeyPackage,
+    ) -> Result<(MlsMessageOut, Welcome), RemoveMembersError> {
+        self.is_operational()?;
+
+        let credential = self.credential()?;
+        let credential_bundle: CredentialBundle = backend
+            .key_store()
+            .read(
+                &credential
+                    .signature_key()
+                    .tls_serialize_detached()
+                    .map_err(LibraryError::missing_bound_check)?,
+            )
+            .ok_or(RemoveMembersError::NoMatchingCredentialBundle)?;
+
+        let create_commit_result = self.group.export_welcome_for_existing_member(
+            self.framing_parameters(),
+            &credential_bundle,
+            &self.proposal_store,
+            member,
+            key_package,
+            backend,
+        )?;
+        let welcome = create_commit_result
+            .welcome_option
+            .ok_or_else(|| LibraryError::custom("No welcome after re-adding a member"))?;
+
+        // Convert MlsPlaintext messages to MLSMessage and encrypt them if required by
+        // the configuration
+        let mls_message = self.plaintext_to_mls_message(create_commit_result.commit, backend)?;
+
+        // Set the current group state to [`MlsGroupState::PendingCommit`],
+        // storing the current [`StagedCommit`] from the commit results
+        self.group_state = MlsGroupState::PendingCommit(Box::new(PendingCommitState::Member(
+            create_commit_result.staged_commit,
+        )));
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+
+        Ok((mls_message, welcome))
+    }
+
     /// Creates proposals to add members to the group.
     ///
     /// Returns an error if there is a pending commit.
@@ -308,16 +402,52 @@ impl MlsGroup {
         self.group.treesync().full_leave_members()
     }
 
-    /// Returns the [`Credential`] of a member corresponding to the given
-    /// leaf index. Returns `None` if the member can not be found in this group.
-    pub fn member(&self, leaf_index: u32) -> Option<&Credential> {
+    /// Returns the [`Member`]s of the group in the given `epoch`. This can be
+    /// used to determine the possible senders of an application message from
+    /// a past epoch.
+    ///
+    /// Returns `None` if the epoch is in the future or if it is no longer
+    /// retained (see [`MlsGroupConfig::max_past_epochs()`]).
+    pub fn members_at_epoch(&self, epoch: GroupEpoch) -> Option<Vec<Member>> {
+        self.group.members_at_epoch(epoch)
+    }
+
+    /// Returns the [`Member`] at the given leaf index. Returns `None` if the
+    /// leaf is blank or outside of the tree.
+    pub fn member_at(&self, leaf_index: u32) -> Option<Member> {
         self.group
             .treesync()
-            // This will return an error if the member can't be found.
             .leaf(leaf_index)
-            .map(|leaf| leaf.map(|l| l.credential()))
             .ok()
             .flatten()
+            .map(|leaf| Member::new(leaf_index, leaf.leaf_node()))
+    }
+
+    /// Returns a [`CoverageReport`] of the group's required capabilities for
+    /// each member, together with the member's leaf index. This helps to
+    /// diagnose why a member's proposal or an Add of a member was rejected.
+    pub fn capabilities_coverage(&self) -> Vec<(u32, CoverageReport)> {
+        self.group.capabilities_coverage()
+    }
+
+    /// Returns the highest protocol version that is supported by all members
+    /// of the group. Members can be required to support a newer version once
+    /// the floor has reached it.
+    pub fn membership_version_floor(&self) -> ProtocolVersion {
+        self.group.membership_version_floor()
+    }
+
+    /// Returns the ciphersuites that are supported by all members of the
+    /// group, e.g. to pick the ciphersuite of a group that re-initializes
+    /// this one.
+    pub fn membership_ciphersuite_intersection(&self) -> Vec<Ciphersuite> {
+        self.group.membership_ciphersuite_intersection()
+    }
+
+    /// Returns the [`Credential`] of a member corresponding to the given
+    /// leaf index. Returns `None` if the member can not be found in this group.
+    pub fn member(&self, leaf_index: u32) -> Option<&Credential> {
+        self.group.credential(leaf_index)
     }
 }
 
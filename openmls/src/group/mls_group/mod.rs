@@ -3,6 +3,8 @@
 //! This module contains [`MlsGroup`] and its submodules.
 
 use super::{
+    core_group::{read_state_version, write_state_version},
+    errors::DeserializeError,
     proposals::{ProposalStore, QueuedProposal},
     staged_commit::StagedCommit,
 };
@@ -14,10 +16,17 @@ use crate::{
     key_packages::{KeyPackage, KeyPackageBundle},
     messages::{proposals::*, Welcome},
     schedule::ResumptionPskSecret,
-    treesync::{node::leaf_node::OpenMlsLeafNode, Node},
+    treesync::{
+        node::leaf_node::{LeafNode, OpenMlsLeafNode},
+        Node,
+    },
 };
-use openmls_traits::{key_store::OpenMlsKeyStore, types::Ciphersuite, OpenMlsCryptoProvider};
-use std::io::{Error, Read, Write};
+use openmls_traits::{
+    key_store::OpenMlsKeyStore,
+    types::{Ciphersuite, SignatureScheme},
+    OpenMlsCryptoProvider,
+};
+use std::io::{Error, ErrorKind, Read, Write};
 
 // Private
 mod application;
@@ -36,6 +45,7 @@ pub(crate) mod config;
 pub(crate) mod errors;
 pub(crate) mod membership;
 pub(crate) mod processing;
+pub(crate) mod registry;
 pub(crate) mod ser;
 
 // Tests
@@ -189,6 +199,10 @@ impl MlsGroup {
     /// Sets the configuration.
     pub fn set_configuration(&mut self, mls_group_config: &MlsGroupConfig) {
         self.mls_group_config = mls_group_config.clone();
+        self.group.set_config(mls_group_config.core_group_config());
+        self.group.set_sender_ratchet_configuration(
+            mls_group_config.sender_ratchet_configuration().clone(),
+        );
 
         // Since the state of the group might be changed, arm the state flag
         self.flag_state_change();
@@ -214,6 +228,22 @@ impl MlsGroup {
         self.group.ciphersuite()
     }
 
+    /// Returns the signature scheme of the group's ciphersuite. The signature
+    /// key of every member is of this scheme (see [`Member::signature_scheme()`]).
+    pub fn signature_scheme(&self) -> SignatureScheme {
+        self.group.signature_scheme()
+    }
+
+    /// Returns the length in bytes of the confirmation tags in this group.
+    pub fn confirmation_tag_length(&self) -> usize {
+        self.group.confirmation_tag_length()
+    }
+
+    /// Returns the length in bytes of the membership tags in this group.
+    pub fn membership_tag_length(&self) -> usize {
+        self.group.membership_tag_length()
+    }
+
     /// Returns whether the own client is still a member of the group or if it
     /// was already evicted
     pub fn is_active(&self) -> bool {
@@ -253,11 +283,24 @@ impl MlsGroup {
         self.group.context().epoch()
     }
 
+    /// Returns `true` if a commit with a ReInit proposal was merged into the
+    /// group. A terminated group can't create proposals, commits or
+    /// application messages anymore.
+    pub fn is_terminated(&self) -> bool {
+        self.group.is_terminated()
+    }
+
     /// Returns an `Iterator` over pending proposals.
     pub fn pending_proposals(&self) -> impl Iterator<Item = &QueuedProposal> {
         self.proposal_store.proposals()
     }
 
+    /// Returns a [`ProposalSummary`] for each pending proposal, including the
+    /// identity of its sender.
+    pub fn describe_pending_proposals(&self) -> Vec<ProposalSummary> {
+        self.group.describe_pending_proposals(&self.proposal_store)
+    }
+
     /// Returns a reference to the [`StagedCommit`] of the most recently created
     /// commit. If there was no commit created in this epoch, either because
     /// this commit or another commit was merged, it returns `None`.
@@ -271,6 +314,20 @@ impl MlsGroup {
         }
     }
 
+    /// Returns `true` if this client created a commit in the current epoch
+    /// that hasn't been merged or cleared yet. While a commit is pending, no
+    /// other commits or proposals can be created, so applications can use
+    /// this to, e.g., show that a key rotation is in progress.
+    pub fn has_pending_commit(&self) -> bool {
+        self.pending_commit().is_some()
+    }
+
+    /// Returns the epoch the group enters once the pending commit is merged,
+    /// or `None` if there is no pending commit.
+    pub fn pending_commit_epoch(&self) -> Option<GroupEpoch> {
+        self.pending_commit().and_then(StagedCommit::new_epoch)
+    }
+
     /// Sets the `group_state` to [`MlsGroupState::Operational`], thus clearing
     /// any potentially pending commits.
     ///
@@ -296,16 +353,55 @@ impl MlsGroup {
     // === Load & save ===
 
     /// Loads the state from persisted state.
+    ///
+    /// State persisted with [`Self::save()`] as well as state persisted before
+    /// the state had a format version is accepted. Use [`Self::load_verified()`]
+    /// to also check the consistency of the loaded state.
     pub fn load<R: Read>(reader: R) -> Result<MlsGroup, Error> {
+        Self::read_state(reader).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Loads the state from persisted state like [`Self::load()`] and checks
+    /// it for consistency before it is returned, e.g. in case it was corrupted
+    /// or tampered with. Groups that are still joining through an external
+    /// commit or that were left are not checked.
+    ///
+    /// Returns an error if the state was persisted in an unsupported format
+    /// version, if it can't be read or is malformed, or if it is
+    /// inconsistent. See [`DeserializeError`] for details.
+    pub fn load_verified<R: Read>(
+        reader: R,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<MlsGroup, DeserializeError> {
+        let mls_group = Self::read_state(reader)?;
+        let joining_externally = matches!(
+            &mls_group.group_state,
+            MlsGroupState::PendingCommit(pending_commit_state)
+                if matches!(**pending_commit_state, PendingCommitState::External(_))
+        );
+        if mls_group.is_active() && !joining_externally {
+            mls_group.group.verify_integrity(backend)?;
+        }
+        Ok(mls_group)
+    }
+
+    /// Reads the format version header and the serialized state that follows
+    /// it. State without a header was persisted before the header was added
+    /// and is read with the defaults of the fields added since.
+    fn read_state<R: Read>(reader: R) -> Result<MlsGroup, DeserializeError> {
+        let (_version, reader) = read_state_version(reader)?;
         // TODO #245: Remove this once we have a proper serialization format
         #[allow(deprecated)]
         let serialized_mls_group: SerializedMlsGroup = serde_json::from_reader(reader)?;
         Ok(serialized_mls_group.into_mls_group())
     }
 
-    /// Persists the state.
+    /// Persists the state. The state is prefixed with a format version, so
+    /// that [`Self::load()`] can detect state written in an incompatible
+    /// format.
     pub fn save<W: Write>(&mut self, writer: &mut W) -> Result<(), Error> {
         let serialized_mls_group = serde_json::to_string_pretty(self)?;
+        write_state_version(writer)?;
         writer.write_all(&serialized_mls_group.into_bytes())?;
         self.state_changed = InnerState::Persisted;
         Ok(())
@@ -323,20 +419,81 @@ impl MlsGroup {
     pub fn export_ratchet_tree(&self) -> Vec<Option<Node>> {
         self.group.treesync().export_nodes()
     }
+
+    /// Exports the non-blank leaves of the Ratchet Tree together with their
+    /// leaf indices, omitting all parent nodes.
+    pub fn export_leaves(&self) -> Vec<(u32, LeafNode)> {
+        self.group.treesync().export_leaves()
+    }
+
+    /// Exports the private key material of the own leaf, e.g. for a backup
+    /// that allows rejoining the group. See [`OwnLeafSecrets`] for why these
+    /// secrets must be handled with great care.
+    #[cfg(feature = "crypto-subtle")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "crypto-subtle")))]
+    pub fn export_own_leaf_secrets(&self) -> Result<OwnLeafSecrets, LibraryError> {
+        self.group.export_own_leaf_secrets()
+    }
+
+    /// Copies the message secrets and members of the given `epoch` into an
+    /// [`EpochArchive`] that can decrypt the application messages of exactly
+    /// that epoch. This works for the current epoch and the past epochs
+    /// retained according to the `max_past_epochs` configuration.
+    ///
+    /// Returns `None` if the secrets of the epoch are not available.
+    pub fn archive_epoch(&self, epoch: GroupEpoch) -> Option<EpochArchive> {
+        self.group.archive_epoch(epoch)
+    }
+
+    /// Returns the age of the current epoch at the time `now`. The epoch
+    /// started when the group was created or joined, or when the commit that
+    /// started it was merged (see [`Self::merge_staged_commit_at()`]). This
+    /// can be used to issue a key-rotating update once the epoch exceeds a
+    /// certain age. Returns `None` for groups saved by a version that didn't
+    /// record the start of the epoch.
+    pub fn epoch_age(&self, now: u64) -> Option<u64> {
+        self.group.epoch_age(now)
+    }
+
+    /// Returns the epoch in which the member at `leaf_index` last changed its
+    /// encryption key. Changes are only tracked for the epochs for which past
+    /// secrets are retained (see [`MlsGroupConfig::max_past_epochs()`]), so
+    /// `None` indicates a member that hasn't updated within that window, or
+    /// whose last update we haven't seen.
+    pub fn member_last_update_epoch(&self, leaf_index: u32) -> Option<GroupEpoch> {
+        self.group.member_last_update_epoch(leaf_index)
+    }
+
+    /// Returns the tree hash of the current ratchet tree.
+    pub fn tree_hash(&self) -> &[u8] {
+        self.group.tree_hash()
+    }
+
+    /// Recomputes the tree hash of the current ratchet tree from scratch.
+    ///
+    /// Comparing the result against [`MlsGroup::tree_hash()`] allows detecting
+    /// a corrupted group state, e.g. after loading a persisted group and
+    /// before using it.
+    pub fn recompute_tree_hash(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<Vec<u8>, LibraryError> {
+        self.group.recompute_tree_hash(backend)
+    }
 }
 
 // Private methods of MlsGroup
 impl MlsGroup {
-    /// Converts MlsPlaintext to MlsMessageOut. Depending on whether handshake
-    /// message should be encrypted, MlsPlaintext messages are encrypted to
-    /// MlsCiphertext first.
+    /// Converts MlsPlaintext to MlsMessageOut. Depending on the wire format
+    /// the content was signed for, which follows the outgoing wire format
+    /// policy, MlsPlaintext messages are encrypted to MlsCiphertext first.
     fn plaintext_to_mls_message(
         &mut self,
         mls_auth_content: MlsAuthContent,
         backend: &impl OpenMlsCryptoProvider,
     ) -> Result<MlsMessageOut, LibraryError> {
-        let msg = match self.configuration().wire_format_policy().outgoing() {
-            OutgoingWireFormatPolicy::AlwaysPlaintext => {
+        let msg = match mls_auth_content.wire_format() {
+            WireFormat::MlsPlaintext => {
                 let mut plaintext: MlsPlaintext = mls_auth_content.into();
                 // Set the membership tag only if the sender type is `Member`.
                 if plaintext.sender().is_member() {
@@ -348,7 +505,7 @@ impl MlsGroup {
                 }
                 MlsMessageOut::from(plaintext)
             }
-            OutgoingWireFormatPolicy::AlwaysCiphertext => {
+            WireFormat::MlsCiphertext => {
                 let ciphertext = self
                     .group
                     .encrypt(
@@ -378,11 +535,14 @@ impl MlsGroup {
     }
 
     /// Check if the group is operational. Throws an error if the group is
-    /// inactive or if there is a pending commit.
+    /// inactive, if there is a pending commit or if the group was terminated.
     fn is_operational(&self) -> Result<(), MlsGroupStateError> {
         match self.group_state {
             MlsGroupState::PendingCommit(_) => Err(MlsGroupStateError::PendingCommit),
             MlsGroupState::Inactive => Err(MlsGroupStateError::UseAfterEviction),
+            MlsGroupState::Operational if self.group.is_terminated() => {
+                Err(MlsGroupStateError::GroupTerminated)
+            }
             MlsGroupState::Operational => Ok(()),
         }
     }
@@ -395,9 +555,22 @@ impl MlsGroup {
         self.group.context()
     }
 
+    /// Returns a [`StoreDebugReport`] of the message secrets for the given
+    /// epoch to help debug decryption failures.
     #[cfg(any(feature = "test-utils", test))]
-    pub fn tree_hash(&self) -> &[u8] {
-        self.group.treesync().tree_hash()
+    pub fn message_secrets_debug_report(&self, epoch: GroupEpoch) -> StoreDebugReport {
+        self.group.message_secrets_debug_report(epoch)
+    }
+
+    /// Returns a pointer to the buffer of the sender data secret of the
+    /// current epoch, e.g. to check that it is zeroized when it is freed.
+    #[cfg(any(feature = "test-utils", test))]
+    pub fn sender_data_secret_ptr(&self) -> *const u8 {
+        self.group
+            .message_secrets()
+            .sender_data_secret()
+            .as_slice()
+            .as_ptr()
     }
 
     #[cfg(any(feature = "test-utils", test))]
@@ -6,6 +6,10 @@ use core_group::{create_commit_params::CreateCommitParams, staged_commit::Staged
 use tls_codec::Serialize;
 
 use super::{errors::ProcessMessageError, *};
+use crate::{
+    extensions::{Clock, SystemClock},
+    group::errors::ValidationError,
+};
 
 impl MlsGroup {
     /// Parses incoming messages from the DS. Checks for syntactic errors and
@@ -17,10 +21,92 @@ impl MlsGroup {
     /// # Errors:
     /// Returns an [`ProcessMessageError`] when the validation checks fail
     /// with the exact reason of the failure.
+    ///
+    /// Commits are checked against the configured minimum epoch interval (see
+    /// [`MlsGroupConfig::min_epoch_interval()`]) at the current system time.
     pub fn process_message(
         &mut self,
         backend: &impl OpenMlsCryptoProvider,
         message: MlsMessageIn,
+    ) -> Result<ProcessedMessage, ProcessMessageError> {
+        self.process_message_internal(backend, message, SystemClock.now())
+    }
+
+    /// Like [`Self::process_message()`], but rejects commits that arrive less
+    /// than the configured minimum epoch interval (see
+    /// [`MlsGroupConfig::min_epoch_interval()`]) after the start of the
+    /// current epoch at the time `now` instead of the current system time. The
+    /// start of an epoch is the time at which the group was created or joined,
+    /// or at which the commit that started the epoch was merged, so
+    /// applications with their own clock should merge with
+    /// [`Self::merge_staged_commit_at()`] and
+    /// [`Self::merge_pending_commit_at()`] as well. The lifetimes of the key
+    /// packages added by a commit are checked at the time `now`, too, so `now`
+    /// is given in seconds since the Unix epoch.
+    ///
+    /// # Errors:
+    /// Returns a [`ProcessMessageError`] when the validation checks fail. A
+    /// commit that arrived too soon is rejected with a
+    /// [`ProcessMessageError::InvalidCommit`] that contains a
+    /// `StageCommitError::CommitRateLimited`.
+    pub fn process_message_at(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        message: MlsMessageIn,
+        now: u64,
+    ) -> Result<ProcessedMessage, ProcessMessageError> {
+        self.process_message_internal(backend, message, now)
+    }
+
+    /// Cheaply checks whether a received commit can be applied to the current
+    /// state of the group, e.g. to drop commits for an old epoch from a
+    /// backlog before processing them. No cryptographic operations are done,
+    /// so a commit that passes can still be rejected by
+    /// [`Self::process_message()`].
+    ///
+    /// # Errors:
+    /// Returns a [`ValidationError`] if the message is not a commit, belongs
+    /// to another group or epoch, or is sent by an unknown member.
+    pub fn can_process_commit(&self, message: &MlsMessageIn) -> Result<(), ValidationError> {
+        self.group.can_process_commit(message)
+    }
+
+    /// Returns `true` if `commit_a` and `commit_b` are both valid commits for
+    /// the current epoch, but lead to different group states, i.e. the group
+    /// forked. This happens if the DS equivocates or if two members commit
+    /// concurrently, and lets the application apply its own fork resolution
+    /// policy before processing either of the commits. The group state is not
+    /// modified.
+    ///
+    /// Only commits sent as `MlsPlaintext` can be compared, because
+    /// decrypting an `MlsCiphertext` would consume its message key. For
+    /// any other message, `false` is returned.
+    pub fn detect_commit_fork(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        commit_a: &MlsMessageIn,
+        commit_b: &MlsMessageIn,
+    ) -> bool {
+        match (
+            self.group.verified_plaintext(commit_a.clone(), backend),
+            self.group.verified_plaintext(commit_b.clone(), backend),
+        ) {
+            (Some(commit_a), Some(commit_b)) => self.group.detect_fork(
+                &commit_a,
+                &commit_b,
+                &self.proposal_store,
+                &self.own_leaf_nodes,
+                backend,
+            ),
+            _ => false,
+        }
+    }
+
+    fn process_message_internal(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        message: MlsMessageIn,
+        now: u64,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
         // Make sure we are still a member of the group
         if !self.is_active() {
@@ -44,18 +130,66 @@ impl MlsGroup {
         // Since the state of the group might be changed, arm the state flag
         self.flag_state_change();
 
-        // Parse the message
-        let sender_ratchet_configuration =
-            self.configuration().sender_ratchet_configuration().clone();
-        self.group.process_message(
+        // Parse the message. The group decrypts with the sender ratchet
+        // configuration of the `MlsGroupConfig`.
+        self.group.process_message_at(
             backend,
             message,
-            &sender_ratchet_configuration,
+            None,
             &self.proposal_store,
             &self.own_leaf_nodes,
+            Some(now),
         )
     }
 
+    /// Processes a batch of application messages, e.g. a backlog received
+    /// when coming online. The messages may be out of order and may belong to
+    /// any epoch for which the group still holds message secrets. They are
+    /// decrypted in the order of their sender and generation, so that each
+    /// sender ratchet is advanced only once through the sequence.
+    ///
+    /// Returns one result per message, in the order of `messages`. Failures
+    /// don't abort the batch. Messages that aren't encrypted application
+    /// messages are rejected with a [`ProcessMessageError::ValidationError`].
+    pub fn process_application_messages(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        messages: Vec<MlsMessageIn>,
+    ) -> Vec<Result<ProcessedMessage, ProcessMessageError>> {
+        // Make sure we are still a member of the group
+        if !self.is_active() {
+            return messages
+                .iter()
+                .map(|_| {
+                    Err(ProcessMessageError::GroupStateError(
+                        MlsGroupStateError::UseAfterEviction,
+                    ))
+                })
+                .collect();
+        }
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+
+        // The group decrypts with the sender ratchet configuration of the
+        // `MlsGroupConfig`.
+        let unverified_messages = self
+            .group
+            .parse_application_messages(backend, messages, None);
+        unverified_messages
+            .into_iter()
+            .map(|unverified_message| {
+                self.group.process_unverified_message(
+                    unverified_message?,
+                    &self.proposal_store,
+                    &self.own_leaf_nodes,
+                    backend,
+                    None,
+                )
+            })
+            .collect()
+    }
+
     /// Stores a standalone proposal in the internal [ProposalStore]
     pub fn store_pending_proposal(&mut self, proposal: QueuedProposal) {
         // Store the proposal in in the internal ProposalStore
@@ -65,6 +199,16 @@ impl MlsGroup {
         self.flag_state_change();
     }
 
+    /// Stores a batch of standalone proposals in the internal [ProposalStore].
+    /// Proposals with the same content as a proposal that is already stored,
+    /// or that appears earlier in the batch, are skipped.
+    pub fn store_pending_proposals(&mut self, proposals: impl IntoIterator<Item = QueuedProposal>) {
+        self.proposal_store.insert_many_dedup(proposals);
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+    }
+
     /// Creates a Commit message that covers the pending proposals that are
     /// currently stored in the group's [ProposalStore].
     ///
@@ -112,8 +256,20 @@ impl MlsGroup {
     }
 
     /// Merge a [StagedCommit] into the group after inspection. As this advances
-    /// the epoch of the group, it also clears any pending commits.
+    /// the epoch of the group, it also clears any pending commits. The current
+    /// system time is recorded as the time at which the new epoch started (see
+    /// [`Self::epoch_age()`]).
     pub fn merge_staged_commit(&mut self, staged_commit: StagedCommit) {
+        self.merge_staged_commit_internal(staged_commit, SystemClock.now())
+    }
+
+    /// Like [`Self::merge_staged_commit()`], but records `now` as the time at
+    /// which the new epoch started (see [`Self::epoch_age()`]).
+    pub fn merge_staged_commit_at(&mut self, staged_commit: StagedCommit, now: u64) {
+        self.merge_staged_commit_internal(staged_commit, now)
+    }
+
+    fn merge_staged_commit_internal(&mut self, staged_commit: StagedCommit, now: u64) {
         // Check if we were removed from the group
         if staged_commit.self_removed() {
             self.group_state = MlsGroupState::Inactive;
@@ -124,7 +280,7 @@ impl MlsGroup {
 
         // Merge staged commit
         self.group
-            .merge_staged_commit(staged_commit, &mut self.proposal_store);
+            .merge_staged_commit(staged_commit, &mut self.proposal_store, Some(now));
 
         // Extract and store the resumption psk for the current epoch
         let resumption_psk = self.group.group_epoch_secrets().resumption_psk();
@@ -139,13 +295,25 @@ impl MlsGroup {
     }
 
     /// Merges the pending [`StagedCommit`] if there is one, and
-    /// clears the field by setting it to `None`.
+    /// clears the field by setting it to `None`. The current system time is
+    /// recorded as the time at which the new epoch started (see
+    /// [`Self::epoch_age()`]).
     pub fn merge_pending_commit(&mut self) -> Result<(), MlsGroupStateError> {
+        self.merge_pending_commit_internal(SystemClock.now())
+    }
+
+    /// Like [`Self::merge_pending_commit()`], but records `now` as the time at
+    /// which the new epoch started (see [`Self::epoch_age()`]).
+    pub fn merge_pending_commit_at(&mut self, now: u64) -> Result<(), MlsGroupStateError> {
+        self.merge_pending_commit_internal(now)
+    }
+
+    fn merge_pending_commit_internal(&mut self, now: u64) -> Result<(), MlsGroupStateError> {
         match &self.group_state {
             MlsGroupState::PendingCommit(_) => {
                 let old_state = mem::replace(&mut self.group_state, MlsGroupState::Operational);
                 if let MlsGroupState::PendingCommit(pending_commit_state) = old_state {
-                    self.merge_staged_commit((*pending_commit_state).into());
+                    self.merge_staged_commit_internal((*pending_commit_state).into(), now);
                 }
                 Ok(())
             }
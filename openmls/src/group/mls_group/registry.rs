@@ -0,0 +1,75 @@
+//! # Group registry
+//!
+//! A [`GroupRegistry`] holds the [`MlsGroup`]s of a client that is a member
+//! of several groups. It makes sure that there is at most one group per group
+//! ID and routes incoming messages to the group they belong to.
+
+use std::collections::HashMap;
+
+use super::*;
+
+/// A collection of [`MlsGroup`]s indexed by their [`GroupId`].
+#[derive(Debug, Default)]
+pub struct GroupRegistry {
+    groups: HashMap<GroupId, MlsGroup>,
+}
+
+impl GroupRegistry {
+    /// Create a new, empty [`GroupRegistry`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a group into the registry. Returns an error if the registry
+    /// already contains a group with the same group ID.
+    pub fn insert(&mut self, group: MlsGroup) -> Result<(), GroupRegistryError> {
+        if self.groups.contains_key(group.group_id()) {
+            return Err(GroupRegistryError::DuplicateGroupId);
+        }
+        self.groups.insert(group.group_id().clone(), group);
+        Ok(())
+    }
+
+    /// Returns the group with the given group ID, if any.
+    pub fn get(&self, group_id: &GroupId) -> Option<&MlsGroup> {
+        self.groups.get(group_id)
+    }
+
+    /// Returns a mutable reference to the group with the given group ID, if
+    /// any.
+    pub fn get_mut(&mut self, group_id: &GroupId) -> Option<&mut MlsGroup> {
+        self.groups.get_mut(group_id)
+    }
+
+    /// Remove the group with the given group ID from the registry and return
+    /// it, if any.
+    pub fn remove(&mut self, group_id: &GroupId) -> Option<MlsGroup> {
+        self.groups.remove(group_id)
+    }
+
+    /// Returns the number of groups in the registry.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns `true` if the registry contains no groups.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Process a message with the group matching the message's group ID. See
+    /// [`MlsGroup::process_message()`] for details.
+    pub fn process_message(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        message: MlsMessageIn,
+    ) -> Result<ProcessedMessage, GroupRegistryError> {
+        let group = self
+            .groups
+            .get_mut(message.group_id())
+            .ok_or(GroupRegistryError::UnknownGroupId)?;
+        group
+            .process_message(backend, message)
+            .map_err(GroupRegistryError::from)
+    }
+}
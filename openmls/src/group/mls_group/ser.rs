@@ -27,9 +27,15 @@ pub struct SerializedMlsGroup {
 impl SerializedMlsGroup {
     /// Helper method that converts the SerializedMlsGroup to MlsGroup.
     pub fn into_mls_group(self) -> MlsGroup {
+        // Older state doesn't persist the configuration in the group.
+        let mut group = self.group;
+        group.set_config(self.mls_group_config.core_group_config());
+        group.set_sender_ratchet_configuration(
+            self.mls_group_config.sender_ratchet_configuration().clone(),
+        );
         MlsGroup {
             mls_group_config: self.mls_group_config,
-            group: self.group,
+            group,
             proposal_store: self.proposal_store,
             own_leaf_nodes: self.own_leaf_nodes,
             aad: self.aad,
@@ -1,17 +1,28 @@
+use std::time::Duration;
+
 use openmls_rust_crypto::OpenMlsRustCrypto;
-use openmls_traits::{key_store::OpenMlsKeyStore, types::SignatureScheme, OpenMlsCryptoProvider};
-use tls_codec::Serialize;
+use openmls_traits::{
+    crypto::OpenMlsCrypto, key_store::OpenMlsKeyStore, types::SignatureScheme,
+    OpenMlsCryptoProvider,
+};
+use tls_codec::{Deserialize, Serialize};
 
 use crate::{
     credentials::{errors::CredentialError, *},
+    extensions::{
+        ApplicationIdExtension, Clock, Extension, ExtensionType, RequiredCapabilitiesExtension,
+        SystemClock, UnknownExtension,
+    },
     framing::*,
     group::{errors::*, *},
     key_packages::{errors::*, *},
-    messages::proposals::*,
+    messages::{proposals::*, VerifiableGroupInfo},
     test_utils::test_framework::{
         errors::ClientError, ActionType::Commit, CodecUse, MlsGroupTestSetup,
     },
     test_utils::*,
+    treesync::errors::PublicTreeError,
+    versions::ProtocolVersion,
 };
 
 fn generate_credential_bundle(
@@ -120,6 +131,60 @@ fn test_mls_group_persistence(ciphersuite: Ciphersuite, backend: &impl OpenMlsCr
             alice_group_deserialized.export_secret(backend, "test", &[], 32)
         )
     );
+
+    // State written in another format version is rejected.
+    let mut serialized_group = Vec::new();
+    alice_group
+        .save(&mut serialized_group)
+        .expect("Could not write group state.");
+    let mut other_version = serialized_group.clone();
+    other_version[..2].copy_from_slice(&2u16.to_be_bytes());
+    assert_eq!(
+        MlsGroup::load_verified(other_version.as_slice(), backend)
+            .expect_err("Loaded state of another format version."),
+        DeserializeError::UnsupportedVersion { version: 2 }
+    );
+
+    // So is truncated state.
+    assert_eq!(
+        MlsGroup::load_verified(&serialized_group[..serialized_group.len() / 2], backend)
+            .expect_err("Loaded truncated state."),
+        DeserializeError::MalformedState
+    );
+
+    // State persisted before the format version header was added starts
+    // directly with the JSON object and lacks the fields added since. It is
+    // loaded with their defaults.
+    let mut legacy_state: serde_json::Value =
+        serde_json::from_slice(&serialized_group[2..]).expect("Could not parse group state.");
+    let legacy_config = legacy_state["mls_group_config"]
+        .as_object_mut()
+        .expect("Missing group config.");
+    for field in [
+        "always_embed_ratchet_tree",
+        "max_aad_length",
+        "padding_strategy",
+        "min_epoch_interval",
+        "versions",
+        "ciphersuites",
+        "leaf_extensions",
+        "diagnose_confirmation_tag_mismatch",
+    ] {
+        legacy_config.remove(field);
+    }
+    let legacy_state = serde_json::to_vec(&legacy_state).expect("Could not write group state.");
+    assert_eq!(legacy_state[0], b'{');
+
+    let legacy_group =
+        MlsGroup::load(legacy_state.as_slice()).expect("Could not load legacy group state.");
+    assert_eq!(
+        legacy_group.export_secret(backend, "test", &[], 32),
+        alice_group.export_secret(backend, "test", &[], 32)
+    );
+    assert!(!legacy_group.configuration().always_embed_ratchet_tree());
+    assert!(legacy_group.configuration().leaf_extensions().is_empty());
+    MlsGroup::load_verified(legacy_state.as_slice(), backend)
+        .expect("Could not load and verify legacy group state.");
 }
 
 // This tests if the remover is correctly passed to the callback when one member
@@ -482,6 +547,8 @@ fn test_pending_commit_logic(ciphersuite: Ciphersuite, backend: &impl OpenMlsCry
 
     // There should be no pending commit after group creation.
     assert!(alice_group.pending_commit().is_none());
+    assert!(!alice_group.has_pending_commit());
+    assert_eq!(alice_group.pending_commit_epoch(), None);
 
     // Let's add bob
     let proposal = alice_group
@@ -512,6 +579,11 @@ fn test_pending_commit_logic(ciphersuite: Ciphersuite, backend: &impl OpenMlsCry
 
     // There should be a pending commit after issueing a proposal.
     assert!(alice_group.pending_commit().is_some());
+    assert!(alice_group.has_pending_commit());
+    assert_eq!(
+        alice_group.pending_commit_epoch(),
+        Some(GroupEpoch::from(1))
+    );
 
     // If there is a pending commit, other commit- or proposal-creating actions
     // should fail.
@@ -568,6 +640,8 @@ fn test_pending_commit_logic(ciphersuite: Ciphersuite, backend: &impl OpenMlsCry
     // Clearing the pending commit should actually clear it.
     alice_group.clear_pending_commit();
     assert!(alice_group.pending_commit().is_none());
+    assert!(!alice_group.has_pending_commit());
+    assert_eq!(alice_group.pending_commit_epoch(), None);
 
     // Creating a new commit should commit the same proposals.
     let (_msg, welcome_option) = alice_group
@@ -621,3 +695,2558 @@ fn test_pending_commit_logic(ciphersuite: Ciphersuite, backend: &impl OpenMlsCry
     }
     assert!(alice_group.pending_commit().is_none());
 }
+
+#[apply(ciphersuites_and_backends)]
+fn test_add_members_with_ratchet_tree_selection(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob", "Charlie", "Dave"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let dave_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let charlie_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    // The group setting is ignored when the ratchet tree is selected per member
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+
+    // === Alice adds Bob, who doesn't need the ratchet tree ===
+    let (_message, bob_welcome) = alice_group
+        .add_members_with_ratchet_tree_selection(backend, &[bob_key_package.clone()], |_| false)
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    // Bob's Welcome does not carry the ratchet tree
+    let bob_hash_ref = bob_key_package
+        .hash_ref(backend.crypto())
+        .expect("Could not hash KeyPackage.");
+    let bob_key_package_bundle: KeyPackageBundle = backend
+        .key_store()
+        .read(bob_hash_ref.as_slice())
+        .expect("Missing KeyPackageBundle.");
+    let err = MlsGroup::new_from_welcome(backend, &mls_group_config, bob_welcome.clone(), None)
+        .expect_err("Joined without a ratchet tree.");
+    assert_eq!(err, WelcomeError::MissingRatchetTree);
+
+    // With the ratchet tree, Bob can join
+    backend
+        .key_store()
+        .store(bob_hash_ref.as_slice(), &bob_key_package_bundle)
+        .expect("An unexpected error occurred.");
+    let mut bob_group = MlsGroup::new_from_welcome(
+        backend,
+        &mls_group_config,
+        bob_welcome,
+        Some(alice_group.export_ratchet_tree()),
+    )
+    .expect("Error creating group from Welcome");
+
+    // === Alice adds Charlie and Dave, but only Charlie needs the ratchet tree ===
+    let (message, welcome) = alice_group
+        .add_members_with_ratchet_tree_selection(
+            backend,
+            &[charlie_key_package, dave_key_package],
+            |leaf_index| leaf_index == 2,
+        )
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let processed_message = bob_group
+        .process_message(backend, message.into())
+        .expect("Could not process messages.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    {
+        bob_group.merge_staged_commit(*staged_commit);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+
+    // Both share one Welcome, whose GroupInfo carries the ratchet tree, so
+    // both can join without an out-of-band ratchet tree.
+    assert_eq!(welcome.secrets().len(), 2);
+    let charlie_group =
+        MlsGroup::new_from_welcome(backend, &mls_group_config, welcome.clone(), None)
+            .expect("Error creating group from Welcome");
+    let dave_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+    assert_eq!(
+        charlie_group.export_ratchet_tree(),
+        alice_group.export_ratchet_tree()
+    );
+    assert_eq!(
+        charlie_group.export_secret(backend, "test", &[], 32),
+        bob_group.export_secret(backend, "test", &[], 32)
+    );
+    assert_eq!(
+        dave_group.export_secret(backend, "test", &[], 32),
+        bob_group.export_secret(backend, "test", &[], 32)
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_sender_ratchet_resumes_after_reload(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    use crate::tree::{index::SecretTreeLeafIndex, secret_tree::SecretType};
+
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &MlsGroupConfig::test_default(),
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+
+    // Alice sends two messages, persists and reloads the group.
+    for _ in 0..2 {
+        alice_group
+            .create_message(backend, &[1, 2, 3])
+            .expect("An unexpected error occurred.");
+    }
+    let mut serialized_group = Vec::new();
+    alice_group
+        .save(&mut serialized_group)
+        .expect("Could not write group state.");
+    let mut alice_group = MlsGroup::load_verified(serialized_group.as_slice(), backend)
+        .expect("Could not deserialize MlsGroup");
+
+    // After the reload, the sender ratchet continues where it stopped.
+    let own_index = SecretTreeLeafIndex(alice_group.own_leaf_index());
+    assert_eq!(
+        alice_group
+            .group
+            .message_secrets_test_mut()
+            .secret_tree_mut()
+            .generation(own_index, SecretType::ApplicationSecret),
+        2
+    );
+    alice_group
+        .create_message(backend, &[1, 2, 3])
+        .expect("An unexpected error occurred.");
+    assert_eq!(
+        alice_group
+            .group
+            .message_secrets_test_mut()
+            .secret_tree_mut()
+            .generation(own_index, SecretType::ApplicationSecret),
+        3
+    );
+
+    // Loading the outdated state again is detected before a generation is
+    // reused, and doesn't advance the sender ratchet.
+    let mut alice_group = MlsGroup::load_verified(serialized_group.as_slice(), backend)
+        .expect("Could not deserialize MlsGroup");
+    let err = alice_group
+        .create_message(backend, &[1, 2, 3])
+        .expect_err("Encrypted a message with an outdated sender ratchet.");
+    assert_eq!(err, CreateMessageError::RatchetRegression);
+    assert_eq!(
+        alice_group
+            .group
+            .message_secrets_test_mut()
+            .secret_tree_mut()
+            .generation(own_index, SecretType::ApplicationSecret),
+        2
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_describe_pending_proposals(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let bob_credential = generate_credential_bundle(
+        backend,
+        "Bob".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let charlie_credential = generate_credential_bundle(
+        backend,
+        "Charlie".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+
+    // Generate KeyPackages
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let bob_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &bob_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let charlie_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &charlie_credential, vec![])
+            .expect("An unexpected error occurred.");
+
+    let mls_group_config = MlsGroupConfig::default();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_queued_message, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(
+        backend,
+        &mls_group_config,
+        welcome,
+        Some(alice_group.export_ratchet_tree()),
+    )
+    .expect("Error creating group from Welcome");
+    assert!(bob_group.describe_pending_proposals().is_empty());
+
+    // === Alice proposes to add Charlie ===
+    let queued_message = alice_group
+        .propose_add_member(backend, &charlie_key_package)
+        .expect("Could not propose to add Charlie.");
+    let processed_message = bob_group
+        .process_message(backend, queued_message.into())
+        .expect("Could not process message.");
+    if let ProcessedMessageContent::ProposalMessage(queued_proposal) =
+        processed_message.into_content()
+    {
+        assert_eq!(
+            queued_proposal.proposed_in_epoch(),
+            Some(GroupEpoch::from(1))
+        );
+        bob_group.store_pending_proposal(*queued_proposal);
+    } else {
+        unreachable!("Expected a QueuedProposal.");
+    }
+
+    let expected_summary = ProposalSummary {
+        sender: Sender::Member(0),
+        sender_identity: Some(b"Alice".to_vec()),
+        proposed_in_epoch: GroupEpoch::from(1),
+        proposal: Proposal::Add(AddProposal {
+            key_package: charlie_key_package,
+        }),
+    };
+    assert_eq!(
+        bob_group.describe_pending_proposals(),
+        vec![expected_summary.clone()]
+    );
+    assert_eq!(
+        alice_group.describe_pending_proposals(),
+        vec![expected_summary]
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_members_after_removal(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+    let identities = ["Alice", "Bob", "Charlie", "Dave", "Eve"];
+
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in identities {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let alice_key_package = key_packages.remove(0);
+
+    // === Alice creates a group with four other members ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &MlsGroupConfig::default(),
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    alice_group
+        .add_members(backend, &key_packages)
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    assert_eq!(alice_group.members().count(), 5);
+
+    // === Alice removes the member at leaf index 2 ===
+    let removed_identity = alice_group.member_at(2).expect("Missing member.").identity;
+    alice_group
+        .remove_members(backend, &[2])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    let members: Vec<Member> = alice_group.members().collect();
+    assert_eq!(
+        members
+            .iter()
+            .map(|member| member.index)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 3, 4]
+    );
+    assert!(members
+        .iter()
+        .all(|member| member.identity != removed_identity));
+    for member in &members {
+        assert_eq!(alice_group.member_at(member.index).as_ref(), Some(member));
+    }
+    assert!(alice_group.member_at(2).is_none());
+    assert!(alice_group.member_at(100).is_none());
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_propose_group_context_extensions(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let bob_credential = generate_credential_bundle(
+        backend,
+        "Bob".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let bob_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &bob_credential, vec![])
+            .expect("An unexpected error occurred.");
+
+    let mls_group_config = MlsGroupConfig::default();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_queued_message, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(
+        backend,
+        &mls_group_config,
+        welcome,
+        Some(alice_group.export_ratchet_tree()),
+    )
+    .expect("Error creating group from Welcome");
+
+    // === Alice proposes a new required capability ===
+    let required_capabilities = Extension::RequiredCapabilities(
+        RequiredCapabilitiesExtension::new(&[ExtensionType::ApplicationId], &[]),
+    );
+    let proposal = alice_group
+        .propose_group_context_extensions(backend, &[required_capabilities.clone()])
+        .expect("Could not propose group context extensions.");
+    let bob_processed_message = bob_group
+        .process_message(backend, proposal.into())
+        .expect("Could not process message.");
+    if let ProcessedMessageContent::ProposalMessage(staged_proposal) =
+        bob_processed_message.into_content()
+    {
+        bob_group.store_pending_proposal(*staged_proposal);
+    } else {
+        unreachable!("Expected a QueuedProposal.");
+    }
+
+    // === Alice commits the proposal ===
+    let (commit, _welcome) = alice_group
+        .commit_to_pending_proposals(backend)
+        .expect("Could not commit proposal.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_processed_message = bob_group
+        .process_message(backend, commit.into())
+        .expect("Could not process message.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        bob_processed_message.into_content()
+    {
+        bob_group.merge_staged_commit(*staged_commit);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+
+    // The required capability is part of the group context of both members.
+    for group in [&alice_group, &bob_group] {
+        assert_eq!(
+            group.group().group_context_extensions(),
+            &[required_capabilities.clone()]
+        );
+    }
+    assert_eq!(alice_group.group().context(), bob_group.group().context());
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_propose_psk(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    use crate::schedule::psk::{ExternalPsk, PreSharedKeyId, Psk};
+
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let bob_credential = generate_credential_bundle(
+        backend,
+        "Bob".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let bob_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &bob_credential, vec![])
+            .expect("An unexpected error occurred.");
+
+    let mls_group_config = MlsGroupConfig::default();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_queued_message, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(
+        backend,
+        &mls_group_config,
+        welcome,
+        Some(alice_group.export_ratchet_tree()),
+    )
+    .expect("Error creating group from Welcome");
+
+    let exporter_secret = |group: &MlsGroup| {
+        group
+            .export_secret(backend, "psk test", &[], 32)
+            .expect("Error exporting secret.")
+    };
+    let pre_psk_secret = exporter_secret(&alice_group);
+    assert_eq!(pre_psk_secret, exporter_secret(&bob_group));
+
+    // === Alice proposes an out-of-band PSK that both members know ===
+    let psk_id = PreSharedKeyId::new(
+        ciphersuite,
+        backend.rand(),
+        Psk::External(ExternalPsk::new(b"password".to_vec())),
+    )
+    .expect("An unexpected error occurred.");
+    psk_id
+        .write_to_key_store(backend, ciphersuite, b"password-derived key")
+        .expect("Could not store PSK.");
+    let proposal = alice_group
+        .propose_psk(backend, psk_id)
+        .expect("Could not propose PSK.");
+    let bob_processed_message = bob_group
+        .process_message(backend, proposal.into())
+        .expect("Could not process message.");
+    if let ProcessedMessageContent::ProposalMessage(staged_proposal) =
+        bob_processed_message.into_content()
+    {
+        bob_group.store_pending_proposal(*staged_proposal);
+    } else {
+        unreachable!("Expected a QueuedProposal.");
+    }
+
+    // === Alice commits the proposal ===
+    let (commit, _welcome) = alice_group
+        .commit_to_pending_proposals(backend)
+        .expect("Could not commit proposal.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_processed_message = bob_group
+        .process_message(backend, commit.into())
+        .expect("Could not process message.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        bob_processed_message.into_content()
+    {
+        bob_group.merge_staged_commit(*staged_commit);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+
+    // Both members derive the same new exporter secret.
+    let post_psk_secret = exporter_secret(&alice_group);
+    assert_eq!(post_psk_secret, exporter_secret(&bob_group));
+    assert_ne!(pre_psk_secret, post_psk_secret);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_export_group_info_with_options(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let bob_credential = generate_credential_bundle(
+        backend,
+        "Bob".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let bob_credential_bundle: CredentialBundle = backend
+        .key_store()
+        .read(
+            &bob_credential
+                .signature_key()
+                .tls_serialize_detached()
+                .expect("Error serializing signature key."),
+        )
+        .expect("An unexpected error occurred.");
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+
+    // === Alice creates a group ===
+    let alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &MlsGroupConfig::default(),
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+
+    // By default, the group info contains the external_pub extension.
+    let group_info = alice_group
+        .export_group_info(backend, false)
+        .expect("Could not export group info.");
+    assert!(group_info
+        .extensions()
+        .iter()
+        .any(|extension| extension.extension_type() == ExtensionType::ExternalPub));
+
+    // Export a group info without external_pub but with an additional
+    // extension.
+    let application_id = Extension::ApplicationId(ApplicationIdExtension::new(b"Test"));
+    let options = GroupInfoExportOptions::new()
+        .with_external_pub(false)
+        .with_extensions(vec![application_id.clone()]);
+    let group_info = alice_group
+        .export_group_info_with_options(backend, &options)
+        .expect("Could not export group info.");
+    assert_eq!(group_info.extensions(), &[application_id.clone()]);
+
+    // The group info can't be used for an external join.
+    let error = MlsGroup::join_by_external_commit(
+        backend,
+        Some(&alice_group.export_ratchet_tree()),
+        group_info.into_verifiable_group_info(),
+        None,
+        alice_group.configuration(),
+        &[],
+        &bob_credential_bundle,
+    )
+    .expect_err("Joined the group without the external_pub extension.");
+    assert_eq!(error, ExternalCommitError::MissingExternalPub);
+
+    // Extensions can't be included twice.
+    let options =
+        GroupInfoExportOptions::new().with_extensions(vec![application_id.clone(), application_id]);
+    assert_eq!(
+        alice_group
+            .export_group_info_with_options(backend, &options)
+            .expect_err("Exported a group info with a duplicate extension."),
+        ExportGroupInfoError::DuplicateExtension
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_group_info_unknown_extension_round_trip(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+
+    let alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &MlsGroupConfig::default(),
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+
+    // Export a group info with a vendor extension.
+    let vendor_extension = Extension::Unknown(
+        UnknownExtension::new(0xF00D, b"vendor data".to_vec())
+            .expect("Could not create extension."),
+    );
+    let options = GroupInfoExportOptions::new().with_extensions(vec![vendor_extension.clone()]);
+    let group_info = alice_group
+        .export_group_info_with_options(backend, &options)
+        .expect("Could not export group info.");
+    assert!(group_info.extensions().contains(&vendor_extension));
+
+    // The vendor extension survives a serialization round trip unchanged.
+    let encoded = group_info
+        .into_verifiable_group_info()
+        .tls_serialize_detached()
+        .expect("Error serializing group info.");
+    let decoded = VerifiableGroupInfo::tls_deserialize(&mut encoded.as_slice())
+        .expect("Error deserializing group info.");
+    assert!(decoded.extensions().contains(&vendor_extension));
+    assert_eq!(
+        decoded
+            .tls_serialize_detached()
+            .expect("Error serializing group info."),
+        encoded
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_config_applies_to_joined_groups(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    use crate::tree::sender_ratchet::SenderRatchetConfiguration;
+
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob", "Charlie"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let charlie_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(false)
+        .always_embed_ratchet_tree(true)
+        .max_aad_length(8)
+        .sender_ratchet_configuration(SenderRatchetConfiguration::new(0, 1000))
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_message, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    // The ratchet tree is embedded although the extension is not used
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // === Bob uses the configured sender ratchet ===
+    let first_message = alice_group
+        .create_message(backend, &[1, 2, 3])
+        .expect("An unexpected error occurred.");
+    let second_message = alice_group
+        .create_message(backend, &[4, 5, 6])
+        .expect("An unexpected error occurred.");
+    bob_group
+        .process_message(backend, second_message.into())
+        .expect("An unexpected error occurred.");
+    let err = bob_group
+        .process_message(backend, first_message.into())
+        .expect_err("Decrypted a message outside of the out-of-order tolerance.");
+    assert_eq!(
+        err,
+        ProcessMessageError::ValidationError(ValidationError::UnableToDecrypt(
+            MessageDecryptionError::SecretTreeError(SecretTreeError::TooDistantInThePast)
+        ))
+    );
+
+    // === Bob uses the configured maximum AAD length ===
+    bob_group.set_aad(&[0; 9]);
+    let err = bob_group
+        .create_message(backend, &[1, 2, 3])
+        .expect_err("Created a message with a too large AAD.");
+    assert_eq!(err, CreateMessageError::AadTooLarge);
+    bob_group.set_aad(&[0; 8]);
+    bob_group
+        .create_message(backend, &[1, 2, 3])
+        .expect("An unexpected error occurred.");
+
+    // === Bob adds Charlie, who joins without an out-of-band ratchet tree ===
+    let (_message, welcome) = bob_group
+        .add_members(backend, &[charlie_key_package])
+        .expect("An unexpected error occurred.");
+    bob_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let charlie_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+    assert_eq!(
+        charlie_group.export_ratchet_tree(),
+        bob_group.export_ratchet_tree()
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_process_message_at(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    // Alice accepts at most one commit per minute. Commits are sent as
+    // plaintexts, so that Alice can process the same commit several times.
+    let mls_group_config = MlsGroupConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .use_ratchet_tree_extension(true)
+        .min_epoch_interval(Duration::from_millis(60_500))
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_message, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let now = SystemClock.now();
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // The start of the epoch is recorded when joining a group.
+    assert_eq!(bob_group.epoch_age(now), Some(0));
+
+    // Alice merged her last commit at the current system time, so a commit
+    // arriving right away is rate limited, even without a time.
+    let (message, _welcome) = bob_group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+    bob_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let message: MlsMessageIn = message.into();
+    let err = alice_group
+        .process_message(backend, message.clone())
+        .expect_err("Commit arriving too soon was processed.");
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::CommitRateLimited)
+    );
+
+    let processed_message = alice_group
+        .process_message_at(backend, message, now + 61)
+        .expect("Could not process messages.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    {
+        alice_group.merge_staged_commit_at(*staged_commit, now + 100);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+
+    // A commit arriving too soon after the start of the epoch is rejected,
+    // even if it's only a fraction of a second too soon.
+    let (message, _welcome) = bob_group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+    let message: MlsMessageIn = message.into();
+    let err = alice_group
+        .process_message_at(backend, message.clone(), now + 160)
+        .expect_err("Commit arriving too soon was processed.");
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::CommitRateLimited)
+    );
+
+    // Once the interval has passed, the commit is accepted.
+    alice_group
+        .process_message_at(backend, message, now + 161)
+        .expect("Could not process messages.");
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_process_application_messages(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // === Bob sends a backlog of messages and a commit ===
+    let payloads: Vec<Vec<u8>> = (0..3u8).map(|i| vec![i; 8]).collect();
+    let messages: Vec<MlsMessageIn> = payloads
+        .iter()
+        .map(|payload| {
+            bob_group
+                .create_message(backend, payload)
+                .expect("An unexpected error occurred.")
+                .into()
+        })
+        .collect();
+    let (commit, _welcome) = bob_group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+
+    // === Alice processes them out of order, with a handshake message ===
+    let order = [2, 0, 1];
+    let mut batch: Vec<MlsMessageIn> = order.iter().map(|&i| messages[i].clone()).collect();
+    batch.push(commit.into());
+    let mut results = alice_group.process_application_messages(backend, batch);
+    assert_eq!(results.len(), order.len() + 1);
+
+    // The handshake message is rejected.
+    assert_eq!(
+        results
+            .pop()
+            .expect("Missing result.")
+            .expect_err("Processed a handshake message as application message."),
+        ProcessMessageError::ValidationError(ValidationError::NotAnApplicationMessage)
+    );
+
+    // The results are in the order of the batch.
+    for (result, &index) in results.into_iter().zip(order.iter()) {
+        let processed_message = result.expect("Could not process message.");
+        assert_eq!(
+            processed_message.sender(),
+            &Sender::build_member(bob_group.own_leaf_index())
+        );
+        if let ProcessedMessageContent::ApplicationMessage(application_message) =
+            processed_message.into_content()
+        {
+            assert_eq!(application_message.into_bytes(), payloads[index]);
+        } else {
+            unreachable!("Expected an ApplicationMessage.");
+        }
+    }
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_propose_reinit(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // === Alice proposes to re-initialize the group ===
+    let new_group_id = GroupId::from_slice(b"New Test Group");
+    let proposal = alice_group
+        .propose_reinit(
+            backend,
+            new_group_id.clone(),
+            ProtocolVersion::default(),
+            ciphersuite,
+            vec![],
+        )
+        .expect("Could not propose ReInit.");
+    let bob_processed_message = bob_group
+        .process_message(backend, proposal.into())
+        .expect("Could not process message.");
+    if let ProcessedMessageContent::ProposalMessage(staged_proposal) =
+        bob_processed_message.into_content()
+    {
+        bob_group.store_pending_proposal(*staged_proposal);
+    } else {
+        unreachable!("Expected a QueuedProposal.");
+    }
+
+    // === Alice commits the proposal, which terminates the group ===
+    let (commit, _welcome) = alice_group
+        .commit_to_pending_proposals(backend)
+        .expect("Could not commit proposal.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_processed_message = bob_group
+        .process_message(backend, commit.into())
+        .expect("Could not process message.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        bob_processed_message.into_content()
+    {
+        let reinit_proposal = staged_commit
+            .reinit_proposal()
+            .expect("Missing ReInit proposal.");
+        assert_eq!(reinit_proposal.group_id(), &new_group_id);
+        assert!(!bob_group.is_terminated());
+        bob_group.merge_staged_commit(*staged_commit);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+    assert!(alice_group.is_terminated());
+    assert!(bob_group.is_terminated());
+
+    // === A terminated group can't create proposals or messages ===
+    assert_eq!(
+        bob_group
+            .propose_self_update(backend, None)
+            .expect_err("Created a proposal in a terminated group."),
+        ProposeSelfUpdateError::GroupStateError(MlsGroupStateError::GroupTerminated)
+    );
+    assert_eq!(
+        bob_group
+            .propose_reinit(
+                backend,
+                new_group_id,
+                ProtocolVersion::default(),
+                ciphersuite,
+                vec![],
+            )
+            .expect_err("Created a proposal in a terminated group."),
+        ProposeReInitError::GroupStateError(MlsGroupStateError::GroupTerminated)
+    );
+    assert_eq!(
+        alice_group
+            .self_update(backend, None)
+            .expect_err("Created a commit in a terminated group."),
+        SelfUpdateError::GroupStateError(MlsGroupStateError::GroupTerminated)
+    );
+    assert_eq!(
+        alice_group
+            .create_message(backend, b"Hello")
+            .expect_err("Created an application message in a terminated group."),
+        CreateMessageError::GroupStateError(MlsGroupStateError::GroupTerminated)
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_rekey(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .wire_format_policy(MIXED_CIPHERTEXT_WIRE_FORMAT_POLICY)
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // Alice has a pending proposal that the rekey doesn't cover.
+    let _proposal = alice_group
+        .propose_self_update(backend, None)
+        .expect("Could not create proposal.");
+
+    let exporter_secret = alice_group
+        .export_secret(backend, "label", &[], 32)
+        .expect("Could not export secret.");
+    let alice_encryption_key = alice_group
+        .own_leaf()
+        .expect("Missing own leaf.")
+        .encryption_key()
+        .clone();
+
+    // === Alice rekeys the group ===
+    let commit = alice_group.rekey(backend).expect("Could not rekey.");
+    assert_eq!(commit.wire_format(), WireFormat::MlsCiphertext);
+    assert_eq!(
+        alice_group
+            .rekey(backend)
+            .expect_err("Created a second commit in the same epoch."),
+        SelfUpdateError::GroupStateError(MlsGroupStateError::PendingCommit)
+    );
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    let bob_processed_message = bob_group
+        .process_message(backend, commit.into())
+        .expect("Could not process message.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        bob_processed_message.into_content()
+    {
+        assert_eq!(staged_commit.update_proposals().count(), 0);
+        assert!(staged_commit.commit_update_key_package().is_some());
+        bob_group.merge_staged_commit(*staged_commit);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+
+    // The membership is unchanged, but the own leaf and the epoch secrets
+    // were rotated.
+    assert_eq!(alice_group.members().count(), 2);
+    assert_ne!(
+        alice_group
+            .own_leaf()
+            .expect("Missing own leaf.")
+            .encryption_key(),
+        &alice_encryption_key
+    );
+    let new_exporter_secret = alice_group
+        .export_secret(backend, "label", &[], 32)
+        .expect("Could not export secret.");
+    assert_ne!(new_exporter_secret, exporter_secret);
+    assert_eq!(
+        bob_group
+            .export_secret(backend, "label", &[], 32)
+            .expect("Could not export secret."),
+        new_exporter_secret
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_group_registry(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_other_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // Bob is also in another group, of which a copy of the state is kept.
+    let bob_state = {
+        let mut state = Vec::new();
+        let mut bob_group = MlsGroup::new(
+            backend,
+            &mls_group_config,
+            bob_other_key_package
+                .hash_ref(backend.crypto())
+                .expect("Could not hash KeyPackage.")
+                .as_slice(),
+        )
+        .expect("An unexpected error occurred.");
+        bob_group
+            .save(&mut state)
+            .expect("Could not save group state.");
+        state
+    };
+    let bob_other_group = MlsGroup::load_verified(bob_state.as_slice(), backend)
+        .expect("Could not load group state.");
+
+    let mut registry = GroupRegistry::new();
+    assert!(registry.is_empty());
+    registry
+        .insert(bob_group)
+        .expect("Error inserting group into registry.");
+    registry
+        .insert(bob_other_group)
+        .expect("Error inserting group into registry.");
+    assert_eq!(registry.len(), 2);
+
+    // A second copy of a group with the same group ID is rejected.
+    let bob_duplicate_group = MlsGroup::load_verified(bob_state.as_slice(), backend)
+        .expect("Could not load group state.");
+    assert_eq!(
+        registry.insert(bob_duplicate_group),
+        Err(GroupRegistryError::DuplicateGroupId)
+    );
+    assert_eq!(registry.len(), 2);
+
+    // Messages are routed to the group with the message's group ID.
+    let message = alice_group
+        .create_message(backend, b"Hello, Bob!")
+        .expect("An unexpected error occurred.");
+    let processed_message = registry
+        .process_message(backend, message.into())
+        .expect("Error processing message.");
+    assert_eq!(processed_message.group_id(), alice_group.group_id());
+    if let ProcessedMessageContent::ApplicationMessage(application_message) =
+        processed_message.into_content()
+    {
+        assert_eq!(application_message.into_bytes(), b"Hello, Bob!");
+    } else {
+        unreachable!("Expected an ApplicationMessage.");
+    }
+
+    // Messages for unknown groups are rejected.
+    registry
+        .remove(alice_group.group_id())
+        .expect("Group is missing from registry.");
+    let message = alice_group
+        .create_message(backend, b"Hello, Bob!")
+        .expect("An unexpected error occurred.");
+    let error = registry
+        .process_message(backend, message.into())
+        .expect_err("Message for unknown group was processed.");
+    assert_eq!(error, GroupRegistryError::UnknownGroupId);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_membership_capabilities(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &MlsGroupConfig::default(),
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    alice_group
+        .add_members(backend, &[bob_key_package.clone()])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    // Both members support the version and the ciphersuites of their key
+    // packages.
+    assert_eq!(
+        alice_group.membership_version_floor(),
+        ProtocolVersion::Mls10
+    );
+    let ciphersuites = alice_group.membership_ciphersuite_intersection();
+    assert!(ciphersuites.contains(&ciphersuite));
+    for key_package in [&alice_key_package, &bob_key_package] {
+        assert!(ciphersuites.iter().all(|c| key_package
+            .leaf_node()
+            .capabilities()
+            .ciphersuites()
+            .contains(c)));
+    }
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_can_process_commit(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // Bob's commit can be processed in the current epoch, but an application
+    // message is not a commit.
+    let (bob_commit, _welcome) = bob_group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+    let bob_commit = MlsMessageIn::from(bob_commit);
+    alice_group
+        .can_process_commit(&bob_commit)
+        .expect("Commit for the current epoch was rejected.");
+    bob_group.clear_pending_commit();
+    let message = bob_group
+        .create_message(backend, b"Hello, Alice!")
+        .expect("An unexpected error occurred.");
+    assert_eq!(
+        alice_group.can_process_commit(&message.into()),
+        Err(ValidationError::NotACommit)
+    );
+
+    // Once Alice moved on to the next epoch, Bob's commit is rejected.
+    alice_group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    assert_eq!(
+        alice_group.can_process_commit(&bob_commit),
+        Err(ValidationError::WrongEpoch)
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_recover_member(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut credentials = Vec::new();
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob", "Charlie"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+        credentials.push(credential);
+    }
+    let charlie_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+    let bob_index = bob_group.own_leaf_index();
+
+    // === Bob loses the local state and publishes a fresh KeyPackage ===
+    drop(bob_group);
+    let bob_new_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &credentials[1], vec![])
+            .expect("An unexpected error occurred.");
+
+    // The KeyPackage has to belong to the member that is recovered.
+    assert_eq!(
+        alice_group
+            .recover_member(backend, bob_index, charlie_key_package)
+            .expect_err("Replaced Bob with Charlie."),
+        RemoveMembersError::CreateCommitError(CreateCommitError::RecoveryIdentityMismatch)
+    );
+    assert_eq!(
+        alice_group
+            .recover_member(
+                backend,
+                alice_group.own_leaf_index(),
+                bob_new_key_package.clone()
+            )
+            .expect_err("Replaced the own leaf."),
+        RemoveMembersError::CreateCommitError(CreateCommitError::CannotRemoveSelf)
+    );
+
+    // === Alice recovers Bob and Bob joins again ===
+    let (_commit, welcome) = alice_group
+        .recover_member(backend, bob_index, bob_new_key_package)
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    assert_eq!(bob_group.own_leaf_index(), bob_index);
+    assert_eq!(bob_group.epoch(), alice_group.epoch());
+    assert_eq!(alice_group.members().count(), 2);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_detect_commit_fork(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // === Alice creates two different commits for the same epoch ===
+    let (commit_a, _welcome) = alice_group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+    alice_group.clear_pending_commit();
+    let (commit_b, _welcome) = alice_group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+    let commit_a = MlsMessageIn::from(commit_a);
+    let commit_b = MlsMessageIn::from(commit_b);
+
+    assert!(bob_group.detect_commit_fork(backend, &commit_a, &commit_b));
+    // The same commit twice is not a fork.
+    assert!(!bob_group.detect_commit_fork(backend, &commit_a, &commit_a));
+
+    // Detecting a fork doesn't change the group, so Bob can still process one
+    // of the commits.
+    let processed_message = bob_group
+        .process_message(backend, commit_b)
+        .expect("Could not process commit.");
+    match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+            bob_group.merge_staged_commit(*staged_commit)
+        }
+        _ => panic!("Expected a commit."),
+    }
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    assert_eq!(bob_group.epoch(), alice_group.epoch());
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_advertised_versions(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+
+    // The own leaf advertises all configured versions.
+    let versions = vec![ProtocolVersion::Mls10, ProtocolVersion::Mls10Draft11];
+    let mls_group_config = MlsGroupConfig::builder().versions(versions.clone()).build();
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    assert_eq!(
+        alice_group
+            .own_leaf()
+            .expect("Missing own leaf.")
+            .capabilities()
+            .versions(),
+        versions.as_slice()
+    );
+
+    // The version of the group has to be advertised.
+    let mls_group_config = MlsGroupConfig::builder()
+        .versions(vec![ProtocolVersion::Mls10Draft11])
+        .build();
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let error = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect_err("Created a group that doesn't advertise its version.");
+    assert_eq!(error, NewGroupError::VersionNotAdvertised);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_advertised_ciphersuites(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let bob_credential = generate_credential_bundle(
+        backend,
+        "Bob".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+
+    // The own leaf advertises all configured ciphersuites.
+    let ciphersuites = backend.crypto().supported_ciphersuites();
+    let mls_group_config = MlsGroupConfig::builder()
+        .ciphersuites(ciphersuites.clone())
+        .build();
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let advertised_ciphersuites = alice_group
+        .own_leaf()
+        .expect("Missing own leaf.")
+        .capabilities()
+        .ciphersuites()
+        .to_vec();
+    assert!(ciphersuites
+        .iter()
+        .all(|cs| advertised_ciphersuites.contains(cs)));
+
+    // Bob only supports the ciphersuite of the group and can be added.
+    let bob_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &bob_credential, vec![])
+            .expect("An unexpected error occurred.");
+    alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("Could not add Bob.");
+
+    // The ciphersuite of the group has to be advertised.
+    let other_ciphersuites: Vec<Ciphersuite> = ciphersuites
+        .into_iter()
+        .filter(|cs| *cs != ciphersuite)
+        .collect();
+    let mls_group_config = MlsGroupConfig::builder()
+        .ciphersuites(other_ciphersuites)
+        .build();
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let error = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect_err("Created a group that doesn't advertise its ciphersuite.");
+    assert_eq!(error, NewGroupError::CiphersuiteNotAdvertised);
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_branch(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut credentials = Vec::new();
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+        credentials.push(credential);
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // A branch needs members.
+    assert_eq!(
+        alice_group
+            .branch(backend, &mls_group_config, GroupId::random(backend), &[])
+            .expect_err("Created a branch without members."),
+        BranchGroupError::NoMembers
+    );
+
+    // === Alice branches off a new group with Bob ===
+    let bob_branch_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &credentials[1], vec![])
+            .expect("An unexpected error occurred.");
+    let branch_group_id = GroupId::random(backend);
+    let (alice_branch, welcome) = alice_group
+        .branch(
+            backend,
+            &mls_group_config,
+            branch_group_id.clone(),
+            &[bob_branch_key_package],
+        )
+        .expect("Error branching group.");
+    assert_eq!(alice_branch.group_id(), &branch_group_id);
+    assert_eq!(alice_branch.members().count(), 2);
+
+    // The parent group is unaffected.
+    assert_eq!(alice_group.epoch(), bob_group.epoch());
+
+    // === Bob joins the branch with the resumption PSK of the parent ===
+    let bob_branch =
+        MlsGroup::new_from_branch_welcome(backend, &mls_group_config, welcome, &bob_group, None)
+            .expect("Error joining branch.");
+    assert_eq!(bob_branch.group_id(), &branch_group_id);
+    assert_eq!(
+        alice_branch
+            .export_secret(backend, "test", &[], 32)
+            .expect("Error exporting secret."),
+        bob_branch
+            .export_secret(backend, "test", &[], 32)
+            .expect("Error exporting secret.")
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_leaf_extensions(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let application_id = Extension::ApplicationId(ApplicationIdExtension::new(b"app"));
+
+    // The application id extension is not in the leaf's capabilities.
+    let mls_group_config = MlsGroupConfig::builder()
+        .leaf_extensions(vec![application_id.clone()])
+        .build();
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let error = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect_err("Created a group with an extension that is not advertised.");
+    assert_eq!(error, NewGroupError::LeafExtensionNotAdvertised);
+
+    // Once the extension type is required, the leaf advertises it.
+    let mls_group_config = MlsGroupConfig::builder()
+        .required_capabilities(RequiredCapabilitiesExtension::new(
+            &[ExtensionType::ApplicationId],
+            &[],
+        ))
+        .leaf_extensions(vec![application_id])
+        .build();
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+    let alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    assert!(alice_group
+        .own_leaf()
+        .expect("Missing own leaf.")
+        .capabilities()
+        .extensions()
+        .contains(&ExtensionType::ApplicationId));
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_export_subgroup_key(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob", "Charlie"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let charlie_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob and Charlie ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package, charlie_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // Both members of the subgroup derive the same key, independent of the
+    // order of the leaf indices.
+    let alice_key = alice_group
+        .export_subgroup_key(backend, &[0, 1], "admins")
+        .expect("Error exporting subgroup key.");
+    let bob_key = bob_group
+        .export_subgroup_key(backend, &[1, 0], "admins")
+        .expect("Error exporting subgroup key.");
+    assert_eq!(alice_key, bob_key);
+    assert_eq!(alice_key.len(), ciphersuite.aead_key_length());
+    assert_ne!(
+        alice_key,
+        alice_group
+            .export_subgroup_key(backend, &[0, 2], "admins")
+            .expect("Error exporting subgroup key.")
+    );
+
+    assert_eq!(
+        alice_group
+            .export_subgroup_key(backend, &[], "admins")
+            .expect_err("Exported a key for an empty subgroup."),
+        ExportSecretError::EmptySubgroup
+    );
+    assert_eq!(
+        alice_group
+            .export_subgroup_key(backend, &[0, 5], "admins")
+            .expect_err("Exported a key for an unknown member."),
+        ExportSecretError::UnknownSubgroupMember
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_save_with_pending_commit(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    // === Alice creates a group and commits to adding Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &MlsGroupConfig::default(),
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+
+    // === Alice checkpoints the group together with the pending commit ===
+    let mut serialized_group = Vec::new();
+    alice_group
+        .save(&mut serialized_group)
+        .expect("Could not write group state.");
+    let mut resumed_group = MlsGroup::load_verified(serialized_group.as_slice(), backend)
+        .expect("Could not deserialize MlsGroup");
+    assert!(resumed_group.pending_commit().is_some());
+
+    // Merging the resumed commit leads to the same state as merging the
+    // original one.
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    resumed_group
+        .merge_pending_commit()
+        .expect("error merging resumed pending commit");
+    assert_eq!(resumed_group.epoch(), alice_group.epoch());
+    assert_eq!(
+        resumed_group
+            .export_secret(backend, "test", &[], 32)
+            .expect("Error exporting secret."),
+        alice_group
+            .export_secret(backend, "test", &[], 32)
+            .expect("Error exporting secret.")
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_verify_ratchet_tree(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &MlsGroupConfig::default(),
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    let tree_encoded = alice_group
+        .export_ratchet_tree()
+        .tls_serialize_detached()
+        .expect("An unexpected error occurred.");
+    let public_group_state = alice_group.public_group_state();
+
+    // A valid tree is accepted.
+    MlsGroup::verify_ratchet_tree(
+        backend,
+        ciphersuite,
+        tree_encoded.as_slice(),
+        public_group_state.tree_hash(),
+    )
+    .expect("Error verifying ratchet tree.");
+
+    // The tree hash has to match.
+    assert_eq!(
+        MlsGroup::verify_ratchet_tree(
+            backend,
+            ciphersuite,
+            tree_encoded.as_slice(),
+            &vec![0u8; public_group_state.tree_hash().len()],
+        )
+        .expect_err("Verified a tree with the wrong tree hash."),
+        VerifyRatchetTreeError::PublicTreeError(PublicTreeError::TreeHashMismatch)
+    );
+
+    // A truncated tree is rejected.
+    assert_eq!(
+        MlsGroup::verify_ratchet_tree(
+            backend,
+            ciphersuite,
+            &tree_encoded[..tree_encoded.len() - 1],
+            public_group_state.tree_hash(),
+        )
+        .expect_err("Verified a truncated tree."),
+        VerifyRatchetTreeError::PublicTreeError(PublicTreeError::MalformedTree)
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_public_group(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let alice_credential = generate_credential_bundle(
+        backend,
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let eve_credential = generate_credential_bundle(
+        backend,
+        "Eve".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    )
+    .expect("An unexpected error occurred.");
+    let alice_key_package =
+        generate_key_package_bundle(backend, &[ciphersuite], &alice_credential, vec![])
+            .expect("An unexpected error occurred.");
+
+    // === Alice creates a group that sends its commits as plaintexts ===
+    let mls_group_config = MlsGroupConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .build();
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+
+    let export_group_info = |group: &MlsGroup| {
+        group
+            .export_group_info(backend, false)
+            .expect("Error exporting group info")
+            .into_verifiable_group_info()
+    };
+
+    // The group info has to be verified against the key of the signer.
+    let err = PublicGroup::from_group_info(
+        backend,
+        export_group_info(&alice_group),
+        alice_group.export_ratchet_tree(),
+        &eve_credential,
+    )
+    .expect_err("Group info verified against the wrong credential.");
+    assert_eq!(err, PublicGroupError::InvalidGroupInfoSignature);
+
+    let observer = PublicGroup::from_group_info(
+        backend,
+        export_group_info(&alice_group),
+        alice_group.export_ratchet_tree(),
+        &alice_credential,
+    )
+    .expect("Error creating public group");
+    assert_eq!(
+        observer.public_group_state(),
+        alice_group.public_group_state()
+    );
+    assert_eq!(
+        observer.export_ratchet_tree(),
+        alice_group.export_ratchet_tree()
+    );
+
+    // The observer validates a commit by Alice.
+    let group_info = export_group_info(&alice_group);
+    let (commit, _welcome) = alice_group
+        .self_update(backend, None)
+        .expect("Error creating commit");
+    let commit = MlsMessageIn::from(commit);
+    observer
+        .validate_commit(backend, &commit)
+        .expect("Error validating commit");
+
+    // A commit with an invalid signature is rejected.
+    let mut forged_commit = commit
+        .into_plaintext()
+        .expect("The commit is not a plaintext.");
+    forged_commit.invalidate_signature();
+    assert_eq!(
+        observer.validate_commit(backend, &MlsMessageIn::from(forged_commit)),
+        Err(ValidationError::InvalidSignature)
+    );
+
+    // The tree of the next epoch doesn't match the group info anymore.
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let err = PublicGroup::from_group_info(
+        backend,
+        group_info,
+        alice_group.export_ratchet_tree(),
+        &alice_credential,
+    )
+    .expect_err("Tree hash of the wrong tree matched.");
+    assert_eq!(err, PublicGroupError::TreeHashMismatch);
+
+    // Commits of the next epoch are rejected by the observer of the previous
+    // epoch.
+    let (commit, _welcome) = alice_group
+        .self_update(backend, None)
+        .expect("Error creating commit");
+    assert_eq!(
+        observer.validate_commit(backend, &MlsMessageIn::from(commit)),
+        Err(ValidationError::WrongEpoch)
+    );
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_allowed_proposal_types(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob", "Charlie"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let charlie_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .use_ratchet_tree_extension(true)
+        .build();
+    // Bob doesn't accept commits that remove members.
+    let bob_config = MlsGroupConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .use_ratchet_tree_extension(true)
+        .allowed_proposal_types([ProposalType::Add, ProposalType::Update].into())
+        .build();
+
+    // === Alice creates a group and adds Bob and Charlie ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package, charlie_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &bob_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // === Alice removes Charlie ===
+    let (commit, _welcome) = alice_group
+        .remove_members(backend, &[2])
+        .expect("An unexpected error occurred.");
+
+    assert_eq!(
+        bob_group
+            .process_message(backend, commit.clone().into())
+            .expect_err("Processed a commit with a disallowed proposal type."),
+        ProcessMessageError::InvalidCommit(StageCommitError::ProposalValidationError(
+            ProposalValidationError::ProposalTypeNotAllowed
+        ))
+    );
+
+    // Once Bob allows removals, the same commit is accepted.
+    bob_group.set_configuration(
+        &MlsGroupConfig::builder()
+            .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+            .use_ratchet_tree_extension(true)
+            .allowed_proposal_types(
+                [
+                    ProposalType::Add,
+                    ProposalType::Update,
+                    ProposalType::Remove,
+                ]
+                .into(),
+            )
+            .build(),
+    );
+    bob_group
+        .process_message(backend, commit.into())
+        .expect("Error processing commit.");
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_verify_proposal_signatures_in_commit(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob", "Charlie"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let charlie_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let eve_credential_bundle = CredentialBundle::new(
+        "Eve".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .use_ratchet_tree_extension(true)
+        .verify_proposal_signatures_in_commit(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // === Bob commits a proposal by reference that Eve signed in his name ===
+    // The proposal bypasses the signature check when it is received, e.g.
+    // because of a bug in the delivery path.
+    let forged_proposal = bob_group
+        .group
+        .create_add_proposal(
+            bob_group.framing_parameters(),
+            &eve_credential_bundle,
+            charlie_key_package,
+            backend,
+        )
+        .expect("Could not create proposal.");
+    for group in [&mut alice_group, &mut bob_group] {
+        group.proposal_store.add(
+            QueuedProposal::from_mls_plaintext(ciphersuite, backend, forged_proposal.clone())
+                .expect("Could not create QueuedProposal."),
+        );
+    }
+    let (commit, _welcome) = bob_group
+        .commit_to_pending_proposals(backend)
+        .expect("Error creating commit.");
+
+    assert_eq!(
+        alice_group
+            .process_message(backend, commit.clone().into())
+            .expect_err("Processed a commit with an invalid proposal signature."),
+        ProcessMessageError::InvalidCommit(StageCommitError::ValidationError(
+            ValidationError::InvalidSignature
+        ))
+    );
+
+    // Without the strict mode, the signature is not checked again.
+    alice_group.set_configuration(
+        &MlsGroupConfig::builder()
+            .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+            .use_ratchet_tree_extension(true)
+            .build(),
+    );
+    alice_group
+        .process_message(backend, commit.into())
+        .expect("Error processing commit.");
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_handshake_wire_format(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Generate credential bundles and KeyPackages
+    let mut key_packages = Vec::new();
+    for identity in ["Alice", "Bob"] {
+        let credential = generate_credential_bundle(
+            backend,
+            identity.into(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+        )
+        .expect("An unexpected error occurred.");
+        key_packages.push(
+            generate_key_package_bundle(backend, &[ciphersuite], &credential, vec![])
+                .expect("An unexpected error occurred."),
+        );
+    }
+    let bob_key_package = key_packages.pop().expect("Missing KeyPackage.");
+    let alice_key_package = key_packages.pop().expect("Missing KeyPackage.");
+
+    let mls_group_config = MlsGroupConfig::builder()
+        .wire_format_policy(MIXED_CIPHERTEXT_WIRE_FORMAT_POLICY)
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(
+        backend,
+        &mls_group_config,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let mut bob_group = MlsGroup::new_from_welcome(backend, &mls_group_config, welcome, None)
+        .expect("Error creating group from Welcome");
+
+    // The core groups follow the outgoing wire format policy, even if the
+    // framing parameters ask for plaintext.
+    let plaintext_framing = FramingParameters::new(&[], WireFormat::MlsPlaintext);
+    for group in [&alice_group, &bob_group] {
+        assert_eq!(
+            group
+                .group
+                .handshake_framing_parameters(plaintext_framing)
+                .wire_format(),
+            WireFormat::MlsCiphertext
+        );
+    }
+
+    // Handshake messages are encrypted and can be processed.
+    let (commit, _welcome) = alice_group
+        .self_update(backend, None)
+        .expect("Error creating commit.");
+    assert_eq!(commit.wire_format(), WireFormat::MlsCiphertext);
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let processed_message = bob_group
+        .process_message(backend, commit.into())
+        .expect("Error processing commit.");
+    match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+            bob_group.merge_staged_commit(*staged_commit)
+        }
+        _ => panic!("Expected a commit."),
+    }
+
+    // Switching the policy switches the wire format of the core group.
+    alice_group.set_configuration(
+        &MlsGroupConfig::builder()
+            .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
+            .use_ratchet_tree_extension(true)
+            .build(),
+    );
+    assert_eq!(
+        alice_group
+            .group
+            .handshake_framing_parameters(plaintext_framing)
+            .wire_format(),
+        WireFormat::MlsPlaintext
+    );
+    let (commit, _welcome) = alice_group
+        .self_update(backend, None)
+        .expect("Error creating commit.");
+    assert_eq!(commit.wire_format(), WireFormat::MlsPlaintext);
+    bob_group
+        .process_message(backend, commit.into())
+        .expect("Error processing commit.");
+}
@@ -2,7 +2,10 @@ use core_group::create_commit_params::CreateCommitParams;
 use openmls_traits::types::HpkeKeyPair;
 use tls_codec::Serialize;
 
-use crate::versions::ProtocolVersion;
+use crate::{
+    extensions::Extension, group::errors::CreateGroupContextExtProposalError,
+    schedule::psk::PreSharedKeyId, versions::ProtocolVersion,
+};
 
 use super::*;
 
@@ -90,6 +93,51 @@ impl MlsGroup {
         Ok((mls_message, create_commit_result.welcome_option))
     }
 
+    /// Rekeys the group by committing a fresh path without any proposals.
+    ///
+    /// This leaves the membership of the group unchanged, but rotates the own
+    /// leaf's keys and injects fresh entropy into the key schedule. Pending
+    /// proposals are not covered by the commit. The commit is encrypted if the
+    /// group is configured to encrypt handshake messages.
+    ///
+    /// Returns an error if there is a pending commit.
+    pub fn rekey(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<MlsMessageOut, SelfUpdateError> {
+        self.is_operational()?;
+
+        let credential = self.credential()?;
+        let credential_bundle: CredentialBundle = backend
+            .key_store()
+            .read(
+                &credential
+                    .signature_key()
+                    .tls_serialize_detached()
+                    .map_err(LibraryError::missing_bound_check)?,
+            )
+            .ok_or(SelfUpdateError::NoMatchingCredentialBundle)?;
+
+        let create_commit_result =
+            self.group
+                .rekey(self.framing_parameters(), &credential_bundle, backend)?;
+
+        // Convert MlsPlaintext messages to MLSMessage and encrypt them if required by
+        // the configuration
+        let mls_message = self.plaintext_to_mls_message(create_commit_result.commit, backend)?;
+
+        // Set the current group state to [`MlsGroupState::PendingCommit`],
+        // storing the current [`StagedCommit`] from the commit results
+        self.group_state = MlsGroupState::PendingCommit(Box::new(PendingCommitState::Member(
+            create_commit_result.staged_commit,
+        )));
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+
+        Ok(mls_message)
+    }
+
     /// Creates a proposal to update the own leaf node.
     pub fn propose_self_update(
         &mut self,
@@ -173,4 +221,154 @@ impl MlsGroup {
 
         Ok(mls_message)
     }
+
+    /// Creates a proposal to replace the group context extensions with
+    /// `extensions`, e.g. to add a required capability.
+    ///
+    /// Returns an error if there is a pending commit or if a required
+    /// capability isn't supported by all members of the group.
+    pub fn propose_group_context_extensions(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        extensions: &[Extension],
+    ) -> Result<MlsMessageOut, ProposeGroupContextExtensionsError> {
+        self.is_operational()?;
+
+        let credential = self.credential()?;
+        let credential_bundle: CredentialBundle = backend
+            .key_store()
+            .read(
+                &credential
+                    .signature_key()
+                    .tls_serialize_detached()
+                    .map_err(LibraryError::missing_bound_check)?,
+            )
+            .ok_or(ProposeGroupContextExtensionsError::NoMatchingCredentialBundle)?;
+
+        let gce_proposal = self
+            .group
+            .create_group_context_ext_proposal(
+                self.framing_parameters(),
+                &credential_bundle,
+                extensions,
+                backend,
+            )
+            .map_err(|e| match e {
+                CreateGroupContextExtProposalError::LibraryError(e) => e.into(),
+                _ => ProposeGroupContextExtensionsError::UnsupportedCapabilities,
+            })?;
+
+        self.proposal_store.add(QueuedProposal::from_mls_plaintext(
+            self.ciphersuite(),
+            backend,
+            gce_proposal.clone(),
+        )?);
+
+        let mls_message = self.plaintext_to_mls_message(gce_proposal, backend)?;
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+
+        Ok(mls_message)
+    }
+
+    /// Creates a proposal to inject the PSK referenced by `psk_id` into the
+    /// key schedule of the next epoch. The PSK has to be stored in the key
+    /// store of every member (see [`PreSharedKeyId::write_to_key_store()`]),
+    /// otherwise the commit covering the proposal can't be created or
+    /// processed.
+    ///
+    /// Returns an error if there is a pending commit.
+    // TODO #751: The PSK types are not part of the prelude yet.
+    pub fn propose_psk(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        psk_id: PreSharedKeyId,
+    ) -> Result<MlsMessageOut, ProposePskError> {
+        self.is_operational()?;
+
+        let credential = self.credential()?;
+        let credential_bundle: CredentialBundle = backend
+            .key_store()
+            .read(
+                &credential
+                    .signature_key()
+                    .tls_serialize_detached()
+                    .map_err(LibraryError::missing_bound_check)?,
+            )
+            .ok_or(ProposePskError::NoMatchingCredentialBundle)?;
+
+        let psk_proposal = self.group.create_presharedkey_proposal(
+            self.framing_parameters(),
+            &credential_bundle,
+            psk_id,
+            backend,
+        )?;
+
+        self.proposal_store.add(QueuedProposal::from_mls_plaintext(
+            self.ciphersuite(),
+            backend,
+            psk_proposal.clone(),
+        )?);
+
+        let mls_message = self.plaintext_to_mls_message(psk_proposal, backend)?;
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+
+        Ok(mls_message)
+    }
+
+    /// Creates a proposal to re-initialize the group with the given group ID,
+    /// protocol version, ciphersuite and extensions, e.g. to migrate the
+    /// group to another ciphersuite. The commit covering the proposal must
+    /// not cover other proposals. Once it is merged, the group is terminated
+    /// (see [`Self::is_terminated()`]).
+    ///
+    /// Returns an error if there is a pending commit or if the group was
+    /// terminated.
+    pub fn propose_reinit(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        group_id: GroupId,
+        version: ProtocolVersion,
+        ciphersuite: Ciphersuite,
+        extensions: Vec<Extension>,
+    ) -> Result<MlsMessageOut, ProposeReInitError> {
+        self.is_operational()?;
+
+        let credential = self.credential()?;
+        let credential_bundle: CredentialBundle = backend
+            .key_store()
+            .read(
+                &credential
+                    .signature_key()
+                    .tls_serialize_detached()
+                    .map_err(LibraryError::missing_bound_check)?,
+            )
+            .ok_or(ProposeReInitError::NoMatchingCredentialBundle)?;
+
+        let reinit_proposal = self.group.create_reinit_proposal(
+            self.framing_parameters(),
+            &credential_bundle,
+            group_id,
+            version,
+            ciphersuite,
+            extensions,
+            backend,
+        )?;
+
+        self.proposal_store.add(QueuedProposal::from_mls_plaintext(
+            self.ciphersuite(),
+            backend,
+            reinit_proposal.clone(),
+        )?);
+
+        let mls_message = self.plaintext_to_mls_message(reinit_proposal, backend)?;
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+
+        Ok(mls_message)
+    }
 }
@@ -3,6 +3,8 @@
 //! This module contains the API to interact with groups.
 
 mod group_context;
+mod public_group;
+mod public_group_state;
 
 #[cfg(any(feature = "test-utils", test))]
 use crate::ciphersuite::*;
@@ -23,12 +25,21 @@ pub(crate) use group_context::*;
 // Public
 pub mod errors;
 
+pub use core_group::epoch_archive::EpochArchive;
+#[cfg(any(feature = "test-utils", test))]
+pub use core_group::past_secrets::{SenderRatchetReport, StoreDebugReport};
 pub use core_group::proposals::*;
-pub use core_group::staged_commit::StagedCommit;
+pub use core_group::staged_commit::{MembershipDelta, StagedCommit};
+pub use core_group::GroupInfoExportOptions;
+#[cfg(feature = "crypto-subtle")]
+pub use core_group::OwnLeafSecrets;
 pub use mls_group::config::*;
 pub use mls_group::membership::*;
 pub use mls_group::processing::*;
+pub use mls_group::registry::GroupRegistry;
 pub use mls_group::*;
+pub use public_group::PublicGroup;
+pub use public_group_state::{ForkStatus, PublicGroupState};
 
 // Tests
 #[cfg(any(feature = "test-utils", test))]
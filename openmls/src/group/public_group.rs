@@ -0,0 +1,171 @@
+//! # Public groups
+//!
+//! A [`PublicGroup`] is the view of a group that is available to an observer
+//! that is not a member of the group, e.g. a delivery service or a monitoring
+//! service. It only holds the public state of the group, i.e. the ratchet tree
+//! and the group context, and can thus not decrypt messages or verify
+//! membership tags.
+
+use crate::{
+    ciphersuite::signable::Verifiable,
+    credentials::Credential,
+    error::LibraryError,
+    framing::*,
+    group::errors::{PublicGroupError, ValidationError},
+    messages::{GroupInfo, VerifiableGroupInfo},
+    treesync::{errors::TreeSyncFromNodesError, node::Node, TreeSync},
+    versions::ProtocolVersion,
+};
+use openmls_traits::types::Ciphersuite;
+
+use super::*;
+
+/// The public state of a group as seen by an observer that is not a member of
+/// the group.
+///
+/// The observer is created from a [`VerifiableGroupInfo`] and the ratchet tree
+/// of the group and can validate the public commits that are sent in the
+/// epoch of the group info.
+#[derive(Debug)]
+pub struct PublicGroup {
+    group_context: GroupContext,
+    treesync: TreeSync,
+}
+
+impl PublicGroup {
+    /// Create a [`PublicGroup`] from a [`VerifiableGroupInfo`] and the
+    /// corresponding ratchet tree. The signature on the group info is verified
+    /// against the given `signer` credential and the tree hash of the given
+    /// `ratchet_tree` has to match the one in the group context.
+    pub fn from_group_info(
+        backend: &impl OpenMlsCryptoProvider,
+        verifiable_group_info: VerifiableGroupInfo,
+        ratchet_tree: Vec<Option<Node>>,
+        signer: &Credential,
+    ) -> Result<Self, PublicGroupError> {
+        let ciphersuite = verifiable_group_info.ciphersuite();
+
+        let group_info: GroupInfo = verifiable_group_info
+            .verify(backend, signer)
+            .map_err(|_| PublicGroupError::InvalidGroupInfoSignature)?;
+
+        if group_info.group_context().protocol_version() != ProtocolVersion::Mls10 {
+            return Err(PublicGroupError::UnsupportedMlsVersion);
+        }
+
+        let treesync = TreeSync::from_nodes_without_leaf(backend, ciphersuite, ratchet_tree)
+            .map_err(|e| match e {
+                TreeSyncFromNodesError::LibraryError(e) => e.into(),
+                TreeSyncFromNodesError::PublicTreeError(e) => PublicGroupError::PublicTreeError(e),
+            })?;
+
+        if treesync.tree_hash() != group_info.group_context().tree_hash() {
+            return Err(PublicGroupError::TreeHashMismatch);
+        }
+
+        Ok(Self {
+            group_context: group_info.group_context().clone(),
+            treesync,
+        })
+    }
+
+    /// Validate a commit that was sent as an `MlsPlaintext` in the current
+    /// epoch of the group. Since an observer doesn't have access to the
+    /// membership key, the membership tag is not checked. Commits sent as an
+    /// `MlsCiphertext` can't be validated and are rejected with
+    /// [`ValidationError::WrongWireFormat`].
+    ///
+    /// Checks the following semantic validation:
+    ///  - ValSem002
+    ///  - ValSem003
+    ///  - ValSem004
+    ///  - ValSem009
+    ///  - ValSem010
+    pub fn validate_commit(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        message: &MlsMessageIn,
+    ) -> Result<(), ValidationError> {
+        let commit = match &message.mls_message.body {
+            MlsMessageBody::Plaintext(plaintext) => plaintext,
+            MlsMessageBody::Ciphertext(_) => return Err(ValidationError::WrongWireFormat),
+        };
+
+        // ValSem002
+        if commit.group_id() != self.group_context.group_id() {
+            return Err(ValidationError::WrongGroupId);
+        }
+
+        // ValSem003
+        if commit.epoch() != self.group_context.epoch() {
+            return Err(ValidationError::WrongEpoch);
+        }
+
+        let serialized_context = self
+            .group_context
+            .tls_serialize_detached()
+            .map_err(LibraryError::missing_bound_check)?;
+        let plaintext =
+            VerifiableMlsAuthContent::from_plaintext(commit.clone(), serialized_context);
+
+        let commit_content = match plaintext.content() {
+            MlsContentBody::Commit(commit) => commit,
+            _ => return Err(ValidationError::NotACommit),
+        };
+
+        // ValSem009
+        if plaintext.confirmation_tag().is_none() {
+            return Err(ValidationError::MissingConfirmationTag);
+        }
+
+        // ValSem004
+        let credential = match plaintext.sender() {
+            Sender::Member(leaf_index) => self
+                .treesync
+                .leaf(*leaf_index)
+                .map_err(|_| ValidationError::UnknownMember)?
+                .ok_or(ValidationError::UnknownMember)?
+                .credential(),
+            Sender::NewMemberCommit => commit_content
+                .path
+                .as_ref()
+                .ok_or(ValidationError::NoPath)?
+                .leaf_node()
+                .credential(),
+            Sender::External(_) | Sender::NewMemberProposal => {
+                return Err(ValidationError::UnknownMember)
+            }
+        };
+
+        // ValSem010
+        plaintext
+            .verify_no_out(backend, credential)
+            .map_err(|_| ValidationError::InvalidSignature)
+    }
+
+    /// Returns the ciphersuite of the group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.group_context.ciphersuite()
+    }
+
+    /// Returns the group ID of the group.
+    pub fn group_id(&self) -> &GroupId {
+        self.group_context.group_id()
+    }
+
+    /// Returns the epoch of the group.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.group_context.epoch()
+    }
+
+    /// Returns a snapshot of the public state of the group, e.g. to compare
+    /// it with the state of a member.
+    pub fn public_group_state(&self) -> PublicGroupState {
+        PublicGroupState::from_group_context(&self.group_context)
+    }
+
+    /// Exports the ratchet tree of the group.
+    pub fn export_ratchet_tree(&self) -> Vec<Option<Node>> {
+        self.treesync.export_nodes()
+    }
+}
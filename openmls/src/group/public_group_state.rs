@@ -0,0 +1,115 @@
+//! # Public group state
+//!
+//! A [`PublicGroupState`] is a snapshot of the public parts of the
+//! `GroupContext` of a group in a given epoch. Comparing the snapshots of two
+//! members yields a [`ForkStatus`].
+
+use crate::ciphersuite::equal_ct;
+
+use super::*;
+
+/// The result of comparing the [`PublicGroupState`]s of two members of a
+/// group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkStatus {
+    /// Both members are in the same epoch and agree on the tree hash and the
+    /// confirmed transcript hash.
+    Consistent,
+    /// Both members are in the same epoch, but disagree on the tree hash or
+    /// the confirmed transcript hash, i.e. their views of the group diverged.
+    Forked,
+    /// The members are in different epochs and can't be compared.
+    DifferentEpoch,
+    /// The snapshots belong to different groups and can't be compared.
+    DifferentGroup,
+}
+
+/// A snapshot of the public state of a group in a given epoch.
+///
+/// It contains the group ID, the epoch, the tree hash, the confirmed transcript
+/// hash and the extensions of the group, but no secrets. Members that are in
+/// the same epoch of the same group produce identical snapshots, so the
+/// snapshots can be handed to an external service that compares them to
+/// detect forks of the group state.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TlsSerialize, TlsDeserialize, TlsSize,
+)]
+pub struct PublicGroupState {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    tree_hash: VLBytes,
+    confirmed_transcript_hash: VLBytes,
+    extensions: Vec<Extension>,
+}
+
+impl PublicGroupState {
+    /// Create a snapshot of the public parts of the given [`GroupContext`].
+    pub(crate) fn from_group_context(group_context: &GroupContext) -> Self {
+        Self {
+            group_id: group_context.group_id().clone(),
+            epoch: group_context.epoch(),
+            tree_hash: group_context.tree_hash().into(),
+            confirmed_transcript_hash: group_context.confirmed_transcript_hash().into(),
+            extensions: group_context.extensions().to_vec(),
+        }
+    }
+
+    /// Returns the group ID.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns the tree hash.
+    pub fn tree_hash(&self) -> &[u8] {
+        self.tree_hash.as_slice()
+    }
+
+    /// Returns the confirmed transcript hash.
+    pub fn confirmed_transcript_hash(&self) -> &[u8] {
+        self.confirmed_transcript_hash.as_slice()
+    }
+
+    /// Returns the extensions of the group.
+    pub fn extensions(&self) -> &[Extension] {
+        self.extensions.as_slice()
+    }
+
+    /// Compares this snapshot with the snapshot `other` of another member and
+    /// returns whether the two members have forked.
+    ///
+    /// The group ID and the epoch are compared first. If they match, the tree
+    /// hashes and the confirmed transcript hashes are compared in constant
+    /// time.
+    pub fn fork_status(&self, other: &PublicGroupState) -> ForkStatus {
+        if self.group_id != other.group_id {
+            return ForkStatus::DifferentGroup;
+        }
+        if self.epoch != other.epoch {
+            return ForkStatus::DifferentEpoch;
+        }
+        // The lengths only depend on the ciphersuite and can be compared
+        // before the actual comparison.
+        if self.tree_hash.as_slice().len() != other.tree_hash.as_slice().len()
+            || self.confirmed_transcript_hash.as_slice().len()
+                != other.confirmed_transcript_hash.as_slice().len()
+        {
+            return ForkStatus::Forked;
+        }
+        // Evaluate both comparisons to not leak which of the hashes differs.
+        let tree_hash_equal = equal_ct(self.tree_hash.as_slice(), other.tree_hash.as_slice());
+        let transcript_hash_equal = equal_ct(
+            self.confirmed_transcript_hash.as_slice(),
+            other.confirmed_transcript_hash.as_slice(),
+        );
+        if tree_hash_equal & transcript_hash_equal {
+            ForkStatus::Consistent
+        } else {
+            ForkStatus::Forked
+        }
+    }
+}
@@ -5,6 +5,7 @@ use tls_codec::Serialize;
 
 use crate::{
     credentials::*,
+    extensions::{Extension, ExternalSender},
     framing::*,
     group::{errors::*, *},
     messages::{
@@ -342,3 +343,144 @@ fn new_member_proposal_sender_should_be_reserved_for_join_proposals(
     };
     alice_group.clear_pending_proposals();
 }
+
+#[apply(ciphersuites_and_backends)]
+fn external_remove_proposal_should_be_authorized_by_external_senders(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let ProposalValidationTestSetup {
+        mut alice_group,
+        mut bob_group,
+    } = validation_test_setup(PURE_PLAINTEXT_WIRE_FORMAT_POLICY, ciphersuite, backend);
+
+    // The delivery service is allowed to send proposals to the group
+    let ds_cb = get_credential_bundle(
+        "DS".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .unwrap();
+    let external_senders = Extension::ExternalSenders(vec![ExternalSender::new(
+        ds_cb.credential().signature_key().clone(),
+        ds_cb.credential().clone(),
+    )]);
+
+    // Alice adds the delivery service to the external senders of the group
+    let proposal = alice_group
+        .propose_group_context_extensions(backend, &[external_senders])
+        .unwrap();
+    match bob_group
+        .process_message(backend, proposal.into())
+        .unwrap()
+        .into_content()
+    {
+        ProcessedMessageContent::ProposalMessage(proposal) => {
+            bob_group.store_pending_proposal(*proposal)
+        }
+        _ => unreachable!(),
+    }
+    let (commit, _welcome) = alice_group.commit_to_pending_proposals(backend).unwrap();
+    alice_group.merge_pending_commit().unwrap();
+    match bob_group
+        .process_message(backend, commit.into())
+        .unwrap()
+        .into_content()
+    {
+        ProcessedMessageContent::StagedCommitMessage(commit) => {
+            bob_group.merge_staged_commit(*commit)
+        }
+        _ => unreachable!(),
+    }
+
+    // A remove proposal from a sender that isn't listed is rejected
+    let unauthorized_proposal = ExternalProposal::new_remove(
+        1,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_cb,
+        1,
+        backend,
+    )
+    .unwrap();
+    assert_eq!(
+        alice_group
+            .process_message(backend, unauthorized_proposal.into())
+            .unwrap_err(),
+        ProcessMessageError::ValidationError(ValidationError::UnauthorizedExternalSender)
+    );
+
+    // A remove proposal that isn't signed by the listed key is rejected
+    let attacker_cb = get_credential_bundle(
+        "Attacker".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .unwrap();
+    let forged_proposal = ExternalProposal::new_remove(
+        1,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &attacker_cb,
+        0,
+        backend,
+    )
+    .unwrap();
+    assert_eq!(
+        alice_group
+            .process_message(backend, forged_proposal.into())
+            .unwrap_err(),
+        ProcessMessageError::InvalidSignature
+    );
+
+    // The delivery service asks to remove Bob
+    let remove_proposal = ExternalProposal::new_remove(
+        1,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_cb,
+        0,
+        backend,
+    )
+    .unwrap();
+
+    let msg = alice_group
+        .process_message(backend, remove_proposal.clone().into())
+        .unwrap();
+    assert_eq!(msg.credential(), Some(ds_cb.credential()));
+    match msg.into_content() {
+        ProcessedMessageContent::ProposalMessage(proposal) => {
+            assert_eq!(proposal.sender(), &Sender::External(0));
+            assert!(matches!(
+                proposal.proposal(),
+                Proposal::Remove(remove_proposal) if remove_proposal.removed() == 1
+            ));
+            alice_group.store_pending_proposal(*proposal)
+        }
+        _ => unreachable!(),
+    }
+
+    let msg = bob_group
+        .process_message(backend, remove_proposal.into())
+        .unwrap();
+    match msg.into_content() {
+        ProcessedMessageContent::ProposalMessage(proposal) => {
+            bob_group.store_pending_proposal(*proposal)
+        }
+        _ => unreachable!(),
+    }
+
+    // and Alice will commit it
+    let (commit, _welcome) = alice_group.commit_to_pending_proposals(backend).unwrap();
+    alice_group.merge_pending_commit().unwrap();
+    assert_eq!(alice_group.members().count(), 1);
+
+    // Bob will also process the commit that removes him
+    let msg = bob_group.process_message(backend, commit.into()).unwrap();
+    assert!(matches!(
+        msg.into_content(),
+        ProcessedMessageContent::StagedCommitMessage(_)
+    ));
+}
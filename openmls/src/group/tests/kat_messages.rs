@@ -210,7 +210,11 @@ pub fn generate_test_vector(ciphersuite: Ciphersuite) -> MessagesTestVector {
     let create_commit_result = group
         .create_commit(params, &crypto)
         .expect("An unexpected error occurred.");
-    group.merge_staged_commit(create_commit_result.staged_commit, &mut proposal_store);
+    group.merge_staged_commit(
+        create_commit_result.staged_commit,
+        &mut proposal_store,
+        None,
+    );
     let commit = if let MlsContentBody::Commit(commit) = create_commit_result.commit.content() {
         commit.clone()
     } else {
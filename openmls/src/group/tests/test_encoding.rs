@@ -1,5 +1,16 @@
 use super::utils::*;
-use crate::{framing::*, group::*, key_packages::*, messages::*, test_utils::*, *};
+use crate::{
+    framing::*,
+    group::*,
+    key_packages::*,
+    messages::*,
+    test_utils::*,
+    treesync::{
+        errors::{PublicTreeError, TreeSyncFromNodesError},
+        TreeSync,
+    },
+    *,
+};
 use openmls_rust_crypto::OpenMlsRustCrypto;
 use openmls_traits::crypto::OpenMlsCrypto;
 use tls_codec::{Deserialize, Serialize};
@@ -30,6 +41,7 @@ fn create_encoding_test_setup(backend: &impl OpenMlsCryptoProvider) -> TestSetup
             ciphersuite,
             config: CoreGroupConfig {
                 add_ratchet_tree_extension: true,
+                ..CoreGroupConfig::default()
             },
             members: vec![alice_config.clone(), bob_config.clone()],
         };
@@ -428,3 +440,54 @@ fn test_welcome_message_encoding(backend: &impl OpenMlsCryptoProvider) {
         .is_ok());
     }
 }
+
+/// This test makes sure the streaming import verifies encoded ratchet trees.
+#[apply(backends)]
+fn test_ratchet_tree_streaming_import(backend: &impl OpenMlsCryptoProvider) {
+    let test_setup = create_encoding_test_setup(backend);
+    let test_clients = test_setup.clients.borrow();
+    let alice = test_clients
+        .get("alice")
+        .expect("An unexpected error occurred.")
+        .borrow();
+
+    for group_state in alice.group_states.borrow().values() {
+        let ciphersuite = group_state.ciphersuite();
+        let tree_encoded = group_state
+            .treesync()
+            .export_nodes()
+            .tls_serialize_detached()
+            .expect("An unexpected error occurred.");
+
+        // A valid tree is accepted.
+        TreeSync::verify_streaming(
+            tree_encoded.as_slice(),
+            ciphersuite,
+            group_state.tree_hash(),
+            backend,
+        )
+        .expect("Error verifying ratchet tree.");
+
+        // The tree hash has to match.
+        let err =
+            TreeSync::verify_streaming(tree_encoded.as_slice(), ciphersuite, &[0u8; 32], backend)
+                .expect_err("Verified a tree with the wrong tree hash.");
+        assert_eq!(
+            err,
+            TreeSyncFromNodesError::PublicTreeError(PublicTreeError::TreeHashMismatch)
+        );
+
+        // A truncated tree is rejected.
+        let err = TreeSync::verify_streaming(
+            &tree_encoded[..tree_encoded.len() - 1],
+            ciphersuite,
+            group_state.tree_hash(),
+            backend,
+        )
+        .expect_err("Verified a truncated tree.");
+        assert_eq!(
+            err,
+            TreeSyncFromNodesError::PublicTreeError(PublicTreeError::MalformedTree)
+        );
+    }
+}
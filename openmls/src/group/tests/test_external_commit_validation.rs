@@ -12,6 +12,7 @@ use rstest_reuse::{self, *};
 use crate::{
     ciphersuite::{hash_ref::ProposalRef, signable::Verifiable},
     credentials::{errors::*, *},
+    extensions::{ApplicationIdExtension, Extension},
     framing::*,
     group::{errors::*, tests::utils::resign_external_commit, *},
     messages::proposals::*,
@@ -98,6 +99,7 @@ fn validation_test_setup(
         backend,
         Some(&tree_option),
         verifiable_group_info,
+        None,
         alice_group.configuration(),
         &[],
         &bob_credential_bundle,
@@ -333,6 +335,7 @@ fn test_valsem242(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider
             backend,
             None,
             verifiable_group_info,
+            None,
             alice_group.configuration(),
             &[],
             &bob_credential_bundle,
@@ -429,6 +432,7 @@ fn test_valsem243(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider
         backend,
         Some(&tree_option),
         verifiable_group_info.clone(),
+        None,
         alice_group.configuration(),
         &[],
         &bob_credential_bundle,
@@ -515,6 +519,7 @@ fn test_valsem243(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider
         backend,
         Some(&tree_option),
         verifiable_group_info,
+        None,
         alice_group.configuration(),
         &[],
         &alice_credential_bundle,
@@ -527,6 +532,67 @@ fn test_valsem243(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider
         .expect("Unexpected error.");
 }
 
+// ValSem243: Leaf nodes don't carry an endpoint_id, so only the identity of the
+// removed leaf is compared. In particular, a client that rejoins doesn't have
+// to keep the application ID of its removed leaf.
+#[apply(ciphersuites_and_backends)]
+fn test_valsem243_identity_only(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let ECValidationTestSetup {
+        mut alice_group,
+        bob_credential_bundle,
+        plaintext: _,
+        original_plaintext: _,
+    } = validation_test_setup(PURE_PLAINTEXT_WIRE_FORMAT_POLICY, ciphersuite, backend);
+
+    // Alice adds Bob with a leaf that carries an application ID.
+    let bob_key_package = generate_key_package_bundle(
+        &[ciphersuite],
+        bob_credential_bundle.credential(),
+        vec![Extension::ApplicationId(ApplicationIdExtension::new(
+            b"Bob's phone",
+        ))],
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_message, _welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("Could not add member.");
+
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    // Bob rejoins externally and removes the former leaf. The new leaf has
+    // the same identity, but no application ID.
+    let verifiable_group_info = alice_group
+        .export_group_info(backend, false)
+        .expect("An unexpected error occurred.")
+        .into_verifiable_group_info();
+    let tree_option = alice_group.export_ratchet_tree();
+
+    let (_bob_group, message) = MlsGroup::join_by_external_commit(
+        backend,
+        Some(&tree_option),
+        verifiable_group_info,
+        None,
+        alice_group.configuration(),
+        &[],
+        &bob_credential_bundle,
+    )
+    .expect("Error initializing group externally.");
+
+    let serialized_message = message
+        .tls_serialize_detached()
+        .expect("Could not serialize message.");
+    let message_in = MlsMessageIn::tls_deserialize(&mut serialized_message.as_slice())
+        .expect("Could not deserialize message.");
+
+    alice_group
+        .process_message(backend, message_in)
+        .expect("Could not process message with a different application ID.");
+}
+
 // ValSem244: External Commit must not include any proposals by reference
 #[apply(ciphersuites_and_backends)]
 fn test_valsem244(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
@@ -775,6 +841,7 @@ fn test_pure_ciphertest(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoPr
         backend,
         None,
         verifiable_group_info,
+        None,
         alice_group.configuration(),
         &[],
         &bob_credential_bundle,
@@ -9,123 +9,132 @@ use rstest_reuse::{self, *};
 
 use crate::{
     credentials::{CredentialBundle, CredentialType},
-    framing::{MessageDecryptionError, ProcessedMessageContent},
+    framing::{MessageDecryptionError, ProcessedMessageContent, SecretTreeError, Sender},
     group::{errors::*, *},
-    key_packages::KeyPackageBundle,
+    key_packages::{KeyPackage, KeyPackageBundle},
 };
 
-#[apply(ciphersuites_and_backends)]
-fn test_past_secrets_in_group(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
-    // Test this for different parameters
-    for max_epochs in (0..10usize).step_by(2) {
-        let group_id = GroupId::from_slice(b"Test Group");
+/// Creates a group with Alice and Bob using the given configuration.
+fn setup_alice_and_bob(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+    mls_group_config: &MlsGroupConfig,
+) -> (MlsGroup, MlsGroup) {
+    let group_id = GroupId::from_slice(b"Test Group");
 
-        // Generate credential bundles
+    // Generate credential bundles
 
-        let alice_credential_bundle = CredentialBundle::new(
-            "Alice".into(),
-            CredentialType::Basic,
-            ciphersuite.signature_algorithm(),
-            backend,
+    let alice_credential_bundle = CredentialBundle::new(
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let alice_credential = alice_credential_bundle.credential().clone();
+    backend
+        .key_store()
+        .store(
+            &alice_credential
+                .signature_key()
+                .tls_serialize_detached()
+                .expect("Error serializing signature key."),
+            &alice_credential_bundle,
         )
         .expect("An unexpected error occurred.");
-        let alice_credential = alice_credential_bundle.credential().clone();
-        backend
-            .key_store()
-            .store(
-                &alice_credential
-                    .signature_key()
-                    .tls_serialize_detached()
-                    .expect("Error serializing signature key."),
-                &alice_credential_bundle,
-            )
-            .expect("An unexpected error occurred.");
 
-        let bob_credential_bundle = CredentialBundle::new(
-            "Bob".into(),
-            CredentialType::Basic,
-            ciphersuite.signature_algorithm(),
-            backend,
+    let bob_credential_bundle = CredentialBundle::new(
+        "Bob".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let bob_credential = bob_credential_bundle.credential().clone();
+    backend
+        .key_store()
+        .store(
+            &bob_credential
+                .signature_key()
+                .tls_serialize_detached()
+                .expect("Error serializing signature key."),
+            &bob_credential_bundle,
         )
         .expect("An unexpected error occurred.");
-        let bob_credential = bob_credential_bundle.credential().clone();
-        backend
-            .key_store()
-            .store(
-                &bob_credential
-                    .signature_key()
-                    .tls_serialize_detached()
-                    .expect("Error serializing signature key."),
-                &bob_credential_bundle,
-            )
-            .expect("An unexpected error occurred.");
-
-        // Generate KeyPackages
 
-        let alice_key_package_bundle =
-            KeyPackageBundle::new(&[ciphersuite], &alice_credential_bundle, backend, vec![])
-                .expect("An unexpected error occurred.");
-        let alice_key_package = alice_key_package_bundle.key_package().clone();
-        backend
-            .key_store()
-            .store(
-                alice_key_package
-                    .hash_ref(backend.crypto())
-                    .expect("Could not hash KeyPackage.")
-                    .as_slice(),
-                &alice_key_package_bundle,
-            )
-            .expect("An unexpected error occurred.");
+    // Generate KeyPackages
 
-        let bob_key_package_bundle =
-            KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, vec![])
-                .expect("An unexpected error occurred.");
-        let bob_key_package = bob_key_package_bundle.key_package().clone();
-        backend
-            .key_store()
-            .store(
-                bob_key_package
-                    .hash_ref(backend.crypto())
-                    .expect("Could not hash KeyPackage.")
-                    .as_slice(),
-                &bob_key_package_bundle,
-            )
+    let alice_key_package_bundle =
+        KeyPackageBundle::new(&[ciphersuite], &alice_credential_bundle, backend, vec![])
             .expect("An unexpected error occurred.");
-
-        // Define the MlsGroup configuration
-
-        let mls_group_config = MlsGroupConfig::builder()
-            .max_past_epochs(max_epochs / 2)
-            .build();
-
-        // === Alice creates a group ===
-        let mut alice_group = MlsGroup::new_with_group_id(
-            backend,
-            &mls_group_config,
-            group_id,
+    let alice_key_package = alice_key_package_bundle.key_package().clone();
+    backend
+        .key_store()
+        .store(
             alice_key_package
                 .hash_ref(backend.crypto())
                 .expect("Could not hash KeyPackage.")
                 .as_slice(),
+            &alice_key_package_bundle,
         )
         .expect("An unexpected error occurred.");
 
-        // Alice adds Bob
-        let (_message, welcome) = alice_group
-            .add_members(backend, &[bob_key_package])
+    let bob_key_package_bundle =
+        KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, vec![])
             .expect("An unexpected error occurred.");
+    let bob_key_package = bob_key_package_bundle.key_package().clone();
+    backend
+        .key_store()
+        .store(
+            bob_key_package
+                .hash_ref(backend.crypto())
+                .expect("Could not hash KeyPackage.")
+                .as_slice(),
+            &bob_key_package_bundle,
+        )
+        .expect("An unexpected error occurred.");
 
-        alice_group
-            .merge_pending_commit()
-            .expect("error merging pending commit");
+    // === Alice creates a group ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
 
-        let mut bob_group = MlsGroup::new_from_welcome(
-            backend,
-            &mls_group_config,
-            welcome,
-            Some(alice_group.export_ratchet_tree()),
-        )
-        .expect("Error creating group from Welcome");
+    // Alice adds Bob
+    let (_message, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("An unexpected error occurred.");
+
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    let bob_group = MlsGroup::new_from_welcome(
+        backend,
+        mls_group_config,
+        welcome,
+        Some(alice_group.export_ratchet_tree()),
+    )
+    .expect("Error creating group from Welcome");
+
+    (alice_group, bob_group)
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_past_secrets_in_group(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // Test this for different parameters
+    for max_epochs in (0..10usize).step_by(2) {
+        let mls_group_config = MlsGroupConfig::builder()
+            .max_past_epochs(max_epochs / 2)
+            .build();
+        let (mut alice_group, mut bob_group) =
+            setup_alice_and_bob(ciphersuite, backend, &mls_group_config);
 
         // Generate application message for different epochs
 
@@ -176,8 +185,8 @@ fn test_past_secrets_in_group(ciphersuite: Ciphersuite, backend: &impl OpenMlsCr
             assert_eq!(
                 err,
                 ProcessMessageError::ValidationError(ValidationError::UnableToDecrypt(
-                    MessageDecryptionError::AeadError
-                ),)
+                    MessageDecryptionError::SecretTreeError(SecretTreeError::TooDistantInThePast)
+                ))
             );
         }
 
@@ -197,3 +206,386 @@ fn test_past_secrets_in_group(ciphersuite: Ciphersuite, backend: &impl OpenMlsCr
         }
     }
 }
+
+#[apply(ciphersuites_and_backends)]
+fn test_past_secrets_retained_across_commits(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let mls_group_config = MlsGroupConfig::builder().max_past_epochs(2).build();
+    let (mut alice_group, mut bob_group) =
+        setup_alice_and_bob(ciphersuite, backend, &mls_group_config);
+
+    // Alice sends an application message before each of three commits
+    let mut application_messages = Vec::new();
+    for _ in 0..3 {
+        application_messages.push(
+            alice_group
+                .create_message(backend, &[1, 2, 3])
+                .expect("An unexpected error occurred."),
+        );
+
+        let (commit, _welcome) = alice_group
+            .self_update(backend, None)
+            .expect("An unexpected error occurred.");
+        alice_group
+            .merge_pending_commit()
+            .expect("error merging pending commit");
+
+        let bob_processed_message = bob_group
+            .process_message(backend, commit.into())
+            .expect("An unexpected error occurred.");
+        if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+            bob_processed_message.into_content()
+        {
+            bob_group.merge_staged_commit(*staged_commit);
+        } else {
+            unreachable!("Expected a StagedCommit.");
+        }
+    }
+
+    // The message from three epochs ago has been evicted from the store
+    let err = bob_group
+        .process_message(backend, application_messages[0].clone().into())
+        .expect_err("Message from an evicted epoch was decrypted.");
+    assert_eq!(
+        err,
+        ProcessMessageError::ValidationError(ValidationError::UnableToDecrypt(
+            MessageDecryptionError::SecretTreeError(SecretTreeError::TooDistantInThePast)
+        ))
+    );
+
+    // The messages from the two most recent past epochs still decrypt
+    for application_message in &application_messages[1..] {
+        let bob_processed_message = bob_group
+            .process_message(backend, application_message.clone().into())
+            .expect("An unexpected error occurred.");
+
+        if let ProcessedMessageContent::ApplicationMessage(application_message) =
+            bob_processed_message.into_content()
+        {
+            assert_eq!(application_message.into_bytes(), &[1, 2, 3]);
+        } else {
+            unreachable!("Expected an ApplicationMessage.");
+        }
+    }
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_past_epoch_sender_after_removal(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let mls_group_config = MlsGroupConfig::builder().max_past_epochs(2).build();
+    let (mut alice_group, mut bob_group) =
+        setup_alice_and_bob(ciphersuite, backend, &mls_group_config);
+
+    // Bob sends an application message before Alice removes him
+    let application_message = bob_group
+        .create_message(backend, &[1, 2, 3])
+        .expect("An unexpected error occurred.");
+
+    let (_commit, _welcome) = alice_group
+        .remove_members(backend, &[bob_group.own_leaf_index()])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    // The message is still attributed to Bob, even though he is no longer
+    // part of the group.
+    let alice_processed_message = alice_group
+        .process_message(backend, application_message.into())
+        .expect("An unexpected error occurred.");
+    assert_eq!(
+        alice_processed_message.sender(),
+        &Sender::Member(bob_group.own_leaf_index())
+    );
+    assert_eq!(
+        alice_processed_message
+            .credential()
+            .expect("Missing credential.")
+            .identity(),
+        b"Bob"
+    );
+    if let ProcessedMessageContent::ApplicationMessage(application_message) =
+        alice_processed_message.into_content()
+    {
+        assert_eq!(application_message.into_bytes(), &[1, 2, 3]);
+    } else {
+        unreachable!("Expected an ApplicationMessage.");
+    }
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_message_secrets_debug_report(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let mls_group_config = MlsGroupConfig::builder().max_past_epochs(1).build();
+    let (mut alice_group, mut bob_group) =
+        setup_alice_and_bob(ciphersuite, backend, &mls_group_config);
+    let joined_epoch = bob_group.epoch();
+
+    // Bob decrypts two application messages from Alice
+    for _ in 0..2 {
+        let application_message = alice_group
+            .create_message(backend, &[1, 2, 3])
+            .expect("An unexpected error occurred.");
+        bob_group
+            .process_message(backend, application_message.into())
+            .expect("An unexpected error occurred.");
+    }
+
+    let report = bob_group.message_secrets_debug_report(joined_epoch);
+    assert!(report.epoch_retained);
+    assert_eq!(report.known_senders, vec![0, 1]);
+    let alice_ratchets = report
+        .sender_ratchets
+        .iter()
+        .find(|ratchet| ratchet.leaf_index == 0)
+        .expect("Alice's sender ratchets are not initialized.");
+    assert_eq!(alice_ratchets.application_generation, Some(2));
+
+    // Alice updates, the secrets of Bob's first epoch are kept as past secrets
+    let (commit, _welcome) = alice_group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_processed_message = bob_group
+        .process_message(backend, commit.into())
+        .expect("An unexpected error occurred.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        bob_processed_message.into_content()
+    {
+        bob_group.merge_staged_commit(*staged_commit);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+
+    // Bob decrypted the commit with the past secrets, so Alice's handshake
+    // ratchet moved forward.
+    let past_report = bob_group.message_secrets_debug_report(joined_epoch);
+    assert!(past_report.epoch_retained);
+    assert_eq!(past_report.known_senders, vec![0, 1]);
+    let alice_ratchets = past_report
+        .sender_ratchets
+        .iter()
+        .find(|ratchet| ratchet.leaf_index == 0)
+        .expect("Alice's sender ratchets are not initialized.");
+    assert_eq!(alice_ratchets.handshake_generation, Some(1));
+    assert_eq!(alice_ratchets.application_generation, Some(2));
+
+    let current_report = bob_group.message_secrets_debug_report(bob_group.epoch());
+    assert!(current_report.epoch_retained);
+    assert_eq!(current_report.known_senders, vec![0, 1]);
+    assert!(current_report.sender_ratchets.is_empty());
+
+    // The epoch in which Alice created the group was never known to Bob
+    let unknown_report =
+        bob_group.message_secrets_debug_report(GroupEpoch::from(joined_epoch.as_u64() - 1));
+    assert!(!unknown_report.epoch_retained);
+    assert!(unknown_report.known_senders.is_empty());
+    assert!(unknown_report.sender_ratchets.is_empty());
+}
+
+/// Creates a credential bundle and a key package bundle for `identity` and
+/// stores them in the key store. Returns the key package.
+fn generate_key_package(
+    identity: &str,
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) -> KeyPackage {
+    let credential_bundle = CredentialBundle::new(
+        identity.into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let key_package_bundle =
+        KeyPackageBundle::new(&[ciphersuite], &credential_bundle, backend, vec![])
+            .expect("An unexpected error occurred.");
+    let key_package = key_package_bundle.key_package().clone();
+    backend
+        .key_store()
+        .store(
+            &credential_bundle
+                .credential()
+                .signature_key()
+                .tls_serialize_detached()
+                .expect("Error serializing signature key."),
+            &credential_bundle,
+        )
+        .expect("An unexpected error occurred.");
+    backend
+        .key_store()
+        .store(
+            key_package
+                .hash_ref(backend.crypto())
+                .expect("Could not hash KeyPackage.")
+                .as_slice(),
+            &key_package_bundle,
+        )
+        .expect("An unexpected error occurred.");
+    key_package
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_past_epoch_senders_after_multiple_removals(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let mls_group_config = MlsGroupConfig::builder().max_past_epochs(2).build();
+    let (mut alice_group, mut bob_group) =
+        setup_alice_and_bob(ciphersuite, backend, &mls_group_config);
+
+    // Alice adds Charlie, Dave and Eve
+    let key_packages: Vec<KeyPackage> = ["Charlie", "Dave", "Eve"]
+        .iter()
+        .map(|identity| generate_key_package(identity, ciphersuite, backend))
+        .collect();
+    let (commit, welcome) = alice_group
+        .add_members(backend, &key_packages)
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let bob_processed_message = bob_group
+        .process_message(backend, commit.into())
+        .expect("An unexpected error occurred.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        bob_processed_message.into_content()
+    {
+        bob_group.merge_staged_commit(*staged_commit);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+    let mut new_groups: Vec<MlsGroup> = key_packages
+        .iter()
+        .map(|_| {
+            MlsGroup::new_from_welcome(
+                backend,
+                &mls_group_config,
+                welcome.clone(),
+                Some(alice_group.export_ratchet_tree()),
+            )
+            .expect("Error creating group from Welcome")
+        })
+        .collect();
+    // One of the new members stays in the group.
+    let mut remaining_group = new_groups.pop().expect("An unexpected error occurred.");
+
+    // Bob and the two other new members each send an application message
+    // before Alice removes all three of them in a single commit.
+    let mut application_messages = Vec::new();
+    for group in new_groups.iter_mut().chain([&mut bob_group]) {
+        let credential = group
+            .credential()
+            .expect("An unexpected error occurred.")
+            .clone();
+        let application_message = group
+            .create_message(backend, credential.identity())
+            .expect("An unexpected error occurred.");
+        application_messages.push((credential, group.own_leaf_index(), application_message));
+    }
+    let removed: Vec<u32> = application_messages
+        .iter()
+        .map(|(_, leaf_index, _)| *leaf_index)
+        .collect();
+
+    let (commit, _welcome) = alice_group
+        .remove_members(backend, &removed)
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    let processed_message = remaining_group
+        .process_message(backend, commit.into())
+        .expect("An unexpected error occurred.");
+    if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    {
+        remaining_group.merge_staged_commit(*staged_commit);
+    } else {
+        unreachable!("Expected a StagedCommit.");
+    }
+    assert_eq!(alice_group.members().count(), 2);
+    assert_eq!(remaining_group.members().count(), 2);
+
+    // The remaining members still decrypt the messages of the removed members
+    // and attribute them using the membership of the past epoch.
+    for group in [&mut alice_group, &mut remaining_group] {
+        for (credential, leaf_index, application_message) in &application_messages {
+            let processed_message = group
+                .process_message(backend, application_message.clone().into())
+                .expect("An unexpected error occurred.");
+            assert_eq!(processed_message.sender(), &Sender::Member(*leaf_index));
+            assert_eq!(
+                processed_message.credential().expect("Missing credential."),
+                credential
+            );
+            if let ProcessedMessageContent::ApplicationMessage(application_message) =
+                processed_message.into_content()
+            {
+                assert_eq!(application_message.into_bytes(), credential.identity());
+            } else {
+                unreachable!("Expected an ApplicationMessage.");
+            }
+        }
+    }
+}
+
+#[apply(ciphersuites_and_backends)]
+fn test_members_at_past_epoch(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let mls_group_config = MlsGroupConfig::builder().max_past_epochs(3).build();
+    let (mut alice_group, bob_group) = setup_alice_and_bob(ciphersuite, backend, &mls_group_config);
+    let creation_epoch = GroupEpoch::from(0);
+    let joined_epoch = bob_group.epoch();
+
+    // Alice removes Bob and then updates twice, so that the group advances 3
+    // epochs and the epoch in which Bob joined is the oldest retained one.
+    alice_group
+        .remove_members(backend, &[bob_group.own_leaf_index()])
+        .expect("An unexpected error occurred.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+    for _ in 0..2 {
+        alice_group
+            .self_update(backend, None)
+            .expect("An unexpected error occurred.");
+        alice_group
+            .merge_pending_commit()
+            .expect("error merging pending commit");
+    }
+    assert_eq!(alice_group.epoch().as_u64(), joined_epoch.as_u64() + 3);
+
+    let identities = |members: Vec<Member>| -> Vec<Vec<u8>> {
+        members.into_iter().map(|member| member.identity).collect()
+    };
+
+    // Bob is a member of the oldest retained epoch.
+    let members = alice_group
+        .members_at_epoch(joined_epoch)
+        .expect("The epoch should still be retained.");
+    assert_eq!(
+        identities(members),
+        vec![b"Alice".to_vec(), b"Bob".to_vec()]
+    );
+
+    // In the current epoch, Alice is alone.
+    let members = alice_group
+        .members_at_epoch(alice_group.epoch())
+        .expect("The current epoch is always known.");
+    assert_eq!(identities(members), vec![b"Alice".to_vec()]);
+
+    // The epoch in which the group was created was evicted and future epochs
+    // are unknown.
+    assert!(alice_group.members_at_epoch(creation_epoch).is_none());
+    assert!(alice_group
+        .members_at_epoch(GroupEpoch::from(alice_group.epoch().as_u64() + 1))
+        .is_none());
+}
@@ -160,7 +160,7 @@ pub(crate) fn setup(config: TestSetupConfig, backend: &impl OpenMlsCryptoProvide
             GroupId::from_slice(&group_id.to_be_bytes()),
             initial_key_package_bundle,
         )
-        .with_config(group_config.config)
+        .with_config(group_config.config.clone())
         .build(initial_credential_bundle, backend)
         .expect("Error creating new CoreGroup");
         let mut proposal_list = Vec::new();
@@ -222,7 +222,11 @@ pub(crate) fn setup(config: TestSetupConfig, backend: &impl OpenMlsCryptoProvide
                 .welcome_option
                 .expect("An unexpected error occurred.");
 
-            core_group.merge_staged_commit(create_commit_result.staged_commit, &mut proposal_store);
+            core_group.merge_staged_commit(
+                create_commit_result.staged_commit,
+                &mut proposal_store,
+                None,
+            );
 
             // Distribute the Welcome message to the other members.
             for client_id in 1..group_config.members.len() {
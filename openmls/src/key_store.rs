@@ -1,8 +1,10 @@
 //! Serialization for key store objects.
 
 use crate::{
-    credentials::CredentialBundle, key_packages::KeyPackageBundle, prelude::LibraryError,
-    schedule::psk::PskBundle,
+    credentials::CredentialBundle,
+    key_packages::KeyPackageBundle,
+    prelude::LibraryError,
+    schedule::{message_secrets::EncryptionGeneration, psk::PskBundle},
 };
 
 use openmls_traits::key_store::{FromKeyStoreValue, ToKeyStoreValue};
@@ -54,3 +56,21 @@ impl ToKeyStoreValue for PskBundle {
         serde_json::to_vec(self).map_err(|_| LibraryError::custom("Error serializing PSK bundle."))
     }
 }
+
+// Encryption generations
+
+impl FromKeyStoreValue for EncryptionGeneration {
+    type Error = LibraryError;
+    fn from_key_store_value(ksv: &[u8]) -> Result<Self, Self::Error> {
+        serde_json::from_slice(ksv)
+            .map_err(|_| LibraryError::custom("Invalid encryption generation."))
+    }
+}
+
+impl ToKeyStoreValue for EncryptionGeneration {
+    type Error = LibraryError;
+    fn to_key_store_value(&self) -> Result<Vec<u8>, Self::Error> {
+        serde_json::to_vec(self)
+            .map_err(|_| LibraryError::custom("Error serializing encryption generation."))
+    }
+}
@@ -2,14 +2,17 @@
 //!
 //! Contains the types and methods to build external proposal to add/remove a client from a MLS group
 //!
-//! `Add` (from external sender), `Remove` & `ReInit are not yet implemented`
+//! `Add` and `ReInit` from an external sender are not yet implemented
 
 use crate::{
     credentials::CredentialBundle,
-    framing::{MlsAuthContent, MlsMessageOut, MlsPlaintext},
-    group::{mls_group::errors::ProposeAddMemberError, GroupEpoch, GroupId},
+    framing::{MlsAuthContent, MlsMessageOut, MlsPlaintext, Sender},
+    group::{
+        mls_group::errors::{ProposeAddMemberError, ProposeRemoveMemberError},
+        GroupEpoch, GroupId,
+    },
     key_packages::KeyPackage,
-    messages::{AddProposal, Proposal},
+    messages::{AddProposal, Proposal, RemoveProposal},
 };
 use openmls_traits::OpenMlsCryptoProvider;
 
@@ -37,6 +40,7 @@ impl JoinProposal {
         backend: &impl OpenMlsCryptoProvider,
     ) -> Result<MlsMessageOut, ProposeAddMemberError> {
         MlsAuthContent::new_external_proposal(
+            Sender::NewMemberProposal,
             Proposal::Add(AddProposal { key_package }),
             credential,
             group_id,
@@ -48,3 +52,42 @@ impl JoinProposal {
         .map_err(ProposeAddMemberError::from)
     }
 }
+
+/// External proposal where the sender is a [Preconfigured sender](crate::prelude::Sender::External),
+/// e.g. a delivery service. The sender has to be listed in the group's
+/// [external senders extension](crate::extensions::ExternalSendersExtension)
+/// for members to accept the proposal.
+pub struct ExternalProposal;
+
+impl ExternalProposal {
+    /// Creates an external Remove proposal. For external senders requesting the
+    /// removal of a member. This proposal will have to be committed later by a
+    /// group member.
+    ///
+    /// # Arguments
+    /// * `removed` - leaf index of the member to remove
+    /// * `group_id` - unique group identifier of the group
+    /// * `epoch` - group's epoch
+    /// * `credential` - of the sender to sign the message
+    /// * `sender_index` - index of the sender in the group's external senders extension
+    pub fn new_remove(
+        removed: u32,
+        group_id: GroupId,
+        epoch: GroupEpoch,
+        credential: &CredentialBundle,
+        sender_index: u32,
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<MlsMessageOut, ProposeRemoveMemberError> {
+        MlsAuthContent::new_external_proposal(
+            Sender::External(sender_index),
+            Proposal::Remove(RemoveProposal { removed }),
+            credential,
+            group_id,
+            epoch,
+            backend,
+        )
+        .map(MlsPlaintext::from)
+        .map(MlsMessageOut::from)
+        .map_err(ProposeRemoveMemberError::from)
+    }
+}
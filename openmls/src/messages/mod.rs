@@ -4,11 +4,12 @@
 //! as well as Proposals & the group info used for External Commits.
 
 use crate::{
-    ciphersuite::hash_ref::KeyPackageRef,
+    ciphersuite::hash_ref::{make_welcome_id, KeyPackageRef, WelcomeId},
     ciphersuite::{signable::*, *},
     error::LibraryError,
-    extensions::*,
-    group::*,
+    extensions::{errors::ExtensionError, *},
+    group::{errors::WelcomeError, *},
+    key_packages::KeyPackageBundle,
     schedule::{psk::PreSharedKeyId, JoinerSecret},
     treesync::treekem::UpdatePath,
     versions::ProtocolVersion,
@@ -90,6 +91,85 @@ impl Welcome {
         &self.version
     }
 
+    /// Compute the [`WelcomeId`] of this Welcome message, i.e. the hash of the
+    /// encrypted group info. A delivery service or client can use it to
+    /// detect a Welcome that was delivered more than once, e.g. due to a
+    /// network retry, before processing it.
+    pub fn id(&self, backend: &impl OpenMlsCryptoProvider) -> Result<WelcomeId, LibraryError> {
+        make_welcome_id(
+            self.encrypted_group_info.as_slice(),
+            self.cipher_suite,
+            backend.crypto(),
+        )
+        .map_err(LibraryError::unexpected_crypto_error)
+    }
+
+    /// Inspect this Welcome message before joining the group. This finds the
+    /// [`KeyPackageBundle`] among `own_key_packages` the Welcome is addressed
+    /// to and returns a [`WelcomePreview`] with the ciphersuite of the group
+    /// and, if the group info carries a ratchet tree extension, the number of
+    /// members of the group.
+    ///
+    /// The group info is only decrypted if the matching key package has the
+    /// ciphersuite of the group. No epoch secrets are derived and the ratchet
+    /// tree is not validated, so joining the group with
+    /// [`MlsGroup::new_from_welcome()`](crate::group::mls_group::MlsGroup::new_from_welcome())
+    /// can still fail.
+    ///
+    /// Returns an error ([`WelcomeError::NoMatchingKeyPackageBundle`]) if the
+    /// Welcome isn't addressed to any of the given key packages.
+    pub fn inspect(
+        &self,
+        own_key_packages: &[KeyPackageBundle],
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<WelcomePreview, WelcomeError> {
+        let mut matching_key_package = None;
+        for key_package_bundle in own_key_packages {
+            let key_package_ref = key_package_bundle
+                .key_package()
+                .hash_ref(backend.crypto())?;
+            if self
+                .secrets
+                .iter()
+                .any(|egs| egs.new_member == key_package_ref)
+            {
+                matching_key_package = Some((key_package_bundle, key_package_ref));
+                break;
+            }
+        }
+        let (key_package_bundle, key_package_ref) =
+            matching_key_package.ok_or(WelcomeError::NoMatchingKeyPackageBundle)?;
+
+        // The group info can only be decrypted with a key package of the
+        // group's ciphersuite.
+        let member_count = if key_package_bundle.key_package().ciphersuite() == self.cipher_suite {
+            let (_group_secrets, _key_schedule, group_info) =
+                CoreGroup::decrypt_welcome(self, key_package_bundle, backend)?;
+            try_nodes_from_extensions(group_info.extensions())
+                .map_err(|e| match e {
+                    ExtensionError::DuplicateRatchetTreeExtension => {
+                        WelcomeError::DuplicateRatchetTreeExtension
+                    }
+                    _ => LibraryError::custom("Unexpected extension error").into(),
+                })?
+                .map(|nodes| {
+                    nodes
+                        .iter()
+                        .step_by(2)
+                        .filter(|node| node.is_some())
+                        .count()
+                })
+        } else {
+            None
+        };
+
+        Ok(WelcomePreview {
+            key_package_ref,
+            ciphersuite: self.cipher_suite,
+            member_count,
+        })
+    }
+
     /// Set the welcome's encrypted group info.
     #[cfg(test)]
     pub fn set_encrypted_group_info(&mut self, encrypted_group_info: Vec<u8>) {
@@ -97,6 +177,32 @@ impl Welcome {
     }
 }
 
+/// A preview of a [`Welcome`] message, see [`Welcome::inspect()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WelcomePreview {
+    key_package_ref: KeyPackageRef,
+    ciphersuite: Ciphersuite,
+    member_count: Option<usize>,
+}
+
+impl WelcomePreview {
+    /// Returns the reference of the key package the Welcome is addressed to.
+    pub fn key_package_ref(&self) -> &KeyPackageRef {
+        &self.key_package_ref
+    }
+
+    /// Returns the ciphersuite of the group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.ciphersuite
+    }
+
+    /// Returns the number of members of the group if the Welcome carries a
+    /// ratchet tree extension, and `None` otherwise.
+    pub fn member_count(&self) -> Option<usize> {
+        self.member_count
+    }
+}
+
 /// EncryptedGroupSecrets
 ///
 /// This is part of a [`Welcome`] message. It can be used to correlate the correct secrets with each new member.
@@ -268,6 +374,12 @@ impl GroupInfo {
         &self.payload.confirmation_tag
     }
 
+    /// Set the confirmation tag.
+    #[cfg(test)]
+    pub(crate) fn set_confirmation_tag(&mut self, confirmation_tag: ConfirmationTag) {
+        self.payload.confirmation_tag = confirmation_tag;
+    }
+
     /// Returns the signer.
     pub(crate) fn signer(&self) -> u32 {
         self.payload.signer
@@ -351,6 +463,15 @@ impl VerifiableGroupInfo {
         self.payload.extensions.as_slice()
     }
 
+    /// Get (unverified) epoch of the verifiable group info.
+    ///
+    /// Note: The epoch can be compared against the latest epoch of the group
+    /// the caller knows of, to detect replayed group infos of past epochs
+    /// before verifying the signature.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.payload.group_context.epoch()
+    }
+
     /// Break the signature for testing purposes.
     #[cfg(test)]
     pub(crate) fn break_signature(&mut self) {
@@ -18,7 +18,10 @@ use crate::{
 
 use openmls_traits::{types::Ciphersuite, OpenMlsCryptoProvider};
 use serde::{Deserialize, Serialize};
-use std::convert::TryFrom;
+use std::{
+    convert::TryFrom,
+    hash::{Hash, Hasher},
+};
 use tls_codec::{
     Serialize as TlsSerializeTrait, TlsDeserialize, TlsSerialize, TlsSize, TlsVecU32, VLBytes,
 };
@@ -43,6 +46,7 @@ use tls_codec::{
     Eq,
     PartialOrd,
     Ord,
+    Hash,
     Clone,
     Copy,
     Debug,
@@ -134,6 +138,18 @@ pub enum Proposal {
     GroupContextExtensions(GroupContextExtensionProposal),
 }
 
+// Proposals are compared by content. The derived `PartialEq` is an
+// equivalence relation, so `Proposal` is `Eq` as well.
+impl Eq for Proposal {}
+
+impl Hash for Proposal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hash the encoding, which is also what the `ProposalRef` is computed
+        // over. Equal proposals have equal encodings.
+        self.tls_serialize_detached().ok().hash(state)
+    }
+}
+
 impl Proposal {
     pub(crate) fn proposal_type(&self) -> ProposalType {
         match self {
@@ -232,7 +248,7 @@ impl PreSharedKeyProposal {
     }
 
     /// Returns a reference to the [`PreSharedKeyId`] in this proposal.
-    pub(crate) fn _psk(&self) -> &PreSharedKeyId {
+    pub(crate) fn psk(&self) -> &PreSharedKeyId {
         &self.psk
     }
 
@@ -255,6 +271,43 @@ pub struct ReInitProposal {
     pub(crate) extensions: Vec<Extension>,
 }
 
+impl ReInitProposal {
+    /// Create a new [`ReInitProposal`].
+    pub(crate) fn new(
+        group_id: GroupId,
+        version: ProtocolVersion,
+        ciphersuite: Ciphersuite,
+        extensions: Vec<Extension>,
+    ) -> Self {
+        Self {
+            group_id,
+            version,
+            ciphersuite,
+            extensions,
+        }
+    }
+
+    /// Returns the group ID of the new group.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the protocol version of the new group.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Returns the ciphersuite of the new group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.ciphersuite
+    }
+
+    /// Returns the group context extensions of the new group.
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+}
+
 /// ExternalInit Proposal.
 ///
 /// This proposal is used for External Commits only.
@@ -309,12 +362,16 @@ pub struct GroupContextExtensionProposal {
 
 impl GroupContextExtensionProposal {
     /// Create a new [`GroupContextExtensionProposal`].
-    #[cfg(test)]
     pub(crate) fn new(extensions: &[Extension]) -> Self {
         Self {
             extensions: extensions.into(),
         }
     }
+
+    /// Returns the extensions of the proposal.
+    pub(crate) fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
 }
 
 // Crate-only types
@@ -361,7 +418,9 @@ pub(crate) enum ProposalOrRef {
 }
 
 impl ProposalRef {
-    pub(crate) fn from_proposal(
+    /// Compute the [`ProposalRef`] of a [`Proposal`]. This is the reference
+    /// that has to be used in a Commit to include the proposal by reference.
+    pub fn from_proposal(
         ciphersuite: Ciphersuite,
         backend: &impl OpenMlsCryptoProvider,
         proposal: &Proposal,
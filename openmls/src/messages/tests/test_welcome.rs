@@ -362,3 +362,80 @@ fn invalid_welcomes() {
     let msg = Welcome::tls_deserialize(&mut bytes);
     assert!(msg.is_err());
 }
+
+/// Test that a Welcome can be inspected before joining the group and that a
+/// Welcome that isn't addressed to any of the given key packages is rejected.
+#[apply(ciphersuites_and_backends)]
+fn test_welcome_inspect(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let mls_group_config = MlsGroupConfig::builder()
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // Create credential bundles
+    let alice_credential_bundle = generate_credential_bundle(
+        b"Alice".to_vec(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("Could not create credential bundle.");
+    let bob_credential_bundle = generate_credential_bundle(
+        b"Bob".to_vec(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("Could not create credential bundle.");
+    let charlie_credential_bundle = generate_credential_bundle(
+        b"Charlie".to_vec(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("Could not create credential bundle.");
+
+    // Create key package bundles and store Alice's in the key store
+    let alice_kpb =
+        KeyPackageBundle::new(&[ciphersuite], &alice_credential_bundle, backend, vec![])
+            .expect("Could not create KeyPackageBundle for Alice.");
+    let alice_kp_ref = alice_kpb
+        .key_package()
+        .hash_ref(backend.crypto())
+        .expect("Could not hash KeyPackage.");
+    backend
+        .key_store()
+        .store(alice_kp_ref.as_slice(), &alice_kpb)
+        .expect("An unexpected error occurred.");
+    let bob_kpb = KeyPackageBundle::new(&[ciphersuite], &bob_credential_bundle, backend, vec![])
+        .expect("Could not create KeyPackageBundle for Bob.");
+    let charlie_kpb =
+        KeyPackageBundle::new(&[ciphersuite], &charlie_credential_bundle, backend, vec![])
+            .expect("Could not create KeyPackageBundle for Charlie.");
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new(backend, &mls_group_config, alice_kp_ref.as_slice())
+        .expect("An unexpected error occurred.");
+    let (_queued_message, welcome) = alice_group
+        .add_members(backend, &[bob_kpb.key_package().clone()])
+        .expect("Could not add member to group.");
+
+    // The Welcome isn't addressed to Charlie.
+    let err = welcome
+        .inspect(&[charlie_kpb.clone()], backend)
+        .expect_err("Inspected a Welcome that isn't addressed to us.");
+    assert_eq!(err, WelcomeError::NoMatchingKeyPackageBundle);
+
+    // The Welcome is addressed to Bob.
+    let preview = welcome
+        .inspect(&[charlie_kpb, bob_kpb.clone()], backend)
+        .expect("Could not inspect Welcome.");
+    assert_eq!(
+        preview.key_package_ref(),
+        &bob_kpb
+            .key_package()
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+    );
+    assert_eq!(preview.ciphersuite(), ciphersuite);
+    assert_eq!(preview.member_count(), Some(2));
+}
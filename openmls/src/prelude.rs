@@ -20,7 +20,7 @@ pub use crate::versions::*;
 pub use crate::extensions::{errors::*, *};
 
 // Framing
-pub use crate::framing::{message::*, sender::*, validation::*};
+pub use crate::framing::{message::*, sender::*, validation::*, PaddingStrategy};
 
 // Key packages
 pub use crate::key_packages::{errors::*, *};
@@ -34,7 +34,7 @@ pub use crate::tree::sender_ratchet::SenderRatchetConfiguration;
 // TreeSync
 pub use crate::treesync::{
     errors::{ApplyUpdatePathError, PublicTreeError},
-    node::leaf_node::LeafNode,
+    node::leaf_node::{CoverageReport, LeafNode},
     node::parent_node::ParentNode,
     node::Node,
 };
@@ -19,6 +19,9 @@ pub enum PskError {
     /// The PSK could not be found in the key store.
     #[error("The PSK could not be found in the key store.")]
     KeyNotFound,
+    /// The PSK could not be written to the key store.
+    #[error("The PSK could not be written to the key store.")]
+    KeyStoreError,
 }
 
 // === Crate ===
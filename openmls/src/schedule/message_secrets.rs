@@ -1,9 +1,23 @@
 //! This module defines the [`MessageSecrets`] struct that can be used for message decryption & verification
 
+use openmls_traits::key_store::OpenMlsKeyStore;
+
 use super::*;
 #[cfg(test)]
 use crate::tree::index::SecretTreeLeafIndex;
-/// Combined message secrets that need to be stored for later decryption/verification
+use crate::{framing::errors::MessageEncryptionError, tree::secret_tree::SecretType};
+
+/// Label that prefixes the key store IDs of [`EncryptionGeneration`]s.
+const ENCRYPTION_GENERATION_LABEL: &[u8] = b"OpenMLS encryption generation";
+
+/// The last generation in which a sender encrypted a message of a given type
+/// with the secrets of an epoch. It is kept in the key store rather than in the
+/// persisted group state, so that loading an outdated group state doesn't
+/// reset it.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct EncryptionGeneration(pub(crate) u32);
+/// Combined message secrets that need to be stored for later decryption/verification.
+/// All key material is zeroized on drop.
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct MessageSecrets {
     sender_data_secret: SenderDataSecret,
@@ -13,6 +27,11 @@ pub(crate) struct MessageSecrets {
     secret_tree: SecretTree,
 }
 
+// All secrets, including the ones of the secret tree and its sender ratchets,
+// consist of [`Secret`]s, [`AeadKey`]s and [`AeadNonce`]s, which are zeroized
+// on drop.
+impl ZeroizeOnDrop for MessageSecrets {}
+
 // Public functions
 impl MessageSecrets {
     ///Create new `MessageSecrets`
@@ -32,6 +51,19 @@ impl MessageSecrets {
         }
     }
 
+    /// Explicitly copy the message secrets, e.g. to archive the secrets of an
+    /// epoch. `Clone` is deliberately not implemented, so that the secrets
+    /// don't get duplicated by accident.
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            sender_data_secret: self.sender_data_secret.duplicate(),
+            membership_key: self.membership_key.duplicate(),
+            confirmation_key: self.confirmation_key.duplicate(),
+            serialized_context: self.serialized_context.clone(),
+            secret_tree: self.secret_tree.duplicate(),
+        }
+    }
+
     /// Get a reference to the message secrets's sender data secret.
     pub(crate) fn sender_data_secret(&self) -> &SenderDataSecret {
         &self.sender_data_secret
@@ -56,6 +88,45 @@ impl MessageSecrets {
     pub(crate) fn secret_tree_mut(&mut self) -> &mut SecretTree {
         &mut self.secret_tree
     }
+
+    /// Record in the key store that the given sender is about to encrypt a
+    /// message of the given type in the next generation of its sender ratchet.
+    /// This has to be called before the key material of the generation is
+    /// derived.
+    ///
+    /// Returns a [`MessageEncryptionError::RatchetRegression`] if the sender
+    /// already encrypted a message in this or a later generation, i.e. if the
+    /// sender ratchet went backwards, e.g. because an outdated group state was
+    /// loaded. The generations are stored per epoch, so the key store holds
+    /// two small entries for every epoch in which messages were sent.
+    pub(crate) fn reserve_encryption_generation(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        sender_index: u32,
+        secret_type: SecretType,
+    ) -> Result<(), MessageEncryptionError> {
+        let generation = self
+            .secret_tree
+            .next_encryption_generation(sender_index.into(), secret_type)?;
+        let mut key = ENCRYPTION_GENERATION_LABEL.to_vec();
+        key.extend_from_slice(&self.serialized_context);
+        key.extend_from_slice(&sender_index.to_be_bytes());
+        key.push(match secret_type {
+            SecretType::HandshakeSecret => 0,
+            SecretType::ApplicationSecret => 1,
+        });
+        if let Some(EncryptionGeneration(last_generation)) =
+            backend.key_store().read::<EncryptionGeneration>(&key)
+        {
+            if generation <= last_generation {
+                return Err(MessageEncryptionError::RatchetRegression);
+            }
+        }
+        backend
+            .key_store()
+            .store(&key, &EncryptionGeneration(generation))
+            .map_err(|_| MessageEncryptionError::KeyStoreError)
+    }
 }
 
 // Test functions
@@ -89,6 +160,11 @@ impl MessageSecrets {
         }
     }
 
+    #[cfg(any(feature = "test-utils", test))]
+    pub(crate) fn secret_tree(&self) -> &SecretTree {
+        &self.secret_tree
+    }
+
     #[cfg(any(feature = "test-utils", test))]
     pub(crate) fn replace_secret_tree(&mut self, secret_tree: SecretTree) -> SecretTree {
         std::mem::replace(&mut self.secret_tree, secret_tree)
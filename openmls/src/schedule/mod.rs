@@ -133,13 +133,14 @@ use crate::{
 use openmls_traits::{crypto::OpenMlsCrypto, types::*, OpenMlsCryptoProvider};
 use serde::{Deserialize, Serialize};
 use tls_codec::{TlsDeserialize, TlsSerialize, TlsSize};
+use zeroize::ZeroizeOnDrop;
 
 // Public
 pub mod errors;
+pub mod psk;
 
 // Crate
 pub(crate) mod message_secrets;
-pub(crate) mod psk;
 
 // Private
 use errors::*;
@@ -176,6 +177,11 @@ impl ResumptionPskSecret {
     pub fn as_slice(&self) -> &[u8] {
         self.secret.as_slice()
     }
+
+    /// Returns the secret.
+    pub(crate) fn secret(&self) -> &Secret {
+        &self.secret
+    }
 }
 
 /// A secret that can be used among members to make sure everyone has the same
@@ -782,6 +788,14 @@ pub(crate) struct ConfirmationKey {
 }
 
 impl ConfirmationKey {
+    /// Explicitly copy the key. `Clone` is deliberately not implemented, so
+    /// that the key doesn't get duplicated by accident.
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            secret: self.secret.clone(),
+        }
+    }
+
     /// Derive an `ConfirmationKey` from an `EpochSecret`.
     fn new(
         backend: &impl OpenMlsCryptoProvider,
@@ -847,6 +861,14 @@ pub(crate) struct MembershipKey {
 }
 
 impl MembershipKey {
+    /// Explicitly copy the key. `Clone` is deliberately not implemented, so
+    /// that the key doesn't get duplicated by accident.
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            secret: self.secret.clone(),
+        }
+    }
+
     /// Derive an `MembershipKey` from an `EpochSecret`.
     fn new(
         backend: &impl OpenMlsCryptoProvider,
@@ -918,6 +940,14 @@ pub(crate) struct SenderDataSecret {
 }
 
 impl SenderDataSecret {
+    /// Explicitly copy the key. `Clone` is deliberately not implemented, so
+    /// that the key doesn't get duplicated by accident.
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            secret: self.secret.clone(),
+        }
+    }
+
     /// Derive an `ExporterSecret` from an `EpochSecret`.
     fn new(
         backend: &impl OpenMlsCryptoProvider,
@@ -1228,6 +1258,20 @@ impl PartialEq for GroupEpochSecrets {
     }
 }
 
+// The secrets of the key schedule only consist of [`Secret`]s, which are
+// zeroized on drop.
+impl ZeroizeOnDrop for InitSecret {}
+impl ZeroizeOnDrop for SenderDataSecret {}
+impl ZeroizeOnDrop for EncryptionSecret {}
+impl ZeroizeOnDrop for ExporterSecret {}
+impl ZeroizeOnDrop for EpochAuthenticator {}
+impl ZeroizeOnDrop for ExternalSecret {}
+impl ZeroizeOnDrop for ConfirmationKey {}
+impl ZeroizeOnDrop for MembershipKey {}
+impl ZeroizeOnDrop for ResumptionPskSecret {}
+impl ZeroizeOnDrop for EpochSecrets {}
+impl ZeroizeOnDrop for GroupEpochSecrets {}
+
 impl GroupEpochSecrets {
     /// Init secret
     pub(crate) fn init_secret(&self) -> &InitSecret {
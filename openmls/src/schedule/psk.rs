@@ -70,7 +70,6 @@ pub(crate) struct PskBundle {
 
 impl PskBundle {
     /// Create a new bundle
-    #[cfg(any(feature = "test-utils", test))]
     pub(crate) fn new(secret: Secret) -> Result<Self, CryptoError> {
         Ok(Self { secret })
     }
@@ -179,6 +178,32 @@ impl PreSharedKeyId {
     pub fn psk_nonce(&self) -> &[u8] {
         self.psk_nonce.as_slice()
     }
+
+    /// Store the secret `psk` that this `PreSharedKeyId` refers to in the key
+    /// store, so that commits covering a PreSharedKey proposal for it can be
+    /// created and processed.
+    pub fn write_to_key_store(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        ciphersuite: Ciphersuite,
+        psk: &[u8],
+    ) -> Result<(), PskError> {
+        let psk_bundle = PskBundle::new(Secret::from_slice(
+            psk,
+            ProtocolVersion::default(),
+            ciphersuite,
+        ))
+        .map_err(|_| LibraryError::custom("Could not create PskBundle"))?;
+        backend
+            .key_store()
+            .store(
+                &self
+                    .tls_serialize_detached()
+                    .map_err(LibraryError::missing_bound_check)?,
+                &psk_bundle,
+            )
+            .map_err(|_| PskError::KeyStoreError)
+    }
 }
 
 /// `PskLabel` is used in the final concatentation of PSKs before they are
@@ -17,6 +17,9 @@ pub enum SecretTreeError {
     /// Generation is too far in the future to be processed.
     #[error("Generation is too far in the future to be processed.")]
     TooDistantInTheFuture,
+    /// Generation is further ahead than the ratchet is willing to skip.
+    #[error("Generation is further ahead than the ratchet is willing to skip.")]
+    GenerationOutOfRange,
     /// Index out of bounds
     #[error("Index out of bounds")]
     IndexOutOfBounds,
@@ -94,6 +97,15 @@ pub(crate) struct SecretTreeNode {
     pub(crate) secret: Secret,
 }
 
+impl SecretTreeNode {
+    /// Explicitly copy the node. See [`SecretTree::duplicate()`].
+    fn duplicate(&self) -> Self {
+        Self {
+            secret: self.secret.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(any(feature = "test-utils", test), derive(PartialEq, Clone))]
 pub(crate) struct SecretTree {
@@ -105,6 +117,31 @@ pub(crate) struct SecretTree {
 }
 
 impl SecretTree {
+    /// Explicitly copy the tree, including its sender ratchets. `Clone` is
+    /// only implemented for tests, so that the secrets don't get duplicated
+    /// by accident.
+    pub(crate) fn duplicate(&self) -> Self {
+        SecretTree {
+            own_index: self.own_index,
+            nodes: self
+                .nodes
+                .iter()
+                .map(|node| node.as_ref().map(SecretTreeNode::duplicate))
+                .collect(),
+            handshake_sender_ratchets: self
+                .handshake_sender_ratchets
+                .iter()
+                .map(|ratchet| ratchet.as_ref().map(SenderRatchet::duplicate))
+                .collect(),
+            application_sender_ratchets: self
+                .application_sender_ratchets
+                .iter()
+                .map(|ratchet| ratchet.as_ref().map(SenderRatchet::duplicate))
+                .collect(),
+            size: self.size,
+        }
+    }
+
     /// Creates a new SecretTree based on an `encryption_secret` and group size
     /// `size`. The inner nodes of the tree and the SenderRatchets only get
     /// initialized when secrets are requested either through `secret()`
@@ -154,6 +191,39 @@ impl SecretTree {
         }
     }
 
+    /// Get the generation that the next call to
+    /// [`Self::secret_for_encryption()`] uses for the given index and type.
+    pub(crate) fn next_encryption_generation(
+        &self,
+        index: SecretTreeLeafIndex,
+        secret_type: SecretType,
+    ) -> Result<u32, SecretTreeError> {
+        Ok(self
+            .ratchet_opt(index, secret_type)?
+            .map(|sender_ratchet| sender_ratchet.generation())
+            .unwrap_or(0))
+    }
+
+    /// Returns the leaf index and the current handshake and application
+    /// generation of every leaf for which at least one SenderRatchet is
+    /// initialized.
+    #[cfg(any(feature = "test-utils", test))]
+    pub(crate) fn sender_ratchet_generations(&self) -> Vec<(u32, Option<u32>, Option<u32>)> {
+        self.handshake_sender_ratchets
+            .iter()
+            .zip(self.application_sender_ratchets.iter())
+            .enumerate()
+            .filter(|(_, (handshake, application))| handshake.is_some() || application.is_some())
+            .map(|(index, (handshake, application))| {
+                (
+                    index as u32,
+                    handshake.as_ref().map(|ratchet| ratchet.generation()),
+                    application.as_ref().map(|ratchet| ratchet.generation()),
+                )
+            })
+            .collect()
+    }
+
     /// Initializes a specific SenderRatchet pair for a given index by
     /// calculating and deleting the appropriate values in the SecretTree
     fn initialize_sender_ratchets(
@@ -16,6 +16,11 @@ use super::*;
 
 /// The generation of a given [`SenderRatchet`].
 pub(crate) type Generation = u32;
+
+/// Hard limit on the number of generations a [`DecryptionRatchet`] ratchets
+/// forward to decrypt a single message. This limit applies independently of
+/// the `maximum_forward_distance` of the [`SenderRatchetConfiguration`].
+pub(crate) const MAXIMUM_SKIPPED_GENERATIONS: Generation = 1 << 14;
 /// Stores the configuration parameters for `DecryptionRatchet`s.
 ///
 /// **Parameters**
@@ -27,7 +32,9 @@ pub(crate) type Generation = u32;
 /// The default value is 0.
 ///  - maximum_forward_distance:
 /// This parameter defines how many incoming messages can be skipped. This is useful if the DS
-/// drops application messages. The default value is 1000.
+/// drops application messages. The default value is 1000. Regardless of this
+/// value, a ratchet never skips more than [`MAXIMUM_SKIPPED_GENERATIONS`]
+/// generations at once.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SenderRatchetConfiguration {
     out_of_order_tolerance: Generation,
@@ -77,7 +84,19 @@ pub(crate) enum SenderRatchet {
 }
 
 impl SenderRatchet {
-    #[cfg(test)]
+    /// Explicitly copy the ratchet. `Clone` is only implemented for tests, so
+    /// that the ratchet secrets don't get duplicated by accident.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            SenderRatchet::EncryptionRatchet(enc_ratchet) => {
+                SenderRatchet::EncryptionRatchet(enc_ratchet.duplicate())
+            }
+            SenderRatchet::DecryptionRatchet(dec_ratchet) => {
+                SenderRatchet::DecryptionRatchet(dec_ratchet.duplicate())
+            }
+        }
+    }
+
     pub(crate) fn generation(&self) -> Generation {
         match self {
             SenderRatchet::EncryptionRatchet(enc_ratchet) => enc_ratchet.generation(),
@@ -98,6 +117,14 @@ pub(crate) struct RatchetSecret {
 }
 
 impl RatchetSecret {
+    /// Explicitly copy the ratchet secret. See [`SenderRatchet::duplicate()`].
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            secret: self.secret.clone(),
+            generation: self.generation,
+        }
+    }
+
     /// Create an initial [`RatchetSecret`] with `generation = 0` from the given
     /// [`Secret`].
     pub(crate) fn initial_ratchet_secret(secret: Secret) -> Self {
@@ -170,6 +197,23 @@ pub struct DecryptionRatchet {
 }
 
 impl DecryptionRatchet {
+    /// Explicitly copy the ratchet, including the key material of past
+    /// generations. See [`SenderRatchet::duplicate()`].
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            past_secrets: self
+                .past_secrets
+                .iter()
+                .map(|key_material| {
+                    key_material
+                        .as_ref()
+                        .map(|(key, nonce)| (key.duplicate(), nonce.clone()))
+                })
+                .collect(),
+            ratchet_head: self.ratchet_head.duplicate(),
+        }
+    }
+
     /// Creates e new SenderRatchet
     pub(crate) fn new(secret: Secret) -> Self {
         Self {
@@ -195,6 +239,11 @@ impl DecryptionRatchet {
         &mut self.ratchet_head
     }
 
+    #[cfg(test)]
+    pub(crate) fn past_secrets_len(&self) -> usize {
+        self.past_secrets.len()
+    }
+
     /// Gets a secret from the SenderRatchet. Returns an error if the generation
     /// is out of bound.
     pub(crate) fn secret_for_decryption(
@@ -210,6 +259,11 @@ impl DecryptionRatchet {
         {
             return Err(SecretTreeError::TooDistantInTheFuture);
         }
+        // Independently of the configuration, never skip more than
+        // `MAXIMUM_SKIPPED_GENERATIONS` generations.
+        if generation.saturating_sub(self.generation()) > MAXIMUM_SKIPPED_GENERATIONS {
+            return Err(SecretTreeError::GenerationOutOfRange);
+        }
         // If generation id too distant in the past
         if generation < self.generation()
             && (self.generation() - generation) > configuration.out_of_order_tolerance()
@@ -228,6 +282,8 @@ impl DecryptionRatchet {
                 }?;
                 // Add it to the front of the queue
                 self.past_secrets.push_front(Some(ratchet_secrets));
+                // Prune as we go to keep the queue bounded
+                self.prune_past_secrets(configuration);
             }
             let ratchet_secrets = {
                 self.ratchet_head
@@ -145,3 +145,42 @@ fn sender_ratchet_generation_overflow() {
         .expect_err("no error exceeding generation u32::MAX");
     assert_eq!(err, SecretTreeError::RatchetTooLong)
 }
+
+// Test the hard limit on skipped generations
+#[apply(ciphersuites_and_backends)]
+fn test_maximum_skipped_generations(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    // Configure a forward distance that exceeds the hard limit.
+    let configuration = &SenderRatchetConfiguration::new(5, u32::MAX);
+    let secret = Secret::random(ciphersuite, backend, ProtocolVersion::Mls10)
+        .expect("Not enough randomness.");
+    let mut ratchet = DecryptionRatchet::new(secret);
+
+    // We expect this to return an error
+    let err = ratchet
+        .secret_for_decryption(
+            ciphersuite,
+            backend,
+            MAXIMUM_SKIPPED_GENERATIONS + 1,
+            configuration,
+        )
+        .expect_err("Expected error.");
+    assert_eq!(err, SecretTreeError::GenerationOutOfRange);
+    assert_eq!(ratchet.generation(), 0);
+    assert_eq!(ratchet.past_secrets_len(), 0);
+
+    // We expect this to still work and to only keep a bounded number of
+    // skipped secrets around.
+    let _secret = ratchet
+        .secret_for_decryption(
+            ciphersuite,
+            backend,
+            MAXIMUM_SKIPPED_GENERATIONS,
+            configuration,
+        )
+        .expect("Expected decryption secret.");
+    assert_eq!(ratchet.generation(), MAXIMUM_SKIPPED_GENERATIONS + 1);
+    assert!(ratchet.past_secrets_len() <= configuration.out_of_order_tolerance() as usize);
+}
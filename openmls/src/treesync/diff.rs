@@ -104,6 +104,9 @@ impl<'a> TreeSyncDiff<'a> {
             if self.leaf_count() == 1 {
                 return Ok(());
             }
+            // The nodes on the new right edge of the tree lost part of their
+            // subtree, so their cached tree hashes are no longer valid.
+            self.erase_tree_hashes(self.leaf_count() - 1)?;
             leaf_index = self.diff.leaf(self.leaf_count() - 1);
             parent_index = self.diff.parent(leaf_index)?;
         }
@@ -200,16 +203,25 @@ impl<'a> TreeSyncDiff<'a> {
         Ok(leaf_index)
     }
 
-    /// Clear the tree hash (root and own leaf index).
+    /// Clear the cached tree hashes of the own leaf and its direct path.
     pub(crate) fn clear_tree_hash(&mut self) -> Result<(), LibraryError> {
-        self.diff
-            .node_mut(self.diff.root())
-            .map_err(|_| LibraryError::custom("Root was not in tree."))?
-            .erase_tree_hash();
-        self.diff
-            .node_mut(self.diff.leaf(self.own_leaf_index()))
-            .map_err(|_| LibraryError::custom("Node was not in tree."))?
-            .erase_tree_hash();
+        self.erase_tree_hashes(self.own_leaf_index())
+    }
+
+    /// Erase the cached tree hashes of the leaf at `leaf_index` and of all
+    /// nodes in its direct path, i.e. of all nodes whose tree hash depends on
+    /// the content of the leaf.
+    fn erase_tree_hashes(&mut self, leaf_index: LeafIndex) -> Result<(), LibraryError> {
+        let direct_path = self
+            .diff
+            .direct_path(leaf_index)
+            .map_err(|_| LibraryError::custom("Expected leaf index to be in tree"))?;
+        for node_index in std::iter::once(self.diff.leaf(leaf_index)).chain(direct_path) {
+            self.diff
+                .node_mut(node_index)
+                .map_err(|_| LibraryError::custom("Node was not in tree."))?
+                .erase_tree_hash();
+        }
         Ok(())
     }
 
@@ -739,15 +751,29 @@ impl<'a> TreeSyncDiff<'a> {
     }
 
     /// Helper function to compute and set the tree hash of the given node and
-    /// all nodes below it in the tree. This function respects cached tree hash
-    /// values. If a cached value is found it is returned without further
-    /// computation of hashes of the node or the nodes below it.
+    /// all nodes below it in the tree. If `use_cache` is set, this function
+    /// respects cached tree hash values. If a cached value is found it is
+    /// returned without further computation of hashes of the node or the nodes
+    /// below it.
     fn compute_tree_hash(
         &mut self,
         backend: &impl OpenMlsCryptoProvider,
         ciphersuite: Ciphersuite,
         node_index: u32,
+        use_cache: bool,
     ) -> Result<Vec<u8>, LibraryError> {
+        // Return early if there's already a cached tree hash. Note that we
+        // only borrow the node immutably here, so that unchanged nodes are not
+        // copied into the diff.
+        if use_cache {
+            let node = self
+                .diff
+                .node(node_index)
+                .map_err(|_| LibraryError::custom("Expected node to be in tree"))?;
+            if let Some(tree_hash) = node.tree_hash() {
+                return Ok(tree_hash.to_vec());
+            }
+        }
         // Check if this is a leaf.
         if let Some(leaf_index) = self.diff.leaf_index(node_index) {
             let leaf = self
@@ -758,27 +784,18 @@ impl<'a> TreeSyncDiff<'a> {
                 leaf.compute_tree_hash(backend, ciphersuite, Some(leaf_index), vec![], vec![])?;
             return Ok(tree_hash);
         }
-        // // Return early if there's already a cached tree hash.
-        // TODO[FK]: Do we want to keep caching?
-        // let node = self
-        //     .diff
-        //     .node(node_id)
-        //     .map_err(|_| LibraryError::custom("Expected node to be in tree"))?;
-        // if let Some(tree_hash) = node.tree_hash() {
-        //     return Ok(tree_hash.to_vec());
-        // }
         // Compute left hash.
         let left_child = self
             .diff
             .left_child(node_index)
             .map_err(|_| LibraryError::custom("Expected node to be in tree"))?;
-        let left_hash = self.compute_tree_hash(backend, ciphersuite, left_child)?;
+        let left_hash = self.compute_tree_hash(backend, ciphersuite, left_child, use_cache)?;
         // Compute right hash.
         let right_child = self
             .diff
             .right_child(node_index)
             .map_err(|_| LibraryError::custom("Expected node to be in tree"))?;
-        let right_hash = self.compute_tree_hash(backend, ciphersuite, right_child)?;
+        let right_hash = self.compute_tree_hash(backend, ciphersuite, right_child, use_cache)?;
 
         let node = self
             .diff
@@ -805,8 +822,11 @@ impl<'a> TreeSyncDiff<'a> {
         }
     }
 
-    /// Return a mutable reference to our own leaf.
+    /// Return a mutable reference to our own leaf. Since the leaf might be
+    /// modified, this erases the cached tree hashes of the own leaf and its
+    /// direct path.
     pub(crate) fn own_leaf_mut(&mut self) -> Result<&mut OpenMlsLeafNode, TreeSyncDiffError> {
+        self.clear_tree_hash()?;
         let node_index = self.diff.leaf(self.own_leaf_index);
         let node = self.diff.node_mut(node_index)?;
         match node.node_mut() {
@@ -815,13 +835,25 @@ impl<'a> TreeSyncDiff<'a> {
         }
     }
 
-    /// Compute and set the tree hash of all nodes in the tree.
+    /// Compute and set the tree hash of all nodes in the tree. Cached tree
+    /// hashes are re-used, so that only the hashes of nodes that changed in
+    /// this diff (and of their ancestors) are computed.
     pub(crate) fn compute_tree_hashes(
         &mut self,
         backend: &impl OpenMlsCryptoProvider,
         ciphersuite: Ciphersuite,
     ) -> Result<Vec<u8>, LibraryError> {
-        self.compute_tree_hash(backend, ciphersuite, self.diff.root())
+        self.compute_tree_hash(backend, ciphersuite, self.diff.root(), true)
+    }
+
+    /// Compute and set the tree hash of all nodes in the tree, ignoring any
+    /// cached tree hashes.
+    pub(crate) fn compute_tree_hashes_from_scratch(
+        &mut self,
+        backend: &impl OpenMlsCryptoProvider,
+        ciphersuite: Ciphersuite,
+    ) -> Result<Vec<u8>, LibraryError> {
+        self.compute_tree_hash(backend, ciphersuite, self.diff.root(), false)
     }
 
     /// Returns the position of the subtree root shared by both given indices in
@@ -30,6 +30,9 @@ pub enum PublicTreeError {
     /// A parent hash was invalid.
     #[error("A parent hash was invalid.")]
     InvalidParentHash,
+    /// The tree hash doesn't match the expected one.
+    #[error("The tree hash doesn't match the expected one.")]
+    TreeHashMismatch,
 }
 
 /// Apply update path error
@@ -19,6 +19,7 @@
 // encryption and decryption of updates to the tree.
 
 use openmls_traits::{
+    crypto::OpenMlsCrypto,
     types::{Ciphersuite, CryptoError},
     OpenMlsCryptoProvider,
 };
@@ -26,7 +27,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     binary_tree::{LeafIndex, MlsBinaryTree, MlsBinaryTreeError},
-    ciphersuite::Secret,
+    ciphersuite::{HpkePrivateKey, Secret},
     credentials::CredentialBundle,
     error::LibraryError,
     extensions::{Extension, LifetimeExtension},
@@ -45,6 +46,7 @@ use self::{
 
 // Private
 mod hashes;
+mod streaming;
 use errors::*;
 
 // Crate
@@ -57,7 +59,11 @@ pub(crate) mod treesync_node;
 pub mod errors;
 
 // Public re-exports
-pub use node::{leaf_node::LeafNode, parent_node::ParentNode, Node};
+pub use node::{
+    leaf_node::{Capabilities, CoverageReport, LeafNode},
+    parent_node::ParentNode,
+    Node,
+};
 
 // Tests
 #[cfg(any(feature = "test-utils", test))]
@@ -121,12 +127,11 @@ impl TreeSync {
         let commit_secret: CommitSecret = path_secret
             .derive_path_secret(backend, key_package.ciphersuite())?
             .into();
-        let node_options = vec![Some(node)];
         Ok((
             Self::from_nodes(
                 backend,
                 key_package.ciphersuite(),
-                &node_options,
+                [Ok(Some(node))],
                 key_package_bundle,
             )
             .map_err(|_| LibraryError::custom("Malformed empty tree"))?,
@@ -139,6 +144,17 @@ impl TreeSync {
         self.tree_hash.as_slice()
     }
 
+    /// Recompute the tree hash of the root node from the nodes of the tree,
+    /// ignoring the cached value returned by [`Self::tree_hash()`].
+    pub(crate) fn recompute_tree_hash(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        ciphersuite: Ciphersuite,
+    ) -> Result<Vec<u8>, LibraryError> {
+        self.empty_diff()
+            .compute_tree_hashes_from_scratch(backend, ciphersuite)
+    }
+
     /// Merge the given diff into this `TreeSync` instance, refreshing the
     /// `tree_hash` value in the process.
     pub(crate) fn merge_diff(&mut self, tree_sync_diff: StagedTreeSyncDiff) {
@@ -173,6 +189,29 @@ impl TreeSync {
         sender_index: u32,
         path_secret_option: impl Into<Option<PathSecret>>,
         key_package_bundle: KeyPackageBundle,
+    ) -> Result<(Self, Option<CommitSecret>), TreeSyncFromNodesError> {
+        Self::from_node_stream_with_secrets(
+            backend,
+            ciphersuite,
+            node_options.iter().cloned().map(Ok),
+            sender_index,
+            path_secret_option,
+            key_package_bundle,
+        )
+    }
+
+    /// Like [`TreeSync::from_nodes_with_secrets()`], but takes the nodes one at
+    /// a time, e.g. while they are decoded, instead of as a slice. The nodes
+    /// are moved into the tree without copying them.
+    ///
+    /// Returns the first error of `node_options`, if any.
+    pub(crate) fn from_node_stream_with_secrets(
+        backend: &impl OpenMlsCryptoProvider,
+        ciphersuite: Ciphersuite,
+        node_options: impl IntoIterator<Item = Result<Option<Node>, TreeSyncFromNodesError>>,
+        sender_index: u32,
+        path_secret_option: impl Into<Option<PathSecret>>,
+        key_package_bundle: KeyPackageBundle,
     ) -> Result<(Self, Option<CommitSecret>), TreeSyncFromNodesError> {
         let mut tree_sync =
             Self::from_nodes(backend, ciphersuite, node_options, key_package_bundle)?;
@@ -199,27 +238,27 @@ impl TreeSync {
     }
 
     /// A helper function that generates a [`TreeSync`] instance from the given
-    /// slice of nodes. It verifies that the [`KeyPackage`] of the given
+    /// nodes. It verifies that the [`KeyPackage`] of the given
     /// [`KeyPackageBundle`] is present in the tree and that the invariants
     /// documented in [`TreeSync`] hold.
     fn from_nodes(
         backend: &impl OpenMlsCryptoProvider,
         ciphersuite: Ciphersuite,
-        node_options: &[Option<Node>],
+        node_options: impl IntoIterator<Item = Result<Option<Node>, TreeSyncFromNodesError>>,
         key_package_bundle: KeyPackageBundle,
     ) -> Result<Self, TreeSyncFromNodesError> {
         // TODO #800: Unmerged leaves should be checked
         // Before we can instantiate the TreeSync instance, we have to figure
         // out what our leaf index is.
-        let mut ts_nodes: Vec<TreeSyncNode> = Vec::with_capacity(node_options.len());
+        let node_options = node_options.into_iter();
+        let mut ts_nodes: Vec<TreeSyncNode> = Vec::with_capacity(node_options.size_hint().0);
         let mut own_index_option = None;
         let own_key_package = key_package_bundle.key_package;
         let mut private_key = Some(key_package_bundle.private_key);
         // Check if our own key package is in the tree.
-        for (node_index, node_option) in node_options.iter().enumerate() {
-            let ts_node_option: TreeSyncNode = match node_option {
-                Some(node) => {
-                    let mut node = node.clone();
+        for (node_index, node_option) in node_options.enumerate() {
+            let ts_node_option: TreeSyncNode = match node_option? {
+                Some(mut node) => {
                     if let Node::LeafNode(ref mut leaf_node) = node {
                         let leaf_index = (node_index / 2) as u32;
                         if leaf_node.public_key() == own_key_package.hpke_init_key() {
@@ -371,19 +410,7 @@ impl TreeSync {
                 Err(_) => None,
             })
             // Map to `Member`
-            .map(|(index, leaf_node)| {
-                Member::new(
-                    index,
-                    leaf_node.public_key().as_slice().to_vec(),
-                    leaf_node
-                        .leaf_node
-                        .credential()
-                        .signature_key()
-                        .as_slice()
-                        .to_vec(),
-                    leaf_node.leaf_node.credential().identity().to_vec(),
-                )
-            })
+            .map(|(index, leaf_node)| Member::new(index, &leaf_node.leaf_node))
     }
 
     /// Returns a [`TreeSyncError::UnsupportedExtension`] if an [`ExtensionType`]
@@ -427,6 +454,20 @@ impl TreeSync {
             .collect()
     }
 
+    /// Returns the non-blank leaves in the tree together with their leaf
+    /// indices. In contrast to [`TreeSync::export_nodes()`], this omits parent
+    /// nodes and is thus sufficient to list the members of the group.
+    pub fn export_leaves(&self) -> Vec<(LeafIndex, LeafNode)> {
+        self.tree
+            .leaves()
+            .filter_map(|(index, tsn)| tsn.node().as_ref().map(|node| (index, node)))
+            .filter_map(|(index, node)| match node.as_leaf_node() {
+                Ok(leaf_node) => Some((index, leaf_node.leaf_node().clone())),
+                Err(_) => None,
+            })
+            .collect()
+    }
+
     /// Returns the leaf index of this client.
     pub(crate) fn own_leaf_index(&self) -> LeafIndex {
         self.own_leaf_index
@@ -442,6 +483,51 @@ impl TreeSync {
             .ok_or_else(|| LibraryError::custom("Own leaf is outside of the tree").into())
     }
 
+    /// Returns `true` if the private encryption key of this client's leaf
+    /// matches the public encryption key in the leaf and `false` otherwise,
+    /// including when the leaf or the private key is missing. This is checked
+    /// by encrypting to the public key and decrypting with the private key.
+    pub(crate) fn own_leaf_keys_match(
+        &self,
+        backend: &impl OpenMlsCryptoProvider,
+        ciphersuite: Ciphersuite,
+    ) -> bool {
+        let own_leaf_node = match self.own_leaf_node() {
+            Ok(own_leaf_node) => own_leaf_node,
+            Err(_) => return false,
+        };
+        let private_key = match own_leaf_node.private_key() {
+            Some(private_key) => private_key,
+            None => return false,
+        };
+        let plaintext = b"own leaf key check";
+        let ciphertext = backend.crypto().hpke_seal(
+            ciphersuite.hpke_config(),
+            own_leaf_node.public_key().as_slice(),
+            &[],
+            &[],
+            plaintext,
+        );
+        backend
+            .crypto()
+            .hpke_open(
+                ciphersuite.hpke_config(),
+                &ciphertext,
+                private_key.as_slice(),
+                &[],
+                &[],
+            )
+            .map_or(false, |decrypted| decrypted == plaintext)
+    }
+
+    /// Returns the private encryption key of this client's leaf.
+    #[cfg(any(feature = "crypto-subtle", test))]
+    pub(crate) fn own_leaf_private_key(&self) -> Result<&HpkePrivateKey, TreeSyncError> {
+        self.own_leaf_node()?
+            .private_key()
+            .ok_or_else(|| LibraryError::custom("Own leaf has no private key").into())
+    }
+
     /// Return a reference to the leaf at the given `LeafIndex` or `None` if the
     /// leaf is blank.
     ///
@@ -151,6 +151,12 @@ impl Capabilities {
         &self.extensions
     }
 
+    /// Set the extensions list.
+    #[cfg(test)]
+    pub fn set_extensions(&mut self, extensions: Vec<ExtensionType>) {
+        self.extensions = extensions;
+    }
+
     /// Get a reference to the list of supported proposals.
     pub fn proposals(&self) -> &[ProposalType] {
         &self.proposals
@@ -193,23 +199,72 @@ impl Capabilities {
         &self,
         required_capabilities: &RequiredCapabilitiesExtension,
     ) -> bool {
-        // Check if all required extensions are supported.
-        if required_capabilities
-            .extensions()
-            .iter()
-            .any(|e| !self.extensions().contains(e))
-        {
-            return false;
+        self.coverage(required_capabilities).is_satisfied()
+    }
+
+    /// Returns a [`CoverageReport`] that lists which of the extension and
+    /// proposal types required by the given [`RequiredCapabilitiesExtension`]
+    /// are supported by these [`Capabilities`] and which are missing.
+    pub(crate) fn coverage(
+        &self,
+        required_capabilities: &RequiredCapabilitiesExtension,
+    ) -> CoverageReport {
+        let mut report = CoverageReport::default();
+        for extension_type in required_capabilities.extensions() {
+            if self.extensions().contains(extension_type) {
+                report.satisfied_extensions.push(*extension_type);
+            } else {
+                report.missing_extensions.push(*extension_type);
+            }
         }
-        // Check if all required proposals are supported.
-        if required_capabilities
-            .proposals()
-            .iter()
-            .any(|p| !self.proposals().contains(p))
-        {
-            return false;
+        for proposal_type in required_capabilities.proposals() {
+            if self.proposals().contains(proposal_type) {
+                report.satisfied_proposals.push(*proposal_type);
+            } else {
+                report.missing_proposals.push(*proposal_type);
+            }
         }
-        true
+        report
+    }
+}
+
+/// Lists which of the capabilities required by a group are supported by the
+/// [`Capabilities`] of a leaf and which are missing. The
+/// [`RequiredCapabilitiesExtension`] doesn't contain credential types, so
+/// only extension and proposal types are covered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    satisfied_extensions: Vec<ExtensionType>,
+    missing_extensions: Vec<ExtensionType>,
+    satisfied_proposals: Vec<ProposalType>,
+    missing_proposals: Vec<ProposalType>,
+}
+
+impl CoverageReport {
+    /// Returns the required extension types that are supported.
+    pub fn satisfied_extensions(&self) -> &[ExtensionType] {
+        &self.satisfied_extensions
+    }
+
+    /// Returns the required extension types that are not supported.
+    pub fn missing_extensions(&self) -> &[ExtensionType] {
+        &self.missing_extensions
+    }
+
+    /// Returns the required proposal types that are supported.
+    pub fn satisfied_proposals(&self) -> &[ProposalType] {
+        &self.satisfied_proposals
+    }
+
+    /// Returns the required proposal types that are not supported.
+    pub fn missing_proposals(&self) -> &[ProposalType] {
+        &self.missing_proposals
+    }
+
+    /// Returns `true` if all required capabilities are supported and `false`
+    /// otherwise.
+    pub fn is_satisfied(&self) -> bool {
+        self.missing_extensions.is_empty() && self.missing_proposals.is_empty()
     }
 }
 
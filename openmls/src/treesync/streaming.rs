@@ -0,0 +1,341 @@
+//! This module contains a streaming verifier for TLS-encoded ratchet trees.
+//!
+//! Instead of decoding the whole tree into a vector of nodes before checking
+//! it, the verifier decodes one node at a time and folds it into a stack of
+//! summaries of completed subtrees. Since the nodes of a tree are encoded in
+//! array order (i.e. in-order), the left subtree of a parent node is always
+//! complete when the parent node is decoded, and the parent can be verified as
+//! soon as its right subtree is complete. The stack thus only ever holds the
+//! summaries of subtrees along a single path of the tree.
+//!
+//! A subtree summary consists of its tree hash, its resolution and the parent
+//! hashes needed to verify the parent hash of its parent. In addition, the
+//! verifier keeps the public key of every non-blank leaf so it can filter
+//! unmerged leaves from resolutions. The nodes themselves are dropped as soon
+//! as they are folded into the stack, but the leaf keys and the resolutions of
+//! blank subtrees grow linearly with the number of members, so the memory
+//! needed is O(n) public keys rather than O(n) decoded nodes.
+use std::{collections::HashMap, io::Read};
+
+use openmls_traits::{types::Ciphersuite, OpenMlsCryptoProvider};
+use tls_codec::VLByteSlice;
+
+use crate::{
+    binary_tree::LeafIndex, ciphersuite::HpkePublicKey, error::LibraryError,
+    extensions::RatchetTreeExtension,
+};
+
+use super::{
+    errors::{PublicTreeError, TreeSyncFromNodesError},
+    hashes::TreeHashInput,
+    node::{leaf_node::OpenMlsLeafNode, parent_node::ParentNode, Node},
+    TreeSync,
+};
+
+impl TreeSync {
+    /// Verify the TLS-encoded ratchet tree (i.e. a vector of optional nodes as
+    /// contained in a [`RatchetTreeExtension`](crate::extensions::RatchetTreeExtension))
+    /// read from `reader` without materializing all of its nodes. The public
+    /// keys of the leaves are kept until the whole tree is verified, so memory
+    /// still grows linearly with the number of members.
+    ///
+    /// The parent hashes of all parent nodes are verified with the same
+    /// algorithm as when importing a tree from a vector of nodes and the tree
+    /// hash is computed on the fly and compared to `expected_tree_hash`.
+    ///
+    /// Returns a [`PublicTreeError::MalformedTree`] if the tree can't be
+    /// decoded or doesn't have the shape of a left-balanced binary tree, a
+    /// [`PublicTreeError::InvalidParentHash`] if a parent hash is invalid and a
+    /// [`PublicTreeError::TreeHashMismatch`] if the tree hash doesn't match.
+    pub(crate) fn verify_streaming<R: Read>(
+        reader: R,
+        ciphersuite: Ciphersuite,
+        expected_tree_hash: &[u8],
+        backend: &impl OpenMlsCryptoProvider,
+    ) -> Result<(), TreeSyncFromNodesError> {
+        let nodes = RatchetTreeExtension::deserialize_streaming(reader)
+            .map_err(|_| PublicTreeError::MalformedTree)?;
+        let mut verifier = StreamingVerifier::new(backend, ciphersuite);
+        for node_option in nodes {
+            verifier.push(node_option.map_err(|_| PublicTreeError::MalformedTree)?)?;
+        }
+        if verifier.finish()? != expected_tree_hash {
+            return Err(PublicTreeError::TreeHashMismatch.into());
+        }
+        Ok(())
+    }
+}
+
+/// The parent hash of a non-blank node. This is `None` for leaves without a
+/// parent hash.
+type NodeParentHash = Option<Vec<u8>>;
+
+/// Summary of a complete subtree that has been verified.
+struct SubtreeSummary {
+    /// The level of the subtree's root.
+    level: usize,
+    tree_hash: Vec<u8>,
+    resolution: Vec<HpkePublicKey>,
+    /// The parent hash of the subtree's root or `None` if it is blank.
+    root: Option<NodeParentHash>,
+    /// The parent hash of the first non-blank node on the left edge of the
+    /// subtree or `None` if the left edge ends in a blank leaf.
+    left_edge: Option<NodeParentHash>,
+}
+
+/// Elements of the verifier's stack.
+enum StackEntry {
+    /// A complete subtree.
+    Subtree(SubtreeSummary),
+    /// A parent node with a complete left subtree that is waiting for its
+    /// right subtree.
+    Parent {
+        level: usize,
+        node: Option<ParentNode>,
+    },
+}
+
+/// A verifier that consumes the nodes of a tree in array order.
+struct StreamingVerifier<'a, Backend: OpenMlsCryptoProvider> {
+    backend: &'a Backend,
+    ciphersuite: Ciphersuite,
+    node_index: u32,
+    stack: Vec<StackEntry>,
+    leaf_keys: HashMap<LeafIndex, HpkePublicKey>,
+}
+
+impl<'a, Backend: OpenMlsCryptoProvider> StreamingVerifier<'a, Backend> {
+    fn new(backend: &'a Backend, ciphersuite: Ciphersuite) -> Self {
+        Self {
+            backend,
+            ciphersuite,
+            node_index: 0,
+            stack: Vec::new(),
+            leaf_keys: HashMap::new(),
+        }
+    }
+
+    /// Push the next node of the tree.
+    fn push(&mut self, node_option: Option<Node>) -> Result<(), TreeSyncFromNodesError> {
+        let node_index = self.node_index;
+        self.node_index = self
+            .node_index
+            .checked_add(1)
+            .ok_or(PublicTreeError::MalformedTree)?;
+
+        // Leaves are at even, parents at odd indices.
+        let level = node_index.trailing_ones() as usize;
+        if level == 0 {
+            let leaf_node = match node_option {
+                Some(Node::LeafNode(leaf_node)) => Some(leaf_node),
+                Some(Node::ParentNode(_)) => return Err(PublicTreeError::MalformedTree.into()),
+                None => None,
+            };
+            let subtree = self.leaf_summary(node_index / 2, leaf_node)?;
+            self.stack.push(StackEntry::Subtree(subtree));
+            self.merge_complete_subtrees()
+        } else {
+            let parent_node = match node_option {
+                Some(Node::ParentNode(parent_node)) => Some(parent_node),
+                Some(Node::LeafNode(_)) => return Err(PublicTreeError::MalformedTree.into()),
+                None => None,
+            };
+            // The left subtree of the parent must be complete.
+            match self.stack.last() {
+                Some(StackEntry::Subtree(left)) if left.level + 1 == level => {}
+                _ => return Err(PublicTreeError::MalformedTree.into()),
+            }
+            self.stack.push(StackEntry::Parent {
+                level,
+                node: parent_node,
+            });
+            Ok(())
+        }
+    }
+
+    /// Finish the verification and return the tree hash of the tree.
+    ///
+    /// In a tree that is not full, the parents on the right edge of the tree
+    /// don't have a complete right subtree. Their right child is the root of
+    /// the remaining nodes to the right of them instead.
+    fn finish(mut self) -> Result<Vec<u8>, TreeSyncFromNodesError> {
+        let mut right = match self.stack.pop() {
+            Some(StackEntry::Subtree(subtree)) => subtree,
+            _ => return Err(PublicTreeError::MalformedTree.into()),
+        };
+        while let Some(entry) = self.stack.pop() {
+            let (level, parent_node) = match entry {
+                StackEntry::Parent { level, node } => (level, node),
+                StackEntry::Subtree(_) => {
+                    return Err(LibraryError::custom("Malformed stack").into())
+                }
+            };
+            let left = match self.stack.pop() {
+                Some(StackEntry::Subtree(subtree)) => subtree,
+                _ => return Err(LibraryError::custom("Malformed stack").into()),
+            };
+            right = self.parent_summary(level, parent_node, left, right)?;
+        }
+        Ok(right.tree_hash)
+    }
+
+    /// Merge the two topmost subtrees with the parent between them for as long
+    /// as the right subtree is complete.
+    fn merge_complete_subtrees(&mut self) -> Result<(), TreeSyncFromNodesError> {
+        while self.right_subtree_complete() {
+            let right = self.stack.pop();
+            let parent = self.stack.pop();
+            let left = self.stack.pop();
+            let subtree = match (left, parent, right) {
+                (
+                    Some(StackEntry::Subtree(left)),
+                    Some(StackEntry::Parent { level, node }),
+                    Some(StackEntry::Subtree(right)),
+                ) => self.parent_summary(level, node, left, right)?,
+                _ => return Err(LibraryError::custom("Malformed stack").into()),
+            };
+            self.stack.push(StackEntry::Subtree(subtree));
+        }
+        Ok(())
+    }
+
+    /// Check if the top of the stack is a parent with a complete left and
+    /// right subtree.
+    fn right_subtree_complete(&self) -> bool {
+        matches!(
+            self.stack.as_slice(),
+            [.., StackEntry::Subtree(left), StackEntry::Parent { level, .. }, StackEntry::Subtree(right)]
+                if left.level == right.level && left.level + 1 == *level
+        )
+    }
+
+    /// Compute the summary of a leaf.
+    fn leaf_summary(
+        &mut self,
+        leaf_index: LeafIndex,
+        leaf_node: Option<OpenMlsLeafNode>,
+    ) -> Result<SubtreeSummary, LibraryError> {
+        let tree_hash =
+            TreeHashInput::new_leaf(&leaf_index, leaf_node.as_ref().map(|l| &l.leaf_node))
+                .hash(self.backend, self.ciphersuite)?;
+        let (resolution, root) = match leaf_node {
+            Some(leaf_node) => {
+                self.leaf_keys
+                    .insert(leaf_index, leaf_node.public_key().clone());
+                (
+                    vec![leaf_node.public_key().clone()],
+                    Some(leaf_node.leaf_node.parent_hash().map(|hash| hash.to_vec())),
+                )
+            }
+            None => (vec![], None),
+        };
+        Ok(SubtreeSummary {
+            level: 0,
+            tree_hash,
+            resolution,
+            left_edge: root.clone(),
+            root,
+        })
+    }
+
+    /// Verify the parent hash of the given parent node and compute the summary
+    /// of the subtree rooted at it.
+    fn parent_summary(
+        &self,
+        level: usize,
+        parent_node: Option<ParentNode>,
+        left: SubtreeSummary,
+        right: SubtreeSummary,
+    ) -> Result<SubtreeSummary, TreeSyncFromNodesError> {
+        let tree_hash = TreeHashInput::new_parent(
+            parent_node.as_ref(),
+            VLByteSlice(&left.tree_hash),
+            VLByteSlice(&right.tree_hash),
+        )
+        .hash(self.backend, self.ciphersuite)?;
+        let (resolution, root, left_edge) = match parent_node {
+            Some(parent_node) => {
+                self.verify_parent_hash(&parent_node, &left, &right)?;
+                let mut resolution = vec![parent_node.public_key().clone()];
+                for leaf_index in parent_node.unmerged_leaves() {
+                    resolution.push(self.leaf_key(*leaf_index)?.clone());
+                }
+                let root = Some(Some(parent_node.parent_hash().to_vec()));
+                (resolution, root.clone(), root)
+            }
+            None => {
+                let mut resolution = left.resolution;
+                resolution.extend(right.resolution);
+                (resolution, None, left.left_edge)
+            }
+        };
+        Ok(SubtreeSummary {
+            level,
+            tree_hash,
+            resolution,
+            root,
+            left_edge,
+        })
+    }
+
+    /// Verify the parent hash of the given parent node, either via its left
+    /// child or via the first non-blank node on the left edge of its right
+    /// subtree.
+    fn verify_parent_hash(
+        &self,
+        parent_node: &ParentNode,
+        left: &SubtreeSummary,
+        right: &SubtreeSummary,
+    ) -> Result<(), TreeSyncFromNodesError> {
+        if let Some(left_child_parent_hash) = &left.root {
+            let right_child_resolution = self.filter_resolution(parent_node, &right.resolution)?;
+            let node_hash = parent_node.compute_parent_hash(
+                self.backend,
+                self.ciphersuite,
+                parent_node.parent_hash(),
+                &right_child_resolution,
+            )?;
+            if left_child_parent_hash.as_ref() == Some(&node_hash) {
+                return Ok(());
+            }
+        }
+        if let Some(right_child_parent_hash) = &right.left_edge {
+            let left_child_resolution = self.filter_resolution(parent_node, &left.resolution)?;
+            let node_hash = parent_node.compute_parent_hash(
+                self.backend,
+                self.ciphersuite,
+                parent_node.parent_hash(),
+                &left_child_resolution,
+            )?;
+            if right_child_parent_hash.as_ref() == Some(&node_hash) {
+                return Ok(());
+            }
+        }
+        Err(PublicTreeError::InvalidParentHash.into())
+    }
+
+    /// Return a copy of the given resolution without the unmerged leaves of
+    /// the given parent node.
+    fn filter_resolution(
+        &self,
+        parent_node: &ParentNode,
+        resolution: &[HpkePublicKey],
+    ) -> Result<Vec<HpkePublicKey>, TreeSyncFromNodesError> {
+        let mut resolution = resolution.to_vec();
+        for leaf_index in parent_node.unmerged_leaves() {
+            let leaf_key = self.leaf_key(*leaf_index)?;
+            if let Some(position) = resolution.iter().position(|key| key == leaf_key) {
+                resolution.remove(position);
+            }
+        }
+        Ok(resolution)
+    }
+
+    /// Return the public key of the given (unmerged) leaf. The leaf must have
+    /// been read already and must be non-blank.
+    fn leaf_key(&self, leaf_index: LeafIndex) -> Result<&HpkePublicKey, PublicTreeError> {
+        self.leaf_keys
+            .get(&leaf_index)
+            .ok_or(PublicTreeError::MalformedTree)
+    }
+}
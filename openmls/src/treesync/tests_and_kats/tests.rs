@@ -1,2 +1,3 @@
 mod test_diff;
+mod test_export_leaves;
 mod test_unmerged_leaves;
@@ -4,6 +4,7 @@ use rstest_reuse::apply;
 
 use crate::{
     credentials::{CredentialBundle, CredentialType},
+    group::GroupId,
     key_packages::KeyPackageBundle,
     treesync::{node::Node, TreeSync},
 };
@@ -75,3 +76,91 @@ fn test_free_leaf_computation(ciphersuite: Ciphersuite, backend: &impl OpenMlsCr
 
     assert_eq!(free_leaf_index, 2u32);
 }
+
+// Verifies that the tree hashes computed incrementally from cached values match
+// the tree hashes computed from scratch after every kind of tree modification.
+#[apply(ciphersuites_and_backends)]
+fn test_cached_tree_hashes(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let key_package_bundle = |identity: &str| {
+        let cb = CredentialBundle::new(
+            identity.as_bytes().to_vec(),
+            CredentialType::Basic,
+            ciphersuite.signature_algorithm(),
+            backend,
+        )
+        .expect("error creating credential_bundle");
+        let kpb = KeyPackageBundle::new(&[ciphersuite], &cb, backend, vec![])
+            .expect("error creating kpb");
+        (cb, kpb)
+    };
+    let assert_tree_hash = |tree: &TreeSync| {
+        let tree_hash = tree
+            .recompute_tree_hash(backend, ciphersuite)
+            .expect("error computing tree hash");
+        assert_eq!(tree.tree_hash(), tree_hash.as_slice());
+    };
+
+    let (cb_0, kpb_0) = key_package_bundle("leaf0");
+    let nodes = vec![Some(Node::LeafNode(
+        kpb_0.key_package().leaf_node().clone().into(),
+    ))];
+    let mut tree =
+        TreeSync::from_nodes(backend, ciphersuite, &nodes, kpb_0).expect("error generating tree");
+    assert_tree_hash(&tree);
+
+    // Extend the tree.
+    let mut diff = tree.empty_diff();
+    for identity in ["leaf1", "leaf2", "leaf3", "leaf4", "leaf5"] {
+        let (_, kpb) = key_package_bundle(identity);
+        diff.add_leaf(kpb.key_package().leaf_node().clone().into())
+            .expect("error adding leaf");
+    }
+    let staged_diff = diff
+        .into_staged_diff(backend, ciphersuite)
+        .expect("error staging diff");
+    tree.merge_diff(staged_diff);
+    assert_tree_hash(&tree);
+
+    // Update a leaf and remove the right-most leaf, which shrinks the tree.
+    let mut diff = tree.empty_diff();
+    let (_, kpb) = key_package_bundle("leaf1'");
+    diff.update_leaf(kpb.key_package().leaf_node().clone().into(), 1)
+        .expect("error updating leaf");
+    diff.blank_leaf(5).expect("error blanking leaf");
+    let staged_diff = diff
+        .into_staged_diff(backend, ciphersuite)
+        .expect("error staging diff");
+    tree.merge_diff(staged_diff);
+    assert_eq!(tree.leaf_count(), 5);
+    assert_tree_hash(&tree);
+
+    // Remove a leaf in the middle of the tree and re-fill it.
+    let mut diff = tree.empty_diff();
+    diff.blank_leaf(2).expect("error blanking leaf");
+    let staged_diff = diff
+        .into_staged_diff(backend, ciphersuite)
+        .expect("error staging diff");
+    tree.merge_diff(staged_diff);
+    assert_tree_hash(&tree);
+    let mut diff = tree.empty_diff();
+    let (_, kpb) = key_package_bundle("leaf2'");
+    let leaf_index = diff
+        .add_leaf(kpb.key_package().leaf_node().clone().into())
+        .expect("error adding leaf");
+    assert_eq!(leaf_index, 2);
+    let staged_diff = diff
+        .into_staged_diff(backend, ciphersuite)
+        .expect("error staging diff");
+    tree.merge_diff(staged_diff);
+    assert_tree_hash(&tree);
+
+    // Apply an update path for the own leaf.
+    let mut diff = tree.empty_diff();
+    diff.apply_own_update_path(backend, ciphersuite, GroupId::random(backend), &cb_0)
+        .expect("error applying own update path");
+    let staged_diff = diff
+        .into_staged_diff(backend, ciphersuite)
+        .expect("error staging diff");
+    tree.merge_diff(staged_diff);
+    assert_tree_hash(&tree);
+}
@@ -0,0 +1,53 @@
+use openmls_traits::{types::Ciphersuite, OpenMlsCryptoProvider};
+use rstest::*;
+use rstest_reuse::apply;
+
+use crate::{
+    credentials::{CredentialBundle, CredentialType},
+    key_packages::KeyPackageBundle,
+    treesync::{node::Node, TreeSync},
+};
+
+use openmls_rust_crypto::OpenMlsRustCrypto;
+
+// Verifies that only the populated leaves are exported, together with their leaf indices.
+#[apply(ciphersuites_and_backends)]
+fn test_export_leaves(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    let cb_0 = CredentialBundle::new(
+        "leaf0".as_bytes().to_vec(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("error creating credential_bundle");
+    let kpb_0 =
+        KeyPackageBundle::new(&[ciphersuite], &cb_0, backend, vec![]).expect("error creating kpb");
+
+    let cb_3 = CredentialBundle::new(
+        "leaf3".as_bytes().to_vec(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("error creating credential_bundle");
+    let kpb_3 =
+        KeyPackageBundle::new(&[ciphersuite], &cb_3, backend, vec![]).expect("error creating kpb");
+
+    let leaf_0 = kpb_0.key_package().leaf_node().clone();
+    let leaf_3 = kpb_3.key_package().leaf_node().clone();
+
+    // Build a rudimentary tree with two populated and two empty leaf nodes.
+    let nodes: Vec<Option<Node>> = vec![
+        Some(Node::LeafNode(leaf_0.clone().into())), // Leaf 0
+        None,
+        None, // Leaf 1
+        None,
+        None, // Leaf 2
+        None,
+        Some(Node::LeafNode(leaf_3.clone().into())), // Leaf 3
+    ];
+    let tree =
+        TreeSync::from_nodes(backend, ciphersuite, &nodes, kpb_0).expect("error generating tree");
+
+    assert_eq!(tree.export_leaves(), vec![(0, leaf_0), (3, leaf_3)]);
+}
@@ -238,6 +238,7 @@ pub(crate) struct PlaintextSecret {
     public_key: HpkePublicKey,
     group_secrets_bytes: Vec<u8>,
     new_member: KeyPackageRef,
+    new_leaf_index: LeafIndex,
 }
 
 impl PlaintextSecret {
@@ -288,11 +289,17 @@ impl PlaintextSecret {
                 public_key: key_package.hpke_init_key().clone(),
                 group_secrets_bytes,
                 new_member: key_package.hash_ref(backend.crypto())?,
+                new_leaf_index: leaf_index,
             });
         }
         Ok(plaintext_secrets)
     }
 
+    /// Returns the leaf index of the new member these secrets are destined for.
+    pub(crate) fn new_leaf_index(&self) -> LeafIndex {
+        self.new_leaf_index
+    }
+
     /// Encrypt the `group_secret_bytes` using the `public_key`, both contained
     /// in this [`PlaintextSecret`].
     ///
@@ -75,13 +75,18 @@ impl TreeSyncNode {
         &mut self.node
     }
 
+    /// Return the cached tree hash of this node, if there is one.
+    pub(in crate::treesync) fn tree_hash(&self) -> Option<&[u8]> {
+        self.tree_hash.as_deref()
+    }
+
     /// Replace the current `tree_hash` with `None`.
     pub(in crate::treesync) fn erase_tree_hash(&mut self) {
         self.tree_hash = None
     }
 
     /// Compute the tree hash for this node, thus populating the `tree_hash`
-    /// field.
+    /// field. Any previously cached tree hash is overwritten.
     pub(in crate::treesync) fn compute_tree_hash(
         &mut self,
         backend: &impl OpenMlsCryptoProvider,
@@ -90,12 +95,6 @@ impl TreeSyncNode {
         left_hash: Vec<u8>,
         right_hash: Vec<u8>,
     ) -> Result<Vec<u8>, LibraryError> {
-        // // If there's a cached tree hash, use that one.
-        // TODO[FK]: Do we want to keep caching?
-        // if let Some(hash) = self.tree_hash() {
-        //     return Ok(hash.clone());
-        // };
-        // Otherwise compute it.
         // Check if I'm a leaf node.
         let hash = if let Some(leaf_index) = leaf_index_option {
             let leaf_node = self.node.as_ref().map(|node| node.as_leaf_node());
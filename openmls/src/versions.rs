@@ -1,6 +1,13 @@
 //! # MLS versions
 //!
-//! Only MLS 1.0 is currently supported.
+//! Only MLS 1.0 is currently supported. The encodings of the structs in this
+//! crate follow draft-ietf-mls-protocol-16 for all protocol versions. They are
+//! not compatible with the final RFC 9420 encodings, which e.g. encode the
+//! [`ProtocolVersion`] as `uint16` instead of a single byte.
+
+// TODO #synth-1720: Version-gated RFC 9420 encodings of the group context,
+// key packages and framing are not implemented yet. Until they are, groups
+// can't interoperate with RFC 9420 implementations.
 
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, fmt};
@@ -624,6 +624,7 @@ fn book_operations(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvide
              identity: _,
              encryption_key: _,
              signature_key,
+             ..
          }| signature_key.as_slice() == sender_credential.signature_key().as_slice()
     ));
     drop(alice_members);
@@ -93,6 +93,7 @@ fn test_external_commit(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoPr
             backend,
             None,
             verifiable_group_info,
+            None,
             &MlsGroupConfig::default(),
             b"",
             &bob_cb,
@@ -114,6 +115,7 @@ fn test_external_commit(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoPr
             backend,
             None,
             verifiable_group_info_broken,
+            None,
             &MlsGroupConfig::default(),
             b"",
             &bob_cb,
@@ -1041,3 +1041,79 @@ fn mls_group_ratchet_tree_extension(
         assert_eq!(error, WelcomeError::MissingRatchetTree);
     }
 }
+
+// This tests joining a group with a ratchet tree that is read from a reader
+#[apply(ciphersuites_and_backends)]
+fn mls_group_new_from_welcome_with_tree_reader(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    // Generate credential bundles
+    let alice_credential = generate_credential_bundle(
+        "Alice".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+
+    let bob_credential = generate_credential_bundle(
+        "Bob".into(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+
+    // Generate KeyPackages
+    let alice_key_package =
+        generate_key_package_bundle(&[ciphersuite], &alice_credential, vec![], backend)
+            .expect("An unexpected error occurred.");
+
+    let bob_key_package =
+        generate_key_package_bundle(&[ciphersuite], &bob_credential, vec![], backend)
+            .expect("An unexpected error occurred.");
+
+    // The Welcome doesn't contain the ratchet tree extension.
+    let mls_group_config = MlsGroupConfig::test_default();
+
+    // === Alice creates a group ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        backend,
+        &mls_group_config,
+        group_id,
+        alice_key_package
+            .hash_ref(backend.crypto())
+            .expect("Could not hash KeyPackage.")
+            .as_slice(),
+    )
+    .expect("An unexpected error occurred.");
+
+    // === Alice adds Bob ===
+    let (_queued_message, welcome) = alice_group
+        .add_members(backend, &[bob_key_package])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+
+    // === Bob joins with the ratchet tree read from a reader ===
+    let encoded_tree = RatchetTreeExtension::new(alice_group.export_ratchet_tree())
+        .tls_serialize_detached()
+        .expect("Error serializing ratchet tree.");
+
+    let bob_group = MlsGroup::new_from_welcome_with_tree_reader(
+        backend,
+        &mls_group_config,
+        welcome,
+        encoded_tree.as_slice(),
+    )
+    .expect("Error creating group from Welcome");
+
+    assert_eq!(
+        bob_group.export_ratchet_tree(),
+        alice_group.export_ratchet_tree()
+    );
+}
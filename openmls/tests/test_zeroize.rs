@@ -0,0 +1,126 @@
+//! Checks that the secrets of a group are zeroized when they are freed.
+//!
+//! The test installs an allocator that inspects freed buffers as the global
+//! allocator, so it is kept in its own test binary.
+use openmls::{prelude::*, test_utils::*, *};
+
+use openmls_traits::{key_store::OpenMlsKeyStore, OpenMlsCryptoProvider};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// An allocator that checks whether a watched buffer only contains zeros when
+/// it is freed.
+struct ZeroCheckingAllocator;
+
+/// The address of the watched buffer, or 0 if no buffer is watched.
+static WATCHED_BUFFER: AtomicUsize = AtomicUsize::new(0);
+/// Whether the watched buffer only contained zeros when it was freed.
+static WATCHED_BUFFER_ZEROED: AtomicBool = AtomicBool::new(false);
+/// Only one buffer can be watched at a time.
+static WATCH_LOCK: Mutex<()> = Mutex::new(());
+
+unsafe impl GlobalAlloc for ZeroCheckingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if WATCHED_BUFFER
+            .compare_exchange(ptr as usize, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            let buffer = std::slice::from_raw_parts(ptr, layout.size());
+            WATCHED_BUFFER_ZEROED.store(buffer.iter().all(|byte| *byte == 0), Ordering::SeqCst);
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: ZeroCheckingAllocator = ZeroCheckingAllocator;
+
+/// Runs `f` and returns whether the heap `buffer` was freed by `f` and only
+/// contained zeros at that point.
+fn zeroized_when_freed(buffer: *const u8, f: impl FnOnce()) -> bool {
+    let _guard = WATCH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    WATCHED_BUFFER_ZEROED.store(false, Ordering::SeqCst);
+    WATCHED_BUFFER.store(buffer as usize, Ordering::SeqCst);
+    f();
+    // The allocator resets the watched buffer when it's freed.
+    let freed = WATCHED_BUFFER.swap(0, Ordering::SeqCst) == 0;
+    freed && WATCHED_BUFFER_ZEROED.load(Ordering::SeqCst)
+}
+
+/// Creates a group with a single member and the given configuration.
+fn create_group(
+    ciphersuite: Ciphersuite,
+    backend: &impl OpenMlsCryptoProvider,
+    mls_group_config: &MlsGroupConfig,
+) -> MlsGroup {
+    let credential_bundle = CredentialBundle::new(
+        b"Alice".to_vec(),
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+        backend,
+    )
+    .expect("An unexpected error occurred.");
+    let key_package_bundle =
+        KeyPackageBundle::new(&[ciphersuite], &credential_bundle, backend, vec![])
+            .expect("An unexpected error occurred.");
+    let key_package_hash = key_package_bundle
+        .key_package()
+        .hash_ref(backend.crypto())
+        .expect("Could not hash KeyPackage.");
+    backend
+        .key_store()
+        .store(
+            &credential_bundle
+                .credential()
+                .signature_key()
+                .tls_serialize_detached()
+                .expect("Error serializing signature key."),
+            &credential_bundle,
+        )
+        .expect("An unexpected error occurred.");
+    backend
+        .key_store()
+        .store(key_package_hash.as_slice(), &key_package_bundle)
+        .expect("An unexpected error occurred.");
+    MlsGroup::new(backend, mls_group_config, key_package_hash.as_slice())
+        .expect("An unexpected error occurred.")
+}
+
+/// Creates a commit that updates the own leaf and merges it.
+fn self_update(group: &mut MlsGroup, backend: &impl OpenMlsCryptoProvider) {
+    group
+        .self_update(backend, None)
+        .expect("An unexpected error occurred.");
+    group
+        .merge_pending_commit()
+        .expect("error merging pending commit");
+}
+
+#[apply(ciphersuites_and_backends)]
+fn message_secrets_zeroize(ciphersuite: Ciphersuite, backend: &impl OpenMlsCryptoProvider) {
+    // The message secrets that are replaced when merging a commit are
+    // zeroized if no past epochs are kept.
+    let mut group = create_group(ciphersuite, backend, &MlsGroupConfig::test_default());
+    let buffer = group.sender_data_secret_ptr();
+    assert!(zeroized_when_freed(buffer, || self_update(
+        &mut group, backend
+    )));
+
+    // Message secrets of past epochs are zeroized when they are evicted.
+    let mls_group_config = MlsGroupConfig::builder().max_past_epochs(1).build();
+    let mut group = create_group(ciphersuite, backend, &mls_group_config);
+    let buffer = group.sender_data_secret_ptr();
+    self_update(&mut group, backend);
+    assert!(zeroized_when_freed(buffer, || self_update(
+        &mut group, backend
+    )));
+}